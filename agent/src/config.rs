@@ -0,0 +1,281 @@
+//! Agent configuration
+//!
+//! Settings come from an optional TOML file (`--config`), with command line
+//! flags taking precedence over the file. Settings that predate the config
+//! file (`AGENT_API_KEY`, `AGENT_JOB_JOURNAL`, `AGENT_TLS_*`) are still read
+//! from the environment when neither sets them.
+//!
+//! ```toml
+//! bind_address = "0.0.0.0"
+//! port = 8745
+//! api_key_file = "/etc/nodes-agent/api_key"
+//! require_key = true
+//! job_retention_hours = 48
+//! cleanup_interval_seconds = 3600
+//! max_concurrent_operations = 4
+//! metrics_token_file = "/etc/nodes-agent/metrics_token"
+//! telemetry_cache_seconds = 900
+//!
+//! [paths]
+//! deploy_roots = ["/opt/deploy"]
+//! backup_roots = ["/backup/snapshots"]
+//! log_roots = ["/var/log"]
+//!
+//! [tls]
+//! cert_path = "/etc/nodes-agent/tls/agent.pem"
+//! key_path = "/etc/nodes-agent/tls/agent.key"
+//! ```
+
+use anyhow::{anyhow, Context, Result};
+use clap::Parser;
+use serde::Deserialize;
+use std::net::SocketAddr;
+use tracing::warn;
+
+use crate::services::tls::TlsSettings;
+
+/// Key used when no API key is configured and `require_key` is off
+pub const DEVELOPMENT_API_KEY: &str = "default-development-key";
+
+/// Command line flags of the agent binary
+#[derive(Debug, Default, Parser)]
+#[command(name = "agent", version, about = "Blockchain Server Agent")]
+pub struct Cli {
+    /// TOML configuration file
+    #[arg(long, short = 'c')]
+    pub config: Option<String>,
+
+    /// Address to listen on
+    #[arg(long)]
+    pub bind_address: Option<String>,
+
+    /// Port to listen on
+    #[arg(long)]
+    pub port: Option<u16>,
+
+    /// File holding the API key requests are signed with
+    #[arg(long)]
+    pub api_key_file: Option<String>,
+
+    /// Refuse to start without a configured API key
+    #[arg(long)]
+    pub require_key: bool,
+
+    /// Let `/command/execute` run arbitrary shell commands
+    #[arg(long)]
+    pub allow_raw_commands: bool,
+
+    /// Hours finished jobs are kept before they are purged
+    #[arg(long)]
+    pub job_retention_hours: Option<i64>,
+
+    /// Seconds between cleanups of finished jobs and stuck operations
+    #[arg(long)]
+    pub cleanup_interval_seconds: Option<u64>,
+
+    /// Maximum number of operations running at once
+    #[arg(long)]
+    pub max_concurrent_operations: Option<usize>,
+}
+
+/// Directories the agent's operations are allowed to work in. Request paths
+/// outside them are rejected, as are paths of a kind with an empty list.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PathRoots {
+    /// Node home directories (`deploy_path`)
+    pub deploy_roots: Vec<String>,
+    /// Snapshot backup directories
+    pub backup_roots: Vec<String>,
+    /// Node and Hermes log directories
+    pub log_roots: Vec<String>,
+    /// Accept any path for kinds with an empty list, as agents did before
+    /// roots existed. Only meant for migrating existing deployments.
+    pub allow_unconfined_paths: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AgentConfig {
+    pub bind_address: String,
+    pub port: u16,
+    pub api_key_file: Option<String>,
+    /// Second accepted key, used while keys are rotated
+    pub secondary_api_key_file: Option<String>,
+    pub require_key: bool,
+    pub allow_raw_commands: bool,
+    pub job_journal: Option<String>,
+    pub job_retention_hours: i64,
+    /// Hours after which a node still marked busy is considered stuck
+    pub stuck_operation_hours: i64,
+    pub cleanup_interval_seconds: u64,
+    /// `None` leaves the number of concurrent operations unlimited
+    pub max_concurrent_operations: Option<usize>,
+    /// File holding the bearer token `/metrics` requires; unauthenticated without it
+    pub metrics_token_file: Option<String>,
+    /// Seconds node directory sizes reported by `/telemetry` are reused before re-measuring
+    pub telemetry_cache_seconds: u64,
+    pub paths: PathRoots,
+    pub tls: Option<TlsSettings>,
+}
+
+impl Default for AgentConfig {
+    fn default() -> Self {
+        Self {
+            bind_address: "0.0.0.0".to_string(),
+            port: 8745,
+            api_key_file: None,
+            secondary_api_key_file: None,
+            require_key: false,
+            allow_raw_commands: false,
+            job_journal: None,
+            job_retention_hours: 48,
+            stuck_operation_hours: 24,
+            cleanup_interval_seconds: 3600,
+            max_concurrent_operations: None,
+            metrics_token_file: None,
+            telemetry_cache_seconds: 900,
+            paths: PathRoots::default(),
+            tls: None,
+        }
+    }
+}
+
+impl AgentConfig {
+    /// Parse a TOML configuration file
+    pub fn from_file(path: &str) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read agent config {}", path))?;
+        toml::from_str(&content).with_context(|| format!("Failed to parse agent config {}", path))
+    }
+
+    /// Load the file named by `--config` (if any), then apply command line
+    /// flags and environment fallbacks
+    pub fn load(cli: &Cli) -> Result<Self> {
+        let mut config = match &cli.config {
+            Some(path) => Self::from_file(path)?,
+            None => Self::default(),
+        };
+
+        if let Some(bind_address) = &cli.bind_address {
+            config.bind_address = bind_address.clone();
+        }
+        if let Some(port) = cli.port {
+            config.port = port;
+        }
+        if let Some(api_key_file) = &cli.api_key_file {
+            config.api_key_file = Some(api_key_file.clone());
+        }
+        config.require_key |= cli.require_key;
+        config.allow_raw_commands |= cli.allow_raw_commands;
+        if let Some(hours) = cli.job_retention_hours {
+            config.job_retention_hours = hours;
+        }
+        if let Some(seconds) = cli.cleanup_interval_seconds {
+            config.cleanup_interval_seconds = seconds;
+        }
+        if let Some(max) = cli.max_concurrent_operations {
+            config.max_concurrent_operations = Some(max);
+        }
+
+        if config.job_journal.is_none() {
+            config.job_journal = std::env::var("AGENT_JOB_JOURNAL").ok();
+        }
+        if config.tls.is_none() {
+            config.tls = TlsSettings::from_env()?;
+        }
+
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.cleanup_interval_seconds == 0 {
+            return Err(anyhow!("cleanup_interval_seconds must be greater than 0"));
+        }
+        if self.job_retention_hours <= 0 {
+            return Err(anyhow!("job_retention_hours must be greater than 0"));
+        }
+        if self.max_concurrent_operations == Some(0) {
+            return Err(anyhow!("max_concurrent_operations must be greater than 0"));
+        }
+        for root in self
+            .paths
+            .deploy_roots
+            .iter()
+            .chain(&self.paths.backup_roots)
+            .chain(&self.paths.log_roots)
+        {
+            let path = std::path::Path::new(root);
+            if !path.is_absolute() || path.parent().is_none() {
+                return Err(anyhow!(
+                    "Allowed root {} must be an absolute path below /",
+                    root
+                ));
+            }
+        }
+        self.socket_addr().map(|_| ())
+    }
+
+    pub fn socket_addr(&self) -> Result<SocketAddr> {
+        format!("{}:{}", self.bind_address, self.port)
+            .parse()
+            .with_context(|| format!("Invalid bind address {}:{}", self.bind_address, self.port))
+    }
+
+    pub fn job_journal_path(&self) -> &str {
+        self.job_journal
+            .as_deref()
+            .unwrap_or("data/agent_jobs.jsonl")
+    }
+
+    /// Active API keys: the primary key from `api_key_file` (or `AGENT_API_KEY`),
+    /// followed by the optional secondary key. Without a primary key the
+    /// development key is used, unless `require_key` is set.
+    pub fn api_keys(&self) -> Result<Vec<String>> {
+        let primary = match &self.api_key_file {
+            Some(path) => Some(read_key_file(path)?),
+            None => std::env::var("AGENT_API_KEY").ok(),
+        }
+        .filter(|key| !key.is_empty() && key != DEVELOPMENT_API_KEY);
+
+        let primary = match primary {
+            Some(key) => key,
+            None if self.require_key => {
+                return Err(anyhow!(
+                    "No API key configured - set api_key_file or AGENT_API_KEY (required by require_key)"
+                ));
+            }
+            None => {
+                warn!("Using default development API key - configure api_key_file or AGENT_API_KEY for production");
+                DEVELOPMENT_API_KEY.to_string()
+            }
+        };
+
+        let mut keys = vec![primary];
+        let secondary = match &self.secondary_api_key_file {
+            Some(path) => Some(read_key_file(path)?),
+            None => std::env::var("AGENT_API_KEY_SECONDARY").ok(),
+        };
+        keys.extend(secondary.filter(|key| !key.is_empty()));
+        Ok(keys)
+    }
+
+    /// Bearer token required by `/metrics`, read from `metrics_token_file`
+    pub fn metrics_token(&self) -> Result<Option<String>> {
+        self.metrics_token_file
+            .as_deref()
+            .map(read_key_file)
+            .transpose()
+    }
+}
+
+fn read_key_file(path: &str) -> Result<String> {
+    let key = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read API key file {}", path))?;
+    let key = key.trim().to_string();
+    if key.is_empty() {
+        return Err(anyhow!("API key file {} is empty", path));
+    }
+    Ok(key)
+}
@@ -1,24 +1,60 @@
 //! HTTP request handlers for the agent server
 
 use axum::{
-    extract::{Json, Path, State},
-    http::StatusCode,
-    response::Json as ResponseJson,
+    extract::{Json, Path, Query, Request, State},
+    http::{
+        header::{AUTHORIZATION, CONTENT_TYPE},
+        HeaderMap, StatusCode,
+    },
+    response::{
+        sse::{KeepAlive, Sse},
+        IntoResponse, Json as ResponseJson, Response,
+    },
 };
 use std::sync::Arc;
+use tracing::warn;
 
 use crate::middleware::ApiKeyAuth;
+use crate::operations::cancel::CancelCleanup;
 use crate::operations::{pruning, restore, snapshots, state_sync};
-use crate::services::{commands, logs, systemctl};
+use crate::services::sandbox::RootKind;
+use crate::services::{
+    archive, commands, config_editor, log_rotation, log_stream, logs, manifest, metrics, paths,
+    signing, snapshot_files, systemctl, telemetry, transfer,
+};
 use crate::types::*;
 use crate::AppState;
 
+/// Error response for a request whose paths are outside the agent's allowed roots
+fn reject_paths(
+    state: &AppState,
+    paths: &[(RootKind, &str)],
+) -> Option<ResponseJson<ApiResponse<()>>> {
+    let violation = state.sandbox.check_all(paths).err()?;
+    warn!("Rejected request: {}", violation);
+    Some(ResponseJson(ApiResponse::error(violation.to_string())))
+}
+
+/// The optional `log_path` of an operation, checked against the log roots
+fn optional_log_path(log_path: &Option<String>) -> Option<(RootKind, &str)> {
+    log_path.as_deref().map(|path| (RootKind::Log, path))
+}
+
 // === Command handlers ===
 
 pub async fn execute_command(
     _auth: ApiKeyAuth,
+    State(state): State<Arc<AppState>>,
     Json(request): Json<CommandRequest>,
 ) -> Result<ResponseJson<ApiResponse<()>>, StatusCode> {
+    if !state.allow_raw_commands {
+        return Ok(ResponseJson(ApiResponse::error(
+            "Raw command execution is disabled on this agent (start with --allow-raw-commands)"
+                .to_string(),
+        )));
+    }
+
+    warn!("Executing raw command: {}", request.command);
     match commands::execute_shell_command(&request.command).await {
         Ok(output) => Ok(ResponseJson(ApiResponse::success_with_output(output))),
         Err(e) => Ok(ResponseJson(ApiResponse::error(e.to_string()))),
@@ -76,6 +112,10 @@ pub async fn truncate_logs(
     State(state): State<Arc<AppState>>,
     Json(request): Json<LogTruncateRequest>,
 ) -> Result<ResponseJson<ApiResponse<()>>, StatusCode> {
+    if let Some(rejection) = reject_paths(&state, &[(RootKind::Log, &request.log_path)]) {
+        return Ok(rejection);
+    }
+
     if let Err(err) = state
         .try_start_operation(&request.service_name, "log_truncation")
         .await
@@ -83,7 +123,12 @@ pub async fn truncate_logs(
         return Ok(ResponseJson(ApiResponse::error(err)));
     }
 
-    let result = logs::truncate_service_logs(&request.service_name, &request.log_path).await;
+    let result = logs::truncate_service_logs(
+        &request.service_name,
+        &request.log_path,
+        request.log_rotation.as_ref(),
+    )
+    .await;
     state.finish_operation(&request.service_name).await;
 
     match result {
@@ -94,14 +139,230 @@ pub async fn truncate_logs(
 
 pub async fn delete_all_files_in_directory(
     _auth: ApiKeyAuth,
+    State(state): State<Arc<AppState>>,
     Json(request): Json<LogDeleteAllRequest>,
 ) -> Result<ResponseJson<ApiResponse<()>>, StatusCode> {
+    if let Some(rejection) = reject_paths(&state, &[(RootKind::Log, &request.log_path)]) {
+        return Ok(rejection);
+    }
+
     match logs::delete_all_files_in_directory(&request.log_path).await {
         Ok(_) => Ok(ResponseJson(ApiResponse::success())),
         Err(e) => Ok(ResponseJson(ApiResponse::error(e.to_string()))),
     }
 }
 
+pub async fn list_log_archives(
+    _auth: ApiKeyAuth,
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<LogArchiveListRequest>,
+) -> Result<ResponseJson<ApiResponse<()>>, StatusCode> {
+    if let Some(rejection) = reject_paths(&state, &[(RootKind::Log, &request.log_path)]) {
+        return Ok(rejection);
+    }
+
+    let log_path = request.log_path;
+    match tokio::task::spawn_blocking(move || log_rotation::list_archives(&log_path)).await {
+        Ok(Ok(listing)) => Ok(ResponseJson(ApiResponse::success_with_json(&listing))),
+        Ok(Err(e)) => Ok(ResponseJson(ApiResponse::error(e.to_string()))),
+        Err(e) => Ok(ResponseJson(ApiResponse::error(e.to_string()))),
+    }
+}
+
+/// Download a compressed log kept by a rotation
+pub async fn download_log_archive(
+    _auth: ApiKeyAuth,
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<LogArchiveDownloadQuery>,
+    request: Request,
+) -> Response {
+    if let Err(violation) = state.sandbox.check(RootKind::Log, &query.log_path) {
+        warn!("Rejected log archive download: {}", violation);
+        return (StatusCode::FORBIDDEN, violation.to_string()).into_response();
+    }
+    log_rotation::serve_archive(&query.log_path, &query.name, request).await
+}
+
+pub async fn search_log(
+    _auth: ApiKeyAuth,
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<LogSearchRequest>,
+) -> Result<ResponseJson<ApiResponse<()>>, StatusCode> {
+    // A journal has no path; its unit name is validated when it is read
+    if request.journald_unit.is_none() {
+        if let Some(rejection) = reject_paths(&state, &[(RootKind::Log, &request.path)]) {
+            return Ok(rejection);
+        }
+    }
+
+    match logs::search_logs(request).await {
+        Ok(result) => Ok(ResponseJson(ApiResponse::success_with_json(&result))),
+        Err(e) => Ok(ResponseJson(ApiResponse::error(e.to_string()))),
+    }
+}
+
+/// Follow a log file, directory or journal as Server-Sent Events
+pub async fn stream_logs(
+    _auth: ApiKeyAuth,
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<LogStreamQuery>,
+) -> Response {
+    if query.journald_unit.is_none() {
+        if let Err(violation) = state.sandbox.check(RootKind::Log, &query.path) {
+            warn!("Rejected log stream: {}", violation);
+            return (StatusCode::FORBIDDEN, violation.to_string()).into_response();
+        }
+    }
+
+    match log_stream::follow(query).await {
+        Ok(events) => Sse::new(events)
+            .keep_alive(KeepAlive::default())
+            .into_response(),
+        Err(e) => (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+    }
+}
+
+// === Config handlers ===
+
+/// Set keys in a node's TOML config, returning the diff of the change
+pub async fn patch_config(
+    _auth: ApiKeyAuth,
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<ConfigPatchRequest>,
+) -> Result<ResponseJson<ApiResponse<()>>, StatusCode> {
+    if let Some(rejection) = reject_paths(&state, &[(RootKind::Deploy, &request.config_path)]) {
+        return Ok(rejection);
+    }
+
+    match config_editor::patch_config(&request.config_path, &request.changes, request.dry_run).await
+    {
+        Ok(result) => Ok(ResponseJson(ApiResponse::success_with_json(&result))),
+        Err(e) => Ok(ResponseJson(ApiResponse::error(e.to_string()))),
+    }
+}
+
+// === Snapshot file handlers ===
+
+pub async fn list_snapshots(
+    _auth: ApiKeyAuth,
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<SnapshotListRequest>,
+) -> Result<ResponseJson<ApiResponse<()>>, StatusCode> {
+    if let Some(rejection) = reject_paths(&state, &[(RootKind::Backup, &request.backup_path)]) {
+        return Ok(rejection);
+    }
+
+    match snapshot_files::list_snapshots(&request.backup_path, &request.network).await {
+        Ok(listing) => Ok(ResponseJson(ApiResponse::success_with_json(&listing))),
+        Err(e) => Ok(ResponseJson(ApiResponse::error(e.to_string()))),
+    }
+}
+
+pub async fn delete_snapshot(
+    _auth: ApiKeyAuth,
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<SnapshotDeleteRequest>,
+) -> Result<ResponseJson<ApiResponse<()>>, StatusCode> {
+    if let Some(rejection) = reject_paths(&state, &[(RootKind::Backup, &request.backup_path)]) {
+        return Ok(rejection);
+    }
+
+    match snapshot_files::delete_snapshot(&request.backup_path, &request.snapshot_name).await {
+        Ok(result) => Ok(ResponseJson(ApiResponse::success_with_json(&result))),
+        Err(e) => Ok(ResponseJson(ApiResponse::error(e.to_string()))),
+    }
+}
+
+/// Issue the token another agent needs to download one archive from this
+/// agent. The manager requests it and hands only the token to the pulling
+/// agent, never this agent's API key.
+pub async fn issue_archive_download_token(
+    _auth: ApiKeyAuth,
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<ArchiveDownloadQuery>,
+) -> Result<ResponseJson<ApiResponse<()>>, StatusCode> {
+    if let Some(rejection) = reject_paths(&state, &[(RootKind::Backup, &request.backup_path)]) {
+        return Ok(rejection);
+    }
+    if let Err(e) = snapshot_files::validate_snapshot_name(&request.snapshot_name) {
+        return Ok(ResponseJson(ApiResponse::error(e.to_string())));
+    }
+
+    let expires_at = chrono::Utc::now().timestamp() + signing::DOWNLOAD_TOKEN_TTL_SECONDS;
+    let token = ArchiveDownloadToken {
+        token: state.request_verifier.issue_download_token(
+            &request.backup_path,
+            &request.snapshot_name,
+            expires_at,
+        ),
+        expires_at,
+    };
+    Ok(ResponseJson(ApiResponse::success_with_json(&token)))
+}
+
+/// Stream a complete archive to another agent pulling it with `/snapshot/pull`.
+/// Requests are not signed; they carry the download token issued for the
+/// archive as a bearer token.
+pub async fn download_snapshot_archive(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ArchiveDownloadQuery>,
+    request: Request,
+) -> Response {
+    let presented = request
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .unwrap_or_default();
+    if let Err(e) = state.request_verifier.verify_download_token(
+        presented,
+        &query.backup_path,
+        &query.snapshot_name,
+        chrono::Utc::now().timestamp(),
+    ) {
+        warn!(
+            "Rejected download of archive {}: {}",
+            query.snapshot_name, e
+        );
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+    if let Err(violation) = state.sandbox.check(RootKind::Backup, &query.backup_path) {
+        warn!("Rejected archive download: {}", violation);
+        return (StatusCode::FORBIDDEN, violation.to_string()).into_response();
+    }
+    transfer::serve_archive(&query.backup_path, &query.snapshot_name, request).await
+}
+
+// === Path handlers ===
+
+pub async fn check_path(
+    _auth: ApiKeyAuth,
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<PathCheckRequest>,
+) -> Result<ResponseJson<ApiResponse<()>>, StatusCode> {
+    if let Err(violation) = state.sandbox.check_any(&request.path) {
+        return Ok(ResponseJson(ApiResponse::error(violation.to_string())));
+    }
+
+    match paths::check_path(&request.path).await {
+        Ok(info) => Ok(ResponseJson(ApiResponse::success_with_json(&info))),
+        Err(e) => Ok(ResponseJson(ApiResponse::error(e.to_string()))),
+    }
+}
+
+// === Telemetry handlers ===
+
+pub async fn get_telemetry(
+    _auth: ApiKeyAuth,
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<TelemetryRequest>,
+) -> Result<ResponseJson<ApiResponse<()>>, StatusCode> {
+    match telemetry::collect(&state.size_cache, &state.sandbox, &request.nodes).await {
+        Ok(report) => Ok(ResponseJson(ApiResponse::success_with_json(&report))),
+        Err(e) => Ok(ResponseJson(ApiResponse::error(e.to_string()))),
+    }
+}
+
 // === Async operation handlers ===
 
 pub async fn execute_pruning_async(
@@ -109,12 +370,26 @@ pub async fn execute_pruning_async(
     State(state): State<Arc<AppState>>,
     Json(request): Json<PruningRequest>,
 ) -> Result<ResponseJson<ApiResponse<()>>, StatusCode> {
+    let mut checked = vec![(RootKind::Deploy, request.deploy_path.as_str())];
+    checked.extend(optional_log_path(&request.log_path));
+    if let Some(rejection) = reject_paths(&state, &checked) {
+        return Ok(rejection);
+    }
+
     let service_name = request.service_name.clone();
+    let cleanup = CancelCleanup::RestartService {
+        service_name: service_name.clone(),
+    };
     match state
-        .execute_async_operation(&service_name, "pruning", move || async move {
-            let output = pruning::execute_full_pruning_sequence(&request).await?;
-            Ok(serde_json::json!({ "output": output, "operation": "pruning" }))
-        })
+        .execute_async_operation(
+            &service_name,
+            "pruning",
+            cleanup,
+            move |progress| async move {
+                let output = pruning::execute_full_pruning_sequence(&request, &progress).await?;
+                Ok(serde_json::json!({ "output": output, "operation": "pruning" }))
+            },
+        )
         .await
     {
         Ok(job_id) => Ok(ResponseJson(ApiResponse::success_with_job(
@@ -130,26 +405,124 @@ pub async fn create_snapshot_async(
     State(state): State<Arc<AppState>>,
     Json(request): Json<SnapshotRequest>,
 ) -> Result<ResponseJson<ApiResponse<()>>, StatusCode> {
+    let mut checked = vec![
+        (RootKind::Deploy, request.deploy_path.as_str()),
+        (RootKind::Backup, request.backup_path.as_str()),
+    ];
+    checked.extend(optional_log_path(&request.log_path));
+    if let Some(rejection) = reject_paths(&state, &checked) {
+        return Ok(rejection);
+    }
+    if let Err(e) = snapshot_files::validate_snapshot_name(&request.snapshot_name) {
+        return Ok(ResponseJson(ApiResponse::error(e.to_string())));
+    }
+
     let node_name = request.node_name.clone();
+    let cleanup = CancelCleanup::RemovePartialSnapshot {
+        snapshot_path: format!("{}/{}", request.backup_path, request.snapshot_name),
+        service_name: request.service_name.clone(),
+    };
+    let compression_state = state.clone();
     match state
-        .execute_async_operation(&node_name, "snapshot_creation", move || async move {
-            let snapshot_info = snapshots::execute_full_snapshot_sequence(&request).await?;
-
-            // Spawn LZ4 compression in background
-            let backup_path = request.backup_path.clone();
-            let snapshot_dirname = snapshot_info.filename.clone();
-            tokio::spawn(async move {
-                commands::create_lz4_compressed_snapshot(&backup_path, &snapshot_dirname).await;
-            });
+        .execute_async_operation(
+            &node_name,
+            "snapshot_creation",
+            cleanup,
+            move |progress| async move {
+                let snapshot_info =
+                    snapshots::execute_full_snapshot_sequence(&request, &progress).await?;
+
+                let compression_job_id = start_snapshot_compression(
+                    &compression_state,
+                    &request.backup_path,
+                    &snapshot_info.filename,
+                    progress.job_id(),
+                )
+                .await;
+
+                Ok(serde_json::json!({
+                    "filename": snapshot_info.filename,
+                    "size_bytes": snapshot_info.size_bytes,
+                    "path": snapshot_info.path,
+                    "compression": "directory",
+                    "compression_job_id": compression_job_id,
+                    "operation": "snapshot_creation"
+                }))
+            },
+        )
+        .await
+    {
+        Ok(job_id) => Ok(ResponseJson(ApiResponse::success_with_job(
+            job_id,
+            "started".to_string(),
+        ))),
+        Err(err) => Ok(ResponseJson(ApiResponse::error(err))),
+    }
+}
 
-            Ok(serde_json::json!({
-                "filename": snapshot_info.filename,
-                "size_bytes": snapshot_info.size_bytes,
-                "path": snapshot_info.path,
-                "compression": "directory",
-                "operation": "snapshot_creation"
-            }))
-        })
+/// Archive a finished snapshot to `.tar.lz4` as its own job, linked to the
+/// snapshot job. Returns the compression job id, or `None` if it could not start.
+async fn start_snapshot_compression(
+    state: &Arc<AppState>,
+    backup_path: &str,
+    snapshot_name: &str,
+    snapshot_job_id: Option<&str>,
+) -> Option<String> {
+    let cleanup = CancelCleanup::RemovePartialArchive {
+        archive_path: archive::partial_archive_path(backup_path, snapshot_name),
+    };
+    let backup_path = backup_path.to_string();
+    let name = snapshot_name.to_string();
+    let started = state
+        .execute_async_operation(
+            snapshot_name,
+            "snapshot_compression",
+            cleanup,
+            move |progress| async move {
+                let result = archive::create_archive(&backup_path, &name, &progress).await?;
+                Ok(serde_json::to_value(result)?)
+            },
+        )
+        .await;
+
+    match started {
+        Ok(job_id) => {
+            if let Some(parent) = snapshot_job_id {
+                state.job_manager.link_to_parent(&job_id, parent).await;
+            }
+            Some(job_id)
+        }
+        Err(e) => {
+            warn!("Could not start compression of {}: {}", snapshot_name, e);
+            None
+        }
+    }
+}
+
+pub async fn pull_snapshot_async(
+    _auth: ApiKeyAuth,
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<SnapshotPullRequest>,
+) -> Result<ResponseJson<ApiResponse<()>>, StatusCode> {
+    if let Some(rejection) = reject_paths(&state, &[(RootKind::Backup, &request.backup_path)]) {
+        return Ok(rejection);
+    }
+
+    let snapshot_name = request.snapshot_name.clone();
+    // Agents that require client certificates expect the same from their peers
+    let tls = state.tls.clone().filter(|tls| tls.requires_client_cert());
+    // A cancelled pull keeps its download so the next pull resumes it
+    match state
+        .execute_async_operation(
+            &snapshot_name,
+            "snapshot_transfer",
+            CancelCleanup::Nothing,
+            move |progress| async move {
+                let client_identity = tls.map(|tls| tls.client_identity()).transpose()?;
+                let result = transfer::pull_archive(&request, client_identity, &progress).await?;
+                Ok(serde_json::to_value(result)?)
+            },
+        )
         .await
     {
         Ok(job_id) => Ok(ResponseJson(ApiResponse::success_with_job(
@@ -165,12 +538,68 @@ pub async fn restore_snapshot_async(
     State(state): State<Arc<AppState>>,
     Json(request): Json<RestoreRequest>,
 ) -> Result<ResponseJson<ApiResponse<()>>, StatusCode> {
+    let mut checked = vec![(RootKind::Deploy, request.deploy_path.as_str())];
+    match &request.archive_path {
+        Some(archive_path) => checked.push((RootKind::Backup, archive_path)),
+        None => checked.push((RootKind::Backup, &request.snapshot_dir)),
+    }
+    checked.extend(optional_log_path(&request.log_path));
+    if let Some(rejection) = reject_paths(&state, &checked) {
+        return Ok(rejection);
+    }
+
     let node_name = request.node_name.clone();
+    let cleanup = CancelCleanup::RollBackRestore {
+        deploy_path: request.deploy_path.clone(),
+        service_name: request.service_name.clone(),
+    };
     match state
-        .execute_async_operation(&node_name, "snapshot_restore", move || async move {
-            let output = restore::execute_full_restore_sequence(&request).await?;
-            Ok(serde_json::json!({ "output": output, "operation": "snapshot_restore" }))
-        })
+        .execute_async_operation(
+            &node_name,
+            "snapshot_restore",
+            cleanup,
+            move |progress| async move {
+                let output = restore::execute_full_restore_sequence(&request, &progress).await?;
+                Ok(serde_json::json!({ "output": output, "operation": "snapshot_restore" }))
+            },
+        )
+        .await
+    {
+        Ok(job_id) => Ok(ResponseJson(ApiResponse::success_with_job(
+            job_id,
+            "started".to_string(),
+        ))),
+        Err(err) => Ok(ResponseJson(ApiResponse::error(err))),
+    }
+}
+
+pub async fn verify_snapshot_async(
+    _auth: ApiKeyAuth,
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<SnapshotVerifyRequest>,
+) -> Result<ResponseJson<ApiResponse<()>>, StatusCode> {
+    if let Some(rejection) = reject_paths(&state, &[(RootKind::Backup, &request.snapshot_dir)]) {
+        return Ok(rejection);
+    }
+
+    let snapshot_name = request
+        .snapshot_dir
+        .trim_end_matches('/')
+        .rsplit('/')
+        .next()
+        .unwrap_or(&request.snapshot_dir)
+        .to_string();
+    match state
+        .execute_async_operation(
+            &snapshot_name,
+            "snapshot_verify",
+            CancelCleanup::Nothing,
+            move |progress| async move {
+                progress.step("Verifying checksums").await;
+                let report = manifest::verify_snapshot(&request.snapshot_dir, &progress).await?;
+                Ok(serde_json::to_value(report)?)
+            },
+        )
         .await
     {
         Ok(job_id) => Ok(ResponseJson(ApiResponse::success_with_job(
@@ -186,12 +615,29 @@ pub async fn execute_state_sync_async(
     State(state): State<Arc<AppState>>,
     Json(request): Json<StateSyncRequest>,
 ) -> Result<ResponseJson<ApiResponse<()>>, StatusCode> {
+    let mut checked = vec![
+        (RootKind::Deploy, request.home_dir.as_str()),
+        (RootKind::Deploy, request.config_path.as_str()),
+    ];
+    checked.extend(optional_log_path(&request.log_path));
+    if let Some(rejection) = reject_paths(&state, &checked) {
+        return Ok(rejection);
+    }
+
     let service_name = request.service_name.clone();
+    let cleanup = CancelCleanup::RestartService {
+        service_name: service_name.clone(),
+    };
     match state
-        .execute_async_operation(&service_name, "state_sync", move || async move {
-            let output = state_sync::execute_state_sync_sequence(&request).await?;
-            Ok(serde_json::json!({ "output": output, "operation": "state_sync" }))
-        })
+        .execute_async_operation(
+            &service_name,
+            "state_sync",
+            cleanup,
+            move |progress| async move {
+                let output = state_sync::execute_state_sync_sequence(&request, &progress).await?;
+                Ok(serde_json::json!({ "output": output, "operation": "state_sync" }))
+            },
+        )
         .await
     {
         Ok(job_id) => Ok(ResponseJson(ApiResponse::success_with_job(
@@ -214,6 +660,7 @@ pub async fn get_job_status(
             let mut response = ApiResponse::success();
             response.job_id = Some(job_info.job_id);
             response.job_status = Some(format!("{:?}", job_info.status));
+            response.progress = Some(job_info.progress);
 
             match job_info.status {
                 JobStatus::Completed => {
@@ -230,6 +677,18 @@ pub async fn get_job_status(
                 JobStatus::Running => {
                     response.output = Some("Operation still running".to_string());
                 }
+                JobStatus::Cancelled => {
+                    response.error = job_info.error_message;
+                    response.success = false;
+                }
+                JobStatus::Interrupted => {
+                    response.error = job_info.error_message;
+                    response.success = false;
+                    if let Some(left_stopped) = job_info.service_left_stopped {
+                        response.status =
+                            Some(if left_stopped { "stopped" } else { "running" }.to_string());
+                    }
+                }
             }
 
             Ok(ResponseJson(response))
@@ -241,14 +700,56 @@ pub async fn get_job_status(
     }
 }
 
+pub async fn cancel_job(
+    _auth: ApiKeyAuth,
+    State(state): State<Arc<AppState>>,
+    Path(job_id): Path<String>,
+) -> Result<ResponseJson<ApiResponse<()>>, StatusCode> {
+    match state.cancel_job(&job_id).await {
+        Ok(()) => Ok(ResponseJson(ApiResponse::success_with_job(
+            job_id,
+            "cancelling".to_string(),
+        ))),
+        Err(err) => Ok(ResponseJson(ApiResponse::error(err))),
+    }
+}
+
+pub async fn get_interrupted_jobs(
+    _auth: ApiKeyAuth,
+    State(state): State<Arc<AppState>>,
+) -> Result<ResponseJson<ApiResponse<()>>, StatusCode> {
+    let interrupted = state.job_manager.get_interrupted_jobs().await;
+
+    let response = serde_json::json!({
+        "interrupted_jobs": interrupted,
+        "total": interrupted.len(),
+    });
+
+    Ok(ResponseJson(ApiResponse::success_with_output(
+        response.to_string(),
+    )))
+}
+
 pub async fn check_restore_triggers(
     _auth: ApiKeyAuth,
+    State(state): State<Arc<AppState>>,
     Json(request): Json<serde_json::Value>,
 ) -> Result<ResponseJson<ApiResponse<()>>, StatusCode> {
-    let log_file = request
-        .get("log_file")
-        .and_then(|v| v.as_str())
-        .ok_or(StatusCode::BAD_REQUEST)?;
+    let journald_unit = request.get("journald_unit").and_then(|v| v.as_str());
+    let log_file = match (
+        request.get("log_file").and_then(|v| v.as_str()),
+        journald_unit,
+    ) {
+        (Some(log_file), _) => log_file,
+        (None, Some(_)) => "",
+        (None, None) => return Err(StatusCode::BAD_REQUEST),
+    };
+    if journald_unit.is_none() {
+        if let Some(rejection) = reject_paths(&state, &[(RootKind::Log, log_file)]) {
+            return Ok(rejection);
+        }
+    }
+    let glob = request.get("glob").and_then(|v| v.as_str());
 
     let trigger_words: Vec<String> = request
         .get("trigger_words")
@@ -260,11 +761,13 @@ pub async fn check_restore_triggers(
         })
         .unwrap_or_default();
 
-    match commands::check_log_for_trigger_words(log_file, &trigger_words).await {
+    match commands::check_log_for_trigger_words(log_file, glob, journald_unit, &trigger_words).await
+    {
         Ok(found) => {
             let response = serde_json::json!({
                 "triggers_found": found,
                 "log_file": log_file,
+                "journald_unit": journald_unit,
                 "trigger_words": trigger_words
             });
             Ok(ResponseJson(ApiResponse::success_with_output(
@@ -318,3 +821,33 @@ pub async fn cleanup_operations(
         response.to_string(),
     )))
 }
+
+// === Metrics ===
+
+/// Prometheus scrape endpoint. Requests are not signed; when a metrics token
+/// is configured it has to be sent as a bearer token.
+pub async fn get_metrics(State(state): State<Arc<AppState>>, headers: HeaderMap) -> Response {
+    if let Some(token) = &state.metrics_token {
+        let presented = headers
+            .get(AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "));
+        if presented != Some(token.as_str()) {
+            return StatusCode::UNAUTHORIZED.into_response();
+        }
+    }
+
+    let busy_nodes = state.get_busy_status().await.len();
+    let running_operations: Vec<String> = state
+        .job_manager
+        .get_running_jobs()
+        .await
+        .into_iter()
+        .map(|job| job.operation_type)
+        .collect();
+
+    match metrics::global().render(busy_nodes, &running_operations, &state.sandbox.roots()) {
+        Ok(body) => ([(CONTENT_TYPE, "text/plain; version=0.0.4")], body).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
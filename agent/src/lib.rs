@@ -2,6 +2,7 @@
 //!
 //! Exposes agent modules for testing
 
+pub mod config;
 pub mod operations;
 pub mod services;
 pub mod types;
@@ -7,25 +7,49 @@ use axum::{
     routing::{get, post},
     Router,
 };
+use axum_server::tls_rustls::RustlsConfig;
 use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::RwLock;
+use tokio::task::AbortHandle;
 use tracing::{error, info, warn};
 
+mod config;
 mod handlers;
 mod middleware;
 mod operations;
 mod services;
 pub mod types;
 
-use services::job_manager::JobManager;
+use operations::cancel::CancelCleanup;
+use services::job_manager::{JobManager, ProgressReporter};
+use services::sandbox::{PathSandbox, RootKind};
+use services::signing::RequestVerifier;
+use services::telemetry::SizeCache;
+use services::tls::TlsSettings;
+
+use clap::Parser;
+use config::{AgentConfig, Cli};
 
 /// Application state shared across all handlers
 #[derive(Clone)]
 pub struct AppState {
-    pub api_key: String,
+    pub request_verifier: Arc<RequestVerifier>,
     pub busy_nodes: Arc<RwLock<HashMap<String, BusyState>>>,
     pub job_manager: JobManager,
+    pub running_jobs: Arc<RwLock<HashMap<String, AbortHandle>>>,
+    /// Whether `/command/execute` may run arbitrary shell commands
+    pub allow_raw_commands: bool,
+    /// Limit on operations running at once, `None` for no limit
+    pub max_concurrent_operations: Option<usize>,
+    /// Certificate the agent serves HTTPS with, if TLS is enabled
+    pub tls: Option<TlsSettings>,
+    /// Roots request paths must stay inside
+    pub sandbox: PathSandbox,
+    /// Bearer token required on `/metrics`, if any
+    pub metrics_token: Option<String>,
+    /// Cached sizes of node directories reported by `/telemetry`
+    pub size_cache: Arc<SizeCache>,
 }
 
 /// Tracks busy state for a node operation
@@ -53,6 +77,16 @@ impl AppState {
             ));
         }
 
+        if let Some(max) = self.max_concurrent_operations {
+            if busy.len() >= max {
+                return Err(format!(
+                    "Agent is already running {} operations (limit {}), try again later",
+                    busy.len(),
+                    max
+                ));
+            }
+        }
+
         busy.insert(
             node_name.to_string(),
             BusyState {
@@ -113,89 +147,258 @@ impl AppState {
 
     /// Execute an async operation with standard lifecycle handling.
     /// Returns the job_id for tracking, or an error message if the node is busy.
+    /// `cleanup` runs if the job is cancelled through `cancel_job`.
     pub async fn execute_async_operation<F, Fut>(
         self: &Arc<Self>,
         target_name: &str,
         operation_type: &str,
+        cleanup: CancelCleanup,
         operation: F,
     ) -> Result<String, String>
     where
-        F: FnOnce() -> Fut + Send + 'static,
-        Fut: std::future::Future<Output = Result<serde_json::Value, anyhow::Error>> + Send,
+        F: FnOnce(ProgressReporter) -> Fut + Send + 'static,
+        Fut:
+            std::future::Future<Output = Result<serde_json::Value, anyhow::Error>> + Send + 'static,
     {
         self.try_start_operation(target_name, operation_type)
             .await?;
 
         let job_id = self
             .job_manager
-            .create_job(operation_type, target_name)
+            .create_job(operation_type, target_name, cleanup.service_name())
             .await;
 
+        // The operation runs in its own task so it can be aborted independently
+        // of the lifecycle handling below
+        let progress = self.job_manager.progress_reporter(&job_id);
+        let operation_handle = tokio::spawn(operation(progress));
+        self.running_jobs
+            .write()
+            .await
+            .insert(job_id.clone(), operation_handle.abort_handle());
+
         let state = self.clone();
         let job_id_clone = job_id.clone();
         let target_name = target_name.to_string();
         let operation_type = operation_type.to_string();
+        let started = std::time::Instant::now();
 
         tokio::spawn(async move {
-            let result = operation().await;
+            let outcome = operation_handle.await;
+            let status = match &outcome {
+                Ok(Ok(_)) => "completed",
+                Err(e) if e.is_cancelled() => "cancelled",
+                _ => "failed",
+            };
+            services::metrics::global().record_job(&operation_type, status, started.elapsed());
 
-            match result {
-                Ok(result_json) => {
+            match outcome {
+                Ok(Ok(result_json)) => {
                     state
                         .job_manager
                         .complete_job(&job_id_clone, result_json)
                         .await;
                 }
-                Err(e) => {
+                Ok(Err(e)) => {
                     error!("{} failed for {}: {}", operation_type, target_name, e);
                     state
                         .job_manager
                         .fail_job(&job_id_clone, e.to_string())
                         .await;
                 }
+                Err(e) if e.is_cancelled() => {
+                    warn!(
+                        "{} cancelled for {}, running cleanup",
+                        operation_type, target_name
+                    );
+                    let summary = cleanup.run().await;
+                    state.job_manager.cancel_job(&job_id_clone, summary).await;
+                }
+                Err(e) => {
+                    error!("{} panicked for {}: {}", operation_type, target_name, e);
+                    state
+                        .job_manager
+                        .fail_job(&job_id_clone, format!("Operation panicked: {}", e))
+                        .await;
+                }
             }
 
+            state.running_jobs.write().await.remove(&job_id_clone);
             state.finish_operation(&target_name).await;
         });
 
         Ok(job_id)
     }
+
+    /// Abort a running job. Child processes are killed as the operation task is
+    /// dropped; the job's cleanup then runs and the job is marked `Cancelled`.
+    pub async fn cancel_job(&self, job_id: &str) -> Result<(), String> {
+        let job = self
+            .job_manager
+            .get_job_status(job_id)
+            .await
+            .ok_or_else(|| format!("Job {} not found", job_id))?;
+
+        if !matches!(job.status, types::JobStatus::Running) {
+            return Err(format!(
+                "Job {} is not running (status: {:?})",
+                job_id, job.status
+            ));
+        }
+
+        let abort_handle = self
+            .running_jobs
+            .read()
+            .await
+            .get(job_id)
+            .cloned()
+            .ok_or_else(|| format!("Job {} has no running task to cancel", job_id))?;
+
+        abort_handle.abort();
+        warn!(
+            "Cancellation requested for job {} ({} on {})",
+            job_id, job.operation_type, job.target_name
+        );
+        Ok(())
+    }
+}
+
+/// Check the services touched by jobs that were cut off by the last restart,
+/// so the manager can see which nodes were left stopped.
+async fn report_interrupted_jobs(job_manager: &JobManager, interrupted: &[types::JobInfo]) {
+    for job in interrupted {
+        let Some(service_name) = job.service_name.as_deref() else {
+            continue;
+        };
+
+        let left_stopped = match services::systemctl::get_service_status(service_name).await {
+            Ok(status) => status != "active",
+            Err(e) => {
+                warn!(
+                    "Could not check service {} for interrupted job {}: {}",
+                    service_name, job.job_id, e
+                );
+                continue;
+            }
+        };
+
+        if left_stopped {
+            warn!(
+                "Interrupted job {} left service {} stopped",
+                job.job_id, service_name
+            );
+        }
+        job_manager
+            .record_service_state(&job.job_id, left_stopped)
+            .await;
+    }
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
     tracing_subscriber::fmt::init();
 
-    info!("Starting Blockchain Server Agent on 0.0.0.0:8745");
+    let cli = Cli::parse();
+    let config = AgentConfig::load(&cli)?;
+    let bind_address = config.socket_addr()?;
+    info!("Starting Blockchain Server Agent on {}", bind_address);
+
+    let api_keys = config.api_keys()?;
+    if api_keys.len() > 1 {
+        info!("Accepting requests signed with the secondary API key as well");
+    }
+
+    if config.allow_raw_commands {
+        warn!("Raw command execution enabled - /command/execute accepts arbitrary shell commands");
+    }
+    if let Some(max) = config.max_concurrent_operations {
+        info!("Running at most {} operations at once", max);
+    }
 
-    let api_key =
-        std::env::var("AGENT_API_KEY").unwrap_or_else(|_| "default-development-key".to_string());
+    let sandbox = PathSandbox::new(config.paths.clone());
+    for kind in [RootKind::Deploy, RootKind::Backup, RootKind::Log] {
+        if sandbox.is_restricted(kind) {
+            continue;
+        }
+        if config.paths.allow_unconfined_paths {
+            warn!(
+                "No {} roots configured and allow_unconfined_paths is set - {} paths in requests are not restricted",
+                kind, kind
+            );
+        } else {
+            warn!(
+                "No {} roots configured - requests with {} paths will be refused (set [paths] {}_roots)",
+                kind, kind, kind
+            );
+        }
+    }
 
-    if api_key == "default-development-key" {
-        warn!("Using default development API key - set AGENT_API_KEY environment variable for production");
+    let tls = config.tls.clone();
+    match &tls {
+        Some(tls) if tls.requires_client_cert() => {
+            info!("TLS enabled with client certificate verification (mutual TLS)")
+        }
+        Some(_) => info!("TLS enabled"),
+        None => warn!(
+            "TLS disabled - configure [tls] or AGENT_TLS_CERT and AGENT_TLS_KEY to serve HTTPS, API keys travel in cleartext"
+        ),
     }
 
-    let job_manager = JobManager::new();
-    let app_state = AppState {
-        api_key,
+    let journal_path = config.job_journal_path();
+    let job_manager = match JobManager::with_journal(&journal_path).await {
+        Ok((job_manager, interrupted)) => {
+            report_interrupted_jobs(&job_manager, &interrupted).await;
+            job_manager
+        }
+        Err(e) => {
+            error!(
+                "Failed to open job journal {}: {} - jobs will not survive a restart",
+                journal_path, e
+            );
+            JobManager::new()
+        }
+    };
+    let app_state = Arc::new(AppState {
+        request_verifier: Arc::new(RequestVerifier::new(api_keys)),
         busy_nodes: Arc::new(RwLock::new(HashMap::new())),
         job_manager: job_manager.clone(),
-    };
+        running_jobs: Arc::new(RwLock::new(HashMap::new())),
+        allow_raw_commands: config.allow_raw_commands,
+        max_concurrent_operations: config.max_concurrent_operations,
+        tls: tls.clone(),
+        sandbox,
+        metrics_token: config.metrics_token()?,
+        size_cache: Arc::new(SizeCache::new(std::time::Duration::from_secs(
+            config.telemetry_cache_seconds,
+        ))),
+    });
 
     // Spawn background cleanup task
     let cleanup_state = app_state.clone();
+    let cleanup_interval = tokio::time::Duration::from_secs(config.cleanup_interval_seconds);
+    let stuck_operation_hours = config.stuck_operation_hours;
+    let job_retention_hours = config.job_retention_hours;
     tokio::spawn(async move {
-        let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(3600));
+        let mut interval = tokio::time::interval(cleanup_interval);
         loop {
             interval.tick().await;
-            cleanup_state.cleanup_old_operations(24).await;
-            cleanup_state.job_manager.cleanup_old_jobs(48).await;
+            cleanup_state
+                .cleanup_old_operations(stuck_operation_hours)
+                .await;
+            cleanup_state
+                .job_manager
+                .cleanup_old_jobs(job_retention_hours)
+                .await;
         }
     });
 
     let app = Router::new()
-        // Command execution
+        // Raw command execution (disabled unless started with --allow-raw-commands)
         .route("/command/execute", post(handlers::execute_command))
+        // Path inspection
+        .route("/path/check", post(handlers::check_path))
+        // Host and node resources
+        .route("/telemetry", post(handlers::get_telemetry))
         // Service management
         .route("/service/status", post(handlers::get_service_status))
         .route("/service/start", post(handlers::start_service))
@@ -207,10 +410,25 @@ async fn main() -> Result<()> {
             "/logs/delete-all",
             post(handlers::delete_all_files_in_directory),
         )
+        .route("/logs/search", post(handlers::search_log))
+        .route("/logs/stream", get(handlers::stream_logs))
+        .route("/logs/archives", post(handlers::list_log_archives))
+        .route("/logs/archive", get(handlers::download_log_archive))
+        // Node config
+        .route("/config/patch", post(handlers::patch_config))
+        // Snapshot files
+        .route("/snapshot/list", post(handlers::list_snapshots))
+        .route("/snapshot/delete", post(handlers::delete_snapshot))
+        .route(
+            "/snapshot/archive/token",
+            post(handlers::issue_archive_download_token),
+        )
         // Async operations
         .route("/pruning/execute", post(handlers::execute_pruning_async))
         .route("/snapshot/create", post(handlers::create_snapshot_async))
         .route("/snapshot/restore", post(handlers::restore_snapshot_async))
+        .route("/snapshot/verify", post(handlers::verify_snapshot_async))
+        .route("/snapshot/pull", post(handlers::pull_snapshot_async))
         .route(
             "/snapshot/check-triggers",
             post(handlers::check_restore_triggers),
@@ -221,13 +439,39 @@ async fn main() -> Result<()> {
         )
         // Status and job management
         .route("/operation/status/{job_id}", get(handlers::get_job_status))
+        .route(
+            "/operation/interrupted",
+            get(handlers::get_interrupted_jobs),
+        )
+        .route("/operation/cancel/{job_id}", post(handlers::cancel_job))
         .route("/status/busy", post(handlers::get_busy_status))
         .route("/status/cleanup", post(handlers::cleanup_operations))
-        .with_state(Arc::new(app_state));
-
-    let listener = tokio::net::TcpListener::bind("0.0.0.0:8745").await?;
-    info!("Server agent listening on 0.0.0.0:8745");
+        .layer(axum::middleware::from_fn_with_state(
+            app_state.clone(),
+            middleware::verify_signature,
+        ))
+        // Prometheus cannot sign requests, so metrics are served outside the signing layer
+        .route("/metrics", get(handlers::get_metrics))
+        // Pulling agents hold a download token for one archive, not the signing key
+        .route(
+            "/snapshot/archive",
+            get(handlers::download_snapshot_archive),
+        )
+        .with_state(app_state);
 
-    axum::serve(listener, app).await?;
+    match tls {
+        Some(tls) => {
+            let rustls_config = RustlsConfig::from_config(Arc::new(tls.server_config()?));
+            info!("Server agent listening on https://{}", bind_address);
+            axum_server::bind_rustls(bind_address, rustls_config)
+                .serve(app.into_make_service())
+                .await?;
+        }
+        None => {
+            let listener = tokio::net::TcpListener::bind(bind_address).await?;
+            info!("Server agent listening on http://{}", bind_address);
+            axum::serve(listener, app).await?;
+        }
+    }
     Ok(())
 }
@@ -3,24 +3,84 @@
 //! Provides authentication and other cross-cutting concerns.
 
 use axum::{
-    extract::FromRequestParts,
+    body::Body,
+    extract::{FromRequestParts, Request, State},
     http::{request::Parts, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
 };
 use std::sync::Arc;
+use tracing::warn;
 
+use crate::services::signing::{NONCE_HEADER, SIGNATURE_HEADER, TIMESTAMP_HEADER};
 use crate::AppState;
 
-/// Extractor that validates the API key from the Authorization header.
-/// Use this instead of manually calling validate_api_key in each handler.
+/// Largest request body buffered for signature verification
+const MAX_SIGNED_BODY_BYTES: usize = 16 * 1024 * 1024;
+
+/// Marker inserted into a request's extensions once its signature has been verified
+#[derive(Clone, Copy)]
+struct SignatureVerified;
+
+/// Layer that verifies the HMAC signature of every request. The body has to be
+/// buffered because the signature covers its hash; it is handed on unchanged.
+/// Rejections are left to the `ApiKeyAuth` extractor.
+pub async fn verify_signature(
+    State(state): State<Arc<AppState>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let (mut parts, body) = request.into_parts();
+    let bytes = match axum::body::to_bytes(body, MAX_SIGNED_BODY_BYTES).await {
+        Ok(bytes) => bytes,
+        Err(_) => return StatusCode::PAYLOAD_TOO_LARGE.into_response(),
+    };
+
+    let path_and_query = parts
+        .uri
+        .path_and_query()
+        .map(|p| p.as_str())
+        .unwrap_or("/");
+    let header = |name: &str| parts.headers.get(name).and_then(|v| v.to_str().ok());
+    let result = state.request_verifier.verify(
+        parts.method.as_str(),
+        path_and_query,
+        (
+            header(TIMESTAMP_HEADER),
+            header(NONCE_HEADER),
+            header(SIGNATURE_HEADER),
+        ),
+        &bytes,
+        chrono::Utc::now().timestamp(),
+    );
+
+    match result {
+        Ok(()) => {
+            parts.extensions.insert(SignatureVerified);
+        }
+        Err(e) => warn!(
+            "Rejected signature on {} {}: {}",
+            parts.method,
+            parts.uri.path(),
+            e
+        ),
+    }
+
+    next.run(Request::from_parts(parts, Body::from(bytes)))
+        .await
+}
+
+/// Extractor that requires the request signature to have been verified by
+/// the `verify_signature` layer.
 ///
 /// # Example
 /// ```ignore
 /// async fn my_handler(
-///     _auth: ApiKeyAuth,  // This validates the API key
+///     _auth: ApiKeyAuth,  // This requires a valid signature
 ///     State(state): State<Arc<AppState>>,
 ///     Json(request): Json<MyRequest>,
 /// ) -> Result<ResponseJson<ApiResponse<()>>, StatusCode> {
-///     // Handler logic here - API key is already validated
+///     // Handler logic here - the request is already authenticated
 /// }
 /// ```
 pub struct ApiKeyAuth;
@@ -30,17 +90,11 @@ impl FromRequestParts<Arc<AppState>> for ApiKeyAuth {
 
     async fn from_request_parts(
         parts: &mut Parts,
-        state: &Arc<AppState>,
+        _state: &Arc<AppState>,
     ) -> Result<Self, Self::Rejection> {
-        let auth_header = parts
-            .headers
-            .get("authorization")
-            .and_then(|v| v.to_str().ok())
-            .and_then(|s| s.strip_prefix("Bearer "));
-
-        match auth_header {
-            Some(token) if token == state.api_key => Ok(ApiKeyAuth),
-            _ => Err(StatusCode::UNAUTHORIZED),
+        match parts.extensions.get::<SignatureVerified>() {
+            Some(_) => Ok(ApiKeyAuth),
+            None => Err(StatusCode::UNAUTHORIZED),
         }
     }
 }
@@ -0,0 +1,100 @@
+// File: agent/src/operations/cancel.rs
+use tracing::{info, warn};
+
+use crate::services::{filesystem, rollback, systemctl};
+
+/// Cleanup to run when a job is cancelled, chosen by the operation that started it
+#[derive(Debug, Clone)]
+pub enum CancelCleanup {
+    /// Read-only operations (verification) leave nothing to undo
+    Nothing,
+    /// Start the service again if the operation left it stopped (pruning, state sync)
+    RestartService { service_name: String },
+    /// Remove the half-written snapshot directory, then start the service again
+    RemovePartialSnapshot {
+        snapshot_path: String,
+        service_name: String,
+    },
+    /// Remove the `.tar.lz4.partial` file of an interrupted snapshot compression
+    RemovePartialArchive { archive_path: String },
+    /// Put back the node's previous data if the restore already moved it aside,
+    /// then start the service again
+    RollBackRestore {
+        deploy_path: String,
+        service_name: String,
+    },
+}
+
+impl CancelCleanup {
+    pub fn service_name(&self) -> Option<&str> {
+        match self {
+            CancelCleanup::Nothing | CancelCleanup::RemovePartialArchive { .. } => None,
+            CancelCleanup::RestartService { service_name }
+            | CancelCleanup::RemovePartialSnapshot { service_name, .. }
+            | CancelCleanup::RollBackRestore { service_name, .. } => Some(service_name),
+        }
+    }
+
+    /// Run the cleanup and return a summary of what was done. Individual step
+    /// failures are reported in the summary rather than aborting the cleanup.
+    pub async fn run(&self) -> String {
+        let mut steps = Vec::new();
+
+        match self {
+            CancelCleanup::Nothing => steps.push("nothing to clean up".to_string()),
+            CancelCleanup::RestartService { service_name } => {
+                steps.push(start_if_stopped(service_name).await);
+            }
+            CancelCleanup::RemovePartialSnapshot {
+                snapshot_path,
+                service_name,
+            } => {
+                match filesystem::remove_dir_all(snapshot_path).await {
+                    Ok(_) => steps.push(format!("removed partial snapshot {}", snapshot_path)),
+                    Err(e) => steps.push(format!(
+                        "failed to remove partial snapshot {}: {}",
+                        snapshot_path, e
+                    )),
+                }
+                steps.push(start_if_stopped(service_name).await);
+            }
+            CancelCleanup::RemovePartialArchive { archive_path } => {
+                match filesystem::remove_file_if_exists(archive_path).await {
+                    Ok(_) => steps.push(format!("removed partial archive {}", archive_path)),
+                    Err(e) => steps.push(format!(
+                        "failed to remove partial archive {}: {}",
+                        archive_path, e
+                    )),
+                }
+            }
+            CancelCleanup::RollBackRestore {
+                deploy_path,
+                service_name,
+            } => {
+                if rollback::has_previous_data(deploy_path).await {
+                    steps.push(super::restore::roll_back(deploy_path, service_name).await);
+                } else {
+                    // Restore was cancelled before it touched the data directory
+                    steps.push(start_if_stopped(service_name).await);
+                }
+            }
+        }
+
+        let summary = steps.join("; ");
+        info!("Cancellation cleanup finished: {}", summary);
+        summary
+    }
+}
+
+async fn start_if_stopped(service_name: &str) -> String {
+    match systemctl::get_service_status(service_name).await {
+        Ok(status) if status == "active" => format!("service {} already running", service_name),
+        _ => match systemctl::start_service(service_name).await {
+            Ok(_) => format!("started service {}", service_name),
+            Err(e) => {
+                warn!("Failed to start {} after cancellation: {}", service_name, e);
+                format!("failed to start service {}: {}", service_name, e)
+            }
+        },
+    }
+}
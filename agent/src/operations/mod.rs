@@ -1,4 +1,5 @@
 // File: agent/src/operations/mod.rs
+pub mod cancel;
 pub mod pruning;
 pub mod restore;
 pub mod snapshots;
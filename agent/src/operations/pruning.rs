@@ -2,10 +2,14 @@
 use anyhow::Result;
 use tracing::info;
 
-use crate::services::{commands, logs, systemctl};
+use crate::services::job_manager::ProgressReporter;
+use crate::services::{commands, disk, filesystem, logs, systemctl};
 use crate::types::PruningRequest;
 
-pub async fn execute_full_pruning_sequence(request: &PruningRequest) -> Result<String> {
+pub async fn execute_full_pruning_sequence(
+    request: &PruningRequest,
+    progress: &ProgressReporter,
+) -> Result<String> {
     info!(
         "Starting FULL pruning sequence for service: {}",
         request.service_name
@@ -17,16 +21,34 @@ pub async fn execute_full_pruning_sequence(request: &PruningRequest) -> Result<S
 
     let mut operation_log = Vec::new();
 
+    // Step 0: Leave the pruner room to compact the database before stopping the node
+    progress.step("Checking disk space").await;
+    let data_size = filesystem::dir_size(&format!("{}/data", request.deploy_path)).await?;
+    disk::ensure_space(
+        &request.deploy_path,
+        data_size / 100 * disk::PRUNING_HEADROOM_PERCENT,
+        "pruning",
+    )?;
+    operation_log.push("✓ Checked free disk space".to_string());
+
     // Step 1: Stop the node service
     info!("Step 1: Stopping service {}", request.service_name);
+    progress.step("Stopping service").await;
     systemctl::stop_service(&request.service_name).await?;
     operation_log.push(format!("✓ Stopped service: {}", request.service_name));
 
     // Step 2: Truncate logs (if configured)
     if let Some(log_path) = &request.log_path {
-        info!("Step 2: Truncating logs at: {}", log_path);
-        logs::truncate_log_path(log_path).await?;
-        operation_log.push(format!("✓ Truncated logs: {}", log_path));
+        info!("Step 2: Clearing logs at: {}", log_path);
+        progress
+            .step(logs::clear_step(request.log_rotation.as_ref()))
+            .await;
+        logs::clear_log_path(log_path, request.log_rotation.as_ref()).await?;
+        let cleared = match request.log_rotation {
+            Some(_) => "Rotated",
+            None => "Truncated",
+        };
+        operation_log.push(format!("✓ {} logs: {}", cleared, log_path));
     } else {
         info!("Step 2: No log path configured, skipping log truncation");
         operation_log.push("• Skipped log truncation (not configured)".to_string());
@@ -34,6 +56,7 @@ pub async fn execute_full_pruning_sequence(request: &PruningRequest) -> Result<S
 
     // Step 3: Execute pruning
     info!("Step 3: Executing cosmos-pruner");
+    progress.step("Running cosmos-pruner").await;
     let pruning_output = commands::execute_cosmos_pruner(
         &request.deploy_path,
         request.keep_blocks,
@@ -44,10 +67,12 @@ pub async fn execute_full_pruning_sequence(request: &PruningRequest) -> Result<S
 
     // Step 4: Start the node service
     info!("Step 4: Starting service {}", request.service_name);
+    progress.step("Starting service").await;
     systemctl::start_service(&request.service_name).await?;
     operation_log.push(format!("✓ Started service: {}", request.service_name));
 
     // Step 5: Verify service is running
+    progress.step("Verifying service").await;
     let status = systemctl::get_service_status(&request.service_name).await?;
     if status != "active" {
         return Err(anyhow::anyhow!(
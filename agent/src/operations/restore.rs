@@ -1,74 +1,90 @@
 // File: agent/src/operations/restore.rs
 use anyhow::Result;
-use tracing::info;
+use std::time::Duration;
+use tracing::{error, info, warn};
 
-use crate::services::{commands, logs, systemctl};
-use crate::types::RestoreRequest;
+use crate::services::job_manager::ProgressReporter;
+use crate::services::{archive, disk, filesystem, logs, manifest, node_rpc, rollback, systemctl};
+use crate::types::{RestoreRequest, SnapshotVerifyReport};
 
-pub async fn execute_full_restore_sequence(request: &RestoreRequest) -> Result<String> {
+/// How long a restored node may take to produce its first new blocks before
+/// the restore is rolled back
+const BLOCK_WAIT_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+const BLOCK_POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+pub async fn execute_full_restore_sequence(
+    request: &RestoreRequest,
+    progress: &ProgressReporter,
+) -> Result<String> {
     info!(
         "Starting snapshot restore for node: {} from network snapshot",
         request.node_name
     );
 
-    // Step 1: Verify snapshot directory exists
-    let dir_check_command = format!("test -d '{}'", request.snapshot_dir);
-    commands::execute_shell_command(&dir_check_command)
-        .await
-        .map_err(|_| {
-            anyhow::anyhow!(
+    // Step 1: Verify the snapshot source exists
+    progress.step("Verifying snapshot").await;
+    let mut warnings = Vec::new();
+    let data_source = format!("{}/data", request.snapshot_dir);
+    let wasm_source = format!("{}/wasm", request.snapshot_dir);
+
+    if let Some(archive_path) = &request.archive_path {
+        if !filesystem::is_file(archive_path).await {
+            return Err(anyhow::anyhow!(
+                "Snapshot archive does not exist: {}",
+                archive_path
+            ));
+        }
+        // The archive's content can only be checked while it is extracted (step 8)
+        info!("✓ Restoring from snapshot archive {}", archive_path);
+
+        progress.step("Checking disk space").await;
+        let archive_size = tokio::fs::metadata(archive_path).await?.len();
+        check_restore_space(
+            request,
+            archive_size.saturating_mul(disk::ARCHIVE_EXPANSION_ESTIMATE),
+        )
+        .await?;
+    } else {
+        if !filesystem::is_dir(&request.snapshot_dir).await {
+            return Err(anyhow::anyhow!(
                 "Snapshot directory does not exist: {}",
                 request.snapshot_dir
-            )
-        })?;
-
-    // Step 2: MANDATORY - Verify both data and wasm directories exist in snapshot
-    let data_check_command = format!("test -d '{}/data'", request.snapshot_dir);
-    commands::execute_shell_command(&data_check_command)
-        .await
-        .map_err(|_| {
-            anyhow::anyhow!(
-                "CRITICAL: data directory missing from snapshot: {}/data",
-                request.snapshot_dir
-            )
-        })?;
-
-    let wasm_check_command = format!("test -d '{}/wasm'", request.snapshot_dir);
-    commands::execute_shell_command(&wasm_check_command)
-        .await
-        .map_err(|_| {
-            anyhow::anyhow!(
-                "CRITICAL: wasm directory missing from snapshot: {}/wasm",
-                request.snapshot_dir
-            )
-        })?;
-
-    info!("✓ Verified both data and wasm directories exist in snapshot");
-
-    // Step 3: Get directory sizes for logging
-    let data_size_command = format!("du -sb '{}/data' | cut -f1", request.snapshot_dir);
-    let data_size = commands::execute_shell_command(&data_size_command)
-        .await
-        .unwrap_or_default()
-        .trim()
-        .parse::<u64>()
-        .unwrap_or(0);
-
-    let wasm_size_command = format!("du -sb '{}/wasm' | cut -f1", request.snapshot_dir);
-    let wasm_size = commands::execute_shell_command(&wasm_size_command)
-        .await
-        .unwrap_or_default()
-        .trim()
-        .parse::<u64>()
-        .unwrap_or(0);
+            ));
+        }
 
-    info!(
-        "Snapshot data size: {:.1} MB, wasm size: {:.1} MB",
-        data_size as f64 / 1024.0 / 1024.0,
-        wasm_size as f64 / 1024.0 / 1024.0
-    );
+        // Step 2: MANDATORY - Verify both data and wasm directories exist in snapshot
+        for source in [&data_source, &wasm_source] {
+            if !filesystem::is_dir(source).await {
+                return Err(anyhow::anyhow!(
+                    "CRITICAL: directory missing from snapshot: {}",
+                    source
+                ));
+            }
+        }
+
+        info!("✓ Verified both data and wasm directories exist in snapshot");
+
+        // Step 2b: Check file checksums against the snapshot manifest before touching the node
+        progress.step("Verifying snapshot checksums").await;
+        warnings.extend(verify_snapshot_integrity(request, progress).await?);
+
+        // Step 3: Get directory sizes for logging and progress
+        let data_size = filesystem::dir_size(&data_source).await.unwrap_or(0);
+        let wasm_size = filesystem::dir_size(&wasm_source).await.unwrap_or(0);
+
+        progress.bytes(0, Some(data_size + wasm_size)).await;
+        info!(
+            "Snapshot data size: {:.1} MB, wasm size: {:.1} MB",
+            data_size as f64 / 1024.0 / 1024.0,
+            wasm_size as f64 / 1024.0 / 1024.0
+        );
+
+        progress.step("Checking disk space").await;
+        check_restore_space(request, data_size + wasm_size).await?;
+    }
 
     // Step 4: Stop the node service
+    progress.step("Stopping service").await;
     systemctl::stop_service(&request.service_name).await?;
     info!("✓ Node service stopped");
 
@@ -77,96 +93,157 @@ pub async fn execute_full_restore_sequence(request: &RestoreRequest) -> Result<S
     let validator_backup_path = format!("{}/priv_validator_state_backup.json", request.deploy_path);
 
     info!("Backing up current validator state to preserve individual signing information");
-    commands::backup_current_validator_state(&current_validator_path, &validator_backup_path)
-        .await?;
-    info!("✓ Current validator state backed up");
+    progress.step("Backing up validator state").await;
+    if filesystem::copy_file_if_exists(&current_validator_path, &validator_backup_path).await? {
+        info!("✓ Current validator state backed up");
+    } else {
+        info!("No current validator state found - will create default after restore");
+    }
 
     // Step 6: Truncate logs (if configured)
     if let Some(log_path) = &request.log_path {
-        logs::truncate_log_path(log_path).await?;
-        info!("✓ Logs truncated");
+        progress
+            .step(logs::clear_step(request.log_rotation.as_ref()))
+            .await;
+        logs::clear_log_path(log_path, request.log_rotation.as_ref()).await?;
+        info!("✓ Logs cleared");
+    }
+
+    // Steps 7-14 work on a fresh data directory while the previous one is kept
+    // aside; any failure up to the health check puts the previous one back
+    match install_snapshot(request, progress).await {
+        Ok(warning) => warnings.extend(warning),
+        Err(e) => {
+            error!(
+                "Restore of {} failed, rolling back to its previous data: {}",
+                request.node_name, e
+            );
+            progress.step("Rolling back").await;
+            let summary = roll_back(&request.deploy_path, &request.service_name).await;
+            return Err(anyhow::anyhow!(
+                "{}; rolled back to the previous data: {}",
+                e,
+                summary
+            ));
+        }
     }
 
-    // Step 7: Delete existing data and wasm directories
+    // Step 15: The restored node is healthy, so its previous data can go
+    progress.step("Removing previous data").await;
+    rollback::discard_previous_data(&request.deploy_path).await?;
+    info!("✓ Previous data and wasm directories removed");
+
+    info!("Network snapshot restore completed successfully for node: {} (individual validator state preserved by overwriting snapshot's validator state)", request.node_name);
+
+    let mut output = format!("Network snapshot restore completed for {} (individual validator state preserved, snapshot's validator state overwritten)", request.node_name);
+    for warning in warnings {
+        output.push_str(&format!("; warning: {}", warning));
+    }
+    Ok(output)
+}
+
+/// Swap the snapshot in for the node's data and bring the node back up.
+/// Returns a warning when an archive without a manifest was restored.
+async fn install_snapshot(
+    request: &RestoreRequest,
+    progress: &ProgressReporter,
+) -> Result<Option<String>> {
+    let current_validator_path = format!("{}/data/priv_validator_state.json", request.deploy_path);
+    let validator_backup_path = format!("{}/priv_validator_state_backup.json", request.deploy_path);
     let data_dir = format!("{}/data", request.deploy_path);
     let wasm_dir = format!("{}/wasm", request.deploy_path);
 
-    let data_exists_cmd = format!("test -d '{}'", data_dir);
-    if commands::execute_shell_command(&data_exists_cmd)
-        .await
-        .is_ok()
-    {
-        commands::delete_directory(&data_dir).await?;
-        info!("✓ Existing data directory deleted");
-    }
+    let mut warning = None;
 
-    let wasm_exists_cmd = format!("test -d '{}'", wasm_dir);
-    if commands::execute_shell_command(&wasm_exists_cmd)
-        .await
-        .is_ok()
-    {
-        commands::delete_directory(&wasm_dir).await?;
-        info!("✓ Existing wasm directory deleted");
-    }
+    // Step 7: Move existing data and wasm directories aside for rollback
+    progress.step("Moving existing data aside").await;
+    rollback::set_aside_current_data(&request.deploy_path).await?;
+    info!("✓ Existing data and wasm directories moved aside");
 
     // Step 8: MANDATORY - Copy BOTH data and wasm directories from network snapshot (includes snapshot's validator state)
-    info!("Copying network snapshot data and wasm directories (including snapshot's validator state)...");
-    commands::copy_snapshot_directories_mandatory(&request.snapshot_dir, &request.deploy_path)
-        .await?;
-    info!("✓ Both data and wasm directories copied successfully from snapshot");
+    if let Some(archive_path) = &request.archive_path {
+        info!("Extracting network snapshot archive (including snapshot's validator state)...");
+        progress.step("Extracting snapshot archive").await;
+        let report =
+            archive::extract_snapshot_archive(archive_path, &request.deploy_path, progress)
+                .await
+                .map_err(|e| anyhow::anyhow!("CRITICAL: Failed to extract snapshot: {}", e))?;
+        info!("✓ Snapshot archive extracted");
+
+        progress.step("Verifying snapshot checksums").await;
+        match &report.manifest {
+            None => warning = Some(warn_unverified(archive_path, progress).await),
+            Some(snapshot_manifest) => {
+                let verify = manifest::compare_with_manifest(snapshot_manifest, &report.files);
+                if verify.verified {
+                    info!(
+                        "✓ Snapshot verified: {} extracted files match the manifest",
+                        report.files_extracted
+                    );
+                } else {
+                    // Archives can only be checked once extracted, so a
+                    // refused archive is undone by the rollback
+                    refuse_unless_forced(
+                        request,
+                        describe_failed_verification(archive_path, &verify),
+                    )?;
+                }
+            }
+        }
+    } else {
+        info!("Copying network snapshot data and wasm directories (including snapshot's validator state)...");
+        progress.step("Copying snapshot data").await;
+        let pairs = [
+            (format!("{}/data", request.snapshot_dir), data_dir.clone()),
+            (format!("{}/wasm", request.snapshot_dir), wasm_dir.clone()),
+        ];
+        filesystem::copy_trees(&pairs, false, progress)
+            .await?
+            .into_result()
+            .map_err(|e| anyhow::anyhow!("CRITICAL: Failed to copy snapshot data: {}", e))?;
+        info!("✓ Both data and wasm directories copied successfully from snapshot");
+    }
 
     // Step 9: MANDATORY - Verify both directories were copied successfully
-    let verify_data_cmd = format!("test -d '{}/data'", request.deploy_path);
-    commands::execute_shell_command(&verify_data_cmd)
-        .await
-        .map_err(|_| {
-            anyhow::anyhow!(
-                "CRITICAL: data directory not found after copy to {}/data",
-                request.deploy_path
-            )
-        })?;
-
-    let verify_wasm_cmd = format!("test -d '{}/wasm'", request.deploy_path);
-    commands::execute_shell_command(&verify_wasm_cmd)
-        .await
-        .map_err(|_| {
-            anyhow::anyhow!(
-                "CRITICAL: wasm directory not found after copy to {}/wasm",
-                request.deploy_path
-            )
-        })?;
+    progress.step("Verifying copied data").await;
+    for target in [&data_dir, &wasm_dir] {
+        if !filesystem::is_dir(target).await {
+            return Err(anyhow::anyhow!(
+                "CRITICAL: directory not found after copy: {}",
+                target
+            ));
+        }
+    }
 
     info!("✓ Verified both data and wasm directories exist after copy");
 
     // Step 10: Overwrite snapshot's validator state with CURRENT node's validator state (preserving individual signing state)
     info!("Overwriting snapshot's validator state with current node's individual validator state");
-    commands::restore_current_validator_state(&validator_backup_path, &current_validator_path)
-        .await?;
-    info!("✓ Current validator state restored, overwriting snapshot's validator state to prevent double-signing");
-
-    // Step 11: Set proper ownership/permissions
-    let chown_cmd = format!(
-        "chown -R $(stat -c '%U:%G' '{}') '{}/data'",
-        request.deploy_path, request.deploy_path
-    );
-    commands::execute_shell_command(&chown_cmd).await?;
+    progress.step("Restoring validator state").await;
+    if filesystem::copy_file_if_exists(&validator_backup_path, &current_validator_path).await? {
+        info!("✓ Current validator state restored, overwriting snapshot's validator state to prevent double-signing");
+    } else {
+        warn!("No validator state backup found - node will start with default validator state");
+    }
 
-    let wasm_chown_cmd = format!(
-        "chown -R $(stat -c '%U:%G' '{}') '{}/wasm'",
-        request.deploy_path, request.deploy_path
-    );
-    commands::execute_shell_command(&wasm_chown_cmd).await?;
+    // Step 11: Set proper ownership/permissions to match the deploy directory
+    progress.step("Setting ownership").await;
+    let (uid, gid) = filesystem::owner(&request.deploy_path).await?;
+    filesystem::chown_tree(&data_dir, uid, gid).await?;
+    filesystem::chown_tree(&wasm_dir, uid, gid).await?;
     info!("✓ Permissions set for both data and wasm directories");
 
     // Step 12: Clean up validator backup
-    commands::remove_file_if_exists(&validator_backup_path).await?;
+    filesystem::remove_file_if_exists(&validator_backup_path).await?;
     info!("✓ Validator backup file cleaned up");
 
     // Step 13: Start the node service
+    progress.step("Starting service").await;
     systemctl::start_service(&request.service_name).await?;
     info!("✓ Node service started");
 
     // Step 14: Verify service is running
+    progress.step("Verifying service").await;
     let status = systemctl::get_service_status(&request.service_name).await?;
     if status != "active" {
         return Err(anyhow::anyhow!(
@@ -177,7 +254,134 @@ pub async fn execute_full_restore_sequence(request: &RestoreRequest) -> Result<S
     }
     info!("✓ Service verified as active");
 
-    info!("Network snapshot restore completed successfully for node: {} (individual validator state preserved by overwriting snapshot's validator state)", request.node_name);
+    // Step 14b: Keep the previous data until the node makes progress on the restored data
+    if let Some(rpc_url) = &request.rpc_url {
+        progress
+            .step(&format!(
+                "Waiting for {} new blocks",
+                request.rollback_after_blocks
+            ))
+            .await;
+        node_rpc::wait_for_new_blocks(
+            rpc_url,
+            request.rollback_after_blocks,
+            BLOCK_WAIT_TIMEOUT,
+            BLOCK_POLL_INTERVAL,
+        )
+        .await?;
+    }
+
+    Ok(warning)
+}
+
+/// Stop the node and put its previous data back, then start it again. Used
+/// when a restore fails and when a restore job is cancelled. Returns a
+/// summary of what was done.
+pub async fn roll_back(deploy_path: &str, service_name: &str) -> String {
+    if let Err(e) = systemctl::stop_service(service_name).await {
+        warn!("Not rolling back {}: {}", deploy_path, e);
+        return format!(
+            "could not stop service {}, previous data left at {}/*{}: {}",
+            service_name,
+            deploy_path,
+            rollback::ROLLBACK_SUFFIX,
+            e
+        );
+    }
+
+    match rollback::restore_previous_data(deploy_path).await {
+        Ok(summary) => match systemctl::start_service(service_name).await {
+            Ok(_) => format!("{}; started service {}", summary, service_name),
+            Err(e) => format!(
+                "{}; failed to start service {}: {}",
+                summary, service_name, e
+            ),
+        },
+        Err(e) => {
+            warn!("Rollback of {} failed: {}", deploy_path, e);
+            format!(
+                "rollback failed, service {} left stopped: {}",
+                service_name, e
+            )
+        }
+    }
+}
+
+/// Check the node's filesystem can take `incoming` bytes of snapshot data.
+/// The current data and wasm stay on disk as the rollback copy until the
+/// restored node is healthy, so they free nothing.
+async fn check_restore_space(request: &RestoreRequest, incoming: u64) -> Result<()> {
+    disk::ensure_space(&request.deploy_path, incoming, "restore")
+}
 
-    Ok(format!("Network snapshot restore completed for {} (individual validator state preserved, snapshot's validator state overwritten)", request.node_name))
+/// Refuse snapshots with files that do not match their manifest, unless
+/// forced. Snapshots taken before manifests were written have none; they are
+/// restored unverified and the returned warning says so.
+pub async fn verify_snapshot_integrity(
+    request: &RestoreRequest,
+    progress: &ProgressReporter,
+) -> Result<Option<String>> {
+    if manifest::read_manifest(&request.snapshot_dir)
+        .await?
+        .is_none()
+    {
+        return Ok(Some(warn_unverified(&request.snapshot_dir, progress).await));
+    }
+
+    let report = manifest::verify_snapshot(&request.snapshot_dir, progress).await?;
+    if report.verified {
+        info!(
+            "✓ Snapshot verified: {} files match the manifest",
+            report.files_checked
+        );
+        return Ok(None);
+    }
+    refuse_unless_forced(
+        request,
+        describe_failed_verification(&request.snapshot_dir, &report),
+    )?;
+    Ok(None)
+}
+
+/// Record in the job's steps that a snapshot without a manifest is restored unverified
+async fn warn_unverified(snapshot: &str, progress: &ProgressReporter) -> String {
+    let warning = format!(
+        "snapshot {} has no manifest, checksums not verified",
+        snapshot
+    );
+    warn!("{}", warning);
+    progress
+        .step("Snapshot has no manifest, checksums not verified")
+        .await;
+    warning
+}
+
+fn describe_failed_verification(snapshot: &str, report: &SnapshotVerifyReport) -> String {
+    format!(
+        "Snapshot {} failed verification: {} mismatched, {} missing, {} unexpected files (first: {})",
+        snapshot,
+        report.mismatched.len(),
+        report.missing.len(),
+        report.unexpected.len(),
+        report
+            .mismatched
+            .iter()
+            .chain(&report.missing)
+            .chain(&report.unexpected)
+            .next()
+            .map(String::as_str)
+            .unwrap_or("-")
+    )
+}
+
+fn refuse_unless_forced(request: &RestoreRequest, problem: String) -> Result<()> {
+    if request.force {
+        warn!("{} - restoring anyway because force was requested", problem);
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!(
+            "{}; restore with force to proceed anyway",
+            problem
+        ))
+    }
 }
@@ -2,10 +2,14 @@
 use anyhow::Result;
 use tracing::info;
 
-use crate::services::{commands, logs, systemctl};
+use crate::services::job_manager::ProgressReporter;
+use crate::services::{disk, filesystem, logs, manifest, systemctl};
 use crate::types::{SnapshotInfo, SnapshotRequest};
 
-pub async fn execute_full_snapshot_sequence(request: &SnapshotRequest) -> Result<SnapshotInfo> {
+pub async fn execute_full_snapshot_sequence(
+    request: &SnapshotRequest,
+    progress: &ProgressReporter,
+) -> Result<SnapshotInfo> {
     info!(
         "Starting snapshot creation: {} (from node: {})",
         request.snapshot_name, request.node_name
@@ -16,86 +20,108 @@ pub async fn execute_full_snapshot_sequence(request: &SnapshotRequest) -> Result
     let snapshot_path = format!("{}/{}", request.backup_path, snapshot_dirname);
 
     // Step 1: Verify source directories exist BEFORE starting snapshot
-    let data_exists_cmd = format!("test -d '{}/data'", request.deploy_path);
-    commands::execute_shell_command(&data_exists_cmd)
-        .await
-        .map_err(|_| {
-            anyhow::anyhow!(
-                "CRITICAL: Source data directory missing: {}/data",
-                request.deploy_path
-            )
-        })?;
-
-    let wasm_exists_cmd = format!("test -d '{}/wasm'", request.deploy_path);
-    commands::execute_shell_command(&wasm_exists_cmd)
-        .await
-        .map_err(|_| {
-            anyhow::anyhow!(
-                "CRITICAL: Source wasm directory missing: {}/wasm",
-                request.deploy_path
-            )
-        })?;
+    progress.step("Verifying source directories").await;
+    for dir in ["data", "wasm"] {
+        let source = format!("{}/{}", request.deploy_path, dir);
+        if !filesystem::is_dir(&source).await {
+            return Err(anyhow::anyhow!(
+                "CRITICAL: Source {} directory missing: {}",
+                dir,
+                source
+            ));
+        }
+    }
 
     info!("✓ Verified both source data and wasm directories exist");
 
+    // Step 1b: Make sure the backup filesystem can take the copy and its archive
+    // while the node is still running
+    progress.step("Checking disk space").await;
+    let pairs: Vec<(String, String)> = ["data", "wasm"]
+        .iter()
+        .map(|dir| {
+            (
+                format!("{}/{}", request.deploy_path, dir),
+                format!("{}/{}", snapshot_path, dir),
+            )
+        })
+        .collect();
+    let mut source_size = 0;
+    for (source, _) in &pairs {
+        source_size += filesystem::dir_size(source).await?;
+    }
+    disk::ensure_space(
+        &request.backup_path,
+        disk::with_headroom(source_size, disk::ARCHIVE_HEADROOM_PERCENT),
+        "snapshot",
+    )?;
+
     // Step 2: Create backup directory and snapshot directory
-    commands::create_directory(&request.backup_path).await?;
-    commands::create_directory(&snapshot_path).await?;
+    progress.step("Creating snapshot directory").await;
+    filesystem::create_dir_all(&snapshot_path).await?;
     info!("✓ Snapshot directories created");
 
     // Step 3: Stop the node service
+    progress.step("Stopping service").await;
     systemctl::stop_service(&request.service_name).await?;
     info!("✓ Node service stopped");
 
     // Step 4: Truncate logs (if configured)
     if let Some(log_path) = &request.log_path {
-        logs::truncate_log_path(log_path).await?;
-        info!("✓ Logs truncated");
+        progress
+            .step(logs::clear_step(request.log_rotation.as_ref()))
+            .await;
+        logs::clear_log_path(log_path, request.log_rotation.as_ref()).await?;
+        info!("✓ Logs cleared");
     }
 
     // Step 5: MANDATORY - Copy BOTH data and wasm directories to snapshot directory (INCLUDING validator state)
     info!("Copying BOTH blockchain data and wasm directories to snapshot (INCLUDING validator state)...");
-    commands::copy_directories_to_snapshot_mandatory(
-        &request.deploy_path,
-        &snapshot_path,
-        &["data", "wasm"],
-    )
-    .await?;
+    progress.step("Copying data and wasm").await;
+    progress.bytes(0, Some(source_size)).await;
+    let copy_report = filesystem::copy_trees(&pairs, true, progress)
+        .await?
+        .into_result()
+        .map_err(|e| anyhow::anyhow!("CRITICAL: Failed to copy snapshot data: {}", e))?;
     info!("✓ Both data and wasm directories copied to snapshot with validator state included");
 
     // Step 6: MANDATORY - Verify snapshot contains both directories
-    let snapshot_data_check = format!("test -d '{}/data'", snapshot_path);
-    commands::execute_shell_command(&snapshot_data_check)
-        .await
-        .map_err(|_| {
-            anyhow::anyhow!(
-                "CRITICAL: data directory missing from snapshot: {}/data",
-                snapshot_path
-            )
-        })?;
-
-    let snapshot_wasm_check = format!("test -d '{}/wasm'", snapshot_path);
-    commands::execute_shell_command(&snapshot_wasm_check)
-        .await
-        .map_err(|_| {
-            anyhow::anyhow!(
-                "CRITICAL: wasm directory missing from snapshot: {}/wasm",
-                snapshot_path
-            )
-        })?;
+    progress.step("Verifying snapshot").await;
+    for (_, target) in &pairs {
+        if !filesystem::is_dir(target).await {
+            return Err(anyhow::anyhow!(
+                "CRITICAL: directory missing from snapshot: {}",
+                target
+            ));
+        }
+    }
 
     info!("✓ Verified both data and wasm directories exist in snapshot");
 
+    // Record sizes and checksums computed during the copy so restores can verify the snapshot
+    progress.step("Writing manifest").await;
+    let snapshot_manifest = manifest::build_manifest(
+        &snapshot_path,
+        &snapshot_dirname,
+        &request.node_name,
+        &copy_report.files,
+    )?;
+    manifest::write_manifest(&snapshot_path, &snapshot_manifest).await?;
+    info!(
+        "✓ Manifest written with {} file checksums",
+        snapshot_manifest.files.len()
+    );
+
     // Step 7: Verify validator state is included in snapshot
     let validator_in_snapshot = format!("{}/data/priv_validator_state.json", snapshot_path);
-    let validator_check_cmd = format!("test -f '{}'", validator_in_snapshot);
-    match commands::execute_shell_command(&validator_check_cmd).await {
-        Ok(_) => info!("✓ Validator state included in snapshot for external system compatibility"),
-        Err(_) => info!("! No validator state found in snapshot (node may not be a validator)"),
+    if filesystem::is_file(&validator_in_snapshot).await {
+        info!("✓ Validator state included in snapshot for external system compatibility");
+    } else {
+        info!("! No validator state found in snapshot (node may not be a validator)");
     }
 
     // Step 8: Get directory size and verify snapshot
-    let size_bytes = commands::get_directory_size(&snapshot_path).await?;
+    let size_bytes = filesystem::dir_size(&snapshot_path).await?;
     if size_bytes < 1024 {
         return Err(anyhow::anyhow!(
             "Snapshot directory is too small ({} bytes), likely empty or incomplete",
@@ -108,10 +134,12 @@ pub async fn execute_full_snapshot_sequence(request: &SnapshotRequest) -> Result
     );
 
     // Step 9: Start the node service
+    progress.step("Starting service").await;
     systemctl::start_service(&request.service_name).await?;
     info!("✓ Node service started");
 
     // Step 10: Verify service is running
+    progress.step("Verifying service").await;
     let status = systemctl::get_service_status(&request.service_name).await?;
     if status != "active" {
         return Err(anyhow::anyhow!(
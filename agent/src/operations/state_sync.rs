@@ -2,10 +2,14 @@
 use anyhow::Result;
 use tracing::info;
 
-use crate::services::{commands, config_editor, logs, systemctl};
+use crate::services::job_manager::ProgressReporter;
+use crate::services::{commands, config_editor, filesystem, logs, systemctl};
 use crate::types::StateSyncRequest;
 
-pub async fn execute_state_sync_sequence(request: &StateSyncRequest) -> Result<String> {
+pub async fn execute_state_sync_sequence(
+    request: &StateSyncRequest,
+    progress: &ProgressReporter,
+) -> Result<String> {
     info!(
         "🔄 Starting state sync sequence for service: {}",
         request.service_name
@@ -20,14 +24,22 @@ pub async fn execute_state_sync_sequence(request: &StateSyncRequest) -> Result<S
 
     // Step 1: Stop the node service - FAIL FAST
     info!("Step 1: Stopping service {}", request.service_name);
+    progress.step("Stopping service").await;
     systemctl::stop_service(&request.service_name).await?;
     operation_log.push(format!("✓ Stopped service: {}", request.service_name));
 
     // Step 2: Truncate logs (if configured) - FAIL FAST
     if let Some(log_path) = &request.log_path {
-        info!("Step 2: Truncating logs at: {}", log_path);
-        logs::truncate_log_path(log_path).await?;
-        operation_log.push(format!("✓ Truncated logs: {}", log_path));
+        info!("Step 2: Clearing logs at: {}", log_path);
+        progress
+            .step(logs::clear_step(request.log_rotation.as_ref()))
+            .await;
+        logs::clear_log_path(log_path, request.log_rotation.as_ref()).await?;
+        let cleared = match request.log_rotation {
+            Some(_) => "Rotated",
+            None => "Truncated",
+        };
+        operation_log.push(format!("✓ {} logs: {}", cleared, log_path));
     } else {
         info!("Step 2: No log path configured, skipping log truncation");
         operation_log.push("• Skipped log truncation (not configured)".to_string());
@@ -35,7 +47,8 @@ pub async fn execute_state_sync_sequence(request: &StateSyncRequest) -> Result<S
 
     // Step 3: Update config.toml with state sync parameters - FAIL FAST
     info!("Step 3: Updating config.toml with state sync parameters");
-    config_editor::enable_state_sync(
+    progress.step("Enabling state sync in config").await;
+    let patch = config_editor::enable_state_sync(
         &request.config_path,
         &request.rpc_servers,
         request.trust_height,
@@ -43,9 +56,13 @@ pub async fn execute_state_sync_sequence(request: &StateSyncRequest) -> Result<S
     )
     .await?;
     operation_log.push("✓ Updated config.toml with state sync parameters".to_string());
+    if let Some(backup_path) = &patch.backup_path {
+        operation_log.push(format!("  Original config saved to: {}", backup_path));
+    }
 
     // Step 4: Execute unsafe-reset-all - FAIL FAST
     info!("Step 4: Executing unsafe-reset-all");
+    progress.step("Resetting chain state").await;
     let reset_cmd = format!(
         "{} tendermint unsafe-reset-all --home {} --keep-addr-book",
         request.daemon_binary, request.home_dir
@@ -55,16 +72,10 @@ pub async fn execute_state_sync_sequence(request: &StateSyncRequest) -> Result<S
 
     // Step 5: Clean WASM cache (Strategy A: preserve blobs, delete cache only) - FAIL FAST
     info!("Step 5: Cleaning WASM cache");
+    progress.step("Cleaning WASM cache").await;
     let wasm_cache = format!("{}/wasm/cache", request.home_dir);
 
-    // Check if wasm/cache exists before trying to delete
-    let cache_exists_cmd = format!("test -d '{}'", wasm_cache);
-    if commands::execute_shell_command(&cache_exists_cmd)
-        .await
-        .is_ok()
-    {
-        let remove_cache_cmd = format!("rm -rf '{}'", wasm_cache);
-        commands::execute_shell_command(&remove_cache_cmd).await?;
+    if filesystem::remove_dir_all(&wasm_cache).await? {
         operation_log.push("✓ WASM cache cleaned".to_string());
     } else {
         operation_log.push("• WASM cache not found, skipping".to_string());
@@ -72,6 +83,7 @@ pub async fn execute_state_sync_sequence(request: &StateSyncRequest) -> Result<S
 
     // Step 6: Start the node service - FAIL FAST
     info!("Step 6: Starting service {}", request.service_name);
+    progress.step("Starting service").await;
     systemctl::start_service(&request.service_name).await?;
     operation_log.push(format!("✓ Started service: {}", request.service_name));
 
@@ -80,6 +92,7 @@ pub async fn execute_state_sync_sequence(request: &StateSyncRequest) -> Result<S
         "Step 7: Waiting for state sync to complete (timeout: {}s)",
         request.timeout_seconds
     );
+    progress.step("Waiting for state sync").await;
     wait_for_sync_completion(
         &request.daemon_binary,
         &request.home_dir,
@@ -90,17 +103,20 @@ pub async fn execute_state_sync_sequence(request: &StateSyncRequest) -> Result<S
 
     // Step 8: Disable state sync in config - FAIL FAST
     info!("Step 8: Disabling state sync in config");
+    progress.step("Disabling state sync in config").await;
     config_editor::disable_state_sync(&request.config_path).await?;
     operation_log.push("✓ State sync disabled in config".to_string());
 
     // Step 9: Restart service to apply config changes
     info!("Step 9: Restarting service to apply config");
+    progress.step("Restarting service").await;
     systemctl::stop_service(&request.service_name).await?;
     tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
     systemctl::start_service(&request.service_name).await?;
     operation_log.push("✓ Service restarted with state sync disabled".to_string());
 
     // Step 10: Verify service is running
+    progress.step("Verifying service").await;
     let status = systemctl::get_service_status(&request.service_name).await?;
     if status != "active" {
         return Err(anyhow::anyhow!(
@@ -0,0 +1,413 @@
+// File: agent/src/services/archive.rs
+use anyhow::{anyhow, Context, Result};
+use lz4_flex::frame::{FrameDecoder, FrameEncoder};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{BufWriter, Read, Write};
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use tracing::{info, warn};
+
+use super::filesystem;
+use super::job_manager::ProgressReporter;
+use super::manifest::MANIFEST_FILE;
+use super::metrics;
+use crate::types::{ArchiveResult, SnapshotManifest};
+
+pub const ARCHIVE_SUFFIX: &str = ".tar.lz4";
+/// Archives are written under this suffix and renamed once complete, so a
+/// `.tar.lz4` file is never a truncated one
+pub const PARTIAL_ARCHIVE_SUFFIX: &str = ".tar.lz4.partial";
+
+const WRITE_BUFFER_SIZE: usize = 1024 * 1024;
+
+pub fn archive_path(backup_path: &str, snapshot_name: &str) -> String {
+    format!("{}/{}{}", backup_path, snapshot_name, ARCHIVE_SUFFIX)
+}
+
+pub fn partial_archive_path(backup_path: &str, snapshot_name: &str) -> String {
+    format!(
+        "{}/{}{}",
+        backup_path, snapshot_name, PARTIAL_ARCHIVE_SUFFIX
+    )
+}
+
+/// Counts the uncompressed tar stream on its way to the encoder and stops it
+/// once the job is cancelled
+struct CountingWriter<'a, W: Write> {
+    inner: W,
+    written: &'a AtomicU64,
+    cancelled: &'a AtomicBool,
+}
+
+impl<W: Write> Write for CountingWriter<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if self.cancelled.load(Ordering::Relaxed) {
+            return Err(std::io::Error::other("archive cancelled"));
+        }
+        let written = self.inner.write(buf)?;
+        self.written.fetch_add(written as u64, Ordering::Relaxed);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Stream `{backup_path}/{snapshot_name}` through tar and an LZ4 frame encoder
+/// into `{snapshot_name}.tar.lz4` next to it. The archive unpacks to a single
+/// `{snapshot_name}/` directory, the same layout `tar -C backup_path` gives.
+pub async fn create_archive(
+    backup_path: &str,
+    snapshot_name: &str,
+    progress: &ProgressReporter,
+) -> Result<ArchiveResult> {
+    let source = Path::new(backup_path).join(snapshot_name);
+    let final_path = PathBuf::from(archive_path(backup_path, snapshot_name));
+    let partial_path = PathBuf::from(partial_archive_path(backup_path, snapshot_name));
+
+    progress.step("Compressing snapshot archive").await;
+    let source_bytes = filesystem::dir_size(&source.to_string_lossy()).await?;
+    progress.bytes(0, Some(source_bytes)).await;
+    info!(
+        "Creating {} ({:.1} MB of snapshot data)",
+        final_path.display(),
+        source_bytes as f64 / 1024.0 / 1024.0
+    );
+
+    let name = snapshot_name.to_string();
+    let target = partial_path.clone();
+    let written = filesystem::run_blocking_with_progress(progress, move |bytes, cancelled| {
+        let result = write_archive(&source, &name, &target, bytes, cancelled);
+        if result.is_err() {
+            let _ = fs::remove_file(&target);
+        }
+        result
+    })
+    .await??;
+    metrics::global().record_bytes_copied("archive", written);
+
+    tokio::fs::rename(&partial_path, &final_path)
+        .await
+        .with_context(|| {
+            format!(
+                "Failed to move archive into place at {}",
+                final_path.display()
+            )
+        })?;
+
+    let size_bytes = tokio::fs::metadata(&final_path)
+        .await
+        .with_context(|| format!("Failed to read {}", final_path.display()))?
+        .len();
+    let compression_ratio = if size_bytes > 0 {
+        written as f64 / size_bytes as f64
+    } else {
+        0.0
+    };
+
+    info!(
+        "Archive {} complete: {:.1} MB (ratio {:.2})",
+        final_path.display(),
+        size_bytes as f64 / 1024.0 / 1024.0,
+        compression_ratio
+    );
+    Ok(ArchiveResult {
+        archive_path: final_path.to_string_lossy().to_string(),
+        size_bytes,
+        uncompressed_bytes: written,
+        compression_ratio,
+    })
+}
+
+/// Write the archive and return the size of the uncompressed tar stream
+fn write_archive(
+    source: &Path,
+    snapshot_name: &str,
+    target: &Path,
+    written: &AtomicU64,
+    cancelled: &AtomicBool,
+) -> Result<u64> {
+    let file = fs::File::create(target)
+        .with_context(|| format!("Failed to create {}", target.display()))?;
+    let encoder = FrameEncoder::new(BufWriter::with_capacity(WRITE_BUFFER_SIZE, file));
+
+    let mut builder = tar::Builder::new(CountingWriter {
+        inner: encoder,
+        written,
+        cancelled,
+    });
+    builder.follow_symlinks(false);
+    builder
+        .append_dir_all(snapshot_name, source)
+        .with_context(|| format!("Failed to archive {}", source.display()))?;
+
+    let counting = builder
+        .into_inner()
+        .context("Failed to finish tar stream")?;
+    let file = counting
+        .inner
+        .finish()
+        .context("Failed to finish LZ4 frame")?
+        .into_inner()
+        .map_err(|e| e.into_error())
+        .context("Failed to flush archive")?;
+    file.sync_all()
+        .with_context(|| format!("Failed to sync {}", target.display()))?;
+    Ok(written.load(Ordering::Relaxed))
+}
+
+/// What was unpacked from a snapshot archive
+#[derive(Debug, Default)]
+pub struct ExtractReport {
+    pub files_extracted: u64,
+    pub bytes_extracted: u64,
+    /// Size and hex SHA-256 of every regular file written, keyed by path
+    /// relative to the snapshot root (e.g. `data/application.db/000001.log`)
+    pub files: HashMap<String, (u64, String)>,
+    /// The snapshot's manifest, if the archive carried one
+    pub manifest: Option<SnapshotManifest>,
+}
+
+/// Counts compressed bytes as they are read from the archive file
+struct CountingReader<'a, R: Read> {
+    inner: R,
+    read: &'a AtomicU64,
+    cancelled: &'a AtomicBool,
+}
+
+impl<R: Read> Read for CountingReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.cancelled.load(Ordering::Relaxed) {
+            return Err(std::io::Error::other("extraction cancelled"));
+        }
+        let read = self.inner.read(buf)?;
+        self.read.fetch_add(read as u64, Ordering::Relaxed);
+        Ok(read)
+    }
+}
+
+/// Stream-decompress a snapshot archive, writing its `data/` and `wasm/` trees
+/// into `target_dir`. Files are hashed as they are written so the result can be
+/// checked against the manifest without reading them back. Progress is reported
+/// in compressed bytes read, against the archive size.
+pub async fn extract_snapshot_archive(
+    archive_path: &str,
+    target_dir: &str,
+    progress: &ProgressReporter,
+) -> Result<ExtractReport> {
+    let archive_size = tokio::fs::metadata(archive_path)
+        .await
+        .with_context(|| format!("Failed to read {}", archive_path))?
+        .len();
+    progress.bytes(0, Some(archive_size)).await;
+    info!(
+        "Extracting {} ({:.1} MB) into {}",
+        archive_path,
+        archive_size as f64 / 1024.0 / 1024.0,
+        target_dir
+    );
+
+    let source = PathBuf::from(archive_path);
+    let target = PathBuf::from(target_dir);
+    let report = filesystem::run_blocking_with_progress(progress, move |read, cancelled| {
+        extract_blocking(&source, &target, read, cancelled)
+    })
+    .await??;
+    metrics::global().record_bytes_copied("extract", report.bytes_extracted);
+
+    info!(
+        "Extracted {} files ({:.1} MB) from {}",
+        report.files_extracted,
+        report.bytes_extracted as f64 / 1024.0 / 1024.0,
+        archive_path
+    );
+    Ok(report)
+}
+
+fn extract_blocking(
+    source: &Path,
+    target_dir: &Path,
+    read: &AtomicU64,
+    cancelled: &AtomicBool,
+) -> Result<ExtractReport> {
+    let file =
+        fs::File::open(source).with_context(|| format!("Failed to open {}", source.display()))?;
+    let mut archive = tar::Archive::new(FrameDecoder::new(CountingReader {
+        inner: file,
+        read,
+        cancelled,
+    }));
+    archive.set_preserve_permissions(true);
+    archive.set_overwrite(true);
+
+    let mut report = ExtractReport::default();
+    for entry in archive
+        .entries()
+        .with_context(|| format!("Failed to read {}", source.display()))?
+    {
+        let mut entry = entry.with_context(|| format!("Corrupt archive {}", source.display()))?;
+        let relative = snapshot_relative_path(&entry.path()?)?;
+        let Some(relative) = relative else {
+            continue;
+        };
+        let relative_str = relative.to_string_lossy().to_string();
+
+        if relative_str == MANIFEST_FILE {
+            let mut content = Vec::new();
+            entry.read_to_end(&mut content)?;
+            report.manifest = Some(
+                serde_json::from_slice(&content)
+                    .with_context(|| format!("Invalid manifest in {}", source.display()))?,
+            );
+            continue;
+        }
+        if !(relative.starts_with("data") || relative.starts_with("wasm")) {
+            warn!(
+                "Skipping {} from archive: not part of a snapshot",
+                relative_str
+            );
+            continue;
+        }
+
+        let target = target_dir.join(&relative);
+        // Files are opened through their last component, so a symlink there
+        // would be followed as well
+        check_no_symlink_on_path(target_dir, &relative, entry.header().entry_type().is_file())?;
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+
+        if entry.header().entry_type().is_file() {
+            let mode = entry.header().mode().unwrap_or(0o644);
+            let (size, sha256) = write_hashed(&mut entry, &target)
+                .with_context(|| format!("Failed to extract {}", target.display()))?;
+            fs::set_permissions(&target, fs::Permissions::from_mode(mode))?;
+            report.files_extracted += 1;
+            report.bytes_extracted += size;
+            report.files.insert(relative_str, (size, sha256));
+        } else if entry.header().entry_type().is_hard_link() {
+            return Err(anyhow!(
+                "Archive {} contains a hard link at {}, which snapshots never do",
+                source.display(),
+                relative_str
+            ));
+        } else {
+            if entry.header().entry_type().is_symlink() {
+                check_link_target(&relative, entry.link_name()?.as_deref())?;
+            }
+            // Directories and symlinks; the path was validated above
+            entry
+                .unpack(&target)
+                .with_context(|| format!("Failed to extract {}", target.display()))?;
+        }
+    }
+
+    Ok(report)
+}
+
+/// Strip the leading `{snapshot_name}/` from an archive path. Returns `None`
+/// for the root entry itself and rejects paths that could escape the target.
+fn snapshot_relative_path(path: &Path) -> Result<Option<PathBuf>> {
+    let mut components = path.components();
+    match components.next() {
+        Some(Component::Normal(_)) => {}
+        _ => return Err(anyhow!("Unexpected path in archive: {}", path.display())),
+    }
+
+    let mut relative = PathBuf::new();
+    for component in components {
+        match component {
+            Component::Normal(part) => relative.push(part),
+            Component::CurDir => {}
+            _ => return Err(anyhow!("Unsafe path in archive: {}", path.display())),
+        }
+    }
+    Ok((!relative.as_os_str().is_empty()).then_some(relative))
+}
+
+/// Refuse symlinks whose target leaves the top-level `data` or `wasm` tree
+/// the link is in. Later entries could otherwise be written through them, e.g.
+/// `data/x -> ../config` followed by `data/x/priv_validator_key.json`.
+fn check_link_target(link: &Path, target: Option<&Path>) -> Result<()> {
+    let target = target.ok_or_else(|| anyhow!("Symlink {} has no target", link.display()))?;
+    let tree = link
+        .components()
+        .next()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .unwrap_or_default();
+    // Depth below the tree's top-level directory, which must stay above zero
+    let mut depth = link.components().count() as i64 - 1;
+    for component in target.components() {
+        match component {
+            Component::Normal(_) => depth += 1,
+            Component::ParentDir => depth -= 1,
+            Component::CurDir => {}
+            Component::RootDir | Component::Prefix(_) => depth = 0,
+        }
+        if depth < 1 {
+            return Err(anyhow!(
+                "Symlink {} -> {} points outside the snapshot's {} tree",
+                link.display(),
+                target.display(),
+                tree
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Refuse to write an entry through a symlink extracted earlier: no existing
+/// directory between `target_dir` and the entry (nor the entry itself, with
+/// `include_entry`) may be a symlink
+fn check_no_symlink_on_path(target_dir: &Path, relative: &Path, include_entry: bool) -> Result<()> {
+    let components: Vec<_> = relative.components().collect();
+    let checked = if include_entry {
+        components.len()
+    } else {
+        components.len().saturating_sub(1)
+    };
+
+    let mut path = target_dir.to_path_buf();
+    for component in &components[..checked] {
+        path.push(component);
+        match fs::symlink_metadata(&path) {
+            Ok(metadata) if metadata.file_type().is_symlink() => {
+                return Err(anyhow!(
+                    "Refusing to extract {} through the symlink {}",
+                    relative.display(),
+                    path.display()
+                ));
+            }
+            Ok(_) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => break,
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to inspect {}", path.display()))
+            }
+        }
+    }
+    Ok(())
+}
+
+fn write_hashed(reader: &mut impl Read, target: &Path) -> std::io::Result<(u64, String)> {
+    let mut writer = BufWriter::with_capacity(WRITE_BUFFER_SIZE, fs::File::create(target)?);
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; WRITE_BUFFER_SIZE];
+    let mut size = 0u64;
+
+    loop {
+        let read = reader.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        writer.write_all(&buffer[..read])?;
+        hasher.update(&buffer[..read]);
+        size += read as u64;
+    }
+    writer.flush()?;
+    Ok((size, hex::encode(hasher.finalize())))
+}
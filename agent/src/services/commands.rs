@@ -4,16 +4,61 @@ use std::process::Stdio;
 use std::time::Duration;
 use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::process::Command as AsyncCommand;
-use tracing::{debug, error, info, warn};
+use tracing::{debug, info, warn};
+
+use super::{journald, logs};
+
+/// Kills the whole process group of a spawned child if dropped before the child exits.
+///
+/// Jobs are cancelled by aborting their task, which drops this guard mid-await and
+/// takes down `sh -c` pipelines and cosmos-pruner together with the shell itself.
+struct ProcessGroupGuard {
+    pgid: Option<u32>,
+}
+
+impl ProcessGroupGuard {
+    fn new(pgid: Option<u32>) -> Self {
+        Self { pgid }
+    }
+
+    fn disarm(&mut self) {
+        self.pgid = None;
+    }
+}
+
+impl Drop for ProcessGroupGuard {
+    fn drop(&mut self) {
+        if let Some(pgid) = self.pgid {
+            warn!("Killing process group {} of an aborted operation", pgid);
+            let _ = std::process::Command::new("kill")
+                .arg("-KILL")
+                .arg("--")
+                .arg(format!("-{}", pgid))
+                .status();
+        }
+    }
+}
+
+/// Run a command in its own process group and collect its output
+async fn run_in_process_group(command: &mut AsyncCommand) -> Result<std::process::Output> {
+    let child = command
+        .process_group(0)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()?;
+
+    let mut guard = ProcessGroupGuard::new(child.id());
+    let output = child.wait_with_output().await?;
+    guard.disarm();
+    Ok(output)
+}
 
 pub async fn execute_shell_command(command: &str) -> Result<String> {
     debug!("Executing command: {}", command);
 
-    let output = AsyncCommand::new("sh")
-        .arg("-c")
-        .arg(command)
-        .output()
-        .await?;
+    let output = run_in_process_group(AsyncCommand::new("sh").arg("-c").arg(command)).await?;
 
     let stdout = String::from_utf8_lossy(&output.stdout).to_string();
     let stderr = String::from_utf8_lossy(&output.stderr).to_string();
@@ -47,6 +92,7 @@ pub async fn execute_cosmos_pruner(
         .arg(keep_versions.to_string())
         .stdout(Stdio::piped())
         .stderr(Stdio::piped())
+        .process_group(0)
         .kill_on_drop(true); // Ensure cleanup
 
     info!("Executing cosmos-pruner process with stream monitoring...");
@@ -54,6 +100,7 @@ pub async fn execute_cosmos_pruner(
     let mut child = command
         .spawn()
         .map_err(|e| anyhow!("Failed to spawn cosmos-pruner: {}", e))?;
+    let mut process_guard = ProcessGroupGuard::new(child.id());
 
     // Take streams for proper draining
     let stdout = child.stdout.take().unwrap();
@@ -103,6 +150,8 @@ pub async fn execute_cosmos_pruner(
         }
     };
 
+    process_guard.disarm();
+
     // Wait for stream tasks to complete
     let _ = tokio::try_join!(stdout_handle, stderr_handle);
 
@@ -122,256 +171,35 @@ pub async fn execute_cosmos_pruner(
     ))
 }
 
-// NEW: LZ4 compression function for background execution
-pub async fn create_lz4_compressed_snapshot(backup_path: &str, snapshot_dirname: &str) {
-    let lz4_filename = format!("{}.tar.lz4", snapshot_dirname);
-    let lz4_path = format!("{}/{}", backup_path, lz4_filename);
-
-    let command = format!(
-        "tar -cf - -C '{}' '{}' | lz4 -z -c > '{}'",
-        backup_path, snapshot_dirname, lz4_path
-    );
-
-    info!("Starting background LZ4 compression: {}", lz4_filename);
-    debug!("LZ4 command: {}", command);
-
-    match AsyncCommand::new("sh")
-        .arg("-c")
-        .arg(&command)
-        .output()
-        .await
-    {
-        Ok(output) => {
-            let exit_code = output.status.code().unwrap_or(-1);
-            let success = output.status.success();
-
-            if success {
-                info!(
-                    "Background LZ4 compression completed successfully: {} (exit code: {})",
-                    lz4_filename, exit_code
-                );
-            } else {
-                let stderr = String::from_utf8_lossy(&output.stderr);
-                warn!(
-                    "Background LZ4 compression failed: {} (exit code: {}, stderr: {})",
-                    lz4_filename,
-                    exit_code,
-                    stderr.trim()
-                );
-            }
-        }
-        Err(e) => {
-            error!("Background LZ4 compression error: {} - {}", lz4_filename, e);
-        }
-    }
-}
-
-pub async fn create_directory(path: &str) -> Result<()> {
-    let command = format!("mkdir -p '{}'", path);
-    execute_shell_command(&command).await?;
-    Ok(())
-}
-
-pub async fn delete_directory(path: &str) -> Result<()> {
-    info!("Deleting directory: {}", path);
-    let command = format!("rm -rf '{}'", path);
-    execute_shell_command(&command).await?;
-    info!("Directory deleted successfully: {}", path);
-    Ok(())
-}
-
-// NEW - Remove file if it exists (for cleaning validator state from snapshots)
-pub async fn remove_file_if_exists(file_path: &str) -> Result<()> {
-    let command = format!(
-        "if [ -f '{}' ]; then rm '{}' && echo 'removed'; else echo 'not found'; fi",
-        file_path, file_path
-    );
-
-    let output = execute_shell_command(&command).await?;
-    debug!("Remove result for {}: {}", file_path, output.trim());
-    Ok(())
-}
-
-// NEW - Backup current validator state before restore
-pub async fn backup_current_validator_state(source: &str, backup_path: &str) -> Result<()> {
-    info!(
-        "Backing up current validator state from {} to {}",
-        source, backup_path
-    );
-
-    let command = format!(
-        "if [ -f '{}' ]; then cp '{}' '{}' && echo 'validator_state_backed_up'; else echo 'validator_state_not_found'; fi",
-        source, source, backup_path
-    );
-
-    let output = execute_shell_command(&command).await?;
-    if output.contains("validator_state_backed_up") {
-        info!("Current validator state backed up successfully");
-    } else {
-        info!("No current validator state found - will create default after restore");
-    }
-    Ok(())
-}
-
-// NEW - Restore current validator state after snapshot restore
-pub async fn restore_current_validator_state(backup_path: &str, destination: &str) -> Result<()> {
-    info!(
-        "Restoring current validator state from {} to {}",
-        backup_path, destination
-    );
-
-    let command = format!(
-        "if [ -f '{}' ]; then cp '{}' '{}' && echo 'validator_state_restored'; else echo 'validator_backup_not_found'; fi",
-        backup_path, backup_path, destination
-    );
-
-    let output = execute_shell_command(&command).await?;
-    if output.contains("validator_state_restored") {
-        info!("Current validator state restored successfully - signing state preserved");
-    } else {
-        warn!("No validator state backup found - node will start with default validator state");
-    }
-    Ok(())
-}
-
-// SIMPLIFIED: Simple copy function using cp -r like all other operations
-async fn copy_directory(source_path: &str, target_path: &str, operation_name: &str) -> Result<()> {
-    info!(
-        "Starting {} - copying from {} to {}",
-        operation_name, source_path, target_path
-    );
-
-    let command = format!("cp -r '{}' '{}'", source_path, target_path);
-    execute_shell_command(&command).await?;
-
-    info!("{} completed successfully", operation_name);
-    Ok(())
-}
-
-// Copy function that REQUIRES both data and wasm directories for restore - SYNCHRONOUS
-pub async fn copy_snapshot_directories_mandatory(
-    snapshot_dir: &str,
-    target_dir: &str,
-) -> Result<()> {
-    info!(
-        "Copying both data and wasm directories from {} to {}",
-        snapshot_dir, target_dir
-    );
-
-    // Copy data directory first
-    let data_source = format!("{}/data", snapshot_dir);
-
-    let data_exists_cmd = format!("test -d '{}'", data_source);
-    execute_shell_command(&data_exists_cmd).await.map_err(|_| {
-        anyhow!(
-            "CRITICAL: data directory missing from snapshot: {}",
-            data_source
-        )
-    })?;
-
-    // Copy to target_dir (not target_dir/data) - cp -r will create the data subdirectory
-    copy_directory(&data_source, target_dir, "Data restore").await?;
-
-    // Then copy wasm directory
-    let wasm_source = format!("{}/wasm", snapshot_dir);
-
-    let wasm_exists_cmd = format!("test -d '{}'", wasm_source);
-    execute_shell_command(&wasm_exists_cmd).await.map_err(|_| {
-        anyhow!(
-            "CRITICAL: wasm directory missing from snapshot: {}",
-            wasm_source
-        )
-    })?;
-
-    // Copy to target_dir (not target_dir/wasm) - cp -r will create the wasm subdirectory
-    copy_directory(&wasm_source, target_dir, "Wasm restore").await?;
-
-    info!("Copy completed - both data and wasm directories copied successfully");
-    Ok(())
-}
-
-// NEW: copy function for snapshot creation that REQUIRES both data and wasm directories - SYNCHRONOUS
-pub async fn copy_directories_to_snapshot_mandatory(
-    source_dir: &str,
-    snapshot_dir: &str,
-    directories: &[&str],
-) -> Result<()> {
-    info!(
-        "Copying directories {:?} from {} to snapshot {}",
-        directories, source_dir, snapshot_dir
-    );
-
-    for dir in directories {
-        let source_path = format!("{}/{}", source_dir, dir);
-        let target_path = format!("{}/{}", snapshot_dir, dir);
-
-        // Check if source directory exists BEFORE copying
-        let source_exists_cmd = format!("test -d '{}'", source_path);
-        execute_shell_command(&source_exists_cmd)
-            .await
-            .map_err(|_| {
-                anyhow!(
-                    "CRITICAL: Source {} directory missing at: {}",
-                    dir,
-                    source_path
-                )
-            })?;
-
-        copy_directory(
-            &source_path,
-            &target_path,
-            &format!("{} snapshot copy", dir),
-        )
-        .await
-        .map_err(|e| anyhow!("CRITICAL: Failed to copy {} directory: {}", dir, e))?;
-
-        // Verify the copy was successful
-        let target_exists_cmd = format!("test -d '{}'", target_path);
-        execute_shell_command(&target_exists_cmd)
-            .await
-            .map_err(|_| {
-                anyhow!(
-                    "CRITICAL: {} directory not found after copy at: {}",
-                    dir,
-                    target_path
-                )
-            })?;
-
-        info!("Successfully copied {} directory to snapshot", dir);
-    }
-
-    info!("Directory copying to snapshot completed successfully");
-    Ok(())
-}
-
-pub async fn get_directory_size(dir_path: &str) -> Result<u64> {
-    let command = format!("du -sb '{}' | cut -f1", dir_path);
-    let output = execute_shell_command(&command).await?;
-
-    output
-        .trim()
-        .parse::<u64>()
-        .map_err(|e| anyhow!("Failed to parse directory size: {}", e))
-}
-
-pub async fn check_log_for_trigger_words(log_file: &str, trigger_words: &[String]) -> Result<bool> {
+/// Whether the last 1000 lines of a log file, directory or unit journal
+/// contain any of `trigger_words`
+pub async fn check_log_for_trigger_words(
+    log_file: &str,
+    glob: Option<&str>,
+    journald_unit: Option<&str>,
+    trigger_words: &[String],
+) -> Result<bool> {
     if trigger_words.is_empty() {
         return Ok(false);
     }
 
-    let pattern = trigger_words.join("|");
-    let command = format!("tail -n 1000 '{}' | grep -q -E '{}'", log_file, pattern);
-
-    debug!("Checking log for trigger words: {}", command);
+    let source = match journald_unit {
+        Some(unit) => journald::source_name(unit),
+        None => log_file.to_string(),
+    };
+    debug!(
+        "Checking log {} for trigger words: {:?}",
+        source, trigger_words
+    );
 
-    match execute_shell_command(&command).await {
-        Ok(_) => {
-            info!("Auto-restore trigger words found in log: {}", log_file);
-            Ok(true)
-        }
-        Err(_) => {
-            debug!("No trigger words found in log: {}", log_file);
-            Ok(false)
-        }
+    let mut request = logs::tail_request(log_file, trigger_words, 1000, 0);
+    request.glob = glob.map(str::to_string);
+    request.journald_unit = journald_unit.map(str::to_string);
+    let found = logs::search_logs(request).await?.matched;
+    if found {
+        info!("Auto-restore trigger words found in log: {}", source);
+    } else {
+        debug!("No trigger words found in log: {}", source);
     }
+    Ok(found)
 }
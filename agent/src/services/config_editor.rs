@@ -1,138 +1,194 @@
 // File: agent/src/services/config_editor.rs
-use anyhow::Result;
+//! Format-preserving edits of node TOML config files
+//!
+//! Files are edited with `toml_edit`, so only the values being set change:
+//! comments, key order, spacing and every other setting are kept as they
+//! were. A changed file is copied to `{file}.{YYYYmmddTHHMMSSmmmZ}.bak` before
+//! it is written, and every patch reports a unified diff of what it changed.
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use serde_json::Value as JsonValue;
+use similar::TextDiff;
+use toml_edit::{Array, DocumentMut, InlineTable, Item, Table, TableLike, Value};
 use tracing::info;
 
-/// Enable state sync in config.toml - FAIL FAST
-pub async fn enable_state_sync(
-    config_path: &str,
-    rpc_servers: &[String],
-    trust_height: i64,
-    trust_hash: &str,
-) -> Result<()> {
-    info!("Enabling state sync in {}", config_path);
+use crate::types::{ConfigChange, ConfigPatchResult};
 
-    // Read current config
-    let config_content = tokio::fs::read_to_string(config_path)
-        .await
-        .map_err(|e| anyhow::anyhow!("Failed to read config file: {}", e))?;
-
-    // Parse and modify config
-    let mut modified_config = config_content.clone();
-
-    // Find [statesync] section and update it
-    let rpc_servers_str = rpc_servers.join(",");
-
-    // Pattern to match the [statesync] section
-    if let Some(start) = modified_config.find("[statesync]") {
-        // Find the next section or end of file
-        let after_section = &modified_config[start..];
-        let next_section_pos = after_section[11..] // Skip "[statesync]"
-            .find("\n[")
-            .map(|p| p + start + 11);
-
-        let section_end = next_section_pos.unwrap_or(modified_config.len());
-
-        // Extract section content
-        let section = &modified_config[start..section_end];
-
-        // Create updated section
-        let mut new_section = String::from("[statesync]\n");
-
-        for line in section.lines().skip(1) {
-            // Skip [statesync] line
-            let trimmed = line.trim();
-
-            if trimmed.starts_with("enable ") || trimmed.starts_with("enable=") {
-                new_section.push_str("enable = true\n");
-            } else if trimmed.starts_with("rpc_servers ") || trimmed.starts_with("rpc_servers=") {
-                new_section.push_str(&format!("rpc_servers = \"{}\"\n", rpc_servers_str));
-            } else if trimmed.starts_with("trust_height ") || trimmed.starts_with("trust_height=") {
-                new_section.push_str(&format!("trust_height = {}\n", trust_height));
-            } else if trimmed.starts_with("trust_hash ") || trimmed.starts_with("trust_hash=") {
-                new_section.push_str(&format!("trust_hash = \"{}\"\n", trust_hash));
-            } else if trimmed.starts_with("trust_period ") || trimmed.starts_with("trust_period=") {
-                new_section.push_str("trust_period = \"168h0m0s\"\n");
-            } else if !trimmed.is_empty() && !trimmed.starts_with('#') {
-                new_section.push_str(line);
-                new_section.push('\n');
+const BACKUP_TIMESTAMP_FORMAT: &str = "%Y%m%dT%H%M%S%3fZ";
+
+/// Convenience constructor for a change to one key
+pub fn change(key: &str, value: impl Into<JsonValue>) -> ConfigChange {
+    ConfigChange {
+        key: key.to_string(),
+        value: value.into(),
+    }
+}
+
+fn toml_value(key: &str, value: &JsonValue) -> Result<Value> {
+    Ok(match value {
+        JsonValue::Bool(b) => Value::from(*b),
+        JsonValue::Number(n) if n.is_f64() => Value::from(n.as_f64().unwrap_or_default()),
+        JsonValue::Number(n) => Value::from(
+            n.as_i64()
+                .ok_or_else(|| anyhow!("Value of {} is out of range: {}", key, n))?,
+        ),
+        JsonValue::String(s) => Value::from(s.as_str()),
+        JsonValue::Array(items) => Value::Array(
+            items
+                .iter()
+                .map(|item| toml_value(key, item))
+                .collect::<Result<Array>>()?,
+        ),
+        JsonValue::Object(entries) => {
+            let mut table = InlineTable::new();
+            for (name, item) in entries {
+                table.insert(name, toml_value(key, item)?);
             }
+            Value::InlineTable(table)
         }
+        JsonValue::Null => return Err(anyhow!("No value given for {}", key)),
+    })
+}
 
-        // Replace section
-        modified_config.replace_range(start..section_end, &new_section);
-    } else {
-        // [statesync] section not found, append it
-        modified_config.push_str("\n\n");
-        modified_config.push_str("[statesync]\n");
-        modified_config.push_str("enable = true\n");
-        modified_config.push_str(&format!("rpc_servers = \"{}\"\n", rpc_servers_str));
-        modified_config.push_str(&format!("trust_height = {}\n", trust_height));
-        modified_config.push_str(&format!("trust_hash = \"{}\"\n", trust_hash));
-        modified_config.push_str("trust_period = \"168h0m0s\"\n");
+/// Set the value at a dotted key path, creating missing tables. A value that
+/// already exists keeps its surrounding whitespace and trailing comment.
+pub fn set_value(document: &mut DocumentMut, key: &str, value: Value) -> Result<()> {
+    let path: Vec<&str> = key.split('.').collect();
+    if path.iter().any(|segment| segment.trim().is_empty()) {
+        return Err(anyhow!("Invalid config key: {:?}", key));
     }
+    let (name, tables) = path.split_last().expect("split yields a segment");
 
-    // Write modified config back
-    tokio::fs::write(config_path, modified_config)
-        .await
-        .map_err(|e| anyhow::anyhow!("Failed to write config file: {}", e))?;
+    let mut table: &mut dyn TableLike = document.as_table_mut();
+    for segment in tables {
+        if table.get(segment).is_none() {
+            table.insert(segment, Item::Table(Table::new()));
+        }
+        table = table
+            .get_mut(segment)
+            .and_then(Item::as_table_like_mut)
+            .ok_or_else(|| anyhow!("Cannot set {}: {} is not a table", key, segment))?;
+    }
 
-    info!("✓ State sync enabled in config");
+    match table.get_mut(name) {
+        Some(Item::Value(existing)) => {
+            let decor = existing.decor().clone();
+            *existing = value;
+            *existing.decor_mut() = decor;
+        }
+        Some(Item::None) | None => {
+            table.insert(name, Item::Value(value));
+        }
+        Some(_) => return Err(anyhow!("Cannot set {}: it is a table", key)),
+    }
     Ok(())
 }
 
-/// Disable state sync in config.toml - FAIL FAST
-pub async fn disable_state_sync(config_path: &str) -> Result<()> {
-    info!("Disabling state sync in {}", config_path);
+/// Apply the changes to a TOML document, returning the new contents
+pub fn patch_document(content: &str, changes: &[ConfigChange]) -> Result<String> {
+    let mut document: DocumentMut = content
+        .parse()
+        .map_err(|e| anyhow!("Failed to parse config: {}", e))?;
+    for change in changes {
+        let value = toml_value(&change.key, &change.value)?;
+        set_value(&mut document, &change.key, value)?;
+    }
+    Ok(document.to_string())
+}
 
-    // Read current config
-    let config_content = tokio::fs::read_to_string(config_path)
-        .await
-        .map_err(|e| anyhow::anyhow!("Failed to read config file: {}", e))?;
+/// Unified diff between two versions of a config file
+pub fn unified_diff(config_path: &str, original: &str, patched: &str) -> String {
+    if original == patched {
+        return String::new();
+    }
+    let path = config_path.trim_start_matches('/');
+    TextDiff::from_lines(original, patched)
+        .unified_diff()
+        .context_radius(3)
+        .header(&format!("a/{}", path), &format!("b/{}", path))
+        .to_string()
+}
 
-    // Parse and modify config
-    let mut modified_config = config_content.clone();
+pub fn backup_path(config_path: &str, now: DateTime<Utc>) -> String {
+    format!(
+        "{}.{}.bak",
+        config_path,
+        now.format(BACKUP_TIMESTAMP_FORMAT)
+    )
+}
 
-    // Find [statesync] section and update enable flag
-    if let Some(start) = modified_config.find("[statesync]") {
-        // Find the next section or end of file
-        let after_section = &modified_config[start..];
-        let next_section_pos = after_section[11..] // Skip "[statesync]"
-            .find("\n[")
-            .map(|p| p + start + 11);
+/// Apply the changes to a config file. Unless `dry_run` is set, a file the
+/// patch changes is backed up and then rewritten; an unchanged file is left
+/// untouched.
+pub async fn patch_config(
+    config_path: &str,
+    changes: &[ConfigChange],
+    dry_run: bool,
+) -> Result<ConfigPatchResult> {
+    if changes.is_empty() {
+        return Err(anyhow!("No config changes given"));
+    }
 
-        let section_end = next_section_pos.unwrap_or(modified_config.len());
+    let original = tokio::fs::read_to_string(config_path)
+        .await
+        .map_err(|e| anyhow!("Failed to read config file {}: {}", config_path, e))?;
+    let patched = patch_document(&original, changes)
+        .map_err(|e| anyhow!("Failed to patch {}: {}", config_path, e))?;
+
+    let mut result = ConfigPatchResult {
+        config_path: config_path.to_string(),
+        changed: original != patched,
+        diff: unified_diff(config_path, &original, &patched),
+        backup_path: None,
+    };
+    if dry_run || !result.changed {
+        return Ok(result);
+    }
 
-        // Extract section content
-        let section = &modified_config[start..section_end];
+    let backup = backup_path(config_path, Utc::now());
+    tokio::fs::copy(config_path, &backup)
+        .await
+        .map_err(|e| anyhow!("Failed to back up config file to {}: {}", backup, e))?;
+    tokio::fs::write(config_path, patched)
+        .await
+        .map_err(|e| anyhow!("Failed to write config file: {}", e))?;
+
+    info!("Patched {} (backup: {})", config_path, backup);
+    result.backup_path = Some(backup);
+    Ok(result)
+}
 
-        // Create updated section with enable = false
-        let mut new_section = String::from("[statesync]\n");
+/// Enable state sync in config.toml - FAIL FAST
+///
+/// Only the keys a state sync needs are set; `trust_period` and the rest of
+/// the `[statesync]` section keep their configured values.
+pub async fn enable_state_sync(
+    config_path: &str,
+    rpc_servers: &[String],
+    trust_height: i64,
+    trust_hash: &str,
+) -> Result<ConfigPatchResult> {
+    info!("Enabling state sync in {}", config_path);
 
-        for line in section.lines().skip(1) {
-            // Skip [statesync] line
-            let trimmed = line.trim();
+    let changes = [
+        change("statesync.enable", true),
+        change("statesync.rpc_servers", rpc_servers.join(",")),
+        change("statesync.trust_height", trust_height),
+        change("statesync.trust_hash", trust_hash),
+    ];
+    let result = patch_config(config_path, &changes, false).await?;
 
-            if trimmed.starts_with("enable ") || trimmed.starts_with("enable=") {
-                new_section.push_str("enable = false\n");
-            } else if !trimmed.is_empty() {
-                new_section.push_str(line);
-                new_section.push('\n');
-            }
-        }
+    info!("✓ State sync enabled in config");
+    Ok(result)
+}
 
-        // Replace section
-        modified_config.replace_range(start..section_end, &new_section);
-    } else {
-        // No [statesync] section, nothing to do
-        info!("No [statesync] section found, nothing to disable");
-        return Ok(());
-    }
+/// Disable state sync in config.toml - FAIL FAST
+pub async fn disable_state_sync(config_path: &str) -> Result<ConfigPatchResult> {
+    info!("Disabling state sync in {}", config_path);
 
-    // Write modified config back
-    tokio::fs::write(config_path, modified_config)
-        .await
-        .map_err(|e| anyhow::anyhow!("Failed to write config file: {}", e))?;
+    let result = patch_config(config_path, &[change("statesync.enable", false)], false).await?;
 
     info!("✓ State sync disabled in config");
-    Ok(())
+    Ok(result)
 }
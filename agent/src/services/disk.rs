@@ -0,0 +1,68 @@
+// File: agent/src/services/disk.rs
+use anyhow::{anyhow, Context, Result};
+use std::path::Path;
+use tracing::info;
+
+/// Start of the message of every failed space check, which the manager
+/// matches on to raise a disk space alert
+pub const INSUFFICIENT_SPACE: &str = "Insufficient disk space";
+
+/// Space that must stay free on a filesystem after an operation has written its data
+pub const MIN_FREE_BYTES: u64 = 1024 * 1024 * 1024;
+
+/// Room for the `.tar.lz4` archive compressed from a new snapshot, as a
+/// share of the snapshot size
+pub const ARCHIVE_HEADROOM_PERCENT: u64 = 60;
+
+/// Room cosmos-pruner needs while it compacts the database, as a share of the data size
+pub const PRUNING_HEADROOM_PERCENT: u64 = 10;
+
+/// Expected ratio of extracted data to archive size, used when an archive
+/// is restored and the uncompressed size is not known up front
+pub const ARCHIVE_EXPANSION_ESTIMATE: u64 = 3;
+
+/// Bytes available to unprivileged writers on the filesystem holding `path`.
+/// Paths that do not exist yet are resolved to their closest existing parent.
+pub fn available_bytes(path: &str) -> Result<u64> {
+    let existing = Path::new(path)
+        .ancestors()
+        .find(|p| !p.as_os_str().is_empty() && p.exists())
+        .ok_or_else(|| anyhow!("No existing parent directory for {}", path))?;
+    let stat = rustix::fs::statvfs(existing)
+        .with_context(|| format!("Failed to read filesystem stats for {}", existing.display()))?;
+    Ok(stat.f_bavail.saturating_mul(stat.f_frsize))
+}
+
+/// Fail when `path`'s filesystem cannot take `required` more bytes while
+/// keeping `MIN_FREE_BYTES` free
+pub fn ensure_space(path: &str, required: u64, operation: &str) -> Result<()> {
+    let available = available_bytes(path)?;
+    let needed = required.saturating_add(MIN_FREE_BYTES);
+    if available < needed {
+        return Err(anyhow!(
+            "{} on {} for {}: {} required, {} available",
+            INSUFFICIENT_SPACE,
+            path,
+            operation,
+            format_bytes(needed),
+            format_bytes(available)
+        ));
+    }
+    info!(
+        "✓ Disk space check passed for {} on {}: {} required, {} available",
+        operation,
+        path,
+        format_bytes(needed),
+        format_bytes(available)
+    );
+    Ok(())
+}
+
+/// `bytes` plus `percent` of it
+pub fn with_headroom(bytes: u64, percent: u64) -> u64 {
+    bytes.saturating_add(bytes / 100 * percent)
+}
+
+fn format_bytes(bytes: u64) -> String {
+    format!("{:.1} GB", bytes as f64 / 1024.0 / 1024.0 / 1024.0)
+}
@@ -0,0 +1,404 @@
+// File: agent/src/services/filesystem.rs
+use anyhow::{anyhow, Context, Result};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::{Read, Write};
+use std::os::unix::fs::{lchown, symlink, MetadataExt};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+use super::job_manager::ProgressReporter;
+use super::metrics;
+
+const COPY_BUFFER_SIZE: usize = 1024 * 1024;
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(2);
+/// How many per-file errors are spelled out when a copy is turned into an error
+const REPORTED_ERRORS: usize = 5;
+
+#[derive(Debug)]
+pub struct FileError {
+    pub path: PathBuf,
+    pub error: String,
+}
+
+/// A regular file written by a tree copy
+#[derive(Debug)]
+pub struct CopiedFile {
+    pub target: PathBuf,
+    pub size_bytes: u64,
+    /// Hex SHA-256 of the copied content, when the copy was asked to hash
+    pub sha256: Option<String>,
+}
+
+/// Outcome of a tree copy. Copies continue past individual file errors so the
+/// report lists everything that went wrong, not just the first failure.
+#[derive(Debug, Default)]
+pub struct CopyReport {
+    pub files_copied: u64,
+    pub bytes_copied: u64,
+    pub files: Vec<CopiedFile>,
+    pub errors: Vec<FileError>,
+}
+
+impl CopyReport {
+    /// Turn a report with file errors into an error naming the first few of them
+    pub fn into_result(self) -> Result<Self> {
+        if self.errors.is_empty() {
+            return Ok(self);
+        }
+
+        let listed: Vec<String> = self
+            .errors
+            .iter()
+            .take(REPORTED_ERRORS)
+            .map(|e| format!("{}: {}", e.path.display(), e.error))
+            .collect();
+        let more = self.errors.len().saturating_sub(REPORTED_ERRORS);
+        Err(anyhow!(
+            "{} file(s) failed to copy: {}{}",
+            self.errors.len(),
+            listed.join("; "),
+            if more > 0 {
+                format!(" (and {} more)", more)
+            } else {
+                String::new()
+            }
+        ))
+    }
+}
+
+/// Stops blocking work when the awaiting task is dropped, e.g. on job cancel
+struct CancelOnDrop(Arc<AtomicBool>);
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Run blocking file work on a worker thread while reporting the byte counter
+/// it advances to `progress`. The work should stop once the cancel flag is set,
+/// which happens when the awaiting task is dropped.
+pub(crate) async fn run_blocking_with_progress<T, F>(
+    progress: &ProgressReporter,
+    work: F,
+) -> Result<T>
+where
+    F: FnOnce(&AtomicU64, &AtomicBool) -> T + Send + 'static,
+    T: Send + 'static,
+{
+    let bytes = Arc::new(AtomicU64::new(0));
+    let cancelled = Arc::new(AtomicBool::new(false));
+    let _cancel_guard = CancelOnDrop(cancelled.clone());
+
+    let worker_bytes = bytes.clone();
+    let mut task = tokio::task::spawn_blocking(move || work(&worker_bytes, &cancelled));
+
+    let mut ticker = tokio::time::interval(PROGRESS_INTERVAL);
+    let result = loop {
+        tokio::select! {
+            result = &mut task => break result.context("File task panicked")?,
+            _ = ticker.tick() => {
+                progress.bytes(bytes.load(Ordering::Relaxed), None).await;
+            }
+        }
+    };
+    progress.bytes(bytes.load(Ordering::Relaxed), None).await;
+    Ok(result)
+}
+
+struct CopyContext<'a> {
+    bytes_copied: &'a AtomicU64,
+    cancelled: &'a AtomicBool,
+    hash: bool,
+    report: CopyReport,
+}
+
+/// Copy each `(source, target)` directory tree in turn, preserving permissions,
+/// ownership and symlinks, and optionally hashing every file as it is copied.
+/// Bytes copied so far are reported to `progress`; callers set the expected
+/// total beforehand.
+pub async fn copy_trees(
+    pairs: &[(String, String)],
+    hash: bool,
+    progress: &ProgressReporter,
+) -> Result<CopyReport> {
+    let pairs = pairs.to_vec();
+    let report = run_blocking_with_progress(progress, move |bytes_copied, cancelled| {
+        let mut ctx = CopyContext {
+            bytes_copied,
+            cancelled,
+            hash,
+            report: CopyReport::default(),
+        };
+        for (source, target) in &pairs {
+            info!("Copying {} to {}", source, target);
+            copy_entry(Path::new(source), Path::new(target), &mut ctx);
+        }
+        ctx.report.bytes_copied = bytes_copied.load(Ordering::Relaxed);
+        ctx.report
+    })
+    .await?;
+
+    metrics::global().record_bytes_copied("copy", report.bytes_copied);
+    info!(
+        "Copied {} files ({:.1} MB) with {} error(s)",
+        report.files_copied,
+        report.bytes_copied as f64 / 1024.0 / 1024.0,
+        report.errors.len()
+    );
+    Ok(report)
+}
+
+fn copy_entry(source: &Path, target: &Path, ctx: &mut CopyContext) {
+    if ctx.cancelled.load(Ordering::Relaxed) {
+        return;
+    }
+
+    let record = |ctx: &mut CopyContext, error: std::io::Error| {
+        ctx.report.errors.push(FileError {
+            path: source.to_path_buf(),
+            error: error.to_string(),
+        });
+    };
+
+    let metadata = match fs::symlink_metadata(source) {
+        Ok(metadata) => metadata,
+        Err(e) => return record(ctx, e),
+    };
+    let file_type = metadata.file_type();
+
+    let result = if file_type.is_symlink() {
+        fs::read_link(source).and_then(|link| symlink(link, target))
+    } else if file_type.is_dir() {
+        match fs::create_dir(target) {
+            Err(e) if e.kind() != std::io::ErrorKind::AlreadyExists => return record(ctx, e),
+            _ => {}
+        }
+        match fs::read_dir(source) {
+            Ok(entries) => {
+                for entry in entries {
+                    match entry {
+                        Ok(entry) => {
+                            copy_entry(&entry.path(), &target.join(entry.file_name()), ctx)
+                        }
+                        Err(e) => record(ctx, e),
+                    }
+                }
+            }
+            Err(e) => record(ctx, e),
+        }
+        // Permissions last, so read-only directories can still be filled
+        fs::set_permissions(target, metadata.permissions())
+    } else if file_type.is_file() {
+        copy_file_contents(source, target, ctx).and_then(|(size_bytes, sha256)| {
+            ctx.report.files_copied += 1;
+            ctx.report.files.push(CopiedFile {
+                target: target.to_path_buf(),
+                size_bytes,
+                sha256,
+            });
+            fs::set_permissions(target, metadata.permissions())
+        })
+    } else {
+        warn!("Skipping special file {}", source.display());
+        return;
+    };
+
+    if let Err(e) = result.and_then(|_| copy_ownership(&metadata, target)) {
+        record(ctx, e);
+    }
+}
+
+/// Copy one file's content, returning its size and, if requested, its SHA-256
+fn copy_file_contents(
+    source: &Path,
+    target: &Path,
+    ctx: &CopyContext,
+) -> std::io::Result<(u64, Option<String>)> {
+    let mut reader = fs::File::open(source)?;
+    let mut writer = fs::File::create(target)?;
+    let mut hasher = ctx.hash.then(Sha256::new);
+    let mut buffer = vec![0u8; COPY_BUFFER_SIZE];
+    let mut size = 0u64;
+
+    loop {
+        if ctx.cancelled.load(Ordering::Relaxed) {
+            return Err(std::io::Error::other("copy cancelled"));
+        }
+        let read = reader.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        writer.write_all(&buffer[..read])?;
+        if let Some(hasher) = hasher.as_mut() {
+            hasher.update(&buffer[..read]);
+        }
+        size += read as u64;
+        ctx.bytes_copied.fetch_add(read as u64, Ordering::Relaxed);
+    }
+    writer.flush()?;
+    Ok((size, hasher.map(|h| hex::encode(h.finalize()))))
+}
+
+/// SHA-256 of a file, advancing `bytes_read` as it goes. Returns the file size and hex digest.
+pub(crate) fn hash_file(
+    path: &Path,
+    bytes_read: &AtomicU64,
+    cancelled: &AtomicBool,
+) -> std::io::Result<(u64, String)> {
+    let mut reader = fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; COPY_BUFFER_SIZE];
+    let mut size = 0u64;
+
+    loop {
+        if cancelled.load(Ordering::Relaxed) {
+            return Err(std::io::Error::other("hashing cancelled"));
+        }
+        let read = reader.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+        size += read as u64;
+        bytes_read.fetch_add(read as u64, Ordering::Relaxed);
+    }
+    Ok((size, hex::encode(hasher.finalize())))
+}
+
+/// Give `target` the owner of the source entry, skipping the call when it already matches
+fn copy_ownership(source: &fs::Metadata, target: &Path) -> std::io::Result<()> {
+    let current = fs::symlink_metadata(target)?;
+    if current.uid() == source.uid() && current.gid() == source.gid() {
+        return Ok(());
+    }
+    lchown(target, Some(source.uid()), Some(source.gid()))
+}
+
+/// Total size in bytes of the regular files under `path`, without following symlinks
+pub async fn dir_size(path: &str) -> Result<u64> {
+    let path = PathBuf::from(path);
+    tokio::task::spawn_blocking(move || dir_size_blocking(&path))
+        .await
+        .context("Size task panicked")?
+}
+
+fn dir_size_blocking(path: &Path) -> Result<u64> {
+    let metadata =
+        fs::symlink_metadata(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    if !metadata.is_dir() {
+        return Ok(if metadata.is_file() {
+            metadata.len()
+        } else {
+            0
+        });
+    }
+
+    let mut total = 0;
+    for entry in fs::read_dir(path).with_context(|| format!("Failed to read {}", path.display()))? {
+        total += dir_size_blocking(&entry?.path())?;
+    }
+    Ok(total)
+}
+
+/// Recursively set the owner of a tree, without following symlinks
+pub async fn chown_tree(path: &str, uid: u32, gid: u32) -> Result<()> {
+    let path = PathBuf::from(path);
+    tokio::task::spawn_blocking(move || {
+        let mut errors = Vec::new();
+        chown_tree_blocking(&path, uid, gid, &mut errors);
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(anyhow!(
+                "Failed to change owner of {} entries under {}: {}",
+                errors.len(),
+                path.display(),
+                errors
+                    .iter()
+                    .take(REPORTED_ERRORS)
+                    .cloned()
+                    .collect::<Vec<_>>()
+                    .join("; ")
+            ))
+        }
+    })
+    .await
+    .context("Chown task panicked")?
+}
+
+fn chown_tree_blocking(path: &Path, uid: u32, gid: u32, errors: &mut Vec<String>) {
+    if let Err(e) = lchown(path, Some(uid), Some(gid)) {
+        errors.push(format!("{}: {}", path.display(), e));
+        return;
+    }
+    if fs::symlink_metadata(path).is_ok_and(|m| m.is_dir()) {
+        match fs::read_dir(path) {
+            Ok(entries) => {
+                for entry in entries.flatten() {
+                    chown_tree_blocking(&entry.path(), uid, gid, errors);
+                }
+            }
+            Err(e) => errors.push(format!("{}: {}", path.display(), e)),
+        }
+    }
+}
+
+/// Owner `(uid, gid)` of a path
+pub async fn owner(path: &str) -> Result<(u32, u32)> {
+    let metadata = tokio::fs::metadata(path)
+        .await
+        .with_context(|| format!("Failed to read {}", path))?;
+    Ok((metadata.uid(), metadata.gid()))
+}
+
+pub async fn is_dir(path: &str) -> bool {
+    tokio::fs::metadata(path).await.is_ok_and(|m| m.is_dir())
+}
+
+pub async fn is_file(path: &str) -> bool {
+    tokio::fs::metadata(path).await.is_ok_and(|m| m.is_file())
+}
+
+pub async fn create_dir_all(path: &str) -> Result<()> {
+    tokio::fs::create_dir_all(path)
+        .await
+        .with_context(|| format!("Failed to create directory {}", path))
+}
+
+/// Remove a directory tree. Returns false if it did not exist.
+pub async fn remove_dir_all(path: &str) -> Result<bool> {
+    match tokio::fs::remove_dir_all(path).await {
+        Ok(_) => {
+            info!("Directory deleted: {}", path);
+            Ok(true)
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+        Err(e) => Err(e).with_context(|| format!("Failed to delete directory {}", path)),
+    }
+}
+
+/// Remove a file. Returns false if it did not exist.
+pub async fn remove_file_if_exists(path: &str) -> Result<bool> {
+    match tokio::fs::remove_file(path).await {
+        Ok(_) => Ok(true),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            debug!("File {} not found, nothing to remove", path);
+            Ok(false)
+        }
+        Err(e) => Err(e).with_context(|| format!("Failed to remove {}", path)),
+    }
+}
+
+/// Copy a single file if the source exists. Returns false if it did not.
+pub async fn copy_file_if_exists(source: &str, target: &str) -> Result<bool> {
+    match tokio::fs::copy(source, target).await {
+        Ok(_) => Ok(true),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound && !is_file(source).await => Ok(false),
+        Err(e) => Err(e).with_context(|| format!("Failed to copy {} to {}", source, target)),
+    }
+}
@@ -1,15 +1,19 @@
 // File: agent/src/services/job_manager.rs
+use anyhow::{Context, Result};
 use chrono::Utc;
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use tokio::io::AsyncWriteExt;
 use tokio::sync::RwLock;
-use tracing::{info, warn};
+use tracing::{error, info, warn};
 
-use crate::types::{JobInfo, JobStatus};
+use crate::types::{JobInfo, JobProgress, JobStatus, StepProgress};
 
 #[derive(Clone)]
 pub struct JobManager {
     jobs: Arc<RwLock<HashMap<String, JobInfo>>>,
+    journal_path: Option<PathBuf>,
 }
 
 impl Default for JobManager {
@@ -19,13 +23,107 @@ impl Default for JobManager {
 }
 
 impl JobManager {
+    /// In-memory job manager without persistence
     pub fn new() -> Self {
         Self {
             jobs: Arc::new(RwLock::new(HashMap::new())),
+            journal_path: None,
         }
     }
 
-    pub async fn create_job(&self, operation_type: &str, target_name: &str) -> String {
+    /// Open (or create) a job manager backed by an append-only JSON journal.
+    ///
+    /// Every job state change is appended as one JSON line; on load the last
+    /// entry per job wins. Jobs still `Running` in the journal were cut off by
+    /// an agent restart, so they are marked `Interrupted` and returned to the
+    /// caller for recovery reporting. The journal is compacted after loading.
+    pub async fn with_journal(path: impl AsRef<Path>) -> Result<(Self, Vec<JobInfo>)> {
+        let path = path.as_ref().to_path_buf();
+
+        if let Some(parent) = path.parent() {
+            if !parent.as_os_str().is_empty() {
+                tokio::fs::create_dir_all(parent).await.with_context(|| {
+                    format!(
+                        "Failed to create job journal directory {}",
+                        parent.display()
+                    )
+                })?;
+            }
+        }
+
+        let mut jobs: HashMap<String, JobInfo> = HashMap::new();
+        match tokio::fs::read_to_string(&path).await {
+            Ok(content) => {
+                for (line_no, line) in content.lines().enumerate() {
+                    if line.trim().is_empty() {
+                        continue;
+                    }
+                    match serde_json::from_str::<JobInfo>(line) {
+                        Ok(job) => {
+                            jobs.insert(job.job_id.clone(), job);
+                        }
+                        Err(e) => {
+                            // A torn write at crash time only affects the last line
+                            warn!(
+                                "Skipping unreadable job journal line {} in {}: {}",
+                                line_no + 1,
+                                path.display(),
+                                e
+                            );
+                        }
+                    }
+                }
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => {
+                return Err(e)
+                    .with_context(|| format!("Failed to read job journal {}", path.display()));
+            }
+        }
+
+        let now = Utc::now();
+        let mut interrupted = Vec::new();
+        for job in jobs.values_mut() {
+            if matches!(job.status, JobStatus::Running) {
+                job.status = JobStatus::Interrupted;
+                job.completed_at = Some(now);
+                job.error_message = Some(format!(
+                    "Agent restarted while {} was running for {}",
+                    job.operation_type, job.target_name
+                ));
+                warn!(
+                    "Job {} ({} for {}) was interrupted by an agent restart",
+                    job.job_id, job.operation_type, job.target_name
+                );
+                interrupted.push(job.clone());
+            }
+        }
+
+        info!(
+            "Loaded {} jobs from journal {} ({} interrupted)",
+            jobs.len(),
+            path.display(),
+            interrupted.len()
+        );
+
+        let manager = Self {
+            jobs: Arc::new(RwLock::new(jobs)),
+            journal_path: Some(path),
+        };
+        {
+            let jobs = manager.jobs.read().await;
+            manager.compact_journal(&jobs).await?;
+        }
+
+        Ok((manager, interrupted))
+    }
+
+    pub async fn create_job(
+        &self,
+        operation_type: &str,
+        target_name: &str,
+        service_name: Option<&str>,
+    ) -> String {
         let job_id = format!(
             "{}_{}_{}",
             operation_type,
@@ -42,9 +140,14 @@ impl JobManager {
             completed_at: None,
             result: None,
             error_message: None,
+            service_name: service_name.map(|s| s.to_string()),
+            service_left_stopped: None,
+            progress: JobProgress::default(),
+            parent_job_id: None,
         };
 
         let mut jobs = self.jobs.write().await;
+        self.append_to_journal(&job_info).await;
         jobs.insert(job_id.clone(), job_info);
 
         info!(
@@ -60,7 +163,9 @@ impl JobManager {
             job.status = JobStatus::Completed;
             job.completed_at = Some(Utc::now());
             job.result = Some(result);
+            Self::close_current_step(&mut job.progress);
             info!("Job {} completed successfully", job_id);
+            self.append_to_journal(job).await;
         }
     }
 
@@ -70,7 +175,88 @@ impl JobManager {
             job.status = JobStatus::Failed;
             job.completed_at = Some(Utc::now());
             job.error_message = Some(error_message.clone());
+            Self::close_current_step(&mut job.progress);
             warn!("Job {} failed: {}", job_id, error_message);
+            self.append_to_journal(job).await;
+        }
+    }
+
+    /// Record the job that started `job_id`
+    pub async fn link_to_parent(&self, job_id: &str, parent_job_id: &str) {
+        let mut jobs = self.jobs.write().await;
+        if let Some(job) = jobs.get_mut(job_id) {
+            job.parent_job_id = Some(parent_job_id.to_string());
+            self.append_to_journal(job).await;
+        }
+    }
+
+    /// Mark the current step of a job as done and start the next one
+    pub async fn start_step(&self, job_id: &str, step_name: &str) {
+        let mut jobs = self.jobs.write().await;
+        if let Some(job) = jobs.get_mut(job_id) {
+            Self::close_current_step(&mut job.progress);
+            job.progress.current_step = Some(StepProgress {
+                name: step_name.to_string(),
+                started_at: Utc::now(),
+                completed_at: None,
+            });
+            info!("Job {} step: {}", job_id, step_name);
+            self.append_to_journal(job).await;
+        }
+    }
+
+    /// Update byte counters. Not journaled, as copies report this frequently;
+    /// the next step change persists the latest values.
+    pub async fn set_bytes_progress(&self, job_id: &str, processed: u64, total: Option<u64>) {
+        let mut jobs = self.jobs.write().await;
+        if let Some(job) = jobs.get_mut(job_id) {
+            job.progress.bytes_processed = Some(processed);
+            if total.is_some() {
+                job.progress.bytes_total = total;
+            }
+        }
+    }
+
+    /// Progress handle for the operation running under `job_id`
+    pub fn progress_reporter(&self, job_id: &str) -> ProgressReporter {
+        ProgressReporter {
+            job_manager: Some(self.clone()),
+            job_id: job_id.to_string(),
+        }
+    }
+
+    fn close_current_step(progress: &mut JobProgress) {
+        if let Some(mut step) = progress.current_step.take() {
+            step.completed_at = Some(Utc::now());
+            progress.completed_steps.push(step);
+        }
+    }
+
+    pub async fn cancel_job(&self, job_id: &str, cleanup_summary: String) {
+        let mut jobs = self.jobs.write().await;
+        if let Some(job) = jobs.get_mut(job_id) {
+            job.status = JobStatus::Cancelled;
+            job.completed_at = Some(Utc::now());
+            job.error_message = Some(format!("Cancelled by request: {}", cleanup_summary));
+            Self::close_current_step(&mut job.progress);
+            warn!("Job {} cancelled: {}", job_id, cleanup_summary);
+            self.append_to_journal(job).await;
+        }
+    }
+
+    /// Record whether the service of an interrupted job was found stopped on startup
+    pub async fn record_service_state(&self, job_id: &str, service_left_stopped: bool) {
+        let mut jobs = self.jobs.write().await;
+        if let Some(job) = jobs.get_mut(job_id) {
+            job.service_left_stopped = Some(service_left_stopped);
+            if service_left_stopped {
+                if let (Some(service), Some(message)) =
+                    (job.service_name.as_ref(), job.error_message.as_mut())
+                {
+                    message.push_str(&format!("; service {} was left stopped", service));
+                }
+            }
+            self.append_to_journal(job).await;
         }
     }
 
@@ -95,6 +281,9 @@ impl JobManager {
         let cleaned = initial_count - jobs.len();
         if cleaned > 0 {
             info!("Cleaned up {} old jobs older than {}h", cleaned, max_hours);
+            if let Err(e) = self.compact_journal(&jobs).await {
+                error!("Failed to compact job journal: {}", e);
+            }
         }
         cleaned as u32
     }
@@ -106,4 +295,101 @@ impl JobManager {
             .cloned()
             .collect()
     }
+
+    pub async fn get_interrupted_jobs(&self) -> Vec<JobInfo> {
+        let jobs = self.jobs.read().await;
+        jobs.values()
+            .filter(|job| matches!(job.status, JobStatus::Interrupted))
+            .cloned()
+            .collect()
+    }
+
+    /// Append one job snapshot to the journal. Callers hold the jobs write
+    /// lock, which keeps appends from interleaving.
+    async fn append_to_journal(&self, job: &JobInfo) {
+        let Some(path) = &self.journal_path else {
+            return;
+        };
+
+        let result: Result<()> = async {
+            let mut line = serde_json::to_string(job)?;
+            line.push('\n');
+            let mut file = tokio::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .await?;
+            file.write_all(line.as_bytes()).await?;
+            file.sync_data().await?;
+            Ok(())
+        }
+        .await;
+
+        if let Err(e) = result {
+            error!(
+                "Failed to persist job {} to journal {}: {}",
+                job.job_id,
+                path.display(),
+                e
+            );
+        }
+    }
+
+    /// Rewrite the journal with one line per known job
+    async fn compact_journal(&self, jobs: &HashMap<String, JobInfo>) -> Result<()> {
+        let Some(path) = &self.journal_path else {
+            return Ok(());
+        };
+
+        let mut content = String::new();
+        for job in jobs.values() {
+            content.push_str(&serde_json::to_string(job)?);
+            content.push('\n');
+        }
+
+        let tmp_path = path.with_extension("tmp");
+        tokio::fs::write(&tmp_path, content)
+            .await
+            .with_context(|| format!("Failed to write {}", tmp_path.display()))?;
+        tokio::fs::rename(&tmp_path, path)
+            .await
+            .with_context(|| format!("Failed to replace job journal {}", path.display()))?;
+        Ok(())
+    }
+}
+
+/// Handle passed to operations so they can report step-level progress on their job
+#[derive(Clone)]
+pub struct ProgressReporter {
+    job_manager: Option<JobManager>,
+    job_id: String,
+}
+
+impl ProgressReporter {
+    /// Reporter that is not attached to any job, for running operations directly
+    pub fn detached() -> Self {
+        Self {
+            job_manager: None,
+            job_id: String::new(),
+        }
+    }
+
+    /// Id of the job being reported on, `None` when detached
+    pub fn job_id(&self) -> Option<&str> {
+        self.job_manager.as_ref().map(|_| self.job_id.as_str())
+    }
+
+    pub async fn step(&self, step_name: &str) {
+        if let Some(job_manager) = &self.job_manager {
+            job_manager.start_step(&self.job_id, step_name).await;
+        }
+    }
+
+    pub async fn bytes(&self, processed: u64, total: Option<u64>) {
+        if let Some(job_manager) = &self.job_manager {
+            job_manager
+                .set_bytes_progress(&self.job_id, processed, total)
+                .await;
+        }
+    }
 }
@@ -0,0 +1,221 @@
+// File: agent/src/services/journald.rs
+//! Node logs kept in the systemd journal
+//!
+//! Entries are read with `journalctl --output=json`, which reports each
+//! entry's cursor so a later read can resume after it. The journal is shared
+//! by every unit and cannot be truncated for one of them, so reads from the
+//! tail are limited to the unit's current invocation instead: restarting the
+//! service, as every operation that truncates file logs does, starts a fresh
+//! log just the same.
+//!
+//! Reads that resume from a cursor are capped like file reads: only the newest
+//! entries within the line and byte limits are kept, and the size of the
+//! messages passed over is reported.
+
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::process::Stdio;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::process::Command as AsyncCommand;
+use tracing::{debug, warn};
+
+/// Name a unit's journal is reported under in search results and cursors
+pub fn source_name(unit: &str) -> String {
+    format!("journald:{}", unit)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct JournalEntry {
+    pub cursor: String,
+    pub message: String,
+    pub timestamp: Option<DateTime<Utc>>,
+}
+
+/// Accept only plain systemd unit names, so a name is never taken for a
+/// `journalctl` option or match expression
+pub fn validate_unit_name(unit: &str) -> Result<()> {
+    let valid = !unit.is_empty()
+        && unit.len() <= 256
+        && !unit.starts_with('-')
+        && unit
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || ":-_.@\\".contains(c));
+    if valid {
+        Ok(())
+    } else {
+        Err(anyhow!("Invalid systemd unit name: {}", unit))
+    }
+}
+
+/// One line of `journalctl --output=json` output; lines that are not
+/// entries are skipped
+pub fn parse_entry(line: &str) -> Option<JournalEntry> {
+    let entry: Value = serde_json::from_str(line).ok()?;
+    let cursor = entry.get("__CURSOR")?.as_str()?.to_string();
+    let message = match entry.get("MESSAGE") {
+        Some(Value::String(message)) => message.clone(),
+        // Messages that are not valid UTF-8 are given as arrays of bytes
+        Some(Value::Array(bytes)) => {
+            let bytes: Vec<u8> = bytes
+                .iter()
+                .filter_map(|b| b.as_u64().map(|b| b as u8))
+                .collect();
+            String::from_utf8_lossy(&bytes).into_owned()
+        }
+        _ => String::new(),
+    };
+    let timestamp = entry
+        .get("__REALTIME_TIMESTAMP")
+        .and_then(|v| v.as_str())
+        .and_then(|micros| micros.parse().ok())
+        .and_then(DateTime::from_timestamp_micros);
+
+    Some(JournalEntry {
+        cursor,
+        message,
+        timestamp,
+    })
+}
+
+/// Invocation ID of the unit's current run, `None` while it is not running
+async fn invocation_id(unit: &str) -> Result<Option<String>> {
+    let output = AsyncCommand::new("systemctl")
+        .arg("show")
+        .arg(unit)
+        .arg("--property=InvocationID")
+        .arg("--value")
+        .output()
+        .await?;
+    let id = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    Ok((!id.is_empty()).then_some(id))
+}
+
+/// The newest entries of a journal read, at most `max_entries` of them with
+/// at most `max_bytes` of messages. Older entries are dropped as newer ones
+/// arrive and their message bytes counted in `skipped_bytes`.
+#[derive(Debug)]
+pub struct JournalWindow {
+    pub entries: VecDeque<JournalEntry>,
+    pub skipped_bytes: u64,
+    bytes: u64,
+    max_entries: usize,
+    max_bytes: u64,
+}
+
+impl JournalWindow {
+    pub fn new(max_entries: usize, max_bytes: u64) -> Self {
+        Self {
+            entries: VecDeque::new(),
+            skipped_bytes: 0,
+            bytes: 0,
+            max_entries,
+            max_bytes,
+        }
+    }
+
+    pub fn push(&mut self, entry: JournalEntry) {
+        self.bytes += entry.message.len() as u64;
+        self.entries.push_back(entry);
+        while self.entries.len() > self.max_entries || self.bytes > self.max_bytes {
+            let Some(dropped) = self.entries.pop_front() else {
+                break;
+            };
+            let size = dropped.message.len() as u64;
+            self.bytes -= size;
+            self.skipped_bytes += size;
+        }
+    }
+}
+
+/// Entries of the unit's journal written after `after_cursor`, or the last
+/// entries of its current invocation when there is no cursor. Either way only
+/// the newest `max_entries` entries with at most `max_bytes` of messages are
+/// kept; entries skipped after a cursor are reported in `skipped_bytes`.
+pub async fn read_entries(
+    unit: &str,
+    after_cursor: Option<&str>,
+    max_entries: usize,
+    max_bytes: u64,
+) -> Result<JournalWindow> {
+    validate_unit_name(unit)?;
+
+    let mut command = AsyncCommand::new("journalctl");
+    command.args(["--output=json", "--no-pager", "--quiet"]);
+    match after_cursor {
+        Some(cursor) => {
+            // No `--lines` here: journalctl would drop the older entries
+            // itself, and their size could not be reported
+            command
+                .arg(format!("--unit={}", unit))
+                .arg(format!("--after-cursor={}", cursor));
+        }
+        None => {
+            command.arg(format!("--lines={}", max_entries));
+            // Invocation IDs are unique, so the match alone selects the unit
+            match invocation_id(unit).await? {
+                Some(id) => command.arg(format!("_SYSTEMD_INVOCATION_ID={}", id)),
+                None => command.arg(format!("--unit={}", unit)),
+            };
+        }
+    }
+
+    debug!("Reading journal of {}", unit);
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .context("Failed to run journalctl")?;
+    let stdout = child
+        .stdout
+        .take()
+        .ok_or_else(|| anyhow!("journalctl stdout unavailable"))?;
+
+    // Entries are streamed, so a long backlog never sits in memory at once
+    let mut window = JournalWindow::new(max_entries, max_bytes);
+    let mut lines = BufReader::new(stdout).lines();
+    while let Some(line) = lines.next_line().await? {
+        if let Some(entry) = parse_entry(&line) {
+            window.push(entry);
+        }
+    }
+
+    let output = child.wait_with_output().await?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "Failed to read journal of {}: {}",
+            unit,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    if after_cursor.is_none() {
+        // Tails are expected to start part way through the journal
+        window.skipped_bytes = 0;
+    } else if window.skipped_bytes > 0 {
+        warn!(
+            "Skipping {} bytes of the journal of {} beyond the {} entry, {} byte search limit",
+            window.skipped_bytes, unit, max_entries, max_bytes
+        );
+    }
+    Ok(window)
+}
+
+/// `journalctl --follow` for the unit, printing its last `tail_lines`
+/// entries first. The process is killed when the returned child is dropped.
+pub fn follow_command(unit: &str, tail_lines: usize) -> Result<AsyncCommand> {
+    validate_unit_name(unit)?;
+
+    let mut command = AsyncCommand::new("journalctl");
+    command
+        .args(["--output=json", "--no-pager", "--quiet", "--follow"])
+        .arg(format!("--unit={}", unit))
+        .arg(format!("--lines={}", tail_lines))
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .kill_on_drop(true);
+    Ok(command)
+}
@@ -0,0 +1,307 @@
+// File: agent/src/services/log_rotation.rs
+//! Rotation of node logs before maintenance clears them
+//!
+//! Each log file is compressed into `archive/{file}.{YYYYmmddTHHMMSSmmmZ}.gz`
+//! (or `.zst`) next to it and then truncated in place, so the node keeps
+//! writing through the handle it already has and no file the agent may not
+//! own has to be recreated. Older archives of the file are then pruned to the
+//! configured number of generations or bytes.
+
+use anyhow::{anyhow, Context, Result};
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use axum::response::{IntoResponse, Response};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use tower::ServiceExt;
+use tower_http::services::ServeFile;
+use tracing::{info, warn};
+
+use super::logs;
+use crate::types::{LogArchive, LogArchiveListing, LogCompression, LogRotation};
+
+/// Directory the archives of a log file or directory are kept in
+pub const ARCHIVE_DIR: &str = "archive";
+/// Archives are written under this suffix and renamed once complete
+const PARTIAL_SUFFIX: &str = ".partial";
+/// Generations kept when a rotation sets neither limit
+pub const DEFAULT_KEEP_GENERATIONS: usize = 10;
+
+const TIMESTAMP_FORMAT: &str = "%Y%m%dT%H%M%S%3fZ";
+/// Second-resolution timestamps of archives written by older agents
+const LEGACY_TIMESTAMP_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+const WRITE_BUFFER_SIZE: usize = 1024 * 1024;
+
+/// `archive` next to a log file, or inside a log directory
+pub fn archive_dir(log_path: &Path) -> PathBuf {
+    if log_path.is_dir() {
+        log_path.join(ARCHIVE_DIR)
+    } else {
+        log_path
+            .parent()
+            .unwrap_or_else(|| Path::new("/"))
+            .join(ARCHIVE_DIR)
+    }
+}
+
+fn extension(compression: LogCompression) -> &'static str {
+    match compression {
+        LogCompression::Gzip => ".gz",
+        LogCompression::Zstd => ".zst",
+        LogCompression::None => "",
+    }
+}
+
+/// Split an archive name into the log file it was rotated from and the
+/// rotation time; `None` for anything else in the archive directory
+fn parse_archive_name(name: &str) -> Option<(&str, DateTime<Utc>)> {
+    let stem = name
+        .strip_suffix(".gz")
+        .or_else(|| name.strip_suffix(".zst"))
+        .unwrap_or(name);
+    let (log_file, timestamp) = stem.rsplit_once('.')?;
+    let rotated_at = NaiveDateTime::parse_from_str(timestamp, TIMESTAMP_FORMAT)
+        .or_else(|_| NaiveDateTime::parse_from_str(timestamp, LEGACY_TIMESTAMP_FORMAT))
+        .ok()?;
+    (!log_file.is_empty()).then(|| (log_file, rotated_at.and_utc()))
+}
+
+/// Compress `file` into a new archive in `archive_dir`, returning its path,
+/// or `None` when the file is empty and there is nothing to keep
+pub fn archive_file(
+    file: &Path,
+    archive_dir: &Path,
+    compression: LogCompression,
+    now: DateTime<Utc>,
+) -> Result<Option<PathBuf>> {
+    let file_name = file
+        .file_name()
+        .ok_or_else(|| anyhow!("Not a log file: {}", file.display()))?
+        .to_string_lossy();
+    if fs::metadata(file)?.len() == 0 {
+        return Ok(None);
+    }
+
+    fs::create_dir_all(archive_dir).with_context(|| {
+        format!(
+            "Failed to create log archive directory {}",
+            archive_dir.display()
+        )
+    })?;
+    let name = format!(
+        "{}.{}{}",
+        file_name,
+        now.format(TIMESTAMP_FORMAT),
+        extension(compression)
+    );
+    let final_path = archive_dir.join(&name);
+    let partial_path = archive_dir.join(format!("{}{}", name, PARTIAL_SUFFIX));
+
+    let result = compress(file, &partial_path, compression);
+    if let Err(e) = result {
+        let _ = fs::remove_file(&partial_path);
+        return Err(e.context(format!("Failed to archive {}", file.display())));
+    }
+    if final_path.exists() {
+        let _ = fs::remove_file(&partial_path);
+        return Err(anyhow!(
+            "Log archive {} already exists",
+            final_path.display()
+        ));
+    }
+    fs::rename(&partial_path, &final_path)?;
+    Ok(Some(final_path))
+}
+
+fn compress(file: &Path, destination: &Path, compression: LogCompression) -> Result<()> {
+    let mut input = File::open(file)?;
+    let output = BufWriter::with_capacity(WRITE_BUFFER_SIZE, File::create_new(destination)?);
+    let mut output = match compression {
+        LogCompression::Gzip => {
+            let mut encoder = flate2::write::GzEncoder::new(output, flate2::Compression::default());
+            io::copy(&mut input, &mut encoder)?;
+            encoder.finish()?
+        }
+        LogCompression::Zstd => {
+            let mut encoder = zstd::Encoder::new(output, 0)?;
+            io::copy(&mut input, &mut encoder)?;
+            encoder.finish()?
+        }
+        LogCompression::None => {
+            let mut output = output;
+            io::copy(&mut input, &mut output)?;
+            output
+        }
+    };
+    output.flush()?;
+    output.get_ref().sync_all()?;
+    Ok(())
+}
+
+/// Remove the archives of `log_file` beyond the rotation's limits, oldest
+/// first. The newest archive is always kept.
+pub fn prune_archives(
+    archive_dir: &Path,
+    log_file: &str,
+    rotation: &LogRotation,
+) -> Result<Vec<PathBuf>> {
+    let keep_generations = match (rotation.keep_generations, rotation.keep_bytes) {
+        (Some(generations), _) => generations.max(1),
+        (None, Some(_)) => usize::MAX,
+        (None, None) => DEFAULT_KEEP_GENERATIONS,
+    };
+    let keep_bytes = rotation.keep_bytes.unwrap_or(u64::MAX);
+
+    let mut archives: Vec<LogArchive> = read_archives(archive_dir)?
+        .into_iter()
+        .filter(|archive| archive.log_file == log_file)
+        .collect();
+    archives.sort_by_key(|archive| std::cmp::Reverse(archive.rotated_at));
+
+    let mut removed = Vec::new();
+    let mut kept_bytes = 0u64;
+    for (index, archive) in archives.iter().enumerate() {
+        kept_bytes = kept_bytes.saturating_add(archive.size_bytes);
+        if index == 0 || (index < keep_generations && kept_bytes <= keep_bytes) {
+            continue;
+        }
+        let path = archive_dir.join(&archive.name);
+        fs::remove_file(&path)
+            .with_context(|| format!("Failed to remove log archive {}", path.display()))?;
+        removed.push(path);
+    }
+    Ok(removed)
+}
+
+fn read_archives(archive_dir: &Path) -> Result<Vec<LogArchive>> {
+    let entries = match fs::read_dir(archive_dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => {
+            return Err(anyhow!(
+                "Failed to read log archive directory {}: {}",
+                archive_dir.display(),
+                e
+            ))
+        }
+    };
+
+    let mut archives = Vec::new();
+    for entry in entries {
+        let entry = entry?;
+        if !entry.file_type()?.is_file() {
+            continue;
+        }
+        let name = entry.file_name().to_string_lossy().to_string();
+        if let Some((log_file, rotated_at)) = parse_archive_name(&name) {
+            archives.push(LogArchive {
+                log_file: log_file.to_string(),
+                size_bytes: entry.metadata()?.len(),
+                rotated_at,
+                name,
+            });
+        }
+    }
+    Ok(archives)
+}
+
+/// Archives kept for a log file or directory, newest first
+pub fn list_archives(log_path: &str) -> Result<LogArchiveListing> {
+    let archive_dir = archive_dir(Path::new(log_path));
+    let mut archives = read_archives(&archive_dir)?;
+    archives.sort_by(|a, b| {
+        b.rotated_at
+            .cmp(&a.rotated_at)
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    Ok(LogArchiveListing {
+        archive_dir: archive_dir.to_string_lossy().to_string(),
+        archives,
+    })
+}
+
+/// Path of one archive of a log file or directory, refusing names that
+/// are not archives or would leave the archive directory
+pub fn archive_path(log_path: &str, name: &str) -> Result<PathBuf> {
+    if name.contains('/') || parse_archive_name(name).is_none() {
+        return Err(anyhow!("Invalid log archive name: {}", name));
+    }
+    Ok(archive_dir(Path::new(log_path)).join(name))
+}
+
+/// Serve one archive of a log file or directory for download
+pub async fn serve_archive(log_path: &str, name: &str, request: Request<Body>) -> Response {
+    let path = match archive_path(log_path, name) {
+        Ok(path) => path,
+        Err(e) => return (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+    };
+    if !path.is_file() {
+        return (StatusCode::NOT_FOUND, format!("No log archive {}", name)).into_response();
+    }
+
+    info!("Serving log archive {}", path.display());
+    match ServeFile::new(&path).oneshot(request).await {
+        Ok(response) => response.into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+/// Files a rotation of `log_path` archives: the log file itself, or the files
+/// of a log directory matching the rotation's `log_glob`
+pub fn files_to_rotate(log_path: &str, rotation: &LogRotation) -> Result<Vec<PathBuf>> {
+    let path = PathBuf::from(log_path);
+    if path.is_dir() {
+        logs::log_files_in(&path, rotation.log_glob.as_deref())
+    } else if path.is_file() {
+        Ok(vec![path])
+    } else {
+        warn!("Log path does not exist or is not accessible: {}", log_path);
+        Err(anyhow!(
+            "Log path does not exist or is not accessible: {}",
+            log_path
+        ))
+    }
+}
+
+/// Archive and then truncate the `files_to_rotate` of a log path, returning
+/// the archives written
+pub async fn rotate_log_path(log_path: &str, rotation: &LogRotation) -> Result<Vec<PathBuf>> {
+    info!("Rotating logs at: {}", log_path);
+
+    let path = PathBuf::from(log_path);
+    let files = files_to_rotate(log_path, rotation)?;
+    let archive_dir = archive_dir(&path);
+    let now = Utc::now();
+
+    let mut archived = Vec::new();
+    for file in files {
+        let (source, dir, compression) = (file.clone(), archive_dir.clone(), rotation.compression);
+        let archive =
+            tokio::task::spawn_blocking(move || archive_file(&source, &dir, compression, now))
+                .await??;
+        // Only cleared once its contents are safely archived
+        logs::truncate_log_file(&file.to_string_lossy()).await?;
+
+        let log_file = file
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let (dir, policy) = (archive_dir.clone(), rotation.clone());
+        let removed =
+            tokio::task::spawn_blocking(move || prune_archives(&dir, &log_file, &policy)).await??;
+        if !removed.is_empty() {
+            info!(
+                "Removed {} old archives of {}",
+                removed.len(),
+                file.display()
+            );
+        }
+        if let Some(archive) = archive {
+            info!("Archived {} to {}", file.display(), archive.display());
+            archived.push(archive);
+        }
+    }
+    Ok(archived)
+}
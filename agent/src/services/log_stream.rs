@@ -0,0 +1,290 @@
+// File: agent/src/services/log_stream.rs
+//! Live following of node logs for `GET /logs/stream`
+//!
+//! A follower sends the tail of a log file, or of the matching files in a log
+//! directory, and then polls for lines written since. It keeps the same
+//! cursors as incremental searches, so a rotated or truncated file is picked
+//! up again from its start. Journald sources are followed with
+//! `journalctl --follow` instead.
+
+use anyhow::{anyhow, Context, Result};
+use axum::response::sse::Event;
+use futures::Stream;
+use regex::Regex;
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::process::{Child, ChildStdout};
+use tokio::sync::mpsc;
+use tracing::{debug, info, warn};
+
+use super::journald;
+use super::logs::{cursor_at, log_files_in, parse_log_timestamp, read_window};
+use crate::types::{self, LogCursor, LogStreamLine, LogStreamQuery};
+
+/// How often followed files are checked for new output
+pub const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Events buffered for a client that is reading slower than the log grows
+const STREAM_BUFFER_EVENTS: usize = 1024;
+
+pub struct LogFollower {
+    path: PathBuf,
+    glob: Option<String>,
+    filter: Option<Regex>,
+    cursors: HashMap<PathBuf, LogCursor>,
+}
+
+impl LogFollower {
+    /// Start following `path`, or the files matching `glob` in it when it is
+    /// a directory, returning the last `tail_lines` complete lines of each
+    /// file that pass `filter`
+    pub fn open(
+        path: &str,
+        glob: Option<&str>,
+        filter: Option<&str>,
+        tail_lines: usize,
+    ) -> Result<(Self, Vec<LogStreamLine>)> {
+        let mut follower = Self {
+            path: PathBuf::from(path),
+            glob: glob.map(str::to_string),
+            filter: compile_filter(filter)?,
+            cursors: HashMap::new(),
+        };
+
+        let mut lines = Vec::new();
+        for file in follower.files()? {
+            let window = read_window(&file, None, tail_lines, types::default_tail_bytes())?;
+            // A line still being written is sent once it is complete
+            let complete = window
+                .lines
+                .into_iter()
+                .filter(|(offset, _)| *offset < window.cursor.offset);
+            follower.collect(&file, complete, &mut lines);
+            follower.cursors.insert(file, window.cursor);
+        }
+        Ok((follower, lines))
+    }
+
+    /// Complete lines written since the previous call. Files that are
+    /// temporarily missing, as during rotation, are skipped until they return.
+    pub fn poll(&mut self) -> Result<Vec<LogStreamLine>> {
+        let files = self.files()?;
+        self.cursors.retain(|file, _| files.contains(file));
+
+        let mut lines = Vec::new();
+        for file in files {
+            let cursor = match self.cursors.get(&file) {
+                Some(cursor) => cursor.clone(),
+                // Created since the last poll, so all of it is new
+                None => match cursor_at(&file, 0) {
+                    Ok(cursor) => cursor,
+                    Err(e) => {
+                        debug!("Cannot follow {} yet: {}", file.display(), e);
+                        continue;
+                    }
+                },
+            };
+            let window = match read_window(&file, Some(&cursor), 0, types::default_tail_bytes()) {
+                Ok(window) => window,
+                Err(e) => {
+                    debug!("Cannot follow {} yet: {}", file.display(), e);
+                    continue;
+                }
+            };
+            self.collect(&file, window.lines.into_iter(), &mut lines);
+            self.cursors.insert(file, window.cursor);
+        }
+        Ok(lines)
+    }
+
+    fn files(&self) -> Result<Vec<PathBuf>> {
+        if self.path.is_dir() {
+            log_files_in(&self.path, self.glob.as_deref())
+        } else {
+            Ok(vec![self.path.clone()])
+        }
+    }
+
+    fn collect(
+        &self,
+        file: &Path,
+        window: impl Iterator<Item = (u64, String)>,
+        lines: &mut Vec<LogStreamLine>,
+    ) {
+        let file = file.to_string_lossy();
+        for (_, line) in window {
+            if self
+                .filter
+                .as_ref()
+                .is_some_and(|filter| !filter.is_match(&line))
+            {
+                continue;
+            }
+            lines.push(LogStreamLine {
+                file: file.to_string(),
+                timestamp: parse_log_timestamp(&line),
+                line,
+            });
+        }
+    }
+}
+
+/// Follow the requested log as a stream of Server-Sent Events: a `line`
+/// event per log line, and a final `error` event if the log can no longer be
+/// read. Following stops once the client disconnects.
+pub async fn follow(
+    query: LogStreamQuery,
+) -> Result<impl Stream<Item = Result<Event, Infallible>>> {
+    let (tx, rx) = mpsc::channel(STREAM_BUFFER_EVENTS);
+    match query.journald_unit {
+        Some(unit) => {
+            let filter = compile_filter(query.filter.as_deref())?;
+            let mut child = journald::follow_command(&unit, query.tail_lines)?
+                .spawn()
+                .context("Failed to start journalctl")?;
+            let stdout = child
+                .stdout
+                .take()
+                .ok_or_else(|| anyhow!("journalctl has no output"))?;
+            info!("Streaming journal of {}", unit);
+            tokio::spawn(stream_journal(child, stdout, unit, filter, tx));
+        }
+        None => {
+            let path = query.path.clone();
+            let (follower, initial) = tokio::task::spawn_blocking(move || {
+                LogFollower::open(
+                    &query.path,
+                    query.glob.as_deref(),
+                    query.filter.as_deref(),
+                    query.tail_lines,
+                )
+            })
+            .await??;
+            info!("Streaming log {}", path);
+            tokio::spawn(stream_files(follower, initial, path, tx));
+        }
+    }
+
+    Ok(futures::stream::unfold(rx, |mut rx| async move {
+        rx.recv().await.map(|event| (Ok(event), rx))
+    }))
+}
+
+async fn stream_files(
+    mut follower: LogFollower,
+    initial: Vec<LogStreamLine>,
+    path: String,
+    tx: mpsc::Sender<Event>,
+) {
+    for line in &initial {
+        if tx.send(line_event(line)).await.is_err() {
+            return;
+        }
+    }
+
+    loop {
+        tokio::time::sleep(POLL_INTERVAL).await;
+        if tx.is_closed() {
+            break;
+        }
+
+        let polled = tokio::task::spawn_blocking(move || {
+            let lines = follower.poll();
+            (follower, lines)
+        })
+        .await;
+        let (returned, lines) = match polled {
+            Ok(polled) => polled,
+            Err(e) => return send_error(&tx, &path, e.into()).await,
+        };
+        follower = returned;
+
+        match lines {
+            Ok(lines) => {
+                for line in &lines {
+                    if tx.send(line_event(line)).await.is_err() {
+                        info!("Client stopped streaming log {}", path);
+                        return;
+                    }
+                }
+            }
+            Err(e) => return send_error(&tx, &path, e).await,
+        }
+    }
+    info!("Client stopped streaming log {}", path);
+}
+
+/// Forward `journalctl --follow` output until the client disconnects; the
+/// process is killed when `child` is dropped
+async fn stream_journal(
+    mut child: Child,
+    stdout: ChildStdout,
+    unit: String,
+    filter: Option<Regex>,
+    tx: mpsc::Sender<Event>,
+) {
+    let name = journald::source_name(&unit);
+    let mut lines = BufReader::new(stdout).lines();
+    loop {
+        let line = tokio::select! {
+            line = lines.next_line() => line,
+            _ = tx.closed() => {
+                info!("Client stopped streaming journal of {}", unit);
+                return;
+            }
+        };
+
+        let entry = match line {
+            Ok(Some(line)) => match journald::parse_entry(&line) {
+                Some(entry) => entry,
+                None => continue,
+            },
+            Ok(None) => {
+                let status = child.wait().await;
+                return send_error(&tx, &name, anyhow!("journalctl exited: {:?}", status)).await;
+            }
+            Err(e) => return send_error(&tx, &name, e.into()).await,
+        };
+        if filter
+            .as_ref()
+            .is_some_and(|filter| !filter.is_match(&entry.message))
+        {
+            continue;
+        }
+
+        let line = LogStreamLine {
+            file: name.clone(),
+            timestamp: entry
+                .timestamp
+                .or_else(|| parse_log_timestamp(&entry.message)),
+            line: entry.message,
+        };
+        if tx.send(line_event(&line)).await.is_err() {
+            return;
+        }
+    }
+}
+
+fn compile_filter(filter: Option<&str>) -> Result<Option<Regex>> {
+    filter
+        .map(Regex::new)
+        .transpose()
+        .map_err(|e| anyhow!("Invalid log stream filter: {}", e))
+}
+
+async fn send_error(tx: &mpsc::Sender<Event>, path: &str, error: anyhow::Error) {
+    warn!("Stopped streaming log {}: {}", path, error);
+    let _ = tx
+        .send(Event::default().event("error").data(error.to_string()))
+        .await;
+}
+
+fn line_event(line: &LogStreamLine) -> Event {
+    Event::default()
+        .event("line")
+        .json_data(line)
+        .unwrap_or_else(|e| Event::default().event("error").data(e.to_string()))
+}
@@ -1,9 +1,19 @@
 // File: agent/src/services/logs.rs
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use regex::{Regex, RegexSet};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
 use tokio::process::Command as AsyncCommand;
-use tracing::{info, warn};
+use tracing::{debug, info, warn};
 
-use super::systemctl;
+use super::{journald, log_rotation, systemctl};
+use crate::types::{self, LogCursor, LogMatch, LogRotation, LogSearchRequest, LogSearchResult};
 
 pub async fn truncate_log_file(log_path: &str) -> Result<()> {
     info!("Truncating log file: {}", log_path);
@@ -159,7 +169,30 @@ pub async fn truncate_log_path(log_path: &str) -> Result<()> {
     }
 }
 
-pub async fn truncate_service_logs(service_name: &str, log_path: &str) -> Result<()> {
+/// Clear a log file or directory before an operation: archived first when a
+/// rotation is given, otherwise truncated in place
+pub async fn clear_log_path(log_path: &str, rotation: Option<&LogRotation>) -> Result<()> {
+    match rotation {
+        Some(rotation) => log_rotation::rotate_log_path(log_path, rotation)
+            .await
+            .map(|_| ()),
+        None => truncate_log_path(log_path).await,
+    }
+}
+
+/// Progress step shown while `clear_log_path` runs
+pub fn clear_step(rotation: Option<&LogRotation>) -> &'static str {
+    match rotation {
+        Some(_) => "Rotating logs",
+        None => "Truncating logs",
+    }
+}
+
+pub async fn truncate_service_logs(
+    service_name: &str,
+    log_path: &str,
+    rotation: Option<&LogRotation>,
+) -> Result<()> {
     info!(
         "Truncating logs for service: {} at path: {}",
         service_name, log_path
@@ -167,7 +200,7 @@ pub async fn truncate_service_logs(service_name: &str, log_path: &str) -> Result
 
     systemctl::stop_service(service_name).await?;
 
-    if let Err(e) = truncate_log_path(log_path).await {
+    if let Err(e) = clear_log_path(log_path, rotation).await {
         warn!("Log truncation failed: {}", e);
         if let Err(start_err) = systemctl::start_service(service_name).await {
             return Err(anyhow!(
@@ -184,3 +217,438 @@ pub async fn truncate_service_logs(service_name: &str, log_path: &str) -> Result
     info!("Service logs truncated successfully for: {}", service_name);
     Ok(())
 }
+
+/// Request for a one-off search of the log tail at `path`, with the default
+/// byte and match limits
+pub fn tail_request(
+    path: &str,
+    patterns: &[String],
+    tail_lines: usize,
+    context_lines: usize,
+) -> LogSearchRequest {
+    LogSearchRequest {
+        path: path.to_string(),
+        glob: None,
+        journald_unit: None,
+        patterns: patterns.to_vec(),
+        tail_lines,
+        tail_bytes: types::default_tail_bytes(),
+        context_lines,
+        context_before: None,
+        context_after: None,
+        max_matches: types::default_max_matches(),
+        cursors: None,
+    }
+}
+
+/// Search the tail of a log file, of the files matching the request's glob in
+/// a directory, or of a unit's journal, for lines matching any of the
+/// request's regexes.
+///
+/// Files are read directly and patterns evaluated in process, so neither
+/// reaches a shell.
+pub async fn search_logs(request: LogSearchRequest) -> Result<LogSearchResult> {
+    match request.journald_unit.clone() {
+        Some(unit) => search_journal(&unit, &request).await,
+        None => tokio::task::spawn_blocking(move || search_logs_blocking(&request)).await?,
+    }
+}
+
+fn search_logs_blocking(request: &LogSearchRequest) -> Result<LogSearchResult> {
+    let Some(matcher) = Matcher::new(request)? else {
+        return Ok(LogSearchResult::default());
+    };
+
+    let path = Path::new(&request.path);
+    let is_dir = path.is_dir();
+    let files = if is_dir {
+        log_files_in(path, request.glob.as_deref())?
+    } else {
+        vec![path.to_path_buf()]
+    };
+
+    let mut result = LogSearchResult::default();
+    let mut output = Vec::new();
+    for file in files {
+        let file_name = file.to_string_lossy().to_string();
+        let cursor = request
+            .cursors
+            .as_ref()
+            .and_then(|cursors| cursors.get(&file_name));
+        let window = read_window(&file, cursor, request.tail_lines, request.tail_bytes)?;
+        result.skipped_bytes += window.skipped_bytes;
+        result.cursors.insert(file_name.clone(), window.cursor);
+
+        let unreported =
+            matcher.search(&file_name, &window.lines, is_dir, &mut result, &mut output);
+        if let Some(index) = unreported {
+            // Resume at the first unreported match next time
+            if request.cursors.is_some() {
+                result
+                    .cursors
+                    .insert(file_name.clone(), cursor_at(&file, window.lines[index].0)?);
+            }
+            break;
+        }
+    }
+
+    Ok(finish(result, output, &request.path))
+}
+
+async fn search_journal(unit: &str, request: &LogSearchRequest) -> Result<LogSearchResult> {
+    let Some(matcher) = Matcher::new(request)? else {
+        return Ok(LogSearchResult::default());
+    };
+
+    let name = journald::source_name(unit);
+    let previous = request
+        .cursors
+        .as_ref()
+        .and_then(|cursors| cursors.get(&name))
+        .and_then(|cursor| cursor.journal_cursor.clone());
+    let window = journald::read_entries(
+        unit,
+        previous.as_deref(),
+        request.tail_lines,
+        request.tail_bytes,
+    )
+    .await?;
+    let entries = Vec::from(window.entries);
+    let lines: Vec<(u64, String)> = entries
+        .iter()
+        .map(|entry| (0, entry.message.clone()))
+        .collect();
+
+    let mut result = LogSearchResult {
+        skipped_bytes: window.skipped_bytes,
+        ..LogSearchResult::default()
+    };
+    let mut output = Vec::new();
+    let unreported = matcher.search(&name, &lines, false, &mut result, &mut output);
+    for found in &mut result.matches {
+        found.timestamp = entries[found.line_number - 1].timestamp.or(found.timestamp);
+    }
+
+    // Resume after the last entry read, or before the first unreported match
+    let last_read = match unreported {
+        Some(index) if request.cursors.is_some() => index.checked_sub(1),
+        _ => entries.len().checked_sub(1),
+    };
+    if let Some(journal_cursor) = last_read.map(|i| entries[i].cursor.clone()).or(previous) {
+        result.cursors.insert(
+            name,
+            LogCursor {
+                journal_cursor: Some(journal_cursor),
+                ..LogCursor::default()
+            },
+        );
+    }
+
+    Ok(finish(result, output, &journald::source_name(unit)))
+}
+
+/// Compiled patterns and context sizes of a search request
+struct Matcher<'a> {
+    request: &'a LogSearchRequest,
+    patterns: RegexSet,
+    before: usize,
+    after: usize,
+}
+
+impl<'a> Matcher<'a> {
+    /// `None` when the request has no patterns, so nothing can match
+    fn new(request: &'a LogSearchRequest) -> Result<Option<Self>> {
+        if request.patterns.is_empty() {
+            return Ok(None);
+        }
+        let patterns = RegexSet::new(&request.patterns)
+            .map_err(|e| anyhow!("Invalid log search pattern: {}", e))?;
+        Ok(Some(Self {
+            request,
+            patterns,
+            before: request.context_before.unwrap_or(request.context_lines),
+            after: request.context_after.unwrap_or(request.context_lines),
+        }))
+    }
+
+    /// Add the matches among one source's `(offset, line)` pairs to `result`
+    /// and their grep-style lines to `output`, prefixed with the source name
+    /// when several sources are searched. Returns the index of the first
+    /// match left out because `max_matches` was reached.
+    fn search(
+        &self,
+        name: &str,
+        lines: &[(u64, String)],
+        prefix_name: bool,
+        result: &mut LogSearchResult,
+        output: &mut Vec<String>,
+    ) -> Option<usize> {
+        let mut unreported = None;
+        // Line index -> whether it matched, for the grep-style output
+        let mut printed = BTreeMap::new();
+        for (index, (offset, line)) in lines.iter().enumerate() {
+            let Some(pattern) = self.patterns.matches(line).into_iter().next() else {
+                continue;
+            };
+            if result.matches.len() >= self.request.max_matches {
+                result.truncated = true;
+                unreported = Some(index);
+                break;
+            }
+
+            let first = index.saturating_sub(self.before);
+            let last = (index + self.after).min(lines.len() - 1);
+            for context in first..=last {
+                printed.entry(context).or_insert(false);
+            }
+            printed.insert(index, true);
+
+            result.matches.push(LogMatch {
+                file: name.to_string(),
+                line_number: index + 1,
+                offset: *offset,
+                line: line.clone(),
+                pattern: self.request.patterns[pattern].clone(),
+                context_before: lines[first..index].iter().map(|(_, l)| l.clone()).collect(),
+                context_after: lines[index + 1..=last]
+                    .iter()
+                    .map(|(_, l)| l.clone())
+                    .collect(),
+                timestamp: parse_log_timestamp(line),
+            });
+        }
+
+        let mut previous = None;
+        for (index, is_match) in printed {
+            if previous.is_some_and(|previous| previous + 1 != index) {
+                output.push("--".to_string());
+            }
+            let separator = if is_match { ':' } else { '-' };
+            let line = &lines[index].1;
+            output.push(if prefix_name {
+                format!("{}{}{}{}{}", name, separator, index + 1, separator, line)
+            } else {
+                format!("{}{}{}", index + 1, separator, line)
+            });
+            previous = Some(index);
+        }
+        unreported
+    }
+}
+
+fn finish(mut result: LogSearchResult, mut output: Vec<String>, source: &str) -> LogSearchResult {
+    result.matched = !result.matches.is_empty();
+    if result.matched {
+        output.push(String::new());
+        result.output = output.join("\n");
+    } else {
+        debug!("No matches in {}", source);
+    }
+    result
+}
+
+/// Regular files directly inside `dir` whose names match `glob` (`*.log`
+/// when unset), sorted by name
+pub(super) fn log_files_in(dir: &Path, glob: Option<&str>) -> Result<Vec<PathBuf>> {
+    let glob = glob.unwrap_or("*.log");
+    let pattern =
+        glob::Pattern::new(glob).map_err(|e| anyhow!("Invalid log file glob {}: {}", glob, e))?;
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(dir)
+        .with_context(|| format!("Failed to read log directory {}", dir.display()))?
+    {
+        let entry = entry?;
+        if entry.file_type()?.is_file() && pattern.matches(&entry.file_name().to_string_lossy()) {
+            files.push(entry.path());
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Bytes hashed into a cursor's fingerprint
+const FINGERPRINT_BYTES: u64 = 256;
+
+/// Lines read from a log file, with the cursor where reading stopped
+pub(super) struct LogWindow {
+    pub lines: Vec<(u64, String)>,
+    pub cursor: LogCursor,
+    pub skipped_bytes: u64,
+}
+
+/// Read the lines of a log file written since `cursor`, or its last
+/// `max_lines` lines when there is no usable cursor. At most `max_bytes` are
+/// read either way.
+///
+/// A cursor is unusable when the file was replaced (different inode, as after
+/// rotation) or truncated (shorter than the cursor, or with different leading
+/// bytes); the file is then read from its start.
+pub(super) fn read_window(
+    path: &Path,
+    cursor: Option<&LogCursor>,
+    max_lines: usize,
+    max_bytes: u64,
+) -> Result<LogWindow> {
+    let mut file =
+        File::open(path).with_context(|| format!("Failed to read log file {}", path.display()))?;
+    let metadata = file.metadata()?;
+    let (inode, len) = (metadata.ino(), metadata.len());
+
+    let resume_at = match cursor {
+        Some(cursor) if cursor.inode == inode && cursor.offset <= len => {
+            if fingerprint(&mut file, cursor.offset)? == cursor.fingerprint {
+                Some(cursor.offset)
+            } else {
+                debug!("{} was truncated, reading from the start", path.display());
+                Some(0)
+            }
+        }
+        Some(_) => {
+            debug!(
+                "{} was replaced or truncated, reading from the start",
+                path.display()
+            );
+            Some(0)
+        }
+        None => None,
+    };
+
+    let start = match resume_at {
+        Some(offset) => offset.max(len.saturating_sub(max_bytes)),
+        None => len.saturating_sub(max_bytes),
+    };
+    let skipped_bytes = resume_at.map_or(0, |offset| start - offset);
+    if skipped_bytes > 0 {
+        warn!(
+            "Skipping {} bytes of {} beyond the {} byte search limit",
+            skipped_bytes,
+            path.display(),
+            max_bytes
+        );
+    }
+
+    file.seek(SeekFrom::Start(start))?;
+    let mut buffer = Vec::new();
+    (&mut file).take(len - start).read_to_end(&mut buffer)?;
+
+    // Starting mid-line, the first line is only partially read
+    let mut position = 0;
+    if start > 0 && resume_at != Some(start) {
+        position = buffer
+            .iter()
+            .position(|&b| b == b'\n')
+            .map_or(buffer.len(), |newline| newline + 1);
+    }
+
+    let mut lines = Vec::new();
+    let mut complete_up_to = position;
+    while position < buffer.len() {
+        let newline = buffer[position..].iter().position(|&b| b == b'\n');
+        let end = newline.map_or(buffer.len(), |i| position + i);
+        // A line still being written is searched when tailing, but picked up
+        // again in full on the next incremental search
+        if newline.is_none() && resume_at.is_some() {
+            break;
+        }
+        let line = buffer[position..end]
+            .strip_suffix(b"\r")
+            .unwrap_or(&buffer[position..end]);
+        lines.push((
+            start + position as u64,
+            String::from_utf8_lossy(line).into_owned(),
+        ));
+        position = end + 1;
+        if newline.is_some() {
+            complete_up_to = position;
+        }
+    }
+
+    if resume_at.is_none() {
+        let skip = lines.len().saturating_sub(max_lines);
+        lines.drain(..skip);
+    }
+
+    let offset = start + complete_up_to as u64;
+    Ok(LogWindow {
+        lines,
+        cursor: LogCursor {
+            inode,
+            offset,
+            fingerprint: fingerprint(&mut file, offset)?,
+            journal_cursor: None,
+        },
+        skipped_bytes,
+    })
+}
+
+pub(super) fn cursor_at(path: &Path, offset: u64) -> Result<LogCursor> {
+    let mut file =
+        File::open(path).with_context(|| format!("Failed to read log file {}", path.display()))?;
+    Ok(LogCursor {
+        inode: file.metadata()?.ino(),
+        offset,
+        fingerprint: fingerprint(&mut file, offset)?,
+        journal_cursor: None,
+    })
+}
+
+/// Hex SHA-256 of the first `min(offset, FINGERPRINT_BYTES)` bytes of a file
+fn fingerprint(file: &mut File, offset: u64) -> Result<String> {
+    file.seek(SeekFrom::Start(0))?;
+    let mut head = Vec::new();
+    file.take(offset.min(FINGERPRINT_BYTES))
+        .read_to_end(&mut head)?;
+    Ok(hex::encode(Sha256::digest(&head)))
+}
+
+/// RFC 3339 style timestamps, e.g. `2024-05-01T12:00:00.123Z`
+static ISO_TIMESTAMP: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(\d{4}-\d{2}-\d{2})[T ](\d{2}:\d{2}:\d{2}(?:\.\d{1,9})?)(Z|[+-]\d{2}:?\d{2})?")
+        .expect("valid regex")
+});
+
+/// Tendermint's legacy format, e.g. `I[2024-05-01|12:00:00.123]`
+static TENDERMINT_TIMESTAMP: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(\d{4}-\d{2}-\d{2})\|(\d{2}:\d{2}:\d{2}(?:\.\d{1,9})?)").expect("valid regex")
+});
+
+/// Unix seconds in JSON logs, e.g. `"time":1714564800`
+static EPOCH_TIMESTAMP: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#""(?:time|ts|timestamp)"\s*:\s*(\d{10})\b"#).expect("valid regex")
+});
+
+/// Timestamp of a log line in one of the formats nodes commonly write.
+/// Timestamps without a UTC offset are taken as UTC.
+pub fn parse_log_timestamp(line: &str) -> Option<DateTime<Utc>> {
+    let naive = |date: &str, time: &str| -> Option<NaiveDateTime> {
+        Some(NaiveDateTime::new(
+            NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()?,
+            NaiveTime::parse_from_str(time, "%H:%M:%S%.f").ok()?,
+        ))
+    };
+
+    if let Some(captures) = ISO_TIMESTAMP.captures(line) {
+        let local = naive(&captures[1], &captures[2])?;
+        return match captures.get(3).map(|m| m.as_str()) {
+            None | Some("Z") => Some(Utc.from_utc_datetime(&local)),
+            Some(offset) => {
+                let offset = offset.replace(':', "");
+                let sign = if offset.starts_with('-') { -1 } else { 1 };
+                let hours: i32 = offset[1..3].parse().ok()?;
+                let minutes: i32 = offset[3..5].parse().ok()?;
+                let offset = chrono::FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))?;
+                offset
+                    .from_local_datetime(&local)
+                    .single()
+                    .map(|t| t.with_timezone(&Utc))
+            }
+        };
+    }
+    if let Some(captures) = TENDERMINT_TIMESTAMP.captures(line) {
+        return naive(&captures[1], &captures[2]).map(|t| Utc.from_utc_datetime(&t));
+    }
+    if let Some(captures) = EPOCH_TIMESTAMP.captures(line) {
+        return Utc.timestamp_opt(captures[1].parse().ok()?, 0).single();
+    }
+    None
+}
@@ -0,0 +1,218 @@
+// File: agent/src/services/manifest.rs
+use anyhow::{anyhow, Context, Result};
+use chrono::Utc;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+use tracing::{info, warn};
+
+use super::filesystem::{self, CopiedFile};
+use super::job_manager::ProgressReporter;
+use crate::types::{ManifestFile, SnapshotManifest, SnapshotVerifyReport};
+
+pub const MANIFEST_FILE: &str = "snapshot_manifest.json";
+
+/// Split a `{network}_{date}_{block_height}` snapshot name into network and block height
+pub fn parse_snapshot_name(snapshot_name: &str) -> (String, Option<u64>) {
+    let mut parts = snapshot_name.rsplitn(3, '_');
+    let height = parts.next().and_then(|h| h.parse().ok());
+    let _date = parts.next();
+    match (parts.next(), height) {
+        (Some(network), Some(height)) => (network.to_string(), Some(height)),
+        _ => (snapshot_name.to_string(), None),
+    }
+}
+
+/// Build a manifest from the files a hashing copy wrote into `snapshot_path`
+pub fn build_manifest(
+    snapshot_path: &str,
+    snapshot_name: &str,
+    source_node: &str,
+    copied: &[CopiedFile],
+) -> Result<SnapshotManifest> {
+    let root = Path::new(snapshot_path);
+    let mut files = Vec::with_capacity(copied.len());
+    for file in copied {
+        let relative = file.target.strip_prefix(root).with_context(|| {
+            format!("{} is not inside {}", file.target.display(), root.display())
+        })?;
+        let sha256 = file
+            .sha256
+            .clone()
+            .ok_or_else(|| anyhow!("{} was copied without a checksum", relative.display()))?;
+        files.push(ManifestFile {
+            path: relative.to_string_lossy().to_string(),
+            size_bytes: file.size_bytes,
+            sha256,
+        });
+    }
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+
+    let (network, block_height) = parse_snapshot_name(snapshot_name);
+    Ok(SnapshotManifest {
+        network,
+        block_height,
+        source_node: source_node.to_string(),
+        created_at: Utc::now(),
+        total_bytes: files.iter().map(|f| f.size_bytes).sum(),
+        files,
+    })
+}
+
+pub async fn write_manifest(snapshot_path: &str, manifest: &SnapshotManifest) -> Result<()> {
+    let path = Path::new(snapshot_path).join(MANIFEST_FILE);
+    let tmp_path = path.with_extension("json.tmp");
+    let content = serde_json::to_vec_pretty(manifest)?;
+    tokio::fs::write(&tmp_path, content)
+        .await
+        .with_context(|| format!("Failed to write {}", tmp_path.display()))?;
+    tokio::fs::rename(&tmp_path, &path)
+        .await
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    info!(
+        "Wrote snapshot manifest {} ({} files)",
+        path.display(),
+        manifest.files.len()
+    );
+    Ok(())
+}
+
+/// Read a snapshot's manifest. Snapshots taken before manifests existed return `None`.
+pub async fn read_manifest(snapshot_path: &str) -> Result<Option<SnapshotManifest>> {
+    let path = Path::new(snapshot_path).join(MANIFEST_FILE);
+    match tokio::fs::read(&path).await {
+        Ok(content) => serde_json::from_slice(&content)
+            .map(Some)
+            .with_context(|| format!("Invalid snapshot manifest {}", path.display())),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    }
+}
+
+/// Re-hash every file of a snapshot and compare it with the manifest
+pub async fn verify_snapshot(
+    snapshot_path: &str,
+    progress: &ProgressReporter,
+) -> Result<SnapshotVerifyReport> {
+    let manifest = read_manifest(snapshot_path)
+        .await?
+        .ok_or_else(|| anyhow!("Snapshot {} has no manifest", snapshot_path))?;
+
+    progress.bytes(0, Some(manifest.total_bytes)).await;
+    info!(
+        "Verifying {} files ({:.1} MB) of snapshot {}",
+        manifest.files.len(),
+        manifest.total_bytes as f64 / 1024.0 / 1024.0,
+        snapshot_path
+    );
+
+    let root = Path::new(snapshot_path).to_path_buf();
+    let report = filesystem::run_blocking_with_progress(progress, move |bytes, cancelled| {
+        let mut report = SnapshotVerifyReport::default();
+
+        for file in &manifest.files {
+            let path = root.join(&file.path);
+            match fs::symlink_metadata(&path) {
+                Err(_) => {
+                    report.missing.push(file.path.clone());
+                    continue;
+                }
+                Ok(metadata) if !metadata.is_file() || metadata.len() != file.size_bytes => {
+                    report.mismatched.push(file.path.clone());
+                    continue;
+                }
+                Ok(_) => {}
+            }
+
+            match filesystem::hash_file(&path, bytes, cancelled) {
+                Ok((_, sha256)) if sha256 == file.sha256 => {}
+                Ok(_) => report.mismatched.push(file.path.clone()),
+                Err(e) => {
+                    warn!("Failed to hash {}: {}", path.display(), e);
+                    report.mismatched.push(file.path.clone());
+                }
+            }
+            report.files_checked += 1;
+        }
+
+        let listed: HashSet<&str> = manifest.files.iter().map(|f| f.path.as_str()).collect();
+        let mut present = Vec::new();
+        collect_files(&root, &root, &mut present);
+        report.unexpected = present
+            .into_iter()
+            .filter(|path| path != MANIFEST_FILE && !listed.contains(path.as_str()))
+            .collect();
+
+        report.bytes_checked = bytes.load(std::sync::atomic::Ordering::Relaxed);
+        report.verified = report.mismatched.is_empty()
+            && report.missing.is_empty()
+            && report.unexpected.is_empty();
+        report
+    })
+    .await?;
+
+    if report.verified {
+        info!("Snapshot {} verified against its manifest", snapshot_path);
+    } else {
+        warn!(
+            "Snapshot {} failed verification: {} mismatched, {} missing, {} unexpected",
+            snapshot_path,
+            report.mismatched.len(),
+            report.missing.len(),
+            report.unexpected.len()
+        );
+    }
+    Ok(report)
+}
+
+/// Compare the sizes and checksums of files already hashed elsewhere, e.g.
+/// while extracting an archive, with a manifest
+pub fn compare_with_manifest(
+    manifest: &SnapshotManifest,
+    files: &HashMap<String, (u64, String)>,
+) -> SnapshotVerifyReport {
+    let mut report = SnapshotVerifyReport::default();
+    for file in &manifest.files {
+        match files.get(&file.path) {
+            None => report.missing.push(file.path.clone()),
+            Some((size, sha256)) => {
+                if *size != file.size_bytes || *sha256 != file.sha256 {
+                    report.mismatched.push(file.path.clone());
+                }
+                report.files_checked += 1;
+                report.bytes_checked += size;
+            }
+        }
+    }
+
+    let listed: HashSet<&str> = manifest.files.iter().map(|f| f.path.as_str()).collect();
+    report.unexpected = files
+        .keys()
+        .filter(|path| !listed.contains(path.as_str()))
+        .cloned()
+        .collect();
+    report.unexpected.sort();
+
+    report.verified =
+        report.mismatched.is_empty() && report.missing.is_empty() && report.unexpected.is_empty();
+    report
+}
+
+/// Relative paths of all regular files under `dir`, without following symlinks
+fn collect_files(root: &Path, dir: &Path, files: &mut Vec<String>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        match entry.file_type() {
+            Ok(file_type) if file_type.is_dir() => collect_files(root, &path, files),
+            Ok(file_type) if file_type.is_file() => {
+                if let Ok(relative) = path.strip_prefix(root) {
+                    files.push(relative.to_string_lossy().to_string());
+                }
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,186 @@
+// File: agent/src/services/metrics.rs
+//! Prometheus metrics served on `/metrics`
+//!
+//! Counters are recorded where the work happens (job lifecycle, file copies,
+//! systemctl calls) through the process-wide [`global`] instance. Values that
+//! describe the current state, such as busy nodes and free disk space, are
+//! read when the endpoint is scraped.
+
+use anyhow::Result;
+use prometheus::{
+    Encoder, Gauge, HistogramOpts, HistogramVec, IntCounterVec, IntGauge, IntGaugeVec, Opts,
+    Registry, TextEncoder,
+};
+use std::sync::LazyLock;
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+use super::disk;
+use super::sandbox::RootKind;
+
+/// Upper bounds of the operation duration histogram, from seconds to hours
+const DURATION_BUCKETS: &[f64] = &[
+    1.0, 5.0, 15.0, 30.0, 60.0, 300.0, 900.0, 1800.0, 3600.0, 7200.0, 14400.0,
+];
+
+static METRICS: LazyLock<AgentMetrics> = LazyLock::new(AgentMetrics::new);
+
+/// Metrics of this agent process
+pub fn global() -> &'static AgentMetrics {
+    &METRICS
+}
+
+pub struct AgentMetrics {
+    registry: Registry,
+    started: Instant,
+    jobs_total: IntCounterVec,
+    jobs_running: IntGaugeVec,
+    operation_duration: HistogramVec,
+    bytes_copied: IntCounterVec,
+    busy_nodes: IntGauge,
+    systemctl_failures: IntCounterVec,
+    free_disk_bytes: IntGaugeVec,
+    uptime_seconds: Gauge,
+}
+
+impl Default for AgentMetrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AgentMetrics {
+    /// A separate set of metrics with its own registry
+    pub fn new() -> Self {
+        let jobs_total = IntCounterVec::new(
+            Opts::new("agent_jobs_total", "Finished jobs by operation and status"),
+            &["operation", "status"],
+        )
+        .expect("valid metric");
+        let jobs_running = IntGaugeVec::new(
+            Opts::new("agent_jobs_running", "Jobs currently running by operation"),
+            &["operation"],
+        )
+        .expect("valid metric");
+        let operation_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "agent_operation_duration_seconds",
+                "Duration of finished jobs by operation and status",
+            )
+            .buckets(DURATION_BUCKETS.to_vec()),
+            &["operation", "status"],
+        )
+        .expect("valid metric");
+        let bytes_copied = IntCounterVec::new(
+            Opts::new(
+                "agent_bytes_copied_total",
+                "Bytes written by file copies, archive creation and extraction, and downloads",
+            ),
+            &["kind"],
+        )
+        .expect("valid metric");
+        let busy_nodes = IntGauge::new("agent_busy_nodes", "Nodes with an operation in progress")
+            .expect("valid metric");
+        let systemctl_failures = IntCounterVec::new(
+            Opts::new(
+                "agent_systemctl_failures_total",
+                "systemctl calls that failed by action",
+            ),
+            &["action"],
+        )
+        .expect("valid metric");
+        let free_disk_bytes = IntGaugeVec::new(
+            Opts::new(
+                "agent_free_disk_bytes",
+                "Bytes available on the filesystem of each configured root",
+            ),
+            &["kind", "root"],
+        )
+        .expect("valid metric");
+        let uptime_seconds = Gauge::new("agent_uptime_seconds", "Seconds since the agent started")
+            .expect("valid metric");
+
+        let registry = Registry::new();
+        for collector in [
+            Box::new(jobs_total.clone()) as Box<dyn prometheus::core::Collector>,
+            Box::new(jobs_running.clone()),
+            Box::new(operation_duration.clone()),
+            Box::new(bytes_copied.clone()),
+            Box::new(busy_nodes.clone()),
+            Box::new(systemctl_failures.clone()),
+            Box::new(free_disk_bytes.clone()),
+            Box::new(uptime_seconds.clone()),
+        ] {
+            registry
+                .register(collector)
+                .expect("metric registered once");
+        }
+
+        Self {
+            registry,
+            started: Instant::now(),
+            jobs_total,
+            jobs_running,
+            operation_duration,
+            bytes_copied,
+            busy_nodes,
+            systemctl_failures,
+            free_disk_bytes,
+            uptime_seconds,
+        }
+    }
+
+    /// Record a job that ended with `status` (`completed`, `failed` or `cancelled`)
+    pub fn record_job(&self, operation: &str, status: &str, duration: Duration) {
+        self.jobs_total
+            .with_label_values(&[operation, status])
+            .inc();
+        self.operation_duration
+            .with_label_values(&[operation, status])
+            .observe(duration.as_secs_f64());
+    }
+
+    /// Record bytes written by a copy (`copy`, `archive`, `extract` or `download`)
+    pub fn record_bytes_copied(&self, kind: &str, bytes: u64) {
+        self.bytes_copied.with_label_values(&[kind]).inc_by(bytes);
+    }
+
+    /// Record a failed systemctl call (`start`, `stop`, `status` or `uptime`)
+    pub fn record_systemctl_failure(&self, action: &str) {
+        self.systemctl_failures.with_label_values(&[action]).inc();
+    }
+
+    /// Render all metrics in the Prometheus text format, after refreshing the
+    /// gauges that describe the current state
+    pub fn render(
+        &self,
+        busy_nodes: usize,
+        running_operations: &[String],
+        roots: &[(RootKind, String)],
+    ) -> Result<String> {
+        self.busy_nodes.set(busy_nodes as i64);
+
+        self.jobs_running.reset();
+        for operation in running_operations {
+            self.jobs_running.with_label_values(&[operation]).inc();
+        }
+
+        self.free_disk_bytes.reset();
+        for (kind, root) in roots {
+            match disk::available_bytes(root) {
+                Ok(bytes) => self
+                    .free_disk_bytes
+                    .with_label_values(&[&kind.to_string(), root])
+                    .set(bytes.min(i64::MAX as u64) as i64),
+                Err(e) => warn!("Could not read free space for {}: {}", root, e),
+            }
+        }
+
+        self.uptime_seconds
+            .set(self.started.elapsed().as_secs_f64());
+
+        let mut buffer = Vec::new();
+        TextEncoder::new().encode(&self.registry.gather(), &mut buffer)?;
+        Ok(String::from_utf8(buffer)?)
+    }
+}
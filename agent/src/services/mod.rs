@@ -1,6 +1,23 @@
 // File: agent/src/services/mod.rs
+pub mod archive;
 pub mod commands;
 pub mod config_editor;
+pub mod disk;
+pub mod filesystem;
 pub mod job_manager;
+pub mod journald;
+pub mod log_rotation;
+pub mod log_stream;
 pub mod logs;
+pub mod manifest;
+pub mod metrics;
+pub mod node_rpc;
+pub mod paths;
+pub mod rollback;
+pub mod sandbox;
+pub mod signing;
+pub mod snapshot_files;
 pub mod systemctl;
+pub mod telemetry;
+pub mod tls;
+pub mod transfer;
@@ -0,0 +1,75 @@
+// File: agent/src/services/node_rpc.rs
+use anyhow::{anyhow, Context, Result};
+use std::time::{Duration, Instant};
+use tracing::{debug, info};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Latest block height reported by a CometBFT node's `/status`
+pub async fn latest_block_height(client: &reqwest::Client, rpc_url: &str) -> Result<u64> {
+    let url = format!("{}/status", rpc_url.trim_end_matches('/'));
+    let status: serde_json::Value = client
+        .get(&url)
+        .timeout(REQUEST_TIMEOUT)
+        .send()
+        .await
+        .with_context(|| format!("Failed to reach {}", url))?
+        .error_for_status()?
+        .json()
+        .await
+        .with_context(|| format!("Invalid status response from {}", url))?;
+
+    status["result"]["sync_info"]["latest_block_height"]
+        .as_str()
+        .and_then(|height| height.parse().ok())
+        .ok_or_else(|| anyhow!("No latest_block_height in status response from {}", url))
+}
+
+/// Wait until the node's height has grown by `blocks` from the first height it
+/// reports. The RPC may not answer yet right after a restart, so errors are
+/// retried until `timeout`. Returns the first and last heights seen.
+pub async fn wait_for_new_blocks(
+    rpc_url: &str,
+    blocks: u64,
+    timeout: Duration,
+    poll_interval: Duration,
+) -> Result<(u64, u64)> {
+    let client = reqwest::Client::new();
+    let deadline = Instant::now() + timeout;
+    let mut start = None;
+    let mut latest = None;
+
+    loop {
+        match latest_block_height(&client, rpc_url).await {
+            Ok(height) => {
+                let first = *start.get_or_insert(height);
+                latest = Some(height);
+                if height >= first + blocks {
+                    info!(
+                        "✓ Node produced {} new blocks (height {} -> {})",
+                        height - first,
+                        first,
+                        height
+                    );
+                    return Ok((first, height));
+                }
+            }
+            Err(e) => debug!("Node RPC not ready: {}", e),
+        }
+
+        if Instant::now() + poll_interval > deadline {
+            return Err(match (start, latest) {
+                (Some(first), Some(height)) => anyhow!(
+                    "Node produced {} of {} new blocks within {:?} (height {} -> {})",
+                    height - first,
+                    blocks,
+                    timeout,
+                    first,
+                    height
+                ),
+                _ => anyhow!("Node RPC {} did not answer within {:?}", rpc_url, timeout),
+            });
+        }
+        tokio::time::sleep(poll_interval).await;
+    }
+}
@@ -0,0 +1,23 @@
+// File: agent/src/services/paths.rs
+use anyhow::Result;
+
+use crate::types::PathInfo;
+
+/// Report whether a path exists and what it is, without following it through a shell
+pub async fn check_path(path: &str) -> Result<PathInfo> {
+    match tokio::fs::metadata(path).await {
+        Ok(metadata) => Ok(PathInfo {
+            exists: true,
+            is_dir: metadata.is_dir(),
+            is_file: metadata.is_file(),
+            size_bytes: metadata.is_file().then_some(metadata.len()),
+        }),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(PathInfo {
+            exists: false,
+            is_dir: false,
+            is_file: false,
+            size_bytes: None,
+        }),
+        Err(e) => Err(e.into()),
+    }
+}
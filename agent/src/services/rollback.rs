@@ -0,0 +1,131 @@
+// File: agent/src/services/rollback.rs
+use anyhow::{Context, Result};
+use tracing::{info, warn};
+
+use super::filesystem;
+
+/// Suffix of the node's previous `data` and `wasm` directories, kept next to
+/// the restored ones until the node has proven healthy
+pub const ROLLBACK_SUFFIX: &str = ".rollback";
+
+/// Rollback copies are renamed to this before deletion, so an interrupted
+/// cleanup never leaves a half-deleted tree that looks like a rollback copy
+const DISCARD_SUFFIX: &str = ".rollback.discard";
+
+const NODE_DIRS: [&str; 2] = ["data", "wasm"];
+
+pub fn rollback_path(deploy_path: &str, dir: &str) -> String {
+    format!("{}/{}{}", deploy_path, dir, ROLLBACK_SUFFIX)
+}
+
+/// Whether a restore has moved the node's previous data aside
+pub async fn has_previous_data(deploy_path: &str) -> bool {
+    for dir in NODE_DIRS {
+        if filesystem::is_dir(&rollback_path(deploy_path, dir)).await {
+            return true;
+        }
+    }
+    false
+}
+
+/// Rename `data` and `wasm` to their rollback names. Both live in the node's
+/// home, so this is a rename on one filesystem and takes no extra space.
+pub async fn set_aside_current_data(deploy_path: &str) -> Result<()> {
+    for dir in NODE_DIRS {
+        let current = format!("{}/{}", deploy_path, dir);
+        let rollback = rollback_path(deploy_path, dir);
+
+        for stale in [
+            rollback.clone(),
+            format!("{}/{}{}", deploy_path, dir, DISCARD_SUFFIX),
+        ] {
+            if filesystem::remove_dir_all(&stale).await? {
+                warn!("Removed leftover {} from an earlier restore", stale);
+            }
+        }
+
+        if filesystem::is_dir(&current).await {
+            tokio::fs::rename(&current, &rollback)
+                .await
+                .with_context(|| format!("Failed to move {} aside to {}", current, rollback))?;
+            info!("Moved {} aside to {}", current, rollback);
+        }
+    }
+    Ok(())
+}
+
+/// Put the previous `data` and `wasm` back in place of the restored ones.
+/// The validator state with the higher height is kept, so blocks the restored
+/// node signed are never signed again. Returns a summary of the steps taken.
+pub async fn restore_previous_data(deploy_path: &str) -> Result<String> {
+    let mut steps = Vec::new();
+
+    let restored_state = format!("{}/data/priv_validator_state.json", deploy_path);
+    let previous_state = format!(
+        "{}/priv_validator_state.json",
+        rollback_path(deploy_path, "data")
+    );
+    if let Some(restored) = validator_height(&restored_state).await {
+        let signed_since = match validator_height(&previous_state).await {
+            Some(previous) => restored > previous,
+            None => true,
+        };
+        if signed_since && filesystem::is_dir(&rollback_path(deploy_path, "data")).await {
+            filesystem::copy_file_if_exists(&restored_state, &previous_state).await?;
+            steps.push(format!(
+                "kept validator state at height {} signed after the restore",
+                restored
+            ));
+        }
+    }
+
+    for dir in NODE_DIRS {
+        let current = format!("{}/{}", deploy_path, dir);
+        let rollback = rollback_path(deploy_path, dir);
+        if !filesystem::is_dir(&rollback).await {
+            continue;
+        }
+        filesystem::remove_dir_all(&current).await?;
+        tokio::fs::rename(&rollback, &current)
+            .await
+            .with_context(|| format!("Failed to move {} back to {}", rollback, current))?;
+        steps.push(format!("restored previous {}", dir));
+    }
+
+    if steps.is_empty() {
+        steps.push("no previous data to roll back to".to_string());
+    }
+    filesystem::remove_file_if_exists(&format!("{}/priv_validator_state_backup.json", deploy_path))
+        .await?;
+
+    let summary = steps.join("; ");
+    info!("Rolled back {}: {}", deploy_path, summary);
+    Ok(summary)
+}
+
+/// Delete the rollback copies once the restored node is healthy
+pub async fn discard_previous_data(deploy_path: &str) -> Result<()> {
+    for dir in NODE_DIRS {
+        let rollback = rollback_path(deploy_path, dir);
+        if !filesystem::is_dir(&rollback).await {
+            continue;
+        }
+        let discard = format!("{}/{}{}", deploy_path, dir, DISCARD_SUFFIX);
+        tokio::fs::rename(&rollback, &discard)
+            .await
+            .with_context(|| format!("Failed to move {} to {}", rollback, discard))?;
+        filesystem::remove_dir_all(&discard).await?;
+        info!("Removed previous {} kept for rollback", dir);
+    }
+    Ok(())
+}
+
+/// Height recorded in a `priv_validator_state.json`, if the file exists and is readable
+pub async fn validator_height(path: &str) -> Option<u64> {
+    let content = tokio::fs::read(path).await.ok()?;
+    let state: serde_json::Value = serde_json::from_slice(&content).ok()?;
+    match &state["height"] {
+        serde_json::Value::String(height) => height.parse().ok(),
+        height => height.as_u64(),
+    }
+}
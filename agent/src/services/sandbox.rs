@@ -0,0 +1,276 @@
+// File: agent/src/services/sandbox.rs
+//! Allowed roots for the paths operations work on
+//!
+//! Paths in requests (`deploy_path`, `backup_path`, `log_path`, ...) are
+//! checked against the roots configured under `[paths]` before anything
+//! touches the filesystem. Paths are resolved through symlinks, so a link
+//! inside a root that points elsewhere is rejected as well. Paths of a kind
+//! with no configured roots are refused, unless `allow_unconfined_paths` opts
+//! back into accepting any path.
+
+use std::fmt;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+use crate::config::PathRoots;
+
+/// Which set of roots a path must be inside
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RootKind {
+    Deploy,
+    Backup,
+    Log,
+}
+
+impl fmt::Display for RootKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            RootKind::Deploy => "deploy",
+            RootKind::Backup => "backup",
+            RootKind::Log => "log",
+        })
+    }
+}
+
+/// Why a path was rejected
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathViolation {
+    /// Relative paths depend on the agent's working directory
+    NotAbsolute { path: String },
+    /// `..` components are not accepted, even when they stay inside a root
+    ParentTraversal { path: String },
+    /// The filesystem root itself is never a valid target
+    FilesystemRoot { path: String },
+    /// The path is not below any allowed root of its kind
+    OutsideRoots { path: String, kind: RootKind },
+    /// No roots of the path's kind are configured
+    NoRoots { path: String, kind: RootKind },
+    /// The path is below a root, but a symlink resolves it to somewhere outside
+    SymlinkEscape {
+        path: String,
+        resolved: PathBuf,
+        kind: RootKind,
+    },
+    /// The path could not be resolved
+    Unresolvable { path: String, error: String },
+}
+
+impl fmt::Display for PathViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PathViolation::NotAbsolute { path } => write!(f, "Path {} is not absolute", path),
+            PathViolation::ParentTraversal { path } => {
+                write!(f, "Path {} contains '..' components", path)
+            }
+            PathViolation::FilesystemRoot { path } => {
+                write!(f, "Path {} is the filesystem root", path)
+            }
+            PathViolation::OutsideRoots { path, kind } => {
+                write!(f, "Path {} is outside the allowed {} roots", path, kind)
+            }
+            PathViolation::NoRoots { path, kind } => write!(
+                f,
+                "Path {} refused: no {} roots are configured (set [paths] {}_roots, or allow_unconfined_paths)",
+                path, kind, kind
+            ),
+            PathViolation::SymlinkEscape {
+                path,
+                resolved,
+                kind,
+            } => write!(
+                f,
+                "Path {} resolves through a symlink to {}, outside the allowed {} roots",
+                path,
+                resolved.display(),
+                kind
+            ),
+            PathViolation::Unresolvable { path, error } => {
+                write!(f, "Could not resolve path {}: {}", path, error)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PathViolation {}
+
+#[derive(Debug, Clone, Default)]
+pub struct PathSandbox {
+    roots: PathRoots,
+}
+
+impl PathSandbox {
+    pub fn new(roots: PathRoots) -> Self {
+        Self { roots }
+    }
+
+    /// Whether any roots are configured for `kind`
+    pub fn is_restricted(&self, kind: RootKind) -> bool {
+        !self.roots_for(kind).is_empty()
+    }
+
+    /// Every configured root with its kind
+    pub fn roots(&self) -> Vec<(RootKind, String)> {
+        [RootKind::Deploy, RootKind::Backup, RootKind::Log]
+            .into_iter()
+            .flat_map(|kind| {
+                self.roots_for(kind)
+                    .iter()
+                    .map(move |root| (kind, root.clone()))
+            })
+            .collect()
+    }
+
+    fn roots_for(&self, kind: RootKind) -> &[String] {
+        match kind {
+            RootKind::Deploy => &self.roots.deploy_roots,
+            RootKind::Backup => &self.roots.backup_roots,
+            RootKind::Log => &self.roots.log_roots,
+        }
+    }
+
+    /// Check that `path` is inside one of the roots of `kind`, returning the
+    /// path with symlinks resolved
+    pub fn check(&self, kind: RootKind, path: &str) -> Result<PathBuf, PathViolation> {
+        let requested = Path::new(path);
+        if !requested.is_absolute() {
+            return Err(PathViolation::NotAbsolute {
+                path: path.to_string(),
+            });
+        }
+        if requested
+            .components()
+            .any(|component| component == Component::ParentDir)
+        {
+            return Err(PathViolation::ParentTraversal {
+                path: path.to_string(),
+            });
+        }
+        let lexical: PathBuf = requested.components().collect();
+        if lexical.parent().is_none() {
+            return Err(PathViolation::FilesystemRoot {
+                path: path.to_string(),
+            });
+        }
+
+        let unresolvable = |e: io::Error| PathViolation::Unresolvable {
+            path: path.to_string(),
+            error: e.to_string(),
+        };
+        let resolved = resolve(&lexical).map_err(unresolvable)?;
+        if resolved.parent().is_none() {
+            return Err(PathViolation::FilesystemRoot {
+                path: path.to_string(),
+            });
+        }
+
+        let roots = self.roots_for(kind);
+        if roots.is_empty() {
+            if self.roots.allow_unconfined_paths {
+                return Ok(resolved);
+            }
+            return Err(PathViolation::NoRoots {
+                path: path.to_string(),
+                kind,
+            });
+        }
+
+        let mut lexically_inside = false;
+        for root in roots {
+            let root_lexical: PathBuf = Path::new(root).components().collect();
+            lexically_inside |= lexical.starts_with(&root_lexical);
+            let root_resolved = resolve(&root_lexical).map_err(unresolvable)?;
+            if resolved.starts_with(&root_resolved) {
+                return Ok(resolved);
+            }
+        }
+
+        if lexically_inside {
+            Err(PathViolation::SymlinkEscape {
+                path: path.to_string(),
+                resolved,
+                kind,
+            })
+        } else {
+            Err(PathViolation::OutsideRoots {
+                path: path.to_string(),
+                kind,
+            })
+        }
+    }
+
+    /// Check several `(kind, path)` pairs, stopping at the first violation
+    pub fn check_all(&self, paths: &[(RootKind, &str)]) -> Result<(), PathViolation> {
+        for (kind, path) in paths {
+            self.check(*kind, path)?;
+        }
+        Ok(())
+    }
+
+    /// Check a path that may belong to any kind of root, as for `/path/check`
+    pub fn check_any(&self, path: &str) -> Result<PathBuf, PathViolation> {
+        let mut violation = None;
+        for kind in [RootKind::Deploy, RootKind::Backup, RootKind::Log] {
+            match self.check(kind, path) {
+                Ok(resolved) => return Ok(resolved),
+                // A symlink escape says more than a plain miss, and a miss
+                // more than a kind without roots, so they are preferred
+                Err(e @ PathViolation::SymlinkEscape { .. }) => violation = Some(e),
+                Err(e @ PathViolation::OutsideRoots { .. }) => {
+                    if !matches!(violation, Some(PathViolation::SymlinkEscape { .. })) {
+                        violation = Some(e);
+                    }
+                }
+                Err(e @ PathViolation::NoRoots { .. }) => {
+                    violation.get_or_insert(e);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        Err(violation.expect("every root kind was checked"))
+    }
+}
+
+/// Resolve symlinks in an absolute path without `..` components. Components
+/// that do not exist yet (e.g. a backup directory about to be created) are
+/// appended to the deepest existing ancestor as they are.
+fn resolve(path: &Path) -> io::Result<PathBuf> {
+    let mut existing = path;
+    let mut missing = Vec::new();
+    loop {
+        match std::fs::canonicalize(existing) {
+            Ok(mut resolved) => {
+                resolved.extend(missing.iter().rev());
+                return Ok(resolved);
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                // A dangling symlink would be followed once something is
+                // created through it, so resolve its target instead
+                if let Ok(target) = std::fs::read_link(existing) {
+                    let parent = existing.parent().unwrap_or(Path::new("/"));
+                    let target = parent.join(target);
+                    if target.components().any(|c| c == Component::ParentDir) {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidInput,
+                            format!(
+                                "dangling symlink {} points to {}",
+                                existing.display(),
+                                target.display()
+                            ),
+                        ));
+                    }
+                    let mut resolved = resolve(&target)?;
+                    resolved.extend(missing.iter().rev());
+                    return Ok(resolved);
+                }
+                match (existing.parent(), existing.file_name()) {
+                    (Some(parent), Some(name)) => {
+                        missing.push(name);
+                        existing = parent;
+                    }
+                    _ => return Err(e),
+                }
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
@@ -0,0 +1,216 @@
+// File: agent/src/services/signing.rs
+//! HMAC request signing between the manager and agents
+//!
+//! Every request carries a timestamp, a random nonce and an HMAC-SHA256 over
+//! the canonical string
+//!
+//! ```text
+//! {METHOD}\n{path and query}\n{timestamp}\n{nonce}\n{hex sha256 of body}
+//! ```
+//!
+//! keyed with the server's API key. The agent rejects requests whose
+//! timestamp is too far from its clock and nonces it has already seen, so a
+//! captured request cannot be replayed. Up to two keys can be active at once
+//! so keys can be rotated without downtime.
+//!
+//! Other agents never get the key. An agent pulling a snapshot archive is
+//! handed a download token instead: an HMAC over the one archive it may
+//! fetch and an expiry, issued by the source agent on the manager's request.
+
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Mutex;
+
+pub const TIMESTAMP_HEADER: &str = "x-agent-timestamp";
+pub const NONCE_HEADER: &str = "x-agent-nonce";
+pub const SIGNATURE_HEADER: &str = "x-agent-signature";
+
+/// How long a snapshot archive download token stays valid. Every resumed
+/// attempt of a pull presents the token again, so it has to outlast slow
+/// downloads of large archives.
+pub const DOWNLOAD_TOKEN_TTL_SECONDS: i64 = 6 * 60 * 60;
+
+/// Largest accepted difference between the request timestamp and the agent's clock
+pub const MAX_CLOCK_SKEW_SECONDS: i64 = 300;
+
+type HmacSha256 = Hmac<Sha256>;
+
+fn canonical_request(
+    method: &str,
+    path_and_query: &str,
+    timestamp: i64,
+    nonce: &str,
+    body: &[u8],
+) -> String {
+    format!(
+        "{}\n{}\n{}\n{}\n{}",
+        method.to_ascii_uppercase(),
+        path_and_query,
+        timestamp,
+        nonce,
+        hex::encode(Sha256::digest(body))
+    )
+}
+
+/// Lowercase prefix, so the string can never equal a canonical request,
+/// whose method is uppercased
+fn canonical_download(backup_path: &str, snapshot_name: &str, expires_at: i64) -> String {
+    format!(
+        "snapshot-archive-download\n{}\n{}\n{}",
+        backup_path, snapshot_name, expires_at
+    )
+}
+
+fn mac(api_key: &str, canonical: &str) -> HmacSha256 {
+    let mut mac =
+        HmacSha256::new_from_slice(api_key.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(canonical.as_bytes());
+    mac
+}
+
+/// Hex HMAC-SHA256 signature of a request. Only the manager signs requests;
+/// the agent binary has no use for it, but tests sign with it.
+#[allow(dead_code)]
+pub fn signature(
+    api_key: &str,
+    method: &str,
+    path_and_query: &str,
+    timestamp: i64,
+    nonce: &str,
+    body: &[u8],
+) -> String {
+    let canonical = canonical_request(method, path_and_query, timestamp, nonce, body);
+    hex::encode(mac(api_key, &canonical).finalize().into_bytes())
+}
+
+/// Why a request signature was rejected
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignatureError {
+    /// Timestamp, nonce or signature header is absent or malformed
+    Missing,
+    /// Timestamp is outside the accepted clock skew
+    Stale,
+    /// Nonce was already used within the accepted window
+    Replayed,
+    /// Signature does not match any active key
+    Invalid,
+    /// Download token is past its expiry
+    Expired,
+}
+
+impl fmt::Display for SignatureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match self {
+            SignatureError::Missing => "missing or malformed signature headers",
+            SignatureError::Stale => "request timestamp outside the accepted window",
+            SignatureError::Replayed => "nonce already used",
+            SignatureError::Invalid => "signature does not match",
+            SignatureError::Expired => "download token has expired",
+        };
+        f.write_str(message)
+    }
+}
+
+impl std::error::Error for SignatureError {}
+
+/// Checks incoming request signatures against the agent's active keys and
+/// remembers recently used nonces
+pub struct RequestVerifier {
+    keys: Vec<String>,
+    seen_nonces: Mutex<HashMap<String, i64>>,
+}
+
+impl RequestVerifier {
+    /// `keys` are the active API keys; the second one is kept during rotation
+    pub fn new(keys: Vec<String>) -> Self {
+        Self {
+            keys,
+            seen_nonces: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Verify a request given its headers as `(timestamp, nonce, signature)`
+    /// and the agent's current unix time
+    pub fn verify(
+        &self,
+        method: &str,
+        path_and_query: &str,
+        headers: (Option<&str>, Option<&str>, Option<&str>),
+        body: &[u8],
+        now: i64,
+    ) -> Result<(), SignatureError> {
+        let (Some(timestamp), Some(nonce), Some(signature)) = headers else {
+            return Err(SignatureError::Missing);
+        };
+        let timestamp: i64 = timestamp.parse().map_err(|_| SignatureError::Missing)?;
+        let signature = hex::decode(signature).map_err(|_| SignatureError::Missing)?;
+        if nonce.is_empty() || nonce.len() > 128 {
+            return Err(SignatureError::Missing);
+        }
+
+        if (now - timestamp).abs() > MAX_CLOCK_SKEW_SECONDS {
+            return Err(SignatureError::Stale);
+        }
+
+        let canonical = canonical_request(method, path_and_query, timestamp, nonce, body);
+        if !self.matches_key(&canonical, &signature) {
+            return Err(SignatureError::Invalid);
+        }
+
+        // Only correctly signed requests are remembered, so unauthenticated
+        // traffic cannot fill the nonce cache
+        let mut seen = self.seen_nonces.lock().unwrap_or_else(|e| e.into_inner());
+        seen.retain(|_, seen_at| now - *seen_at <= 2 * MAX_CLOCK_SKEW_SECONDS);
+        if seen.insert(nonce.to_string(), now).is_some() {
+            return Err(SignatureError::Replayed);
+        }
+        Ok(())
+    }
+
+    fn matches_key(&self, canonical: &str, signature: &[u8]) -> bool {
+        self.keys
+            .iter()
+            .any(|key| mac(key, canonical).verify_slice(signature).is_ok())
+    }
+
+    /// Token that lets another agent download one snapshot archive from this
+    /// agent until `expires_at` (unix time), formatted `{expires_at}.{hex hmac}`
+    pub fn issue_download_token(
+        &self,
+        backup_path: &str,
+        snapshot_name: &str,
+        expires_at: i64,
+    ) -> String {
+        let canonical = canonical_download(backup_path, snapshot_name, expires_at);
+        let key = self.keys.first().map(String::as_str).unwrap_or_default();
+        format!(
+            "{}.{}",
+            expires_at,
+            hex::encode(mac(key, &canonical).finalize().into_bytes())
+        )
+    }
+
+    /// Check a download token against the archive it is presented for
+    pub fn verify_download_token(
+        &self,
+        token: &str,
+        backup_path: &str,
+        snapshot_name: &str,
+        now: i64,
+    ) -> Result<(), SignatureError> {
+        let (expires_at, signature) = token.split_once('.').ok_or(SignatureError::Missing)?;
+        let expires_at: i64 = expires_at.parse().map_err(|_| SignatureError::Missing)?;
+        let signature = hex::decode(signature).map_err(|_| SignatureError::Missing)?;
+
+        let canonical = canonical_download(backup_path, snapshot_name, expires_at);
+        if !self.matches_key(&canonical, &signature) {
+            return Err(SignatureError::Invalid);
+        }
+        if now > expires_at {
+            return Err(SignatureError::Expired);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,163 @@
+// File: agent/src/services/snapshot_files.rs
+use anyhow::{anyhow, Context, Result};
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+use tracing::{debug, info};
+
+use super::archive::{ARCHIVE_SUFFIX, PARTIAL_ARCHIVE_SUFFIX};
+use super::transfer::{DOWNLOAD_SUFFIX, VALIDATOR_SUFFIX};
+use crate::types::{ArchiveEntry, SnapshotDeleteResult, SnapshotEntry, SnapshotListing};
+
+/// Reject names that could address anything outside the backup directory
+pub fn validate_snapshot_name(name: &str) -> Result<()> {
+    if name.is_empty() || name == "." || name == ".." || name.contains('/') || name.contains('\0') {
+        return Err(anyhow!("Invalid snapshot name: {:?}", name));
+    }
+    Ok(())
+}
+
+/// List snapshot directories and archives for a network in a backup directory.
+/// Snapshots are named `{network}_{date}_{block_height}`.
+pub async fn list_snapshots(backup_path: &str, network: &str) -> Result<SnapshotListing> {
+    validate_snapshot_name(network)?;
+    let prefix = format!("{}_", network);
+
+    let mut listing = SnapshotListing::default();
+    let mut entries = match tokio::fs::read_dir(backup_path).await {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            debug!("Backup path {} does not exist yet", backup_path);
+            return Ok(listing);
+        }
+        Err(e) => {
+            return Err(e).with_context(|| format!("Failed to read {}", backup_path));
+        }
+    };
+
+    while let Some(entry) = entries.next_entry().await? {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if !name.starts_with(&prefix) {
+            continue;
+        }
+
+        // symlink_metadata so links in the backup dir are never followed
+        let metadata = match tokio::fs::symlink_metadata(entry.path()).await {
+            Ok(metadata) => metadata,
+            Err(_) => continue,
+        };
+        let modified_unix = metadata
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(0);
+
+        if metadata.is_dir() {
+            let path = entry.path();
+            listing.snapshots.push(SnapshotEntry {
+                path: path.to_string_lossy().to_string(),
+                modified_unix,
+                has_data: path.join("data").is_dir(),
+                has_wasm: path.join("wasm").is_dir(),
+                archive_size_bytes: None,
+                archive_in_progress: false,
+                name,
+            });
+        } else if metadata.is_file() {
+            let archive = ArchiveEntry {
+                size_bytes: metadata.len(),
+                modified_unix,
+                name,
+            };
+            if archive.name.ends_with(ARCHIVE_SUFFIX) {
+                listing.archives.push(archive);
+            } else if archive.name.ends_with(PARTIAL_ARCHIVE_SUFFIX) {
+                listing.partial_archives.push(archive);
+            } else if archive.name.ends_with(DOWNLOAD_SUFFIX) {
+                listing.downloads.push(archive);
+            }
+        }
+    }
+
+    for snapshot in &mut listing.snapshots {
+        let archive_name = format!("{}{}", snapshot.name, ARCHIVE_SUFFIX);
+        snapshot.archive_size_bytes = listing
+            .archives
+            .iter()
+            .find(|archive| archive.name == archive_name)
+            .map(|archive| archive.size_bytes);
+
+        let partial_name = format!("{}{}", snapshot.name, PARTIAL_ARCHIVE_SUFFIX);
+        snapshot.archive_in_progress = listing
+            .partial_archives
+            .iter()
+            .any(|archive| archive.name == partial_name);
+    }
+
+    listing
+        .snapshots
+        .sort_by_key(|s| std::cmp::Reverse(s.modified_unix));
+    listing.archives.sort_by(|a, b| a.name.cmp(&b.name));
+    listing.partial_archives.sort_by(|a, b| a.name.cmp(&b.name));
+    listing.downloads.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok(listing)
+}
+
+/// Delete a snapshot directory and its archive, including a partially written
+/// or partially downloaded one. Either may already be gone, which makes this usable for orphaned
+/// archives too.
+pub async fn delete_snapshot(
+    backup_path: &str,
+    snapshot_name: &str,
+) -> Result<SnapshotDeleteResult> {
+    validate_snapshot_name(snapshot_name)?;
+
+    let dir_path = Path::new(backup_path).join(snapshot_name);
+    let archive_path = Path::new(backup_path).join(format!("{}{}", snapshot_name, ARCHIVE_SUFFIX));
+    let leftover_paths = [PARTIAL_ARCHIVE_SUFFIX, DOWNLOAD_SUFFIX, VALIDATOR_SUFFIX]
+        .map(|suffix| Path::new(backup_path).join(format!("{}{}", snapshot_name, suffix)));
+
+    let directory_removed = match tokio::fs::symlink_metadata(&dir_path).await {
+        Ok(metadata) if metadata.is_dir() => {
+            tokio::fs::remove_dir_all(&dir_path)
+                .await
+                .with_context(|| format!("Failed to delete {}", dir_path.display()))?;
+            info!("Deleted snapshot directory {}", dir_path.display());
+            true
+        }
+        Ok(_) => {
+            return Err(anyhow!(
+                "{} exists but is not a directory",
+                dir_path.display()
+            ));
+        }
+        Err(_) => false,
+    };
+
+    let archive_removed = match tokio::fs::remove_file(&archive_path).await {
+        Ok(_) => {
+            info!("Deleted snapshot archive {}", archive_path.display());
+            true
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => false,
+        Err(e) => {
+            return Err(e).with_context(|| format!("Failed to delete {}", archive_path.display()));
+        }
+    };
+
+    for path in &leftover_paths {
+        match tokio::fs::remove_file(path).await {
+            Ok(_) => info!("Deleted unfinished archive {}", path.display()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to delete {}", path.display()));
+            }
+        }
+    }
+
+    Ok(SnapshotDeleteResult {
+        directory_removed,
+        archive_removed,
+    })
+}
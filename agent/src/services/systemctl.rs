@@ -3,6 +3,8 @@ use anyhow::{anyhow, Result};
 use tokio::process::Command as AsyncCommand;
 use tracing::{debug, info};
 
+use super::metrics;
+
 pub async fn get_service_status(service_name: &str) -> Result<String> {
     debug!("Checking service status: {}", service_name);
 
@@ -10,7 +12,8 @@ pub async fn get_service_status(service_name: &str) -> Result<String> {
         .arg("is-active")
         .arg(service_name)
         .output()
-        .await?;
+        .await
+        .inspect_err(|_| metrics::global().record_systemctl_failure("status"))?;
 
     let status = String::from_utf8_lossy(&output.stdout).trim().to_string();
     Ok(status)
@@ -24,9 +27,11 @@ pub async fn start_service(service_name: &str) -> Result<()> {
         .arg("start")
         .arg(service_name)
         .output()
-        .await?;
+        .await
+        .inspect_err(|_| metrics::global().record_systemctl_failure("start"))?;
 
     if !output.status.success() {
+        metrics::global().record_systemctl_failure("start");
         let error = String::from_utf8_lossy(&output.stderr);
         return Err(anyhow!(
             "Failed to start service {}: {}",
@@ -47,9 +52,11 @@ pub async fn stop_service(service_name: &str) -> Result<()> {
         .arg("stop")
         .arg(service_name)
         .output()
-        .await?;
+        .await
+        .inspect_err(|_| metrics::global().record_systemctl_failure("stop"))?;
 
     if !output.status.success() {
+        metrics::global().record_systemctl_failure("stop");
         let error = String::from_utf8_lossy(&output.stderr);
         return Err(anyhow!(
             "Failed to stop service {}: {}",
@@ -71,7 +78,8 @@ pub async fn get_service_uptime(service_name: &str) -> Result<u64> {
         .arg("--property=ActiveEnterTimestamp")
         .arg("--value")
         .output()
-        .await?;
+        .await
+        .inspect_err(|_| metrics::global().record_systemctl_failure("uptime"))?;
 
     let timestamp_str = String::from_utf8_lossy(&output.stdout).trim().to_string();
 
@@ -0,0 +1,335 @@
+// File: agent/src/services/telemetry.rs
+//! Host and node resource telemetry (`/telemetry`)
+//!
+//! Load and memory come from `/proc`, filesystem usage from `statvfs` on each
+//! configured root and on each requested node's deploy, backup and log paths,
+//! reported once per device. Walking a node's data directory can take minutes
+//! on large trees, so directory sizes are cached: a stale size is returned
+//! as-is while it is re-measured in the background.
+
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{debug, warn};
+
+use super::filesystem;
+use super::sandbox::{PathSandbox, RootKind};
+use crate::types::{
+    FilesystemUsage, HostTelemetry, NodeDiskUsage, NodeTelemetryRequest, TelemetryReport,
+};
+
+/// Parse the three load averages from `/proc/loadavg`
+pub fn parse_loadavg(content: &str) -> Result<[f64; 3]> {
+    let mut fields = content.split_whitespace().map(str::parse::<f64>);
+    let mut load = [0.0; 3];
+    for value in &mut load {
+        *value = fields
+            .next()
+            .ok_or_else(|| anyhow!("Truncated load average: {:?}", content))?
+            .with_context(|| format!("Invalid load average: {:?}", content))?;
+    }
+    Ok(load)
+}
+
+/// Parse `MemTotal` and `MemAvailable` from `/proc/meminfo`, in bytes
+pub fn parse_meminfo(content: &str) -> Result<(u64, u64)> {
+    let field = |name: &str| -> Result<u64> {
+        let line = content
+            .lines()
+            .find(|line| line.starts_with(name) && line[name.len()..].starts_with(':'))
+            .ok_or_else(|| anyhow!("{} missing from meminfo", name))?;
+        let kib: u64 = line[name.len() + 1..]
+            .trim()
+            .trim_end_matches("kB")
+            .trim()
+            .parse()
+            .with_context(|| format!("Invalid {} in meminfo", name))?;
+        Ok(kib * 1024)
+    };
+    Ok((field("MemTotal")?, field("MemAvailable")?))
+}
+
+/// Size, used and available bytes of the filesystem holding `root`
+pub fn filesystem_usage(kind: RootKind, root: &str) -> Result<FilesystemUsage> {
+    let stat = rustix::fs::statvfs(root)
+        .with_context(|| format!("Failed to read filesystem stats for {}", root))?;
+    Ok(FilesystemUsage {
+        kind: kind.to_string(),
+        root: root.to_string(),
+        paths: vec![root.to_string()],
+        total_bytes: stat.f_blocks.saturating_mul(stat.f_frsize),
+        used_bytes: stat
+            .f_blocks
+            .saturating_sub(stat.f_bfree)
+            .saturating_mul(stat.f_frsize),
+        available_bytes: stat.f_bavail.saturating_mul(stat.f_frsize),
+    })
+}
+
+/// Usage of the filesystems holding `paths`, one entry per device. The first
+/// path on a device names it as `root`; every path on it is listed in
+/// `paths`. A path that does not exist yet is placed on the filesystem of its
+/// nearest existing ancestor. Only filesystem stats are read, so node paths
+/// outside the sandbox roots are measured too.
+pub fn filesystems_holding(paths: &[(RootKind, String)]) -> Vec<FilesystemUsage> {
+    let mut filesystems: Vec<FilesystemUsage> = Vec::new();
+    let mut devices: HashMap<u64, usize> = HashMap::new();
+    for (kind, path) in paths {
+        let Some(existing) = Path::new(path).ancestors().find(|p| p.exists()) else {
+            warn!("Skipping {} path {}: no existing ancestor", kind, path);
+            continue;
+        };
+        let device = match rustix::fs::stat(existing) {
+            Ok(stat) => stat.st_dev,
+            Err(e) => {
+                warn!("Skipping {} path {}: {}", kind, path, e);
+                continue;
+            }
+        };
+
+        if let Some(&index) = devices.get(&device) {
+            let held = &mut filesystems[index].paths;
+            if !held.contains(path) {
+                held.push(path.clone());
+            }
+            continue;
+        }
+        match filesystem_usage(*kind, &existing.to_string_lossy()) {
+            Ok(mut usage) => {
+                usage.root = path.clone();
+                usage.paths = vec![path.clone()];
+                devices.insert(device, filesystems.len());
+                filesystems.push(usage);
+            }
+            Err(e) => warn!("Skipping {} path {}: {}", kind, path, e),
+        }
+    }
+    filesystems
+}
+
+/// Host load and memory, plus usage of the filesystems holding `paths`
+pub async fn host_telemetry(paths: &[(RootKind, String)]) -> Result<HostTelemetry> {
+    let load_average = parse_loadavg(
+        &tokio::fs::read_to_string("/proc/loadavg")
+            .await
+            .context("Failed to read /proc/loadavg")?,
+    )?;
+    let (memory_total_bytes, memory_available_bytes) = parse_meminfo(
+        &tokio::fs::read_to_string("/proc/meminfo")
+            .await
+            .context("Failed to read /proc/meminfo")?,
+    )?;
+
+    Ok(HostTelemetry {
+        load_average,
+        cpu_count: std::thread::available_parallelism().map_or(1, |n| n.get()),
+        memory_total_bytes,
+        memory_available_bytes,
+        filesystems: filesystems_holding(paths),
+    })
+}
+
+/// How long a request waits for the first measurement of a directory
+const FIRST_MEASUREMENT_WAIT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy)]
+pub struct CachedSize {
+    pub bytes: u64,
+    pub measured_at: DateTime<Utc>,
+}
+
+/// Directory sizes, re-measured once older than the configured age
+pub struct SizeCache {
+    max_age: Duration,
+    sizes: Mutex<HashMap<String, CachedSize>>,
+    refreshing: Mutex<HashSet<String>>,
+}
+
+impl SizeCache {
+    pub fn new(max_age: Duration) -> Self {
+        Self {
+            max_age,
+            sizes: Mutex::new(HashMap::new()),
+            refreshing: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// Size of the tree at `path`, or `None` if it does not exist or has not
+    /// been measured yet. Stale sizes are returned immediately and refreshed in
+    /// the background; unknown paths are measured in the background too, and
+    /// waited for up to `FIRST_MEASUREMENT_WAIT` so small trees are reported
+    /// on the first request.
+    pub async fn size(self: &Arc<Self>, path: &str) -> Option<CachedSize> {
+        match self.cached(path) {
+            Some(cached) => {
+                if self.is_stale(&cached) {
+                    self.refresh_in_background(path);
+                }
+                Some(cached)
+            }
+            None => {
+                self.refresh_in_background(path);
+                let deadline = Instant::now() + FIRST_MEASUREMENT_WAIT;
+                while Instant::now() < deadline && self.is_refreshing(path) {
+                    tokio::time::sleep(Duration::from_millis(50)).await;
+                }
+                self.cached(path)
+            }
+        }
+    }
+
+    fn cached(&self, path: &str) -> Option<CachedSize> {
+        self.sizes
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(path)
+            .copied()
+    }
+
+    fn is_stale(&self, cached: &CachedSize) -> bool {
+        let age = Utc::now().signed_duration_since(cached.measured_at);
+        age.to_std().unwrap_or_default() >= self.max_age
+    }
+
+    fn is_refreshing(&self, path: &str) -> bool {
+        self.refreshing
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .contains(path)
+    }
+
+    fn refresh_in_background(self: &Arc<Self>, path: &str) {
+        let started = self
+            .refreshing
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(path.to_string());
+        if !started {
+            return;
+        }
+
+        let cache = self.clone();
+        let path = path.to_string();
+        tokio::spawn(async move {
+            if let Err(e) = cache.measure(&path).await {
+                warn!("Failed to measure size of {}: {}", path, e);
+            }
+            cache
+                .refreshing
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .remove(&path);
+        });
+    }
+
+    async fn measure(&self, path: &str) -> Result<()> {
+        if tokio::fs::symlink_metadata(path).await.is_err() {
+            self.sizes
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .remove(path);
+            return Ok(());
+        }
+
+        let started = Instant::now();
+        let measured = CachedSize {
+            bytes: filesystem::dir_size(path).await?,
+            measured_at: Utc::now(),
+        };
+        debug!("Measured {} in {:?}", path, started.elapsed());
+        self.sizes
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(path.to_string(), measured);
+        Ok(())
+    }
+}
+
+enum NodeDir {
+    Data,
+    Wasm,
+    Log,
+}
+
+/// Sizes of a node's `data`, `wasm` and log directories
+pub async fn node_disk_usage(
+    cache: &Arc<SizeCache>,
+    sandbox: &PathSandbox,
+    node: &NodeTelemetryRequest,
+) -> NodeDiskUsage {
+    let mut usage = NodeDiskUsage {
+        node_name: node.node_name.clone(),
+        ..NodeDiskUsage::default()
+    };
+
+    let mut measurements = Vec::new();
+    if let Some(deploy_path) = &node.deploy_path {
+        measurements.push((NodeDir::Data, format!("{}/data", deploy_path)));
+        measurements.push((NodeDir::Wasm, format!("{}/wasm", deploy_path)));
+    }
+    if let Some(log_path) = &node.log_path {
+        measurements.push((NodeDir::Log, log_path.clone()));
+    }
+
+    let mut errors = Vec::new();
+    for (dir, path) in measurements {
+        let kind = match dir {
+            NodeDir::Data | NodeDir::Wasm => RootKind::Deploy,
+            NodeDir::Log => RootKind::Log,
+        };
+        if let Err(violation) = sandbox.check(kind, &path) {
+            errors.push(violation.to_string());
+            continue;
+        }
+        let Some(size) = cache.size(&path).await else {
+            continue;
+        };
+
+        let slot = match dir {
+            NodeDir::Data => &mut usage.data_bytes,
+            NodeDir::Wasm => &mut usage.wasm_bytes,
+            NodeDir::Log => &mut usage.log_bytes,
+        };
+        *slot = Some(size.bytes);
+        usage.measured_at = Some(match usage.measured_at {
+            Some(oldest) => oldest.min(size.measured_at),
+            None => size.measured_at,
+        });
+    }
+
+    if !errors.is_empty() {
+        usage.error = Some(errors.join("; "));
+    }
+    usage
+}
+
+pub async fn collect(
+    cache: &Arc<SizeCache>,
+    sandbox: &PathSandbox,
+    nodes: &[NodeTelemetryRequest],
+) -> Result<TelemetryReport> {
+    let mut paths = sandbox.roots();
+    for node in nodes {
+        for (kind, path) in [
+            (RootKind::Deploy, &node.deploy_path),
+            (RootKind::Backup, &node.backup_path),
+            (RootKind::Log, &node.log_path),
+        ] {
+            if let Some(path) = path {
+                paths.push((kind, path.clone()));
+            }
+        }
+    }
+    let host = host_telemetry(&paths).await?;
+    let mut usages = Vec::with_capacity(nodes.len());
+    for node in nodes {
+        usages.push(node_disk_usage(cache, sandbox, node).await);
+    }
+    Ok(TelemetryReport {
+        host,
+        nodes: usages,
+    })
+}
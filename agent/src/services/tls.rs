@@ -0,0 +1,104 @@
+// File: agent/src/services/tls.rs
+use anyhow::{anyhow, Context, Result};
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use rustls::server::WebPkiClientVerifier;
+use rustls::RootCertStore;
+use serde::Deserialize;
+use std::sync::Arc;
+
+/// Certificate and key the agent serves HTTPS with. When `client_ca_path` is
+/// set, clients must present a certificate issued by one of its CAs (mutual TLS).
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TlsSettings {
+    pub cert_path: String,
+    /// PEM private key (PKCS#8, PKCS#1 or SEC1), also used as the client
+    /// identity for peer pulls
+    pub key_path: String,
+    #[serde(default)]
+    pub client_ca_path: Option<String>,
+}
+
+impl TlsSettings {
+    /// Read `AGENT_TLS_CERT`, `AGENT_TLS_KEY` and `AGENT_TLS_CLIENT_CA`.
+    /// Returns `None` when TLS is not configured.
+    pub fn from_env() -> Result<Option<Self>> {
+        let cert_path = std::env::var("AGENT_TLS_CERT").ok();
+        let key_path = std::env::var("AGENT_TLS_KEY").ok();
+        let client_ca_path = std::env::var("AGENT_TLS_CLIENT_CA").ok();
+
+        match (cert_path, key_path) {
+            (Some(cert_path), Some(key_path)) => Ok(Some(Self {
+                cert_path,
+                key_path,
+                client_ca_path,
+            })),
+            (None, None) if client_ca_path.is_none() => Ok(None),
+            _ => Err(anyhow!(
+                "AGENT_TLS_CERT and AGENT_TLS_KEY must both be set to enable TLS"
+            )),
+        }
+    }
+
+    pub fn requires_client_cert(&self) -> bool {
+        self.client_ca_path.is_some()
+    }
+
+    /// rustls server configuration for the agent's listener
+    pub fn server_config(&self) -> Result<rustls::ServerConfig> {
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let certs = load_certs(&self.cert_path)?;
+        let key = PrivateKeyDer::from_pem_file(&self.key_path)
+            .with_context(|| format!("Failed to load private key {}", self.key_path))?;
+
+        let builder = rustls::ServerConfig::builder_with_provider(provider.clone())
+            .with_safe_default_protocol_versions()?;
+        let builder = match &self.client_ca_path {
+            Some(ca_path) => {
+                let mut roots = RootCertStore::empty();
+                for cert in load_certs(ca_path)? {
+                    roots
+                        .add(cert)
+                        .with_context(|| format!("Invalid CA certificate in {}", ca_path))?;
+                }
+                let verifier =
+                    WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider)
+                        .build()
+                        .with_context(|| format!("Invalid client CA bundle {}", ca_path))?;
+                builder.with_client_cert_verifier(verifier)
+            }
+            None => builder.with_no_client_auth(),
+        };
+
+        builder
+            .with_single_cert(certs, key)
+            .with_context(|| format!("Certificate {} does not match its key", self.cert_path))
+    }
+
+    /// The agent's own certificate as a client identity, presented when pulling
+    /// archives from peer agents that require mutual TLS
+    pub fn client_identity(&self) -> Result<reqwest::Identity> {
+        let cert = std::fs::read(&self.cert_path)
+            .with_context(|| format!("Failed to read certificate {}", self.cert_path))?;
+        let key = std::fs::read(&self.key_path)
+            .with_context(|| format!("Failed to read private key {}", self.key_path))?;
+        reqwest::Identity::from_pem(&[cert, key].concat()).with_context(|| {
+            format!(
+                "Certificate {} is not usable as a client identity",
+                self.cert_path
+            )
+        })
+    }
+}
+
+fn load_certs(path: &str) -> Result<Vec<CertificateDer<'static>>> {
+    let certs = CertificateDer::pem_file_iter(path)
+        .with_context(|| format!("Failed to read certificates from {}", path))?
+        .collect::<Result<Vec<_>, _>>()
+        .with_context(|| format!("Invalid certificate in {}", path))?;
+    if certs.is_empty() {
+        return Err(anyhow!("No certificates found in {}", path));
+    }
+    Ok(certs)
+}
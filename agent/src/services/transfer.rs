@@ -0,0 +1,334 @@
+// File: agent/src/services/transfer.rs
+use anyhow::{anyhow, Context, Result};
+use axum::body::Body;
+use axum::http::{header, Request, StatusCode};
+use axum::response::{IntoResponse, Response};
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tower::ServiceExt;
+use tower_http::services::ServeFile;
+use tracing::{info, warn};
+
+use super::archive::{self, ARCHIVE_SUFFIX};
+use super::disk;
+use super::job_manager::ProgressReporter;
+use super::metrics;
+use super::snapshot_files::validate_snapshot_name;
+use crate::types::{SnapshotPullRequest, SnapshotTransferResult};
+
+/// Downloads land under this suffix, distinct from `.tar.lz4.partial` so a
+/// half-written local compression is never resumed as if it were a download
+pub const DOWNLOAD_SUFFIX: &str = ".tar.lz4.download";
+
+/// Next to each download, the `ETag` or `Last-Modified` of the archive it was
+/// started from, sent as `If-Range` so a changed archive is never resumed
+pub const VALIDATOR_SUFFIX: &str = ".tar.lz4.download.validator";
+
+const MAX_ATTEMPTS: u32 = 5;
+const RETRY_DELAY: Duration = Duration::from_secs(5);
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+/// Longest silence tolerated mid-download before the attempt is retried
+const READ_TIMEOUT: Duration = Duration::from_secs(120);
+const PROGRESS_EVERY_BYTES: u64 = 64 * 1024 * 1024;
+
+pub fn download_path(backup_path: &str, snapshot_name: &str) -> String {
+    format!("{}/{}{}", backup_path, snapshot_name, DOWNLOAD_SUFFIX)
+}
+
+pub fn validator_path(backup_path: &str, snapshot_name: &str) -> String {
+    format!("{}/{}{}", backup_path, snapshot_name, VALIDATOR_SUFFIX)
+}
+
+/// `Last-Modified` value of a file, in the format `ServeFile` sends it
+fn last_modified(modified: std::time::SystemTime) -> String {
+    chrono::DateTime::<chrono::Utc>::from(modified)
+        .format("%a, %d %b %Y %H:%M:%S GMT")
+        .to_string()
+}
+
+/// Serve a complete snapshot archive to another agent. Range requests are
+/// honoured so interrupted downloads can resume, unless an `If-Range` no
+/// longer matches the archive's `Last-Modified`, in which case the whole
+/// archive is sent. The caller checks the download token first.
+pub async fn serve_archive(
+    backup_path: &str,
+    snapshot_name: &str,
+    mut request: Request<Body>,
+) -> Response {
+    if let Err(e) = validate_snapshot_name(snapshot_name) {
+        return (StatusCode::BAD_REQUEST, e.to_string()).into_response();
+    }
+
+    let path = archive::archive_path(backup_path, snapshot_name);
+    if !super::filesystem::is_file(&path).await {
+        return (
+            StatusCode::NOT_FOUND,
+            format!("No complete archive {}{}", snapshot_name, ARCHIVE_SUFFIX),
+        )
+            .into_response();
+    }
+
+    if let Some(if_range) = request.headers().get(header::IF_RANGE).cloned() {
+        let current = tokio::fs::metadata(&path)
+            .await
+            .and_then(|m| m.modified())
+            .map(last_modified)
+            .ok();
+        if current.as_deref().map(str::as_bytes) != Some(if_range.as_bytes()) {
+            info!("Archive {} changed since the download started", path);
+            request.headers_mut().remove(header::RANGE);
+        }
+    }
+
+    info!("Serving snapshot archive {}", path);
+    match ServeFile::new(&path).oneshot(request).await {
+        Ok(response) => response.into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+/// Pull a snapshot archive from another agent into `backup_path`. The archive
+/// is downloaded to a `.tar.lz4.download` file that is resumed with range
+/// requests after failures (or on a later pull) and renamed once complete.
+/// Resumes are conditional on the archive being unchanged at the source, and
+/// each attempt checks the backup filesystem can take the rest of it.
+/// The source authorizes it with the download token it issued for the
+/// archive; `client_identity` is presented to sources that require mutual TLS.
+pub async fn pull_archive(
+    request: &SnapshotPullRequest,
+    client_identity: Option<reqwest::Identity>,
+    progress: &ProgressReporter,
+) -> Result<SnapshotTransferResult> {
+    validate_snapshot_name(&request.snapshot_name)?;
+
+    let final_path = archive::archive_path(&request.backup_path, &request.snapshot_name);
+    if let Ok(metadata) = tokio::fs::metadata(&final_path).await {
+        info!("Archive {} is already present, nothing to pull", final_path);
+        return Ok(SnapshotTransferResult {
+            archive_path: final_path,
+            size_bytes: metadata.len(),
+            resumed_from_bytes: 0,
+            attempts: 0,
+            already_present: true,
+        });
+    }
+
+    super::filesystem::create_dir_all(&request.backup_path).await?;
+    let download = download_path(&request.backup_path, &request.snapshot_name);
+    let validator = validator_path(&request.backup_path, &request.snapshot_name);
+    let url = format!(
+        "{}/snapshot/archive",
+        request.source_url.trim_end_matches('/')
+    );
+    let mut builder = reqwest::Client::builder()
+        .connect_timeout(CONNECT_TIMEOUT)
+        .read_timeout(READ_TIMEOUT);
+    if let Some(ca_pem) = &request.source_ca_cert {
+        for cert in reqwest::Certificate::from_pem_bundle(ca_pem.as_bytes())
+            .context("Invalid source CA bundle")?
+        {
+            builder = builder.add_root_certificate(cert);
+        }
+    }
+    if let Some(identity) = client_identity {
+        builder = builder.identity(identity);
+    }
+    let client = builder.build()?;
+
+    progress.step("Downloading snapshot archive").await;
+    let mut resumed_from_bytes = None;
+    let mut attempts = 0;
+    let total = loop {
+        attempts += 1;
+        match download_attempt(
+            &client,
+            &url,
+            request,
+            &download,
+            &validator,
+            &mut resumed_from_bytes,
+            progress,
+        )
+        .await
+        {
+            Ok(total) => break total,
+            // Retrying cannot free up space
+            Err(e) if e.to_string().starts_with(disk::INSUFFICIENT_SPACE) => return Err(e),
+            Err(e) if attempts < MAX_ATTEMPTS => {
+                warn!(
+                    "Download of {} failed (attempt {}/{}), resuming in {:?}: {}",
+                    request.snapshot_name, attempts, MAX_ATTEMPTS, RETRY_DELAY, e
+                );
+                tokio::time::sleep(RETRY_DELAY * attempts).await;
+            }
+            Err(e) => {
+                return Err(e.context(format!(
+                    "Failed to pull {} from {} after {} attempts (partial download kept at {})",
+                    request.snapshot_name, request.source_url, attempts, download
+                )));
+            }
+        }
+    };
+
+    tokio::fs::rename(&download, &final_path)
+        .await
+        .with_context(|| format!("Failed to move download into place at {}", final_path))?;
+    super::filesystem::remove_file_if_exists(&validator).await?;
+    info!(
+        "Pulled {} ({:.1} MB) from {} in {} attempt(s)",
+        final_path,
+        total as f64 / 1024.0 / 1024.0,
+        request.source_url,
+        attempts
+    );
+
+    Ok(SnapshotTransferResult {
+        archive_path: final_path,
+        size_bytes: total,
+        resumed_from_bytes: resumed_from_bytes.unwrap_or(0),
+        attempts,
+        already_present: false,
+    })
+}
+
+/// Fetch the rest of the archive from the current download size, as long as
+/// the archive is still the one the download was started from. Returns the
+/// archive's total size once the download file holds all of it. The offset the
+/// first attempt continues from is recorded in `resumed_from`.
+async fn download_attempt(
+    client: &reqwest::Client,
+    url: &str,
+    request: &SnapshotPullRequest,
+    download: &str,
+    validator: &str,
+    resumed_from: &mut Option<u64>,
+    progress: &ProgressReporter,
+) -> Result<u64> {
+    let mut offset = resumable_size(download, validator).await;
+    let mut get = client
+        .get(url)
+        .query(&[
+            ("backup_path", request.source_backup_path.as_str()),
+            ("snapshot_name", request.snapshot_name.as_str()),
+        ])
+        .bearer_auth(&request.source_token);
+    if offset > 0 {
+        let if_range = tokio::fs::read_to_string(validator).await?;
+        get = get
+            .header(header::RANGE, format!("bytes={}-", offset))
+            .header(header::IF_RANGE, if_range);
+    }
+    let mut response = get
+        .send()
+        .await
+        .with_context(|| format!("Failed to reach {}", request.source_url))?;
+
+    let total = match response.status() {
+        StatusCode::PARTIAL_CONTENT if offset > 0 => {
+            content_range_total(&response).ok_or_else(|| anyhow!("Missing Content-Range"))?
+        }
+        StatusCode::RANGE_NOT_SATISFIABLE => {
+            // Either the download is already complete or the local file is
+            // longer than the source archive; the latter cannot be resumed
+            let total = content_range_total(&response).unwrap_or(0);
+            if total > 0 && total == offset {
+                return Ok(total);
+            }
+            super::filesystem::remove_file_if_exists(download).await?;
+            return Err(anyhow!(
+                "Local download is larger than the source archive, starting over"
+            ));
+        }
+        StatusCode::OK => {
+            // A fresh download, or the archive changed since the download
+            // started: the body is the whole archive
+            offset = 0;
+            let total = response
+                .content_length()
+                .ok_or_else(|| anyhow!("Missing Content-Length"))?;
+            match response_validator(&response) {
+                Some(value) => tokio::fs::write(validator, value)
+                    .await
+                    .with_context(|| format!("Failed to write {}", validator))?,
+                None => {
+                    super::filesystem::remove_file_if_exists(validator).await?;
+                }
+            }
+            total
+        }
+        status => {
+            let body = response.text().await.unwrap_or_default();
+            return Err(anyhow!("Source agent returned {}: {}", status, body.trim()));
+        }
+    };
+
+    resumed_from.get_or_insert(offset);
+    disk::ensure_space(&request.backup_path, total - offset, "snapshot pull")?;
+
+    progress.bytes(offset, Some(total)).await;
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(offset == 0)
+        .append(offset > 0)
+        .open(download)
+        .await
+        .with_context(|| format!("Failed to open {}", download))?;
+
+    let mut written = offset;
+    let mut reported = offset;
+    while let Some(chunk) = response.chunk().await? {
+        file.write_all(&chunk).await?;
+        written += chunk.len() as u64;
+        if written - reported >= PROGRESS_EVERY_BYTES {
+            progress.bytes(written, None).await;
+            reported = written;
+        }
+    }
+    file.flush().await?;
+    file.sync_all().await?;
+    progress.bytes(written, None).await;
+    metrics::global().record_bytes_copied("download", written - offset);
+
+    if written != total {
+        return Err(anyhow!("Download ended at {} of {} bytes", written, total));
+    }
+    Ok(total)
+}
+
+/// Bytes of `download` that can be resumed: none unless the validator of the
+/// archive it was started from was stored next to it
+async fn resumable_size(download: &str, validator: &str) -> u64 {
+    if !super::filesystem::is_file(validator).await {
+        return 0;
+    }
+    tokio::fs::metadata(download)
+        .await
+        .map(|m| m.len())
+        .unwrap_or(0)
+}
+
+/// Strong `ETag`, or else `Last-Modified`, of a full archive response; weak
+/// entity tags cannot be used in `If-Range`
+fn response_validator(response: &reqwest::Response) -> Option<String> {
+    let header = |name| {
+        let value = response.headers().get(name)?.to_str().ok()?;
+        Some(value.to_string())
+    };
+    header(header::ETAG)
+        .filter(|etag| !etag.starts_with("W/"))
+        .or_else(|| header(header::LAST_MODIFIED))
+}
+
+/// Total size from a `Content-Range: bytes a-b/total` (or `bytes */total`) header
+fn content_range_total(response: &reqwest::Response) -> Option<u64> {
+    response
+        .headers()
+        .get(header::CONTENT_RANGE)?
+        .to_str()
+        .ok()?
+        .rsplit('/')
+        .next()?
+        .parse()
+        .ok()
+}
@@ -1,6 +1,7 @@
 // File: agent/src/types.rs
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 // === REQUEST STRUCTURES ===
 
@@ -18,6 +19,48 @@ pub struct ServiceRequest {
 pub struct LogTruncateRequest {
     pub log_path: String,
     pub service_name: String,
+    /// Archive the logs before clearing them instead of only truncating
+    #[serde(default)]
+    pub log_rotation: Option<LogRotation>,
+}
+
+/// How log files are archived before an operation clears them
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LogRotation {
+    #[serde(default)]
+    pub compression: LogCompression,
+    /// Archives kept per log file; 10 when neither limit is set
+    #[serde(default)]
+    pub keep_generations: Option<usize>,
+    /// Total size of the archives kept per log file
+    #[serde(default)]
+    pub keep_bytes: Option<u64>,
+    /// Files rotated in a log directory, `*.log` when unset
+    #[serde(default)]
+    pub log_glob: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogCompression {
+    #[default]
+    Gzip,
+    Zstd,
+    None,
+}
+
+/// Request of `POST /logs/archives`
+#[derive(Debug, Deserialize)]
+pub struct LogArchiveListRequest {
+    /// Log file or directory whose archives are listed
+    pub log_path: String,
+}
+
+/// Query of `GET /logs/archive`
+#[derive(Debug, Deserialize)]
+pub struct LogArchiveDownloadQuery {
+    pub log_path: String,
+    pub name: String,
 }
 
 // NEW: Request to delete all files in a directory
@@ -33,6 +76,8 @@ pub struct PruningRequest {
     pub keep_versions: u64,
     pub service_name: String,
     pub log_path: Option<String>,
+    #[serde(default)]
+    pub log_rotation: Option<LogRotation>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -43,15 +88,212 @@ pub struct SnapshotRequest {
     pub backup_path: String,
     pub service_name: String,
     pub log_path: Option<String>,
+    #[serde(default)]
+    pub log_rotation: Option<LogRotation>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct RestoreRequest {
     pub node_name: String,
     pub deploy_path: String,
-    pub snapshot_dir: String, // FIXED: Changed from snapshot_file to snapshot_dir
+    /// Snapshot directory to copy from; ignored when `archive_path` is set
+    #[serde(default)]
+    pub snapshot_dir: String,
+    /// `.tar.lz4` archive to extract instead of copying a directory
+    #[serde(default)]
+    pub archive_path: Option<String>,
     pub service_name: String,
     pub log_path: Option<String>,
+    #[serde(default)]
+    pub log_rotation: Option<LogRotation>,
+    /// Restore even if the snapshot fails checksum verification
+    #[serde(default)]
+    pub force: bool,
+    /// Node RPC polled after the restart. Without it the previous data is
+    /// discarded as soon as the service is active.
+    #[serde(default)]
+    pub rpc_url: Option<String>,
+    /// New blocks the node must produce before its previous data is discarded
+    #[serde(default = "default_rollback_after_blocks")]
+    pub rollback_after_blocks: u64,
+}
+
+fn default_rollback_after_blocks() -> u64 {
+    5
+}
+
+/// Query of `GET /snapshot/archive`, served to other agents. Also the body of
+/// `POST /snapshot/archive/token`, which issues the token the download needs.
+#[derive(Debug, Deserialize)]
+pub struct ArchiveDownloadQuery {
+    pub backup_path: String,
+    pub snapshot_name: String,
+}
+
+/// Token authorizing another agent to download one snapshot archive
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ArchiveDownloadToken {
+    pub token: String,
+    /// Unix time after which the token is refused
+    pub expires_at: i64,
+}
+
+/// Pull a snapshot archive from another agent into this server's backup path
+#[derive(Debug, Clone, Deserialize)]
+pub struct SnapshotPullRequest {
+    /// Base URL of the source agent, e.g. `https://10.0.0.5:8745`
+    pub source_url: String,
+    /// Token the source agent issued for this archive (`/snapshot/archive/token`)
+    pub source_token: String,
+    /// PEM CA bundle to trust for an `https` source, in addition to the system roots
+    #[serde(default)]
+    pub source_ca_cert: Option<String>,
+    pub source_backup_path: String,
+    pub snapshot_name: String,
+    /// Local backup path the archive is written to
+    pub backup_path: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SnapshotVerifyRequest {
+    pub snapshot_dir: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SnapshotListRequest {
+    pub backup_path: String,
+    pub network: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SnapshotDeleteRequest {
+    pub backup_path: String,
+    pub snapshot_name: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct LogSearchRequest {
+    /// Log file, or a directory whose files matching `glob` are searched
+    #[serde(alias = "log_file", default)]
+    pub path: String,
+    /// File name pattern within a directory `path`; `*.log` when unset
+    #[serde(default)]
+    pub glob: Option<String>,
+    /// Search this systemd unit's journal instead of `path`
+    #[serde(default)]
+    pub journald_unit: Option<String>,
+    /// Regular expressions in the `regex` crate syntax; a line matching any
+    /// of them is reported
+    pub patterns: Vec<String>,
+    /// Lines searched from the end of each file
+    #[serde(default = "default_tail_lines")]
+    pub tail_lines: usize,
+    /// Bytes read from the end of each file (or of journal messages),
+    /// bounding `tail_lines`
+    #[serde(default = "default_tail_bytes")]
+    pub tail_bytes: u64,
+    /// Context on both sides of a match, unless overridden below
+    #[serde(default)]
+    pub context_lines: usize,
+    pub context_before: Option<usize>,
+    pub context_after: Option<usize>,
+    #[serde(default = "default_max_matches")]
+    pub max_matches: usize,
+    /// Where the previous search of each file stopped, keyed by file path
+    /// (`journald:<unit>` for a journal). When set, only content written
+    /// since is searched; files without a cursor are searched from their tail
+    /// as usual.
+    pub cursors: Option<HashMap<String, LogCursor>>,
+}
+
+/// Read position in a log file or journal
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LogCursor {
+    pub inode: u64,
+    /// Byte offset just past the last complete line read
+    pub offset: u64,
+    /// SHA-256 of the file's first `min(offset, 256)` bytes, to notice a file
+    /// that was truncated and has grown past `offset` again
+    pub fingerprint: String,
+    /// Journal cursor of the last entry read, for journald sources; the file
+    /// fields are then unused
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub journal_cursor: Option<String>,
+}
+
+fn default_tail_lines() -> usize {
+    5000
+}
+
+pub(crate) fn default_tail_bytes() -> u64 {
+    16 * 1024 * 1024
+}
+
+pub(crate) fn default_max_matches() -> usize {
+    100
+}
+
+/// Query of `GET /logs/stream`
+#[derive(Debug, Clone, Deserialize)]
+pub struct LogStreamQuery {
+    /// Log file, or a directory whose files matching `glob` are followed
+    #[serde(default)]
+    pub path: String,
+    /// File name pattern within a directory `path`; `*.log` when unset
+    pub glob: Option<String>,
+    /// Follow this systemd unit's journal instead of `path`
+    pub journald_unit: Option<String>,
+    /// Only lines matching this regular expression are sent
+    pub filter: Option<String>,
+    /// Lines of existing output sent from the end of each file before following
+    #[serde(default = "default_stream_tail_lines")]
+    pub tail_lines: usize,
+}
+
+fn default_stream_tail_lines() -> usize {
+    100
+}
+
+/// Request of `POST /config/patch`
+#[derive(Debug, Clone, Deserialize)]
+pub struct ConfigPatchRequest {
+    /// CometBFT `config.toml`, `app.toml` or another TOML file
+    pub config_path: String,
+    /// Applied in order
+    pub changes: Vec<ConfigChange>,
+    /// Only report the diff, leaving the file as it is
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// A key to set, addressed by its dotted path such as `statesync.enable`, or
+/// `minimum-gas-prices` for a top-level key
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConfigChange {
+    pub key: String,
+    pub value: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PathCheckRequest {
+    pub path: String,
+}
+
+/// Host resources plus the disk usage of the listed nodes
+#[derive(Debug, Default, Deserialize)]
+pub struct TelemetryRequest {
+    #[serde(default)]
+    pub nodes: Vec<NodeTelemetryRequest>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct NodeTelemetryRequest {
+    pub node_name: String,
+    /// Node home; the sizes of its `data` and `wasm` directories are reported
+    pub deploy_path: Option<String>,
+    /// Snapshot backup directory; only its filesystem is reported
+    pub backup_path: Option<String>,
+    pub log_path: Option<String>,
 }
 
 // === JOB TRACKING STRUCTURES ===
@@ -61,6 +303,10 @@ pub enum JobStatus {
     Running,
     Completed,
     Failed,
+    /// The agent restarted while the job was still running
+    Interrupted,
+    /// Cancelled on request; `error_message` holds the cleanup summary
+    Cancelled,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -73,6 +319,34 @@ pub struct JobInfo {
     pub completed_at: Option<DateTime<Utc>>,
     pub result: Option<serde_json::Value>,
     pub error_message: Option<String>,
+    /// Service the operation stops/starts, used to report recovery state after a restart
+    #[serde(default)]
+    pub service_name: Option<String>,
+    /// Set for interrupted jobs: whether `service_name` was found not active on startup
+    #[serde(default)]
+    pub service_left_stopped: Option<bool>,
+    #[serde(default)]
+    pub progress: JobProgress,
+    /// Job this one was started by, e.g. the snapshot a compression job archives
+    #[serde(default)]
+    pub parent_job_id: Option<String>,
+}
+
+/// Live progress of a job, updated by the operation as it moves through its steps
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct JobProgress {
+    pub completed_steps: Vec<StepProgress>,
+    pub current_step: Option<StepProgress>,
+    /// Bytes copied/processed so far, for steps where this is known
+    pub bytes_processed: Option<u64>,
+    pub bytes_total: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StepProgress {
+    pub name: String,
+    pub started_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
 }
 
 // === RESPONSE STRUCTURES ===
@@ -102,6 +376,8 @@ pub struct ApiResponse<T> {
     pub job_id: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub job_status: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub progress: Option<JobProgress>,
 }
 
 impl<T: Default> ApiResponse<T> {
@@ -145,6 +421,15 @@ impl ApiResponse<()> {
         }
     }
 
+    /// Success response carrying a serialized result in `output`
+    pub fn success_with_json<R: Serialize>(result: &R) -> Self {
+        Self {
+            success: true,
+            output: Some(serde_json::to_string(result).unwrap_or_default()),
+            ..Default::default()
+        }
+    }
+
     pub fn success_with_job(job_id: String, job_status: String) -> Self {
         Self {
             success: true,
@@ -155,6 +440,228 @@ impl ApiResponse<()> {
     }
 }
 
+// === TYPED OPERATION RESULTS ===
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotEntry {
+    pub name: String,
+    pub path: String,
+    /// Directory modification time (unix seconds)
+    pub modified_unix: i64,
+    pub has_data: bool,
+    pub has_wasm: bool,
+    /// Size of the matching `.tar.lz4` archive, if a complete one exists
+    pub archive_size_bytes: Option<u64>,
+    /// An archive is still being written (or was abandoned mid-write)
+    #[serde(default)]
+    pub archive_in_progress: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchiveEntry {
+    pub name: String,
+    pub size_bytes: u64,
+    pub modified_unix: i64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SnapshotListing {
+    pub snapshots: Vec<SnapshotEntry>,
+    /// Complete `.tar.lz4` archives
+    pub archives: Vec<ArchiveEntry>,
+    /// `.tar.lz4.partial` files of archives that are still being written
+    #[serde(default)]
+    pub partial_archives: Vec<ArchiveEntry>,
+    /// `.tar.lz4.download` files of archive pulls that have not finished
+    #[serde(default)]
+    pub downloads: Vec<ArchiveEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotDeleteResult {
+    pub directory_removed: bool,
+    pub archive_removed: bool,
+}
+
+/// Result of a `snapshot_compression` job
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchiveResult {
+    pub archive_path: String,
+    pub size_bytes: u64,
+    /// Size of the uncompressed tar stream
+    pub uncompressed_bytes: u64,
+    /// `uncompressed_bytes / size_bytes`
+    pub compression_ratio: f64,
+}
+
+/// Result of a `snapshot_transfer` job
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotTransferResult {
+    pub archive_path: String,
+    pub size_bytes: u64,
+    /// Bytes already downloaded by an earlier, interrupted pull
+    pub resumed_from_bytes: u64,
+    pub attempts: u32,
+    /// The archive was already in the backup path and nothing was downloaded
+    pub already_present: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LogSearchResult {
+    pub matched: bool,
+    /// Matching lines with context, in `grep -n` format
+    pub output: String,
+    #[serde(default)]
+    pub matches: Vec<LogMatch>,
+    /// `max_matches` was reached and later matches were not reported
+    #[serde(default)]
+    pub truncated: bool,
+    /// Where each searched file now ends, to pass back on the next search
+    #[serde(default)]
+    pub cursors: HashMap<String, LogCursor>,
+    /// New content skipped because it exceeded `tail_bytes`
+    #[serde(default)]
+    pub skipped_bytes: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LogMatch {
+    pub file: String,
+    /// 1-based line number within the searched tail of the file
+    pub line_number: usize,
+    /// Byte offset of the line in the file
+    pub offset: u64,
+    pub line: String,
+    /// First pattern the line matched
+    pub pattern: String,
+    pub context_before: Vec<String>,
+    pub context_after: Vec<String>,
+    /// Timestamp parsed from the line, when it has a recognisable one
+    pub timestamp: Option<DateTime<Utc>>,
+}
+
+/// A line sent as a `line` event on `/logs/stream`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LogStreamLine {
+    pub file: String,
+    pub line: String,
+    pub timestamp: Option<DateTime<Utc>>,
+}
+
+/// Compressed copy of a log file made when it was rotated
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LogArchive {
+    /// File name within the archive directory
+    pub name: String,
+    /// Name of the log file it was rotated from
+    pub log_file: String,
+    pub size_bytes: u64,
+    pub rotated_at: DateTime<Utc>,
+}
+
+/// Response of `POST /logs/archives`, newest archives first
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LogArchiveListing {
+    pub archive_dir: String,
+    pub archives: Vec<LogArchive>,
+}
+
+/// Result of `POST /config/patch`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ConfigPatchResult {
+    pub config_path: String,
+    /// The patch changed the file's contents
+    pub changed: bool,
+    /// Unified diff of the file before and after the patch, empty when unchanged
+    pub diff: String,
+    /// Copy of the original file, when the patch was written
+    pub backup_path: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PathInfo {
+    pub exists: bool,
+    pub is_dir: bool,
+    pub is_file: bool,
+    pub size_bytes: Option<u64>,
+}
+
+/// Written as `snapshot_manifest.json` at the root of every snapshot directory
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotManifest {
+    pub network: String,
+    pub block_height: Option<u64>,
+    pub source_node: String,
+    pub created_at: DateTime<Utc>,
+    pub total_bytes: u64,
+    pub files: Vec<ManifestFile>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestFile {
+    /// Path relative to the snapshot directory, e.g. `data/application.db/000001.log`
+    pub path: String,
+    pub size_bytes: u64,
+    pub sha256: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SnapshotVerifyReport {
+    pub verified: bool,
+    pub files_checked: u64,
+    pub bytes_checked: u64,
+    /// Files whose size or checksum differs from the manifest
+    pub mismatched: Vec<String>,
+    /// Files listed in the manifest but absent from the snapshot
+    pub missing: Vec<String>,
+    /// Files present in the snapshot but not listed in the manifest
+    pub unexpected: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TelemetryReport {
+    pub host: HostTelemetry,
+    pub nodes: Vec<NodeDiskUsage>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HostTelemetry {
+    /// 1, 5 and 15 minute load averages
+    pub load_average: [f64; 3],
+    pub cpu_count: usize,
+    pub memory_total_bytes: u64,
+    pub memory_available_bytes: u64,
+    /// Usage of the filesystems holding the configured roots and the
+    /// requested nodes' paths, one entry per device
+    pub filesystems: Vec<FilesystemUsage>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FilesystemUsage {
+    /// `deploy`, `backup` or `log`
+    pub kind: String,
+    /// First measured path on the filesystem
+    pub root: String,
+    /// Every measured root and node path on the filesystem
+    pub paths: Vec<String>,
+    pub total_bytes: u64,
+    pub used_bytes: u64,
+    pub available_bytes: u64,
+}
+
+/// Directory sizes of a node. Sizes may come from a cache and are as old as `measured_at`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NodeDiskUsage {
+    pub node_name: String,
+    pub data_bytes: Option<u64>,
+    pub wasm_bytes: Option<u64>,
+    pub log_bytes: Option<u64>,
+    /// When the oldest of the reported sizes was measured
+    pub measured_at: Option<DateTime<Utc>>,
+    /// Why sizes are missing, e.g. a path outside the allowed roots
+    pub error: Option<String>,
+}
+
 // === INTERNAL STRUCTURES ===
 
 #[derive(Debug)]
@@ -175,4 +682,6 @@ pub struct StateSyncRequest {
     pub trust_hash: String,
     pub timeout_seconds: u64,
     pub log_path: Option<String>,
+    #[serde(default)]
+    pub log_rotation: Option<LogRotation>,
 }
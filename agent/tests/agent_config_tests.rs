@@ -0,0 +1,194 @@
+//! Tests for the agent configuration file and command line flags
+//!
+//! These tests verify TOML parsing, that flags override the file, and that
+//! `require_key` refuses to fall back to the development key.
+
+use agent::config::{AgentConfig, Cli, DEVELOPMENT_API_KEY};
+use clap::Parser;
+use serial_test::serial;
+use std::fs;
+use tempfile::TempDir;
+
+fn write_config(dir: &TempDir, content: &str) -> String {
+    let path = dir.path().join("agent.toml");
+    fs::write(&path, content).unwrap();
+    path.to_str().unwrap().to_string()
+}
+
+fn cli(args: &[&str]) -> Cli {
+    Cli::try_parse_from(std::iter::once("agent").chain(args.iter().copied())).unwrap()
+}
+
+#[test]
+fn test_defaults_without_config_file() {
+    let config = AgentConfig::default();
+
+    assert_eq!(config.socket_addr().unwrap().to_string(), "0.0.0.0:8745");
+    assert_eq!(config.job_retention_hours, 48);
+    assert_eq!(config.cleanup_interval_seconds, 3600);
+    assert_eq!(config.max_concurrent_operations, None);
+    assert!(!config.require_key);
+    assert_eq!(config.job_journal_path(), "data/agent_jobs.jsonl");
+}
+
+#[test]
+fn test_parse_config_file() {
+    let dir = TempDir::new().unwrap();
+    let path = write_config(
+        &dir,
+        r#"
+bind_address = "127.0.0.1"
+port = 9000
+api_key_file = "/etc/nodes-agent/api_key"
+require_key = true
+job_retention_hours = 12
+cleanup_interval_seconds = 600
+max_concurrent_operations = 2
+
+[paths]
+deploy_roots = ["/opt/deploy"]
+backup_roots = ["/backup/snapshots"]
+log_roots = ["/var/log"]
+"#,
+    );
+
+    let config = AgentConfig::from_file(&path).unwrap();
+
+    assert_eq!(config.socket_addr().unwrap().to_string(), "127.0.0.1:9000");
+    assert_eq!(
+        config.api_key_file.as_deref(),
+        Some("/etc/nodes-agent/api_key")
+    );
+    assert!(config.require_key);
+    assert_eq!(config.job_retention_hours, 12);
+    assert_eq!(config.cleanup_interval_seconds, 600);
+    assert_eq!(config.max_concurrent_operations, Some(2));
+    assert_eq!(config.paths.deploy_roots, vec!["/opt/deploy"]);
+    assert_eq!(config.paths.backup_roots, vec!["/backup/snapshots"]);
+    assert_eq!(config.paths.log_roots, vec!["/var/log"]);
+}
+
+#[test]
+fn test_unconfined_paths_are_opt_in() {
+    assert!(!AgentConfig::default().paths.allow_unconfined_paths);
+
+    let dir = TempDir::new().unwrap();
+    let path = write_config(&dir, "[paths]\nallow_unconfined_paths = true\n");
+    let config = AgentConfig::from_file(&path).unwrap();
+    assert!(config.paths.allow_unconfined_paths);
+}
+
+#[test]
+fn test_rejects_unknown_keys() {
+    let dir = TempDir::new().unwrap();
+    let path = write_config(&dir, "listen_port = 9000\n");
+
+    assert!(AgentConfig::from_file(&path).is_err());
+}
+
+#[test]
+#[serial]
+fn test_cli_flags_override_config_file() {
+    let dir = TempDir::new().unwrap();
+    let path = write_config(&dir, "port = 9000\nmax_concurrent_operations = 2\n");
+
+    let config = AgentConfig::load(&cli(&[
+        "--config",
+        &path,
+        "--port",
+        "9100",
+        "--max-concurrent-operations",
+        "5",
+        "--require-key",
+    ]))
+    .unwrap();
+
+    assert_eq!(config.port, 9100);
+    assert_eq!(config.max_concurrent_operations, Some(5));
+    assert!(config.require_key);
+}
+
+#[test]
+fn test_rejects_zero_cleanup_interval() {
+    let dir = TempDir::new().unwrap();
+    let path = write_config(&dir, "cleanup_interval_seconds = 0\n");
+
+    assert!(AgentConfig::load(&cli(&["--config", &path])).is_err());
+}
+
+#[test]
+fn test_reads_metrics_token_file() {
+    let dir = TempDir::new().unwrap();
+    let token_path = dir.path().join("metrics_token");
+    std::fs::write(&token_path, "scrape-token\n").unwrap();
+    let path = write_config(
+        &dir,
+        &format!("metrics_token_file = {:?}\n", token_path.to_str().unwrap()),
+    );
+
+    let config = AgentConfig::load(&cli(&["--config", &path])).unwrap();
+
+    assert_eq!(
+        config.metrics_token().unwrap().as_deref(),
+        Some("scrape-token")
+    );
+    assert_eq!(AgentConfig::default().metrics_token().unwrap(), None);
+}
+
+#[test]
+fn test_rejects_relative_path_roots() {
+    let dir = TempDir::new().unwrap();
+    let path = write_config(&dir, "[paths]\nlog_roots = [\"var/log\"]\n");
+
+    assert!(AgentConfig::load(&cli(&["--config", &path])).is_err());
+}
+
+#[test]
+#[serial]
+fn test_reads_api_key_file() {
+    let dir = TempDir::new().unwrap();
+    let key_path = dir.path().join("api_key");
+    fs::write(&key_path, "secret-key\n").unwrap();
+
+    let config = AgentConfig {
+        api_key_file: Some(key_path.to_str().unwrap().to_string()),
+        require_key: true,
+        ..AgentConfig::default()
+    };
+
+    assert_eq!(config.api_keys().unwrap(), vec!["secret-key".to_string()]);
+}
+
+#[test]
+#[serial]
+fn test_require_key_refuses_missing_key() {
+    std::env::remove_var("AGENT_API_KEY");
+    std::env::remove_var("AGENT_API_KEY_SECONDARY");
+
+    let strict = AgentConfig {
+        require_key: true,
+        ..AgentConfig::default()
+    };
+    assert!(strict.api_keys().is_err());
+
+    let lenient = AgentConfig::default();
+    assert_eq!(
+        lenient.api_keys().unwrap(),
+        vec![DEVELOPMENT_API_KEY.to_string()]
+    );
+}
+
+#[test]
+#[serial]
+fn test_require_key_refuses_development_key() {
+    std::env::set_var("AGENT_API_KEY", DEVELOPMENT_API_KEY);
+
+    let config = AgentConfig {
+        require_key: true,
+        ..AgentConfig::default()
+    };
+    let result = config.api_keys();
+    std::env::remove_var("AGENT_API_KEY");
+
+    assert!(result.is_err());
+}
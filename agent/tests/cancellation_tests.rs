@@ -0,0 +1,57 @@
+//! Integration tests for cancelling agent operations
+//!
+//! Jobs are cancelled by aborting their task. These tests verify that
+//! aborting a running shell command also takes down the processes it
+//! spawned, so no copy or pruning keeps running after a cancel.
+
+use agent::operations::cancel::CancelCleanup;
+use agent::services::commands;
+use std::time::Duration;
+use tempfile::TempDir;
+
+#[tokio::test]
+async fn test_abort_kills_whole_process_group() {
+    let temp_dir = TempDir::new().unwrap();
+    let marker = temp_dir.path().join("still_running");
+
+    // The background subshell survives if only `sh` itself is killed
+    let command = format!("(sleep 1; touch '{}') & wait", marker.display());
+    let handle = tokio::spawn(async move { commands::execute_shell_command(&command).await });
+
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    handle.abort();
+    assert!(handle.await.unwrap_err().is_cancelled());
+
+    tokio::time::sleep(Duration::from_millis(1500)).await;
+    assert!(
+        !marker.exists(),
+        "child process outlived the aborted command"
+    );
+}
+
+#[tokio::test]
+async fn test_completed_command_is_unaffected() {
+    let output = commands::execute_shell_command("echo done").await.unwrap();
+    assert_eq!(output.trim(), "done");
+}
+
+#[test]
+fn test_cleanup_service_name() {
+    let cleanups = [
+        CancelCleanup::RestartService {
+            service_name: "nolusd".to_string(),
+        },
+        CancelCleanup::RemovePartialSnapshot {
+            snapshot_path: "/backup/pirin-1_20250121_17154420".to_string(),
+            service_name: "nolusd".to_string(),
+        },
+        CancelCleanup::RollBackRestore {
+            deploy_path: "/opt/deploy/nolus".to_string(),
+            service_name: "nolusd".to_string(),
+        },
+    ];
+
+    for cleanup in &cleanups {
+        assert_eq!(cleanup.service_name(), Some("nolusd"));
+    }
+}
@@ -0,0 +1,174 @@
+//! Tests for format-preserving edits of node config files
+
+use agent::services::config_editor::{
+    change, disable_state_sync, enable_state_sync, patch_config, patch_document,
+};
+use serde_json::json;
+use std::fs;
+use tempfile::TempDir;
+
+const CONFIG_TOML: &str = r#"# This is a TOML config file.
+proxy_app = "tcp://127.0.0.1:26658"
+
+[consensus]
+# How long we wait after committing a block
+timeout_commit = "5s"
+
+#######################################################
+###         State Sync Configuration Options        ###
+#######################################################
+[statesync]
+# State sync rapidly bootstraps a new node
+enable = false
+
+# RPC servers (comma-separated) for light client verification
+rpc_servers = ""
+trust_height = 0
+trust_hash = ""
+trust_period = "336h0m0s" # two weeks
+
+[p2p]
+persistent_peers = ""
+"#;
+
+fn write_config(dir: &TempDir) -> String {
+    let path = dir.path().join("config.toml");
+    fs::write(&path, CONFIG_TOML).unwrap();
+    path.to_string_lossy().to_string()
+}
+
+fn backups(dir: &TempDir) -> Vec<String> {
+    fs::read_dir(dir.path())
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+        .filter(|name| name.ends_with(".bak"))
+        .collect()
+}
+
+#[test]
+fn test_patch_keeps_comments_and_order() {
+    let patched = patch_document(
+        CONFIG_TOML,
+        &[
+            change("consensus.timeout_commit", "2s"),
+            change("statesync.trust_period", "168h0m0s"),
+        ],
+    )
+    .unwrap();
+
+    assert_eq!(
+        patched,
+        CONFIG_TOML
+            .replace("timeout_commit = \"5s\"", "timeout_commit = \"2s\"")
+            .replace(
+                "trust_period = \"336h0m0s\" # two weeks",
+                "trust_period = \"168h0m0s\" # two weeks"
+            )
+    );
+}
+
+#[test]
+fn test_patch_adds_keys_and_tables() {
+    let patched = patch_document(
+        CONFIG_TOML,
+        &[
+            change("minimum-gas-prices", "0.0025unls"),
+            change("p2p.max_num_inbound_peers", 40),
+            change("api.enable", true),
+            change("telemetry.global-labels", json!([["chain_id", "pirin-1"]])),
+        ],
+    )
+    .unwrap();
+
+    let parsed: toml::Value = toml::from_str(&patched).unwrap();
+    assert_eq!(parsed["minimum-gas-prices"].as_str(), Some("0.0025unls"));
+    assert_eq!(
+        parsed["p2p"]["max_num_inbound_peers"].as_integer(),
+        Some(40)
+    );
+    assert_eq!(parsed["api"]["enable"].as_bool(), Some(true));
+    assert_eq!(
+        parsed["telemetry"]["global-labels"][0][1].as_str(),
+        Some("pirin-1")
+    );
+    assert!(patched.contains("trust_period = \"336h0m0s\" # two weeks"));
+}
+
+#[test]
+fn test_patch_rejects_invalid_changes() {
+    assert!(patch_document(CONFIG_TOML, &[change("statesync", true)]).is_err());
+    assert!(patch_document(CONFIG_TOML, &[change("proxy_app.port", 1)]).is_err());
+    assert!(patch_document(CONFIG_TOML, &[change("p2p..seeds", "")]).is_err());
+    assert!(patch_document(CONFIG_TOML, &[change("p2p.seeds", json!(null))]).is_err());
+    assert!(patch_document("[broken", &[change("p2p.seeds", "")]).is_err());
+}
+
+#[tokio::test]
+async fn test_patch_config_backs_up_and_reports_diff() {
+    let dir = TempDir::new().unwrap();
+    let path = write_config(&dir);
+
+    let result = patch_config(&path, &[change("consensus.timeout_commit", "2s")], false)
+        .await
+        .unwrap();
+
+    assert!(result.changed);
+    assert!(result.diff.contains("-timeout_commit = \"5s\""));
+    assert!(result.diff.contains("+timeout_commit = \"2s\""));
+    let backup = result.backup_path.unwrap();
+    assert_eq!(fs::read_to_string(&backup).unwrap(), CONFIG_TOML);
+    assert!(fs::read_to_string(&path)
+        .unwrap()
+        .contains("timeout_commit = \"2s\""));
+}
+
+#[tokio::test]
+async fn test_dry_run_and_unchanged_patches_leave_the_file() {
+    let dir = TempDir::new().unwrap();
+    let path = write_config(&dir);
+
+    let preview = patch_config(&path, &[change("consensus.timeout_commit", "2s")], true)
+        .await
+        .unwrap();
+    assert!(preview.changed);
+    assert!(!preview.diff.is_empty());
+    assert!(preview.backup_path.is_none());
+
+    let unchanged = patch_config(&path, &[change("consensus.timeout_commit", "5s")], false)
+        .await
+        .unwrap();
+    assert!(!unchanged.changed);
+    assert!(unchanged.diff.is_empty());
+
+    assert_eq!(fs::read_to_string(&path).unwrap(), CONFIG_TOML);
+    assert!(backups(&dir).is_empty());
+}
+
+#[tokio::test]
+async fn test_state_sync_keeps_trust_period_and_comments() {
+    let dir = TempDir::new().unwrap();
+    let path = write_config(&dir);
+
+    enable_state_sync(
+        &path,
+        &[
+            "http://rpc-1:26657".to_string(),
+            "http://rpc-2:26657".to_string(),
+        ],
+        1_000_000,
+        "ABCDEF",
+    )
+    .await
+    .unwrap();
+    let enabled = fs::read_to_string(&path).unwrap();
+    assert!(enabled.contains("enable = true"));
+    assert!(enabled.contains("rpc_servers = \"http://rpc-1:26657,http://rpc-2:26657\""));
+    assert!(enabled.contains("trust_height = 1000000"));
+    assert!(enabled.contains("trust_hash = \"ABCDEF\""));
+    assert!(enabled.contains("trust_period = \"336h0m0s\" # two weeks"));
+    assert!(enabled.contains("# State sync rapidly bootstraps a new node"));
+
+    disable_state_sync(&path).await.unwrap();
+    let disabled = fs::read_to_string(&path).unwrap();
+    assert_eq!(disabled, enabled.replace("enable = true", "enable = false"));
+}
@@ -0,0 +1,57 @@
+//! Integration tests for disk space preflight checks
+//!
+//! These tests verify that free space is read from the filesystem holding a
+//! path (even one not created yet), and that checks fail with the error the
+//! manager recognises when an operation would not fit.
+
+use agent::services::disk;
+use tempfile::TempDir;
+
+#[test]
+fn test_available_bytes_resolves_missing_paths_to_their_parent() {
+    let dir = TempDir::new().unwrap();
+    let root = dir.path().to_str().unwrap();
+    let nested = dir.path().join("backups/pirin-1_20250121_17154420");
+
+    let available = disk::available_bytes(root).unwrap();
+    assert!(available > 0);
+    // The figure moves with other writers, but both paths read the same filesystem
+    let nested_available = disk::available_bytes(nested.to_str().unwrap()).unwrap();
+    assert!(nested_available.abs_diff(available) < 64 * 1024 * 1024);
+}
+
+#[test]
+fn test_ensure_space_passes_when_operation_fits() {
+    let dir = TempDir::new().unwrap();
+    let root = dir.path().to_str().unwrap();
+    let available = disk::available_bytes(root).unwrap();
+    if available < 2 * disk::MIN_FREE_BYTES {
+        // Not enough room on this machine to exercise the passing case
+        return;
+    }
+
+    disk::ensure_space(root, 1024, "snapshot").unwrap();
+}
+
+#[test]
+fn test_ensure_space_refuses_operation_that_does_not_fit() {
+    let dir = TempDir::new().unwrap();
+    let root = dir.path().to_str().unwrap();
+    let available = disk::available_bytes(root).unwrap();
+
+    let error = disk::ensure_space(root, available, "restore").unwrap_err();
+    let message = error.to_string();
+    assert!(message.starts_with(disk::INSUFFICIENT_SPACE));
+    assert!(message.contains(root));
+    assert!(message.contains("for restore"));
+    assert!(message.contains("GB required"));
+}
+
+#[test]
+fn test_headroom_adds_share_of_size() {
+    assert_eq!(
+        disk::with_headroom(1000, disk::ARCHIVE_HEADROOM_PERCENT),
+        1600
+    );
+    assert_eq!(disk::with_headroom(u64::MAX, 60), u64::MAX);
+}
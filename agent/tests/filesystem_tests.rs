@@ -0,0 +1,161 @@
+//! Integration tests for the agent's native filesystem operations
+//!
+//! These tests verify that tree copies keep file modes and symlinks, count
+//! the bytes they copy, report per-file errors instead of stopping at the
+//! first one, and cope with paths that would break shell quoting.
+
+use agent::services::filesystem;
+use agent::services::job_manager::{JobManager, ProgressReporter};
+use std::fs;
+use std::os::unix::fs::{symlink, PermissionsExt};
+use tempfile::TempDir;
+
+fn path_str(path: &std::path::Path) -> String {
+    path.to_str().unwrap().to_string()
+}
+
+#[tokio::test]
+async fn test_copy_tree_preserves_modes_and_symlinks() {
+    let temp = TempDir::new().unwrap();
+    let source = temp.path().join("source");
+    fs::create_dir_all(source.join("nested")).unwrap();
+    fs::write(source.join("nested/file.db"), vec![7u8; 3000]).unwrap();
+    fs::write(source.join("run.sh"), b"#!/bin/sh\n").unwrap();
+    fs::set_permissions(source.join("run.sh"), fs::Permissions::from_mode(0o750)).unwrap();
+    symlink("nested/file.db", source.join("link")).unwrap();
+
+    let target = temp.path().join("target");
+    let report = filesystem::copy_trees(
+        &[(path_str(&source), path_str(&target))],
+        false,
+        &ProgressReporter::detached(),
+    )
+    .await
+    .unwrap()
+    .into_result()
+    .unwrap();
+
+    assert_eq!(report.files_copied, 2);
+    assert_eq!(report.bytes_copied, 3010);
+    assert_eq!(fs::read(target.join("nested/file.db")).unwrap().len(), 3000);
+
+    let mode = fs::metadata(target.join("run.sh"))
+        .unwrap()
+        .permissions()
+        .mode();
+    assert_eq!(mode & 0o777, 0o750);
+
+    let link = fs::symlink_metadata(target.join("link")).unwrap();
+    assert!(link.file_type().is_symlink());
+    assert_eq!(
+        fs::read_link(target.join("link")).unwrap(),
+        std::path::PathBuf::from("nested/file.db")
+    );
+}
+
+#[tokio::test]
+async fn test_copy_handles_quotes_in_paths() {
+    let temp = TempDir::new().unwrap();
+    let source = temp.path().join("it's data");
+    fs::create_dir_all(&source).unwrap();
+    fs::write(source.join("a'b.txt"), b"hello").unwrap();
+
+    let target = temp.path().join("copy 'of' data");
+    filesystem::copy_trees(
+        &[(path_str(&source), path_str(&target))],
+        false,
+        &ProgressReporter::detached(),
+    )
+    .await
+    .unwrap()
+    .into_result()
+    .unwrap();
+
+    assert_eq!(fs::read(target.join("a'b.txt")).unwrap(), b"hello");
+    assert_eq!(filesystem::dir_size(&path_str(&target)).await.unwrap(), 5);
+
+    assert!(filesystem::remove_dir_all(&path_str(&target))
+        .await
+        .unwrap());
+    assert!(!target.exists());
+    assert!(!filesystem::remove_dir_all(&path_str(&target))
+        .await
+        .unwrap());
+}
+
+#[tokio::test]
+async fn test_copy_reports_every_failed_file() {
+    let temp = TempDir::new().unwrap();
+    let source = temp.path().join("source");
+    fs::create_dir_all(&source).unwrap();
+    fs::write(source.join("ok.txt"), b"fine").unwrap();
+
+    // Directories in the way of target files make those copies fail even when
+    // running as root, where permission bits would not
+    let target = temp.path().join("target");
+    fs::create_dir_all(target.join("blocked-1.txt")).unwrap();
+    fs::create_dir_all(target.join("blocked-2.txt")).unwrap();
+    fs::write(source.join("blocked-1.txt"), b"x").unwrap();
+    fs::write(source.join("blocked-2.txt"), b"y").unwrap();
+
+    let report = filesystem::copy_trees(
+        &[(path_str(&source), path_str(&target))],
+        false,
+        &ProgressReporter::detached(),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(report.errors.len(), 2);
+    assert_eq!(report.files_copied, 1);
+    assert_eq!(fs::read(target.join("ok.txt")).unwrap(), b"fine");
+
+    let error = report.into_result().unwrap_err().to_string();
+    assert!(error.contains("2 file(s) failed to copy"));
+    assert!(error.contains("blocked-1.txt"));
+}
+
+#[tokio::test]
+async fn test_copy_reports_bytes_to_job_progress() {
+    let temp = TempDir::new().unwrap();
+    let source = temp.path().join("source");
+    fs::create_dir_all(&source).unwrap();
+    fs::write(source.join("blob"), vec![0u8; 4096]).unwrap();
+
+    let manager = JobManager::new();
+    let job_id = manager.create_job("snapshot_restore", "node-a", None).await;
+    let progress = manager.progress_reporter(&job_id);
+    progress.bytes(0, Some(4096)).await;
+
+    filesystem::copy_trees(
+        &[(path_str(&source), path_str(&temp.path().join("target")))],
+        false,
+        &progress,
+    )
+    .await
+    .unwrap();
+
+    let job = manager.get_job_status(&job_id).await.unwrap();
+    assert_eq!(job.progress.bytes_processed, Some(4096));
+    assert_eq!(job.progress.bytes_total, Some(4096));
+}
+
+#[tokio::test]
+async fn test_single_file_helpers() {
+    let temp = TempDir::new().unwrap();
+    let source = path_str(&temp.path().join("state.json"));
+    let backup = path_str(&temp.path().join("state_backup.json"));
+
+    assert!(!filesystem::copy_file_if_exists(&source, &backup)
+        .await
+        .unwrap());
+
+    fs::write(&source, b"{}").unwrap();
+    assert!(filesystem::copy_file_if_exists(&source, &backup)
+        .await
+        .unwrap());
+    assert!(filesystem::is_file(&backup).await);
+
+    assert!(filesystem::remove_file_if_exists(&backup).await.unwrap());
+    assert!(!filesystem::remove_file_if_exists(&backup).await.unwrap());
+}
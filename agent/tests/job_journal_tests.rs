@@ -0,0 +1,147 @@
+//! Integration tests for the agent's durable job journal
+//!
+//! These tests verify that job state survives an agent restart and that
+//! jobs cut off mid-flight come back as `Interrupted` with their service
+//! recorded, so the manager can decide how to recover.
+
+use agent::services::job_manager::JobManager;
+use agent::types::JobStatus;
+use std::fs;
+use tempfile::TempDir;
+
+#[tokio::test]
+async fn test_running_job_is_interrupted_after_restart() {
+    let temp_dir = TempDir::new().unwrap();
+    let journal = temp_dir.path().join("jobs.jsonl");
+
+    let job_id = {
+        let (manager, interrupted) = JobManager::with_journal(&journal).await.unwrap();
+        assert!(interrupted.is_empty());
+        manager
+            .create_job("pruning", "nolus-1", Some("nolusd"))
+            .await
+    };
+
+    // Simulate an agent restart by reopening the same journal
+    let (manager, interrupted) = JobManager::with_journal(&journal).await.unwrap();
+    assert_eq!(interrupted.len(), 1);
+    assert_eq!(interrupted[0].job_id, job_id);
+    assert_eq!(interrupted[0].service_name.as_deref(), Some("nolusd"));
+
+    let job = manager.get_job_status(&job_id).await.unwrap();
+    assert!(matches!(job.status, JobStatus::Interrupted));
+    assert!(job.completed_at.is_some());
+    assert!(manager.get_running_jobs().await.is_empty());
+}
+
+#[tokio::test]
+async fn test_finished_jobs_survive_restart() {
+    let temp_dir = TempDir::new().unwrap();
+    let journal = temp_dir.path().join("jobs.jsonl");
+
+    let (completed_id, failed_id) = {
+        let (manager, _) = JobManager::with_journal(&journal).await.unwrap();
+        let completed_id = manager
+            .create_job("snapshot_creation", "node-a", Some("svc-a"))
+            .await;
+        manager
+            .complete_job(&completed_id, serde_json::json!({"filename": "snap"}))
+            .await;
+        let failed_id = manager
+            .create_job("state_sync", "node-b", Some("svc-b"))
+            .await;
+        manager
+            .fail_job(&failed_id, "rpc unreachable".to_string())
+            .await;
+        (completed_id, failed_id)
+    };
+
+    let (manager, interrupted) = JobManager::with_journal(&journal).await.unwrap();
+    assert!(interrupted.is_empty());
+
+    let completed = manager.get_job_status(&completed_id).await.unwrap();
+    assert!(matches!(completed.status, JobStatus::Completed));
+    assert_eq!(
+        completed.result,
+        Some(serde_json::json!({"filename": "snap"}))
+    );
+
+    let failed = manager.get_job_status(&failed_id).await.unwrap();
+    assert!(matches!(failed.status, JobStatus::Failed));
+    assert_eq!(failed.error_message.as_deref(), Some("rpc unreachable"));
+}
+
+#[tokio::test]
+async fn test_recorded_service_state_is_reported() {
+    let temp_dir = TempDir::new().unwrap();
+    let journal = temp_dir.path().join("jobs.jsonl");
+
+    let job_id = {
+        let (manager, _) = JobManager::with_journal(&journal).await.unwrap();
+        manager
+            .create_job("snapshot_restore", "node-a", Some("svc-a"))
+            .await
+    };
+
+    let (manager, _) = JobManager::with_journal(&journal).await.unwrap();
+    manager.record_service_state(&job_id, true).await;
+
+    let job = manager.get_job_status(&job_id).await.unwrap();
+    assert_eq!(job.service_left_stopped, Some(true));
+    assert!(job
+        .error_message
+        .unwrap()
+        .contains("service svc-a was left stopped"));
+
+    // The recorded state is itself persisted
+    let (manager, interrupted) = JobManager::with_journal(&journal).await.unwrap();
+    assert!(interrupted.is_empty());
+    let job = manager.get_job_status(&job_id).await.unwrap();
+    assert_eq!(job.service_left_stopped, Some(true));
+}
+
+#[tokio::test]
+async fn test_torn_journal_line_is_skipped() {
+    let temp_dir = TempDir::new().unwrap();
+    let journal = temp_dir.path().join("jobs.jsonl");
+
+    let job_id = {
+        let (manager, _) = JobManager::with_journal(&journal).await.unwrap();
+        let job_id = manager.create_job("pruning", "node-a", None).await;
+        manager
+            .complete_job(&job_id, serde_json::json!({"output": "ok"}))
+            .await;
+        job_id
+    };
+
+    // Append a partially written line as left behind by a crash
+    let mut content = fs::read_to_string(&journal).unwrap();
+    content.push_str("{\"job_id\":\"pruning_node-b");
+    fs::write(&journal, content).unwrap();
+
+    let (manager, interrupted) = JobManager::with_journal(&journal).await.unwrap();
+    assert!(interrupted.is_empty());
+    assert!(manager.get_job_status(&job_id).await.is_some());
+
+    // Loading compacts the journal, dropping the torn line
+    let compacted = fs::read_to_string(&journal).unwrap();
+    assert_eq!(compacted.lines().count(), 1);
+}
+
+#[tokio::test]
+async fn test_cleanup_removes_jobs_from_journal() {
+    let temp_dir = TempDir::new().unwrap();
+    let journal = temp_dir.path().join("jobs.jsonl");
+
+    let (manager, _) = JobManager::with_journal(&journal).await.unwrap();
+    let job_id = manager.create_job("pruning", "node-a", None).await;
+    manager
+        .complete_job(&job_id, serde_json::json!({"output": "ok"}))
+        .await;
+
+    // Everything started before "now" is older than 0 hours
+    assert_eq!(manager.cleanup_old_jobs(0).await, 1);
+
+    let (manager, _) = JobManager::with_journal(&journal).await.unwrap();
+    assert!(manager.get_job_status(&job_id).await.is_none());
+}
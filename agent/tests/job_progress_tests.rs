@@ -0,0 +1,139 @@
+//! Integration tests for step-level job progress
+//!
+//! These tests verify that operations can report which step they are in,
+//! that finished steps keep their timing, and that progress is persisted
+//! so an interrupted job still shows where it stopped.
+
+use agent::services::job_manager::{JobManager, ProgressReporter};
+use tempfile::TempDir;
+
+#[tokio::test]
+async fn test_steps_are_recorded_in_order() {
+    let manager = JobManager::new();
+    let job_id = manager
+        .create_job("snapshot_restore", "node-a", Some("svc-a"))
+        .await;
+    let progress = manager.progress_reporter(&job_id);
+
+    progress.step("Stopping service").await;
+    progress.step("Copying snapshot data").await;
+
+    let job = manager.get_job_status(&job_id).await.unwrap();
+    let names: Vec<_> = job
+        .progress
+        .completed_steps
+        .iter()
+        .map(|step| step.name.as_str())
+        .collect();
+    assert_eq!(names, vec!["Stopping service"]);
+    assert!(job.progress.completed_steps[0].completed_at.is_some());
+
+    let current = job.progress.current_step.unwrap();
+    assert_eq!(current.name, "Copying snapshot data");
+    assert!(current.completed_at.is_none());
+    assert!(current.started_at >= job.progress.completed_steps[0].started_at);
+}
+
+#[tokio::test]
+async fn test_completion_closes_current_step() {
+    let manager = JobManager::new();
+    let job_id = manager.create_job("pruning", "node-a", Some("svc-a")).await;
+    let progress = manager.progress_reporter(&job_id);
+
+    progress.step("Running cosmos-pruner").await;
+    manager
+        .complete_job(&job_id, serde_json::json!({"output": "ok"}))
+        .await;
+
+    let job = manager.get_job_status(&job_id).await.unwrap();
+    assert!(job.progress.current_step.is_none());
+    assert_eq!(job.progress.completed_steps.len(), 1);
+}
+
+#[tokio::test]
+async fn test_failure_closes_failing_step() {
+    let manager = JobManager::new();
+    let job_id = manager
+        .create_job("state_sync", "node-a", Some("svc-a"))
+        .await;
+    let progress = manager.progress_reporter(&job_id);
+
+    progress.step("Waiting for state sync").await;
+    manager.fail_job(&job_id, "timeout".to_string()).await;
+
+    let job = manager.get_job_status(&job_id).await.unwrap();
+    assert!(job.progress.current_step.is_none());
+    let failed_step = job.progress.completed_steps.last().unwrap();
+    assert_eq!(failed_step.name, "Waiting for state sync");
+    assert!(failed_step.completed_at.is_some());
+}
+
+#[tokio::test]
+async fn test_cancellation_closes_current_step() {
+    let manager = JobManager::new();
+    let job_id = manager
+        .create_job("snapshot_restore", "node-a", Some("svc-a"))
+        .await;
+    let progress = manager.progress_reporter(&job_id);
+
+    progress.step("Copying snapshot data").await;
+    manager
+        .cancel_job(&job_id, "killed 1 process".to_string())
+        .await;
+
+    let job = manager.get_job_status(&job_id).await.unwrap();
+    assert!(job.progress.current_step.is_none());
+    let cancelled_step = job.progress.completed_steps.last().unwrap();
+    assert_eq!(cancelled_step.name, "Copying snapshot data");
+    assert!(cancelled_step.completed_at.is_some());
+}
+
+#[tokio::test]
+async fn test_bytes_progress() {
+    let manager = JobManager::new();
+    let job_id = manager
+        .create_job("snapshot_restore", "node-a", Some("svc-a"))
+        .await;
+    let progress = manager.progress_reporter(&job_id);
+
+    progress.bytes(0, Some(4096)).await;
+    progress.bytes(1024, None).await;
+
+    let job = manager.get_job_status(&job_id).await.unwrap();
+    assert_eq!(job.progress.bytes_processed, Some(1024));
+    assert_eq!(job.progress.bytes_total, Some(4096));
+}
+
+#[tokio::test]
+async fn test_progress_survives_restart() {
+    let temp_dir = TempDir::new().unwrap();
+    let journal = temp_dir.path().join("jobs.jsonl");
+
+    let job_id = {
+        let (manager, _) = JobManager::with_journal(&journal).await.unwrap();
+        let job_id = manager
+            .create_job("snapshot_restore", "node-a", Some("svc-a"))
+            .await;
+        let progress = manager.progress_reporter(&job_id);
+        progress.step("Stopping service").await;
+        progress.step("Copying snapshot data").await;
+        job_id
+    };
+
+    let (manager, interrupted) = JobManager::with_journal(&journal).await.unwrap();
+    assert_eq!(interrupted.len(), 1);
+
+    let job = manager.get_job_status(&job_id).await.unwrap();
+    assert_eq!(job.progress.completed_steps.len(), 1);
+    assert_eq!(
+        job.progress.current_step.unwrap().name,
+        "Copying snapshot data"
+    );
+}
+
+#[tokio::test]
+async fn test_detached_reporter_is_noop() {
+    let progress = ProgressReporter::detached();
+    progress.step("Stopping service").await;
+    progress.bytes(10, Some(10)).await;
+}
@@ -0,0 +1,91 @@
+//! Tests for reading node logs from the systemd journal
+
+use agent::services::journald::{
+    parse_entry, source_name, validate_unit_name, JournalEntry, JournalWindow,
+};
+use chrono::{TimeZone, Utc};
+
+#[test]
+fn test_parse_entry_reads_cursor_message_and_timestamp() {
+    let entry = parse_entry(
+        r#"{"__CURSOR":"s=abc;i=1f","__REALTIME_TIMESTAMP":"1714564800000000","MESSAGE":"ERR CONSENSUS FAILURE","_SYSTEMD_UNIT":"full-node.service"}"#,
+    )
+    .unwrap();
+
+    assert_eq!(entry.cursor, "s=abc;i=1f");
+    assert_eq!(entry.message, "ERR CONSENSUS FAILURE");
+    assert_eq!(
+        entry.timestamp,
+        Some(Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap())
+    );
+}
+
+#[test]
+fn test_parse_entry_decodes_binary_messages() {
+    let entry = parse_entry(r#"{"__CURSOR":"c","MESSAGE":[112,97,110,105,99,255]}"#).unwrap();
+    assert_eq!(entry.message, "panic\u{fffd}");
+    assert_eq!(entry.timestamp, None);
+}
+
+#[test]
+fn test_parse_entry_skips_non_entries() {
+    assert_eq!(parse_entry("-- No entries --"), None);
+    assert_eq!(parse_entry(r#"{"MESSAGE":"no cursor"}"#), None);
+}
+
+#[test]
+fn test_validate_unit_name() {
+    assert!(validate_unit_name("full-node.service").is_ok());
+    assert!(validate_unit_name("node@mainnet-1.service").is_ok());
+    assert!(validate_unit_name("").is_err());
+    assert!(validate_unit_name("--since=yesterday").is_err());
+    assert!(validate_unit_name("node.service _PID=1").is_err());
+    assert!(validate_unit_name("node;reboot").is_err());
+}
+
+#[test]
+fn test_source_name() {
+    assert_eq!(
+        source_name("full-node.service"),
+        "journald:full-node.service"
+    );
+}
+
+fn entry(cursor: &str, message: &str) -> JournalEntry {
+    JournalEntry {
+        cursor: cursor.to_string(),
+        message: message.to_string(),
+        timestamp: None,
+    }
+}
+
+#[test]
+fn test_journal_window_keeps_newest_entries_within_line_limit() {
+    let mut window = JournalWindow::new(2, 1024);
+    for (cursor, message) in [("a", "first"), ("b", "second"), ("c", "third")] {
+        window.push(entry(cursor, message));
+    }
+
+    let cursors: Vec<_> = window.entries.iter().map(|e| e.cursor.as_str()).collect();
+    assert_eq!(cursors, vec!["b", "c"]);
+    assert_eq!(window.skipped_bytes, "first".len() as u64);
+}
+
+#[test]
+fn test_journal_window_keeps_newest_entries_within_byte_limit() {
+    let mut window = JournalWindow::new(100, 10);
+    window.push(entry("a", "12345"));
+    window.push(entry("b", "67890"));
+    assert_eq!(window.entries.len(), 2);
+    assert_eq!(window.skipped_bytes, 0);
+
+    window.push(entry("c", "abcdef"));
+    let cursors: Vec<_> = window.entries.iter().map(|e| e.cursor.as_str()).collect();
+    assert_eq!(cursors, vec!["c"]);
+    assert_eq!(window.skipped_bytes, 10);
+
+    // An entry larger than the limit on its own is skipped too
+    window.push(entry("d", "x".repeat(11).as_str()));
+    assert!(window.entries.is_empty());
+    assert_eq!(window.skipped_bytes, 27);
+}
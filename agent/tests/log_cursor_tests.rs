@@ -0,0 +1,162 @@
+//! Tests for incremental log searches that resume from per-file cursors
+
+use agent::services::logs::search_logs;
+use agent::types::{LogCursor, LogSearchRequest, LogSearchResult};
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn request(path: &Path, cursors: HashMap<String, LogCursor>) -> LogSearchRequest {
+    LogSearchRequest {
+        path: path.to_str().unwrap().to_string(),
+        patterns: vec!["error".to_string()],
+        tail_lines: 5000,
+        tail_bytes: 16 * 1024 * 1024,
+        context_lines: 0,
+        context_before: None,
+        context_after: None,
+        max_matches: 100,
+        cursors: Some(cursors),
+        glob: None,
+        journald_unit: None,
+    }
+}
+
+fn append(path: &Path, content: &str) {
+    OpenOptions::new()
+        .append(true)
+        .open(path)
+        .unwrap()
+        .write_all(content.as_bytes())
+        .unwrap();
+}
+
+fn lines(result: &LogSearchResult) -> Vec<&str> {
+    result.matches.iter().map(|m| m.line.as_str()).collect()
+}
+
+#[tokio::test]
+async fn test_resumed_search_only_sees_new_lines() {
+    let dir = TempDir::new().unwrap();
+    let log_file = dir.path().join("out1.log");
+    fs::write(&log_file, "error 1\nok\n").unwrap();
+
+    let first = search_logs(request(&log_file, HashMap::new()))
+        .await
+        .unwrap();
+    assert_eq!(lines(&first), vec!["error 1"]);
+    let cursor = &first.cursors[log_file.to_str().unwrap()];
+    assert_eq!(cursor.offset, 11);
+
+    append(&log_file, "error 2\n");
+    let second = search_logs(request(&log_file, first.cursors.clone()))
+        .await
+        .unwrap();
+    assert_eq!(lines(&second), vec!["error 2"]);
+    assert_eq!(second.matches[0].offset, 11);
+
+    let third = search_logs(request(&log_file, second.cursors))
+        .await
+        .unwrap();
+    assert!(!third.matched);
+}
+
+#[tokio::test]
+async fn test_partial_line_is_searched_once_complete() {
+    let dir = TempDir::new().unwrap();
+    let log_file = dir.path().join("out1.log");
+    fs::write(&log_file, "ok\n").unwrap();
+    let first = search_logs(request(&log_file, HashMap::new()))
+        .await
+        .unwrap();
+
+    append(&log_file, "error: half");
+    let second = search_logs(request(&log_file, first.cursors))
+        .await
+        .unwrap();
+    assert!(!second.matched);
+    assert_eq!(second.cursors[log_file.to_str().unwrap()].offset, 3);
+
+    append(&log_file, " written\n");
+    let third = search_logs(request(&log_file, second.cursors))
+        .await
+        .unwrap();
+    assert_eq!(lines(&third), vec!["error: half written"]);
+}
+
+#[tokio::test]
+async fn test_truncated_file_is_searched_from_the_start() {
+    let dir = TempDir::new().unwrap();
+    let log_file = dir.path().join("out1.log");
+    fs::write(&log_file, "first run ok\nerror old\n").unwrap();
+    let first = search_logs(request(&log_file, HashMap::new()))
+        .await
+        .unwrap();
+
+    // Truncated in place and written past the old cursor before the next check
+    fs::write(&log_file, "second run, error new\nmore output after\n").unwrap();
+    let second = search_logs(request(&log_file, first.cursors))
+        .await
+        .unwrap();
+    assert_eq!(lines(&second), vec!["second run, error new"]);
+}
+
+#[tokio::test]
+async fn test_rotated_file_is_searched_from_the_start() {
+    let dir = TempDir::new().unwrap();
+    let log_file = dir.path().join("out1.log");
+    fs::write(&log_file, "ok\n").unwrap();
+    let first = search_logs(request(&log_file, HashMap::new()))
+        .await
+        .unwrap();
+
+    fs::rename(&log_file, dir.path().join("out1.log.1")).unwrap();
+    fs::write(&log_file, "ok\nerror after rotation\n").unwrap();
+    let second = search_logs(request(&log_file, first.cursors.clone()))
+        .await
+        .unwrap();
+    assert_eq!(lines(&second), vec!["error after rotation"]);
+    let key = log_file.to_str().unwrap();
+    assert_ne!(second.cursors[key].inode, first.cursors[key].inode);
+}
+
+#[tokio::test]
+async fn test_truncated_results_resume_at_first_unreported_match() {
+    let dir = TempDir::new().unwrap();
+    let log_file = dir.path().join("out1.log");
+    let content: String = (1..=5).map(|i| format!("error {}\n", i)).collect();
+    fs::write(&log_file, &content).unwrap();
+
+    let mut req = request(&log_file, HashMap::new());
+    req.max_matches = 2;
+    let first = search_logs(req).await.unwrap();
+    assert!(first.truncated);
+    assert_eq!(lines(&first), vec!["error 1", "error 2"]);
+
+    let mut req = request(&log_file, first.cursors);
+    req.max_matches = 2;
+    let second = search_logs(req).await.unwrap();
+    assert_eq!(lines(&second), vec!["error 3", "error 4"]);
+}
+
+#[tokio::test]
+async fn test_growth_beyond_byte_limit_is_reported() {
+    let dir = TempDir::new().unwrap();
+    let log_file = dir.path().join("out1.log");
+    fs::write(&log_file, "ok\n").unwrap();
+    let first = search_logs(request(&log_file, HashMap::new()))
+        .await
+        .unwrap();
+
+    let appended: String = (1..=10).map(|i| format!("error {}\n", i)).collect();
+    append(&log_file, &appended);
+    let mut req = request(&log_file, first.cursors);
+    req.tail_bytes = 20;
+    let second = search_logs(req).await.unwrap();
+
+    // The last 20 bytes start inside "error 8", which is dropped as partial
+    assert_eq!(lines(&second), vec!["error 9", "error 10"]);
+    assert_eq!(second.skipped_bytes, appended.len() as u64 - 20);
+}
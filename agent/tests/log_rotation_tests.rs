@@ -0,0 +1,250 @@
+//! Tests for archiving logs before maintenance clears them
+
+use agent::services::log_rotation::{
+    archive_dir, archive_file, files_to_rotate, list_archives, prune_archives,
+};
+use agent::types::{LogCompression, LogRotation};
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn at(hour: u32) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2024, 5, 1, hour, 0, 0).unwrap()
+}
+
+fn rotation(keep_generations: Option<usize>, keep_bytes: Option<u64>) -> LogRotation {
+    LogRotation {
+        compression: LogCompression::None,
+        keep_generations,
+        keep_bytes,
+        log_glob: None,
+    }
+}
+
+/// Archive `content` as a rotation of `out1.log` at `time`
+fn rotate(dir: &Path, content: &str, time: DateTime<Utc>) {
+    let log_file = dir.join("out1.log");
+    fs::write(&log_file, content).unwrap();
+    archive_file(&log_file, &archive_dir(dir), LogCompression::None, time)
+        .unwrap()
+        .unwrap();
+}
+
+fn archive_names(dir: &Path) -> Vec<String> {
+    list_archives(dir.to_str().unwrap())
+        .unwrap()
+        .archives
+        .into_iter()
+        .map(|archive| archive.name)
+        .collect()
+}
+
+#[test]
+fn test_gzip_archive_keeps_log_contents() {
+    let dir = TempDir::new().unwrap();
+    let log_file = dir.path().join("out1.log");
+    fs::write(&log_file, "ERR CONSENSUS FAILURE\n").unwrap();
+
+    let archive = archive_file(
+        &log_file,
+        &archive_dir(&log_file),
+        LogCompression::Gzip,
+        at(12),
+    )
+    .unwrap()
+    .unwrap();
+
+    assert_eq!(
+        archive,
+        dir.path().join("archive/out1.log.20240501T120000000Z.gz")
+    );
+    let mut content = String::new();
+    flate2::read::GzDecoder::new(fs::File::open(&archive).unwrap())
+        .read_to_string(&mut content)
+        .unwrap();
+    assert_eq!(content, "ERR CONSENSUS FAILURE\n");
+}
+
+#[test]
+fn test_zstd_archive_keeps_log_contents() {
+    let dir = TempDir::new().unwrap();
+    let log_file = dir.path().join("out1.log");
+    fs::write(&log_file, "panic: boom\n").unwrap();
+
+    let archive = archive_file(
+        &log_file,
+        &archive_dir(dir.path()),
+        LogCompression::Zstd,
+        at(12),
+    )
+    .unwrap()
+    .unwrap();
+
+    assert!(archive.to_string_lossy().ends_with(".zst"));
+    let content = zstd::decode_all(fs::File::open(&archive).unwrap()).unwrap();
+    assert_eq!(content, b"panic: boom\n");
+}
+
+#[test]
+fn test_empty_log_is_not_archived() {
+    let dir = TempDir::new().unwrap();
+    let log_file = dir.path().join("out1.log");
+    fs::write(&log_file, "").unwrap();
+
+    let archive = archive_file(
+        &log_file,
+        &archive_dir(dir.path()),
+        LogCompression::Gzip,
+        at(12),
+    );
+    assert!(archive.unwrap().is_none());
+    assert!(!dir.path().join("archive").exists());
+}
+
+#[test]
+fn test_prune_keeps_newest_generations() {
+    let dir = TempDir::new().unwrap();
+    for hour in 1..=4 {
+        rotate(dir.path(), "line\n", at(hour));
+    }
+
+    let removed = prune_archives(
+        &archive_dir(dir.path()),
+        "out1.log",
+        &rotation(Some(2), None),
+    )
+    .unwrap();
+
+    assert_eq!(removed.len(), 2);
+    assert_eq!(
+        archive_names(dir.path()),
+        vec![
+            "out1.log.20240501T040000000Z",
+            "out1.log.20240501T030000000Z"
+        ]
+    );
+}
+
+#[test]
+fn test_prune_by_bytes_always_keeps_newest() {
+    let dir = TempDir::new().unwrap();
+    rotate(dir.path(), "0123456789", at(1));
+    rotate(dir.path(), "0123456789", at(2));
+    rotate(dir.path(), "01234567890123456789", at(3));
+
+    // The newest archive alone is over the limit, but is never removed
+    prune_archives(
+        &archive_dir(dir.path()),
+        "out1.log",
+        &rotation(None, Some(15)),
+    )
+    .unwrap();
+    assert_eq!(
+        archive_names(dir.path()),
+        vec!["out1.log.20240501T030000000Z"]
+    );
+}
+
+#[test]
+fn test_prune_only_touches_archives_of_the_same_file() {
+    let dir = TempDir::new().unwrap();
+    let error_log = dir.path().join("error1.log");
+    fs::write(&error_log, "error\n").unwrap();
+    archive_file(
+        &error_log,
+        &archive_dir(dir.path()),
+        LogCompression::None,
+        at(1),
+    )
+    .unwrap();
+    rotate(dir.path(), "line\n", at(2));
+    rotate(dir.path(), "line\n", at(3));
+
+    prune_archives(
+        &archive_dir(dir.path()),
+        "out1.log",
+        &rotation(Some(1), None),
+    )
+    .unwrap();
+    assert_eq!(
+        archive_names(dir.path()),
+        vec![
+            "out1.log.20240501T030000000Z",
+            "error1.log.20240501T010000000Z"
+        ]
+    );
+}
+
+#[test]
+fn test_listing_ignores_partial_and_unrelated_files() {
+    let dir = TempDir::new().unwrap();
+    rotate(dir.path(), "line\n", at(1));
+    let archives = archive_dir(dir.path());
+    fs::write(
+        archives.join("out1.log.20240501T020000000Z.gz.partial"),
+        "x",
+    )
+    .unwrap();
+    fs::write(archives.join("README"), "x").unwrap();
+
+    let listing = list_archives(dir.path().to_str().unwrap()).unwrap();
+    assert_eq!(listing.archive_dir, archives.to_str().unwrap());
+    assert_eq!(listing.archives.len(), 1);
+    let archive = &listing.archives[0];
+    assert_eq!(archive.log_file, "out1.log");
+    assert_eq!(archive.size_bytes, 5);
+    assert_eq!(archive.rotated_at, at(1));
+}
+
+#[test]
+fn test_rotations_within_a_second_keep_separate_archives() {
+    let dir = TempDir::new().unwrap();
+    rotate(dir.path(), "first\n", at(1));
+    rotate(dir.path(), "second\n", at(1) + Duration::milliseconds(250));
+
+    assert_eq!(
+        archive_names(dir.path()),
+        vec![
+            "out1.log.20240501T010000250Z",
+            "out1.log.20240501T010000000Z"
+        ]
+    );
+}
+
+#[test]
+fn test_listing_reads_second_resolution_archives() {
+    let dir = TempDir::new().unwrap();
+    let archives = archive_dir(dir.path());
+    fs::create_dir_all(&archives).unwrap();
+    fs::write(archives.join("out1.log.20240501T010000Z.gz"), "x").unwrap();
+
+    let listing = list_archives(dir.path().to_str().unwrap()).unwrap();
+    assert_eq!(listing.archives.len(), 1);
+    assert_eq!(listing.archives[0].rotated_at, at(1));
+}
+
+#[test]
+fn test_rotation_only_touches_files_matching_the_log_glob() {
+    let dir = TempDir::new().unwrap();
+    for name in ["out1.log", "out2.log", "hermes.log"] {
+        fs::write(dir.path().join(name), "line\n").unwrap();
+    }
+    let log_path = dir.path().to_str().unwrap();
+
+    let globbed = LogRotation {
+        log_glob: Some("out*.log".to_string()),
+        ..rotation(None, None)
+    };
+    assert_eq!(
+        files_to_rotate(log_path, &globbed).unwrap(),
+        vec![dir.path().join("out1.log"), dir.path().join("out2.log")]
+    );
+    assert_eq!(
+        files_to_rotate(log_path, &rotation(None, None))
+            .unwrap()
+            .len(),
+        3
+    );
+}
@@ -0,0 +1,188 @@
+//! Tests for the structured log search served on `/logs/search`
+
+use agent::services::logs::{parse_log_timestamp, search_logs};
+use agent::types::LogSearchRequest;
+use chrono::{TimeZone, Utc};
+use std::fs;
+use tempfile::TempDir;
+
+fn request(path: &str, patterns: &[&str]) -> LogSearchRequest {
+    LogSearchRequest {
+        path: path.to_string(),
+        patterns: patterns.iter().map(|p| p.to_string()).collect(),
+        tail_lines: 5000,
+        tail_bytes: 16 * 1024 * 1024,
+        context_lines: 0,
+        context_before: None,
+        context_after: None,
+        max_matches: 100,
+        cursors: None,
+        glob: None,
+        journald_unit: None,
+    }
+}
+
+#[tokio::test]
+async fn test_matches_report_line_pattern_and_context() {
+    let dir = TempDir::new().unwrap();
+    let log_file = dir.path().join("out1.log");
+    fs::write(
+        &log_file,
+        "one\ntwo\n2024-05-01T12:00:00Z ERR CONSENSUS FAILURE height=42\nfour\nfive\n",
+    )
+    .unwrap();
+
+    let mut req = request(
+        log_file.to_str().unwrap(),
+        &["panic", r"CONSENSUS\s+FAILURE"],
+    );
+    req.context_before = Some(2);
+    req.context_after = Some(1);
+    let result = search_logs(req).await.unwrap();
+
+    assert!(result.matched);
+    assert!(!result.truncated);
+    assert_eq!(result.matches.len(), 1);
+    let found = &result.matches[0];
+    assert_eq!(found.file, log_file.to_str().unwrap());
+    assert_eq!(found.line_number, 3);
+    assert_eq!(found.offset, 8);
+    assert_eq!(found.pattern, r"CONSENSUS\s+FAILURE");
+    assert_eq!(found.context_before, vec!["one", "two"]);
+    assert_eq!(found.context_after, vec!["four"]);
+    assert_eq!(
+        found.timestamp,
+        Some(Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap())
+    );
+    assert_eq!(
+        result.output,
+        "1-one\n2-two\n3:2024-05-01T12:00:00Z ERR CONSENSUS FAILURE height=42\n4-four\n"
+    );
+}
+
+#[tokio::test]
+async fn test_grep_output_merges_overlapping_context() {
+    let dir = TempDir::new().unwrap();
+    let log_file = dir.path().join("out1.log");
+    fs::write(&log_file, "a\nerror 1\nb\nerror 2\nc\nd\ne\nerror 3\n").unwrap();
+
+    let mut req = request(log_file.to_str().unwrap(), &["error"]);
+    req.context_lines = 1;
+    let result = search_logs(req).await.unwrap();
+
+    assert_eq!(result.matches.len(), 3);
+    assert_eq!(
+        result.output,
+        "1-a\n2:error 1\n3-b\n4:error 2\n5-c\n--\n7-e\n8:error 3\n"
+    );
+}
+
+#[tokio::test]
+async fn test_searches_log_files_in_directory() {
+    let dir = TempDir::new().unwrap();
+    fs::write(dir.path().join("out1.log"), "ok\npanic: boom\n").unwrap();
+    fs::write(dir.path().join("error1.log"), "panic: earlier\n").unwrap();
+    fs::write(dir.path().join("notes.txt"), "panic: ignored\n").unwrap();
+
+    let result = search_logs(request(dir.path().to_str().unwrap(), &["^panic:"]))
+        .await
+        .unwrap();
+
+    let files: Vec<_> = result
+        .matches
+        .iter()
+        .map(|m| m.file.rsplit('/').next().unwrap().to_string())
+        .collect();
+    assert_eq!(files, vec!["error1.log", "out1.log"]);
+    assert!(result
+        .output
+        .lines()
+        .any(|line| line.ends_with("out1.log:2:panic: boom")));
+}
+
+#[tokio::test]
+async fn test_limits_bound_the_search() {
+    let dir = TempDir::new().unwrap();
+    let log_file = dir.path().join("out1.log");
+    let content: String = (1..=10).map(|i| format!("error {}\n", i)).collect();
+    fs::write(&log_file, &content).unwrap();
+    let path = log_file.to_str().unwrap();
+
+    let mut req = request(path, &["error"]);
+    req.max_matches = 3;
+    let result = search_logs(req).await.unwrap();
+    assert_eq!(result.matches.len(), 3);
+    assert!(result.truncated);
+
+    // The last 20 bytes start inside "error 8", which is dropped as partial
+    let mut req = request(path, &["error"]);
+    req.tail_bytes = 20;
+    let result = search_logs(req).await.unwrap();
+    let lines: Vec<_> = result.matches.iter().map(|m| m.line.as_str()).collect();
+    assert_eq!(lines, vec!["error 9", "error 10"]);
+    assert_eq!(
+        result.matches[0].offset,
+        content.find("error 9").unwrap() as u64
+    );
+}
+
+#[tokio::test]
+async fn test_invalid_pattern_is_an_error() {
+    let dir = TempDir::new().unwrap();
+    let log_file = dir.path().join("out1.log");
+    fs::write(&log_file, "line\n").unwrap();
+
+    let error = search_logs(request(log_file.to_str().unwrap(), &["(unclosed"]))
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("Invalid log search pattern"));
+}
+
+#[tokio::test]
+async fn test_missing_file_is_an_error() {
+    assert!(search_logs(request("/nonexistent/out1.log", &["x"]))
+        .await
+        .is_err());
+}
+
+#[test]
+fn test_parse_log_timestamp_formats() {
+    let expected = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+
+    assert_eq!(
+        parse_log_timestamp("2024-05-01T12:00:00Z INF committed block"),
+        Some(expected)
+    );
+    assert_eq!(
+        parse_log_timestamp("[2024-05-01T14:00:00+02:00 INFO solana] slot"),
+        Some(expected)
+    );
+    assert_eq!(
+        parse_log_timestamp("I[2024-05-01|12:00:00.000] Executed block"),
+        Some(expected)
+    );
+    assert_eq!(
+        parse_log_timestamp(r#"{"level":"info","time":1714564800,"message":"x"}"#),
+        Some(expected)
+    );
+    assert_eq!(parse_log_timestamp("12:00PM INF committed state"), None);
+}
+
+#[tokio::test]
+async fn test_directory_search_uses_glob() {
+    let dir = TempDir::new().unwrap();
+    fs::write(dir.path().join("out1.log"), "panic: current\n").unwrap();
+    fs::write(dir.path().join("out1.log.1"), "panic: rotated\n").unwrap();
+    fs::write(dir.path().join("node.txt"), "panic: text\n").unwrap();
+
+    let mut req = request(dir.path().to_str().unwrap(), &["^panic:"]);
+    req.glob = Some("*.txt".to_string());
+    let result = search_logs(req).await.unwrap();
+    let lines: Vec<_> = result.matches.iter().map(|m| m.line.as_str()).collect();
+    assert_eq!(lines, vec!["panic: text"]);
+
+    let mut req = request(dir.path().to_str().unwrap(), &["^panic:"]);
+    req.glob = Some("out1.log*".to_string());
+    let result = search_logs(req).await.unwrap();
+    assert_eq!(result.matches.len(), 2);
+}
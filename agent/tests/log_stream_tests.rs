@@ -0,0 +1,111 @@
+//! Tests for following logs on `/logs/stream`
+
+use agent::services::log_stream::LogFollower;
+use agent::types::LogStreamLine;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn append(path: &Path, content: &str) {
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .unwrap()
+        .write_all(content.as_bytes())
+        .unwrap();
+}
+
+fn lines(lines: &[LogStreamLine]) -> Vec<&str> {
+    lines.iter().map(|l| l.line.as_str()).collect()
+}
+
+#[test]
+fn test_follower_sends_tail_then_new_lines() {
+    let dir = TempDir::new().unwrap();
+    let log_file = dir.path().join("out1.log");
+    fs::write(&log_file, "one\ntwo\nthree\n").unwrap();
+
+    let (mut follower, initial) =
+        LogFollower::open(log_file.to_str().unwrap(), None, None, 2).unwrap();
+    assert_eq!(lines(&initial), vec!["two", "three"]);
+    assert_eq!(initial[0].file, log_file.to_str().unwrap());
+    assert!(follower.poll().unwrap().is_empty());
+
+    append(&log_file, "2024-05-01T12:00:00Z four\n");
+    let polled = follower.poll().unwrap();
+    assert_eq!(lines(&polled), vec!["2024-05-01T12:00:00Z four"]);
+    assert!(polled[0].timestamp.is_some());
+}
+
+#[test]
+fn test_follower_applies_filter() {
+    let dir = TempDir::new().unwrap();
+    let log_file = dir.path().join("out1.log");
+    fs::write(&log_file, "INF ok\nERR bad\n").unwrap();
+
+    let (mut follower, initial) =
+        LogFollower::open(log_file.to_str().unwrap(), None, Some("^ERR"), 100).unwrap();
+    assert_eq!(lines(&initial), vec!["ERR bad"]);
+
+    append(&log_file, "INF fine\nERR worse\n");
+    assert_eq!(lines(&follower.poll().unwrap()), vec!["ERR worse"]);
+}
+
+#[test]
+fn test_follower_waits_for_complete_lines() {
+    let dir = TempDir::new().unwrap();
+    let log_file = dir.path().join("out1.log");
+    fs::write(&log_file, "done\nhalf").unwrap();
+
+    let (mut follower, initial) =
+        LogFollower::open(log_file.to_str().unwrap(), None, None, 100).unwrap();
+    assert_eq!(lines(&initial), vec!["done"]);
+
+    append(&log_file, " way");
+    assert!(follower.poll().unwrap().is_empty());
+    append(&log_file, " there\n");
+    assert_eq!(lines(&follower.poll().unwrap()), vec!["half way there"]);
+}
+
+#[test]
+fn test_follower_survives_rotation() {
+    let dir = TempDir::new().unwrap();
+    let log_file = dir.path().join("out1.log");
+    fs::write(&log_file, "before\n").unwrap();
+    let (mut follower, _) = LogFollower::open(log_file.to_str().unwrap(), None, None, 100).unwrap();
+
+    fs::rename(&log_file, dir.path().join("out1.log.1")).unwrap();
+    assert!(follower.poll().unwrap().is_empty());
+
+    fs::write(&log_file, "after\n").unwrap();
+    assert_eq!(lines(&follower.poll().unwrap()), vec!["after"]);
+}
+
+#[test]
+fn test_follower_picks_up_new_files_in_directory() {
+    let dir = TempDir::new().unwrap();
+    fs::write(dir.path().join("out1.log"), "old\n").unwrap();
+    let (mut follower, initial) =
+        LogFollower::open(dir.path().to_str().unwrap(), None, None, 100).unwrap();
+    assert_eq!(lines(&initial), vec!["old"]);
+
+    append(&dir.path().join("error1.log"), "first\nsecond\n");
+    let polled = follower.poll().unwrap();
+    assert_eq!(lines(&polled), vec!["first", "second"]);
+    assert!(polled[0].file.ends_with("error1.log"));
+}
+
+#[test]
+fn test_follower_rejects_bad_requests() {
+    let dir = TempDir::new().unwrap();
+    let log_file = dir.path().join("out1.log");
+    fs::write(&log_file, "line\n").unwrap();
+
+    let error = LogFollower::open(log_file.to_str().unwrap(), None, Some("(unclosed"), 10)
+        .err()
+        .unwrap();
+    assert!(error.to_string().contains("Invalid log stream filter"));
+    assert!(LogFollower::open("/nonexistent/out1.log", None, None, 10).is_err());
+}
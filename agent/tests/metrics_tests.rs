@@ -0,0 +1,75 @@
+//! Tests for the Prometheus metrics rendered on `/metrics`
+
+use agent::services::metrics::AgentMetrics;
+use agent::services::sandbox::RootKind;
+use std::time::Duration;
+use tempfile::TempDir;
+
+#[test]
+fn test_render_includes_all_metric_families() {
+    let metrics = AgentMetrics::new();
+    metrics.record_job("pruning", "completed", Duration::from_secs(42));
+    metrics.record_bytes_copied("copy", 1024);
+    metrics.record_systemctl_failure("start");
+
+    let output = metrics.render(0, &[], &[]).unwrap();
+
+    for family in [
+        "agent_jobs_total",
+        "agent_operation_duration_seconds",
+        "agent_bytes_copied_total",
+        "agent_busy_nodes",
+        "agent_systemctl_failures_total",
+        "agent_uptime_seconds",
+    ] {
+        assert!(output.contains(family), "missing {}", family);
+    }
+}
+
+#[test]
+fn test_jobs_are_counted_by_operation_and_status() {
+    let metrics = AgentMetrics::new();
+    metrics.record_job("pruning", "completed", Duration::from_secs(10));
+    metrics.record_job("pruning", "completed", Duration::from_secs(20));
+    metrics.record_job("snapshot_restore", "failed", Duration::from_secs(5));
+
+    let output = metrics.render(0, &[], &[]).unwrap();
+
+    assert!(output.contains(r#"agent_jobs_total{operation="pruning",status="completed"} 2"#));
+    assert!(output.contains(r#"agent_jobs_total{operation="snapshot_restore",status="failed"} 1"#));
+    assert!(output.contains(
+        r#"agent_operation_duration_seconds_sum{operation="pruning",status="completed"} 30"#
+    ));
+}
+
+#[test]
+fn test_current_state_gauges_are_refreshed_on_render() {
+    let metrics = AgentMetrics::new();
+    let running = vec!["pruning".to_string(), "pruning".to_string()];
+
+    let output = metrics.render(2, &running, &[]).unwrap();
+    assert!(output.contains("agent_busy_nodes 2"));
+    assert!(output.contains(r#"agent_jobs_running{operation="pruning"} 2"#));
+
+    let output = metrics.render(0, &[], &[]).unwrap();
+    assert!(output.contains("agent_busy_nodes 0"));
+    assert!(!output.contains(r#"agent_jobs_running{operation="pruning"}"#));
+}
+
+#[test]
+fn test_free_disk_is_reported_per_root() {
+    let metrics = AgentMetrics::new();
+    let dir = TempDir::new().unwrap();
+    let root = dir.path().to_str().unwrap().to_string();
+
+    let output = metrics
+        .render(0, &[], &[(RootKind::Backup, root.clone())])
+        .unwrap();
+
+    let line = output
+        .lines()
+        .find(|line| line.starts_with("agent_free_disk_bytes{"))
+        .expect("free disk gauge present");
+    assert!(line.contains(r#"kind="backup""#));
+    assert!(line.contains(&format!(r#"root="{}""#, root)));
+}
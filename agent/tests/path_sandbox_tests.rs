@@ -0,0 +1,219 @@
+//! Tests for the allowed roots enforced on request paths
+
+use agent::config::PathRoots;
+use agent::services::sandbox::{PathSandbox, PathViolation, RootKind};
+use std::os::unix::fs::symlink;
+use tempfile::TempDir;
+
+struct Roots {
+    dir: TempDir,
+}
+
+impl Roots {
+    /// `deploy`, `backup` and `logs` directories plus an `outside` directory
+    fn create() -> Self {
+        let dir = TempDir::new().unwrap();
+        for name in ["deploy", "backup", "logs", "outside"] {
+            std::fs::create_dir(dir.path().join(name)).unwrap();
+        }
+        Self { dir }
+    }
+
+    fn path(&self, relative: &str) -> String {
+        self.dir.path().join(relative).to_str().unwrap().to_string()
+    }
+
+    fn sandbox(&self) -> PathSandbox {
+        PathSandbox::new(PathRoots {
+            deploy_roots: vec![self.path("deploy")],
+            backup_roots: vec![self.path("backup")],
+            log_roots: vec![self.path("logs")],
+            ..PathRoots::default()
+        })
+    }
+}
+
+#[test]
+fn test_accepts_paths_inside_their_roots() {
+    let roots = Roots::create();
+    let sandbox = roots.sandbox();
+
+    assert!(sandbox
+        .check(RootKind::Deploy, &roots.path("deploy/node-1"))
+        .is_ok());
+    assert!(sandbox
+        .check(RootKind::Backup, &roots.path("backup"))
+        .is_ok());
+    // Paths that do not exist yet are checked through their existing ancestor
+    assert!(sandbox
+        .check(RootKind::Log, &roots.path("logs/new/node.log"))
+        .is_ok());
+}
+
+#[test]
+fn test_rejects_paths_outside_their_roots() {
+    let roots = Roots::create();
+    let sandbox = roots.sandbox();
+
+    let result = sandbox.check(RootKind::Log, &roots.path("backup/node.log"));
+    assert!(matches!(
+        result,
+        Err(PathViolation::OutsideRoots {
+            kind: RootKind::Log,
+            ..
+        })
+    ));
+    assert!(matches!(
+        sandbox.check(RootKind::Log, "/etc"),
+        Err(PathViolation::OutsideRoots { .. })
+    ));
+}
+
+#[test]
+fn test_rejects_root_relative_and_traversing_paths() {
+    let roots = Roots::create();
+    let sandbox = roots.sandbox();
+
+    assert!(matches!(
+        sandbox.check(RootKind::Log, "/"),
+        Err(PathViolation::FilesystemRoot { .. })
+    ));
+    assert!(matches!(
+        sandbox.check(RootKind::Log, "logs/node.log"),
+        Err(PathViolation::NotAbsolute { .. })
+    ));
+    assert!(matches!(
+        sandbox.check(RootKind::Log, &roots.path("logs/../outside")),
+        Err(PathViolation::ParentTraversal { .. })
+    ));
+}
+
+#[test]
+fn test_rejects_symlinks_escaping_a_root() {
+    let roots = Roots::create();
+    let sandbox = roots.sandbox();
+    symlink(roots.path("outside"), roots.path("logs/escape")).unwrap();
+
+    let result = sandbox.check(RootKind::Log, &roots.path("logs/escape/node.log"));
+    assert!(matches!(result, Err(PathViolation::SymlinkEscape { .. })));
+}
+
+#[test]
+fn test_rejects_dangling_symlinks_escaping_a_root() {
+    let roots = Roots::create();
+    let sandbox = roots.sandbox();
+    symlink(roots.path("outside/later"), roots.path("backup/later")).unwrap();
+
+    let result = sandbox.check(RootKind::Backup, &roots.path("backup/later"));
+    assert!(matches!(result, Err(PathViolation::SymlinkEscape { .. })));
+}
+
+#[test]
+fn test_accepts_symlinks_staying_inside_a_root() {
+    let roots = Roots::create();
+    let sandbox = roots.sandbox();
+    std::fs::create_dir(roots.path("deploy/node-1")).unwrap();
+    symlink(roots.path("deploy/node-1"), roots.path("deploy/current")).unwrap();
+
+    let resolved = sandbox
+        .check(RootKind::Deploy, &roots.path("deploy/current"))
+        .unwrap();
+    assert!(resolved.ends_with("deploy/node-1"));
+}
+
+#[test]
+fn test_unconfigured_kind_is_refused() {
+    let roots = Roots::create();
+    let sandbox = PathSandbox::new(PathRoots {
+        backup_roots: vec![roots.path("backup")],
+        ..PathRoots::default()
+    });
+
+    assert!(!sandbox.is_restricted(RootKind::Log));
+    assert!(matches!(
+        sandbox.check(RootKind::Log, &roots.path("outside")),
+        Err(PathViolation::NoRoots {
+            kind: RootKind::Log,
+            ..
+        })
+    ));
+    assert!(matches!(
+        sandbox.check(RootKind::Log, "/etc"),
+        Err(PathViolation::NoRoots { .. })
+    ));
+    assert!(sandbox
+        .check(RootKind::Backup, &roots.path("backup/snap"))
+        .is_ok());
+}
+
+#[test]
+fn test_unconfigured_kind_is_open_when_allowed() {
+    let roots = Roots::create();
+    let sandbox = PathSandbox::new(PathRoots {
+        backup_roots: vec![roots.path("backup")],
+        allow_unconfined_paths: true,
+        ..PathRoots::default()
+    });
+
+    assert!(sandbox.check(RootKind::Log, &roots.path("outside")).is_ok());
+    assert!(matches!(
+        sandbox.check(RootKind::Log, "/"),
+        Err(PathViolation::FilesystemRoot { .. })
+    ));
+    // Kinds with roots stay confined to them
+    assert!(matches!(
+        sandbox.check(RootKind::Backup, &roots.path("outside")),
+        Err(PathViolation::OutsideRoots { .. })
+    ));
+}
+
+#[test]
+fn test_check_any_without_any_roots_is_refused() {
+    let roots = Roots::create();
+
+    assert!(matches!(
+        PathSandbox::default().check_any(&roots.path("outside")),
+        Err(PathViolation::NoRoots { .. })
+    ));
+    let partial = PathSandbox::new(PathRoots {
+        log_roots: vec![roots.path("logs")],
+        ..PathRoots::default()
+    });
+    assert!(matches!(
+        partial.check_any(&roots.path("outside")),
+        Err(PathViolation::OutsideRoots {
+            kind: RootKind::Log,
+            ..
+        })
+    ));
+}
+
+#[test]
+fn test_check_any_accepts_any_kind_of_root() {
+    let roots = Roots::create();
+    let sandbox = roots.sandbox();
+
+    assert!(sandbox.check_any(&roots.path("logs/node.log")).is_ok());
+    assert!(sandbox.check_any(&roots.path("backup/snap")).is_ok());
+    assert!(matches!(
+        sandbox.check_any(&roots.path("outside")),
+        Err(PathViolation::OutsideRoots { .. })
+    ));
+}
+
+#[test]
+fn test_check_all_stops_at_first_violation() {
+    let roots = Roots::create();
+    let sandbox = roots.sandbox();
+    let deploy = roots.path("deploy/node-1");
+    let outside = roots.path("outside");
+
+    let result = sandbox.check_all(&[(RootKind::Deploy, &deploy), (RootKind::Log, &outside)]);
+    assert!(matches!(
+        result,
+        Err(PathViolation::OutsideRoots {
+            kind: RootKind::Log,
+            ..
+        })
+    ));
+}
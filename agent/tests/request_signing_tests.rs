@@ -0,0 +1,173 @@
+//! Tests for HMAC request signing and replay protection
+//!
+//! These tests verify that the agent accepts correctly signed requests from
+//! either active key and rejects tampered, stale and replayed requests, and
+//! that archive download tokens only cover their own archive until expiry.
+
+use agent::services::signing::{signature, RequestVerifier, SignatureError};
+
+const KEY: &str = "primary-key";
+const NOW: i64 = 1_760_000_000;
+const PATH: &str = "/snapshot/restore";
+const BODY: &[u8] = br#"{"node_name":"pirin-1"}"#;
+
+fn signed(key: &str, timestamp: i64, nonce: &str) -> String {
+    signature(key, "POST", PATH, timestamp, nonce, BODY)
+}
+
+fn verify(
+    verifier: &RequestVerifier,
+    timestamp: i64,
+    nonce: &str,
+    signature: &str,
+    body: &[u8],
+) -> Result<(), SignatureError> {
+    verifier.verify(
+        "POST",
+        PATH,
+        (Some(&timestamp.to_string()), Some(nonce), Some(signature)),
+        body,
+        NOW,
+    )
+}
+
+#[test]
+fn test_signature_matches_known_vector() {
+    // The manager signs with the same scheme and checks this vector too
+    assert_eq!(
+        signature(
+            "test-key",
+            "post",
+            "/logs/search",
+            1_700_000_000,
+            "abc123",
+            b"{}"
+        ),
+        "372329c14ad75d36ce7154b23dc5e400654f927c44758c909d0c2b9759923a2c"
+    );
+}
+
+#[test]
+fn test_accepts_valid_signature() {
+    let verifier = RequestVerifier::new(vec![KEY.to_string()]);
+    let sig = signed(KEY, NOW, "nonce-1");
+
+    assert_eq!(verify(&verifier, NOW, "nonce-1", &sig, BODY), Ok(()));
+}
+
+#[test]
+fn test_accepts_secondary_key_during_rotation() {
+    let verifier = RequestVerifier::new(vec![KEY.to_string(), "next-key".to_string()]);
+    let sig = signed("next-key", NOW, "nonce-1");
+
+    assert_eq!(verify(&verifier, NOW, "nonce-1", &sig, BODY), Ok(()));
+}
+
+#[test]
+fn test_rejects_unknown_key() {
+    let verifier = RequestVerifier::new(vec![KEY.to_string()]);
+    let sig = signed("other-key", NOW, "nonce-1");
+
+    assert_eq!(
+        verify(&verifier, NOW, "nonce-1", &sig, BODY),
+        Err(SignatureError::Invalid)
+    );
+}
+
+#[test]
+fn test_rejects_tampered_body() {
+    let verifier = RequestVerifier::new(vec![KEY.to_string()]);
+    let sig = signed(KEY, NOW, "nonce-1");
+
+    assert_eq!(
+        verify(&verifier, NOW, "nonce-1", &sig, br#"{"node_name":"other"}"#),
+        Err(SignatureError::Invalid)
+    );
+}
+
+#[test]
+fn test_rejects_stale_timestamp() {
+    let verifier = RequestVerifier::new(vec![KEY.to_string()]);
+    let old = NOW - 3600;
+    let sig = signed(KEY, old, "nonce-1");
+
+    assert_eq!(
+        verify(&verifier, old, "nonce-1", &sig, BODY),
+        Err(SignatureError::Stale)
+    );
+}
+
+#[test]
+fn test_rejects_replayed_nonce() {
+    let verifier = RequestVerifier::new(vec![KEY.to_string()]);
+    let sig = signed(KEY, NOW, "nonce-1");
+
+    assert_eq!(verify(&verifier, NOW, "nonce-1", &sig, BODY), Ok(()));
+    assert_eq!(
+        verify(&verifier, NOW, "nonce-1", &sig, BODY),
+        Err(SignatureError::Replayed)
+    );
+}
+
+#[test]
+fn test_rejects_missing_headers() {
+    let verifier = RequestVerifier::new(vec![KEY.to_string()]);
+
+    let result = verifier.verify("POST", PATH, (None, None, None), BODY, NOW);
+
+    assert_eq!(result, Err(SignatureError::Missing));
+}
+
+#[test]
+fn test_download_token_allows_only_its_archive_until_expiry() {
+    let verifier = RequestVerifier::new(vec![KEY.to_string()]);
+    let backup = "/opt/backups/pirin-1";
+    let snapshot = "pirin-1_20250121_17154420";
+    let token = verifier.issue_download_token(backup, snapshot, NOW + 60);
+
+    assert_eq!(
+        verifier.verify_download_token(&token, backup, snapshot, NOW),
+        Ok(())
+    );
+    assert_eq!(
+        verifier.verify_download_token(&token, backup, "pirin-1_20250120_17000000", NOW),
+        Err(SignatureError::Invalid)
+    );
+    assert_eq!(
+        verifier.verify_download_token(&token, "/opt/deploy", snapshot, NOW),
+        Err(SignatureError::Invalid)
+    );
+    assert_eq!(
+        verifier.verify_download_token(&token, backup, snapshot, NOW + 61),
+        Err(SignatureError::Expired)
+    );
+}
+
+#[test]
+fn test_download_token_cannot_be_forged_or_extended() {
+    let verifier = RequestVerifier::new(vec![KEY.to_string()]);
+    let backup = "/opt/backups/pirin-1";
+    let snapshot = "pirin-1_20250121_17154420";
+    let token = verifier.issue_download_token(backup, snapshot, NOW + 60);
+    let (_, mac) = token.split_once('.').unwrap();
+
+    let extended = format!("{}.{}", NOW + 3600, mac);
+    assert_eq!(
+        verifier.verify_download_token(&extended, backup, snapshot, NOW),
+        Err(SignatureError::Invalid)
+    );
+    let other_agent = RequestVerifier::new(vec!["other-key".to_string()]);
+    assert_eq!(
+        verifier.verify_download_token(
+            &other_agent.issue_download_token(backup, snapshot, NOW + 60),
+            backup,
+            snapshot,
+            NOW
+        ),
+        Err(SignatureError::Invalid)
+    );
+    assert_eq!(
+        verifier.verify_download_token("", backup, snapshot, NOW),
+        Err(SignatureError::Missing)
+    );
+}
@@ -68,6 +68,11 @@ fn test_restore_request_validation() {
         deploy_path: deploy_dir.path().to_string_lossy().to_string(),
         snapshot_dir: snapshot_dir.path().to_string_lossy().to_string(),
         log_path: None,
+        log_rotation: None,
+        archive_path: None,
+        force: false,
+        rpc_url: Some("http://127.0.0.1:26657".to_string()),
+        rollback_after_blocks: 5,
     };
 
     // Verify request fields
@@ -276,6 +281,11 @@ fn test_restore_request_fields() {
         deploy_path: "/opt/deploy/nolus/full-node-3".to_string(),
         snapshot_dir: "/home/backup/snapshots/pirin-1_20250125_17154420".to_string(),
         log_path: Some("/var/log/full-node-3".to_string()),
+        log_rotation: None,
+        archive_path: None,
+        force: false,
+        rpc_url: Some("http://127.0.0.1:26657".to_string()),
+        rollback_after_blocks: 5,
     };
 
     assert_eq!(request.node_name, "pirin-node-3");
@@ -0,0 +1,196 @@
+//! Integration tests for transactional restores
+//!
+//! These tests verify that a restore moves the node's previous data aside
+//! instead of deleting it, that rolling back puts it back without losing
+//! validator signing progress, and that the previous data is only discarded
+//! once the node's RPC shows new blocks.
+
+use agent::services::{node_rpc, rollback};
+use agent::types::RestoreRequest;
+use axum::routing::get;
+use axum::{Json, Router};
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tempfile::TempDir;
+
+fn write_node(deploy: &Path, content: &[u8], validator_height: u64) {
+    fs::create_dir_all(deploy.join("data/application.db")).unwrap();
+    fs::create_dir_all(deploy.join("wasm")).unwrap();
+    fs::write(deploy.join("data/application.db/000001.log"), content).unwrap();
+    fs::write(deploy.join("wasm/contract.wasm"), content).unwrap();
+    fs::write(
+        deploy.join("data/priv_validator_state.json"),
+        format!(r#"{{"height":"{}","round":0,"step":3}}"#, validator_height),
+    )
+    .unwrap();
+}
+
+async fn validator_height(deploy: &Path) -> Option<u64> {
+    rollback::validator_height(
+        deploy
+            .join("data/priv_validator_state.json")
+            .to_str()
+            .unwrap(),
+    )
+    .await
+}
+
+#[tokio::test]
+async fn test_rollback_restores_previous_data_and_keeps_newer_signing_state() {
+    let deploy = TempDir::new().unwrap();
+    let deploy_path = deploy.path().to_str().unwrap();
+    write_node(deploy.path(), b"previous", 100);
+
+    rollback::set_aside_current_data(deploy_path).await.unwrap();
+    assert!(rollback::has_previous_data(deploy_path).await);
+    assert!(!deploy.path().join("data").exists());
+
+    // The restored node signed blocks before failing its health check
+    write_node(deploy.path(), b"restored", 120);
+    let summary = rollback::restore_previous_data(deploy_path).await.unwrap();
+
+    assert!(summary.contains("restored previous data"));
+    assert!(!rollback::has_previous_data(deploy_path).await);
+    assert_eq!(
+        fs::read(deploy.path().join("data/application.db/000001.log")).unwrap(),
+        b"previous"
+    );
+    assert_eq!(
+        fs::read(deploy.path().join("wasm/contract.wasm")).unwrap(),
+        b"previous"
+    );
+    assert_eq!(validator_height(deploy.path()).await, Some(120));
+}
+
+#[tokio::test]
+async fn test_rollback_keeps_previous_state_over_lower_snapshot_state() {
+    let deploy = TempDir::new().unwrap();
+    let deploy_path = deploy.path().to_str().unwrap();
+    write_node(deploy.path(), b"previous", 100);
+    fs::write(
+        deploy.path().join("priv_validator_state_backup.json"),
+        r#"{"height":"100"}"#,
+    )
+    .unwrap();
+
+    rollback::set_aside_current_data(deploy_path).await.unwrap();
+    // The copy failed before the node's own validator state was put back
+    write_node(deploy.path(), b"half copied", 50);
+    rollback::restore_previous_data(deploy_path).await.unwrap();
+
+    assert_eq!(validator_height(deploy.path()).await, Some(100));
+    assert!(!deploy
+        .path()
+        .join("priv_validator_state_backup.json")
+        .exists());
+}
+
+#[tokio::test]
+async fn test_discard_removes_previous_data_only() {
+    let deploy = TempDir::new().unwrap();
+    let deploy_path = deploy.path().to_str().unwrap();
+    write_node(deploy.path(), b"previous", 100);
+    rollback::set_aside_current_data(deploy_path).await.unwrap();
+    write_node(deploy.path(), b"restored", 100);
+
+    rollback::discard_previous_data(deploy_path).await.unwrap();
+
+    assert!(!rollback::has_previous_data(deploy_path).await);
+    let mut entries: Vec<String> = fs::read_dir(deploy.path())
+        .unwrap()
+        .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+        .collect();
+    entries.sort();
+    assert_eq!(entries, vec!["data", "wasm"]);
+    assert_eq!(
+        fs::read(deploy.path().join("wasm/contract.wasm")).unwrap(),
+        b"restored"
+    );
+}
+
+#[tokio::test]
+async fn test_set_aside_replaces_leftover_rollback_copy() {
+    let deploy = TempDir::new().unwrap();
+    let deploy_path = deploy.path().to_str().unwrap();
+    fs::create_dir_all(rollback::rollback_path(deploy_path, "data")).unwrap();
+    fs::write(
+        Path::new(&rollback::rollback_path(deploy_path, "data")).join("stale"),
+        b"x",
+    )
+    .unwrap();
+    write_node(deploy.path(), b"current", 10);
+
+    rollback::set_aside_current_data(deploy_path).await.unwrap();
+
+    let previous = Path::new(&rollback::rollback_path(deploy_path, "data")).to_path_buf();
+    assert!(!previous.join("stale").exists());
+    assert!(previous.join("application.db/000001.log").exists());
+}
+
+/// Serve a CometBFT-style `/status` whose height grows by `step` per request
+async fn start_rpc(step: u64) -> String {
+    let height = Arc::new(AtomicU64::new(1000));
+    let app = Router::new().route(
+        "/status",
+        get(move || {
+            let height = height.clone();
+            async move {
+                let current = height.fetch_add(step, Ordering::SeqCst);
+                Json(serde_json::json!({
+                    "result": { "sync_info": { "latest_block_height": current.to_string() } }
+                }))
+            }
+        }),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    format!("http://{}", address)
+}
+
+#[tokio::test]
+async fn test_wait_for_new_blocks_returns_once_node_progresses() {
+    let url = start_rpc(2).await;
+
+    let (first, last) =
+        node_rpc::wait_for_new_blocks(&url, 5, Duration::from_secs(5), Duration::from_millis(10))
+            .await
+            .unwrap();
+
+    assert_eq!(first, 1000);
+    assert!(last >= 1005);
+}
+
+#[tokio::test]
+async fn test_wait_for_new_blocks_fails_when_node_is_stuck() {
+    let url = start_rpc(0).await;
+
+    let error = node_rpc::wait_for_new_blocks(
+        &url,
+        5,
+        Duration::from_millis(200),
+        Duration::from_millis(20),
+    )
+    .await
+    .unwrap_err();
+
+    assert!(error.to_string().contains("produced 0 of 5 new blocks"));
+}
+
+#[test]
+fn test_restore_request_defaults_to_rollback_after_five_blocks() {
+    let request: RestoreRequest = serde_json::from_value(serde_json::json!({
+        "node_name": "pirin-node-1",
+        "deploy_path": "/opt/deploy/nolus/full-node-1",
+        "snapshot_dir": "/home/backup/snapshots/pirin-1_20250121_17154420",
+        "service_name": "full-node-1",
+        "log_path": null
+    }))
+    .unwrap();
+
+    assert_eq!(request.rpc_url, None);
+    assert_eq!(request.rollback_after_blocks, 5);
+}
@@ -0,0 +1,401 @@
+//! Integration tests for in-process snapshot archiving
+//!
+//! These tests verify that snapshot directories are streamed into `.tar.lz4`
+//! archives that unpack to the original tree, that size and ratio are
+//! reported, that half-written archives are never listed as complete, and
+//! that restores can extract archives straight into a node's home while
+//! checking them against the snapshot manifest.
+
+use agent::services::job_manager::{JobManager, ProgressReporter};
+use agent::services::{archive, filesystem, manifest, snapshot_files, transfer};
+use std::fs;
+use std::os::unix::fs::symlink;
+use tempfile::TempDir;
+
+/// Write an archive of `(path, link target)` symlinks and `(path, content)`
+/// files, in the given order
+fn write_archive(path: &std::path::Path, links: &[(&str, &str)], files: &[(&str, &[u8])]) {
+    let file = fs::File::create(path).unwrap();
+    let mut builder = tar::Builder::new(lz4_flex::frame::FrameEncoder::new(file));
+    for (link, target) in links {
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Symlink);
+        header.set_size(0);
+        builder.append_link(&mut header, link, target).unwrap();
+    }
+    for (name, content) in files {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(content.len() as u64);
+        header.set_mode(0o600);
+        builder.append_data(&mut header, name, *content).unwrap();
+    }
+    builder.into_inner().unwrap().finish().unwrap();
+}
+
+const SNAPSHOT: &str = "pirin-1_20250121_17154420";
+
+fn make_snapshot(backup: &TempDir) {
+    let dir = backup.path().join(SNAPSHOT);
+    fs::create_dir_all(dir.join("data/application.db")).unwrap();
+    fs::create_dir_all(dir.join("wasm")).unwrap();
+    fs::write(
+        dir.join("data/application.db/000001.log"),
+        vec![b'a'; 64 * 1024],
+    )
+    .unwrap();
+    fs::write(dir.join("wasm/contract.wasm"), b"\0asm module").unwrap();
+    symlink("data/application.db", dir.join("db-link")).unwrap();
+}
+
+fn unpack(archive_path: &str, target: &std::path::Path) {
+    let file = fs::File::open(archive_path).unwrap();
+    let mut tar = tar::Archive::new(lz4_flex::frame::FrameDecoder::new(file));
+    tar.unpack(target).unwrap();
+}
+
+#[tokio::test]
+async fn test_archive_round_trips_snapshot_tree() {
+    let backup = TempDir::new().unwrap();
+    make_snapshot(&backup);
+    let backup_path = backup.path().to_str().unwrap();
+
+    let result = archive::create_archive(backup_path, SNAPSHOT, &ProgressReporter::detached())
+        .await
+        .unwrap();
+
+    assert_eq!(
+        result.archive_path,
+        archive::archive_path(backup_path, SNAPSHOT)
+    );
+    assert_eq!(
+        fs::metadata(&result.archive_path).unwrap().len(),
+        result.size_bytes
+    );
+    assert!(result.uncompressed_bytes > 64 * 1024);
+    // Highly repetitive data compresses well
+    assert!(result.compression_ratio > 5.0);
+
+    let unpacked = TempDir::new().unwrap();
+    unpack(&result.archive_path, unpacked.path());
+    let root = unpacked.path().join(SNAPSHOT);
+    assert_eq!(
+        fs::read(root.join("data/application.db/000001.log")).unwrap(),
+        vec![b'a'; 64 * 1024]
+    );
+    assert_eq!(
+        fs::read(root.join("wasm/contract.wasm")).unwrap(),
+        b"\0asm module"
+    );
+    assert_eq!(
+        fs::read_link(root.join("db-link")).unwrap(),
+        std::path::PathBuf::from("data/application.db")
+    );
+}
+
+#[tokio::test]
+async fn test_archive_reports_progress_and_leaves_no_partial_file() {
+    let backup = TempDir::new().unwrap();
+    make_snapshot(&backup);
+    let backup_path = backup.path().to_str().unwrap();
+
+    let manager = JobManager::new();
+    let job_id = manager
+        .create_job("snapshot_compression", SNAPSHOT, None)
+        .await;
+    let progress = manager.progress_reporter(&job_id);
+
+    let result = archive::create_archive(backup_path, SNAPSHOT, &progress)
+        .await
+        .unwrap();
+
+    let job = manager.get_job_status(&job_id).await.unwrap();
+    assert_eq!(
+        job.progress.bytes_processed,
+        Some(result.uncompressed_bytes)
+    );
+    assert_eq!(job.progress.bytes_total, Some(64 * 1024 + 11));
+    assert!(!std::path::Path::new(&archive::partial_archive_path(backup_path, SNAPSHOT)).exists());
+}
+
+#[tokio::test]
+async fn test_archive_of_missing_snapshot_fails_cleanly() {
+    let backup = TempDir::new().unwrap();
+    let backup_path = backup.path().to_str().unwrap();
+
+    assert!(
+        archive::create_archive(backup_path, SNAPSHOT, &ProgressReporter::detached())
+            .await
+            .is_err()
+    );
+    assert_eq!(fs::read_dir(backup.path()).unwrap().count(), 0);
+}
+
+#[tokio::test]
+async fn test_listing_separates_partial_archives() {
+    let backup = TempDir::new().unwrap();
+    make_snapshot(&backup);
+    let backup_path = backup.path().to_str().unwrap();
+    fs::write(
+        archive::partial_archive_path(backup_path, SNAPSHOT),
+        b"half",
+    )
+    .unwrap();
+
+    let listing = snapshot_files::list_snapshots(backup_path, "pirin-1")
+        .await
+        .unwrap();
+    assert!(listing.archives.is_empty());
+    assert_eq!(listing.partial_archives.len(), 1);
+    let snapshot = &listing.snapshots[0];
+    assert_eq!(snapshot.archive_size_bytes, None);
+    assert!(snapshot.archive_in_progress);
+
+    archive::create_archive(backup_path, SNAPSHOT, &ProgressReporter::detached())
+        .await
+        .unwrap();
+    let listing = snapshot_files::list_snapshots(backup_path, "pirin-1")
+        .await
+        .unwrap();
+    assert_eq!(listing.archives.len(), 1);
+    assert!(listing.snapshots[0].archive_size_bytes.is_some());
+}
+
+#[tokio::test]
+async fn test_listing_separates_downloads() {
+    let backup = TempDir::new().unwrap();
+    let backup_path = backup.path().to_str().unwrap();
+    fs::write(transfer::download_path(backup_path, SNAPSHOT), b"half").unwrap();
+
+    let listing = snapshot_files::list_snapshots(backup_path, "pirin-1")
+        .await
+        .unwrap();
+    assert!(listing.archives.is_empty());
+    assert!(listing.partial_archives.is_empty());
+    assert_eq!(listing.downloads.len(), 1);
+}
+
+#[tokio::test]
+async fn test_delete_snapshot_removes_partial_archive() {
+    let backup = TempDir::new().unwrap();
+    let backup_path = backup.path().to_str().unwrap();
+    fs::write(
+        archive::partial_archive_path(backup_path, SNAPSHOT),
+        b"half",
+    )
+    .unwrap();
+
+    snapshot_files::delete_snapshot(backup_path, SNAPSHOT)
+        .await
+        .unwrap();
+    assert_eq!(fs::read_dir(backup.path()).unwrap().count(), 0);
+}
+
+#[tokio::test]
+async fn test_compression_job_links_to_snapshot_job() {
+    let manager = JobManager::new();
+    let snapshot_job = manager
+        .create_job("snapshot_creation", "node-a", Some("nolusd"))
+        .await;
+    let compression_job = manager
+        .create_job("snapshot_compression", SNAPSHOT, None)
+        .await;
+
+    let progress = manager.progress_reporter(&snapshot_job);
+    assert_eq!(progress.job_id(), Some(snapshot_job.as_str()));
+    assert_eq!(ProgressReporter::detached().job_id(), None);
+
+    manager
+        .link_to_parent(&compression_job, &snapshot_job)
+        .await;
+    let job = manager.get_job_status(&compression_job).await.unwrap();
+    assert_eq!(job.parent_job_id.as_deref(), Some(snapshot_job.as_str()));
+}
+
+/// Snapshot with a manifest, as the snapshot sequence writes it
+async fn make_manifested_snapshot(backup: &TempDir) -> String {
+    let node = backup.path().join("node");
+    fs::create_dir_all(node.join("data/application.db")).unwrap();
+    fs::create_dir_all(node.join("wasm/wasm")).unwrap();
+    fs::write(node.join("data/application.db/000001.log"), vec![3u8; 4096]).unwrap();
+    fs::write(
+        node.join("data/priv_validator_state.json"),
+        br#"{"height":"50"}"#,
+    )
+    .unwrap();
+    fs::write(node.join("wasm/wasm/contract.wasm"), b"\0asm").unwrap();
+
+    let snapshot = backup.path().join(SNAPSHOT);
+    let snapshot_path = snapshot.to_str().unwrap().to_string();
+    fs::create_dir_all(&snapshot).unwrap();
+    let pairs: Vec<(String, String)> = ["data", "wasm"]
+        .iter()
+        .map(|dir| {
+            (
+                node.join(dir).to_str().unwrap().to_string(),
+                snapshot.join(dir).to_str().unwrap().to_string(),
+            )
+        })
+        .collect();
+    let report = filesystem::copy_trees(&pairs, true, &ProgressReporter::detached())
+        .await
+        .unwrap()
+        .into_result()
+        .unwrap();
+    let snapshot_manifest =
+        manifest::build_manifest(&snapshot_path, SNAPSHOT, "pirin-node-1", &report.files).unwrap();
+    manifest::write_manifest(&snapshot_path, &snapshot_manifest)
+        .await
+        .unwrap();
+
+    let backup_path = backup.path().to_str().unwrap();
+    archive::create_archive(backup_path, SNAPSHOT, &ProgressReporter::detached())
+        .await
+        .unwrap()
+        .archive_path
+}
+
+#[tokio::test]
+async fn test_extract_archive_into_deploy_path_matches_manifest() {
+    let backup = TempDir::new().unwrap();
+    let archive_path = make_manifested_snapshot(&backup).await;
+    // Restores work from the archive alone
+    fs::remove_dir_all(backup.path().join(SNAPSHOT)).unwrap();
+
+    let deploy = TempDir::new().unwrap();
+    let report = archive::extract_snapshot_archive(
+        &archive_path,
+        deploy.path().to_str().unwrap(),
+        &ProgressReporter::detached(),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(report.files_extracted, 3);
+    assert_eq!(
+        fs::read(deploy.path().join("data/application.db/000001.log")).unwrap(),
+        vec![3u8; 4096]
+    );
+    assert!(deploy.path().join("wasm/wasm/contract.wasm").is_file());
+    // The manifest is read from the archive, not written into the node home
+    assert!(!deploy.path().join(manifest::MANIFEST_FILE).exists());
+
+    let snapshot_manifest = report.manifest.as_ref().unwrap();
+    let verify = manifest::compare_with_manifest(snapshot_manifest, &report.files);
+    assert!(verify.verified);
+    assert_eq!(verify.files_checked, 3);
+}
+
+#[tokio::test]
+async fn test_manifest_comparison_flags_differences() {
+    let backup = TempDir::new().unwrap();
+    let archive_path = make_manifested_snapshot(&backup).await;
+    let deploy = TempDir::new().unwrap();
+    let mut report = archive::extract_snapshot_archive(
+        &archive_path,
+        deploy.path().to_str().unwrap(),
+        &ProgressReporter::detached(),
+    )
+    .await
+    .unwrap();
+
+    report
+        .files
+        .get_mut("data/priv_validator_state.json")
+        .unwrap()
+        .1 = "0".repeat(64);
+    report.files.remove("wasm/wasm/contract.wasm");
+    report
+        .files
+        .insert("data/extra".to_string(), (1, "0".repeat(64)));
+
+    let verify = manifest::compare_with_manifest(report.manifest.as_ref().unwrap(), &report.files);
+    assert!(!verify.verified);
+    assert_eq!(verify.mismatched, vec!["data/priv_validator_state.json"]);
+    assert_eq!(verify.missing, vec!["wasm/wasm/contract.wasm"]);
+    assert_eq!(verify.unexpected, vec!["data/extra"]);
+}
+
+#[tokio::test]
+async fn test_extract_rejects_symlinks_out_of_the_snapshot() {
+    let backup = TempDir::new().unwrap();
+    let archive_path = backup.path().join("evil.tar.lz4");
+    {
+        let file = fs::File::create(&archive_path).unwrap();
+        let mut builder = tar::Builder::new(lz4_flex::frame::FrameEncoder::new(file));
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Symlink);
+        header.set_size(0);
+        builder
+            .append_link(&mut header, "snap/data/escape", "../../../etc")
+            .unwrap();
+        builder.into_inner().unwrap().finish().unwrap();
+    }
+
+    let deploy = TempDir::new().unwrap();
+    let error = archive::extract_snapshot_archive(
+        archive_path.to_str().unwrap(),
+        deploy.path().to_str().unwrap(),
+        &ProgressReporter::detached(),
+    )
+    .await
+    .unwrap_err();
+    assert!(error.to_string().contains("points outside the snapshot"));
+    assert!(!deploy.path().join("data/escape").exists());
+}
+
+#[tokio::test]
+async fn test_extract_rejects_symlinks_out_of_the_data_tree() {
+    let backup = TempDir::new().unwrap();
+    let archive_path = backup.path().join("evil.tar.lz4");
+    write_archive(
+        &archive_path,
+        &[("snap/data/x", "../config")],
+        &[("snap/data/x/priv_validator_key.json", b"{\"key\":\"evil\"}")],
+    );
+
+    let deploy = TempDir::new().unwrap();
+    fs::create_dir_all(deploy.path().join("config")).unwrap();
+    fs::write(
+        deploy.path().join("config/priv_validator_key.json"),
+        b"original",
+    )
+    .unwrap();
+
+    let error = archive::extract_snapshot_archive(
+        archive_path.to_str().unwrap(),
+        deploy.path().to_str().unwrap(),
+        &ProgressReporter::detached(),
+    )
+    .await
+    .unwrap_err();
+    assert!(error
+        .to_string()
+        .contains("points outside the snapshot's data tree"));
+    assert!(deploy.path().join("data/x").symlink_metadata().is_err());
+    assert_eq!(
+        fs::read(deploy.path().join("config/priv_validator_key.json")).unwrap(),
+        b"original"
+    );
+}
+
+#[tokio::test]
+async fn test_extract_never_writes_through_symlinks() {
+    let backup = TempDir::new().unwrap();
+    let archive_path = backup.path().join("evil.tar.lz4");
+    write_archive(
+        &archive_path,
+        &[("snap/data/x", "inner")],
+        &[("snap/data/x/file", b"through the link")],
+    );
+
+    let deploy = TempDir::new().unwrap();
+    let error = archive::extract_snapshot_archive(
+        archive_path.to_str().unwrap(),
+        deploy.path().to_str().unwrap(),
+        &ProgressReporter::detached(),
+    )
+    .await
+    .unwrap_err();
+    assert!(error.to_string().contains("through the symlink"));
+    assert!(!deploy.path().join("data/inner").exists());
+}
@@ -0,0 +1,212 @@
+//! Integration tests for snapshot manifests
+//!
+//! These tests verify that a hashing copy produces a manifest with one
+//! checksum per file, and that verification catches modified, missing and
+//! unexpected files.
+
+use agent::operations::restore;
+use agent::services::filesystem;
+use agent::services::job_manager::{JobManager, ProgressReporter};
+use agent::services::manifest::{self, MANIFEST_FILE};
+use agent::types::RestoreRequest;
+use std::fs;
+use tempfile::TempDir;
+
+const SNAPSHOT_NAME: &str = "pirin-1_20250121_17154420";
+
+/// Copy a small node home into a snapshot directory and write its manifest
+async fn create_snapshot(temp: &TempDir) -> String {
+    let node = temp.path().join("node");
+    fs::create_dir_all(node.join("data/application.db")).unwrap();
+    fs::create_dir_all(node.join("wasm/wasm")).unwrap();
+    fs::write(node.join("data/application.db/000001.log"), vec![1u8; 2048]).unwrap();
+    fs::write(node.join("data/priv_validator_state.json"), b"{}").unwrap();
+    fs::write(node.join("wasm/wasm/contract.wasm"), b"\0asm").unwrap();
+
+    let snapshot = temp.path().join(SNAPSHOT_NAME);
+    let snapshot_path = snapshot.to_str().unwrap().to_string();
+    let pairs: Vec<(String, String)> = ["data", "wasm"]
+        .iter()
+        .map(|dir| {
+            (
+                node.join(dir).to_str().unwrap().to_string(),
+                snapshot.join(dir).to_str().unwrap().to_string(),
+            )
+        })
+        .collect();
+    fs::create_dir_all(&snapshot).unwrap();
+
+    let report = filesystem::copy_trees(&pairs, true, &ProgressReporter::detached())
+        .await
+        .unwrap()
+        .into_result()
+        .unwrap();
+    let snapshot_manifest =
+        manifest::build_manifest(&snapshot_path, SNAPSHOT_NAME, "pirin-node-1", &report.files)
+            .unwrap();
+    manifest::write_manifest(&snapshot_path, &snapshot_manifest)
+        .await
+        .unwrap();
+    snapshot_path
+}
+
+#[test]
+fn test_parse_snapshot_name() {
+    assert_eq!(
+        manifest::parse_snapshot_name("pirin-1_20250121_17154420"),
+        ("pirin-1".to_string(), Some(17154420))
+    );
+    assert_eq!(
+        manifest::parse_snapshot_name("my_net_20250121_42"),
+        ("my_net".to_string(), Some(42))
+    );
+    assert_eq!(
+        manifest::parse_snapshot_name("legacy-snapshot"),
+        ("legacy-snapshot".to_string(), None)
+    );
+}
+
+#[tokio::test]
+async fn test_manifest_records_every_file() {
+    let temp = TempDir::new().unwrap();
+    let snapshot_path = create_snapshot(&temp).await;
+
+    let snapshot_manifest = manifest::read_manifest(&snapshot_path)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(snapshot_manifest.network, "pirin-1");
+    assert_eq!(snapshot_manifest.block_height, Some(17154420));
+    assert_eq!(snapshot_manifest.source_node, "pirin-node-1");
+    assert_eq!(snapshot_manifest.total_bytes, 2048 + 2 + 4);
+
+    let paths: Vec<_> = snapshot_manifest
+        .files
+        .iter()
+        .map(|f| f.path.as_str())
+        .collect();
+    assert_eq!(
+        paths,
+        vec![
+            "data/application.db/000001.log",
+            "data/priv_validator_state.json",
+            "wasm/wasm/contract.wasm"
+        ]
+    );
+    // SHA-256 of "{}"
+    assert_eq!(
+        snapshot_manifest.files[1].sha256,
+        "44136fa355b3678a1146ad16f7e8649e94fb4fc21fe77e8310c060f61caaff8a"
+    );
+}
+
+#[tokio::test]
+async fn test_untouched_snapshot_verifies() {
+    let temp = TempDir::new().unwrap();
+    let snapshot_path = create_snapshot(&temp).await;
+
+    let report = manifest::verify_snapshot(&snapshot_path, &ProgressReporter::detached())
+        .await
+        .unwrap();
+    assert!(report.verified);
+    assert_eq!(report.files_checked, 3);
+    assert_eq!(report.bytes_checked, 2048 + 2 + 4);
+}
+
+#[tokio::test]
+async fn test_verification_catches_changes() {
+    let temp = TempDir::new().unwrap();
+    let snapshot_path = create_snapshot(&temp).await;
+    let snapshot = temp.path().join(SNAPSHOT_NAME);
+
+    // Same size, different content
+    fs::write(snapshot.join("data/priv_validator_state.json"), b"[]").unwrap();
+    fs::remove_file(snapshot.join("wasm/wasm/contract.wasm")).unwrap();
+    fs::write(snapshot.join("data/extra.db"), b"x").unwrap();
+
+    let report = manifest::verify_snapshot(&snapshot_path, &ProgressReporter::detached())
+        .await
+        .unwrap();
+    assert!(!report.verified);
+    assert_eq!(report.mismatched, vec!["data/priv_validator_state.json"]);
+    assert_eq!(report.missing, vec!["wasm/wasm/contract.wasm"]);
+    assert_eq!(report.unexpected, vec!["data/extra.db"]);
+}
+
+#[tokio::test]
+async fn test_snapshot_without_manifest_cannot_be_verified() {
+    let temp = TempDir::new().unwrap();
+    let snapshot_path = create_snapshot(&temp).await;
+    fs::remove_file(temp.path().join(SNAPSHOT_NAME).join(MANIFEST_FILE)).unwrap();
+
+    assert!(manifest::read_manifest(&snapshot_path)
+        .await
+        .unwrap()
+        .is_none());
+    let error = manifest::verify_snapshot(&snapshot_path, &ProgressReporter::detached())
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("has no manifest"));
+}
+
+fn restore_request(snapshot_path: &str) -> RestoreRequest {
+    serde_json::from_value(serde_json::json!({
+        "node_name": "pirin-node-2",
+        "deploy_path": "/opt/deploy/pirin-node-2",
+        "snapshot_dir": snapshot_path,
+        "service_name": "pirin-node-2",
+        "log_path": null,
+    }))
+    .unwrap()
+}
+
+#[tokio::test]
+async fn test_restore_of_snapshot_without_manifest_proceeds_with_warning() {
+    let temp = TempDir::new().unwrap();
+    let snapshot_path = create_snapshot(&temp).await;
+    fs::remove_file(temp.path().join(SNAPSHOT_NAME).join(MANIFEST_FILE)).unwrap();
+
+    let manager = JobManager::new();
+    let job_id = manager
+        .create_job("snapshot_restore", "pirin-node-2", Some("pirin-node-2"))
+        .await;
+    let warning = restore::verify_snapshot_integrity(
+        &restore_request(&snapshot_path),
+        &manager.progress_reporter(&job_id),
+    )
+    .await
+    .unwrap();
+
+    assert!(warning.unwrap().contains("has no manifest"));
+    let job = manager.get_job_status(&job_id).await.unwrap();
+    assert_eq!(
+        job.progress.current_step.unwrap().name,
+        "Snapshot has no manifest, checksums not verified"
+    );
+}
+
+#[tokio::test]
+async fn test_restore_of_mismatched_snapshot_is_refused_unless_forced() {
+    let temp = TempDir::new().unwrap();
+    let snapshot_path = create_snapshot(&temp).await;
+    fs::write(
+        temp.path()
+            .join(SNAPSHOT_NAME)
+            .join("data/priv_validator_state.json"),
+        b"{\"height\":\"1\"}",
+    )
+    .unwrap();
+    let progress = ProgressReporter::detached();
+
+    let mut request = restore_request(&snapshot_path);
+    let error = restore::verify_snapshot_integrity(&request, &progress)
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("failed verification"));
+
+    request.force = true;
+    assert!(restore::verify_snapshot_integrity(&request, &progress)
+        .await
+        .unwrap()
+        .is_none());
+}
@@ -44,6 +44,7 @@ fn create_test_snapshot_request(deploy_path: PathBuf, backup_path: PathBuf) -> S
         deploy_path: deploy_path.to_string_lossy().to_string(),
         backup_path: backup_path.to_string_lossy().to_string(),
         log_path: None, // Skip log truncation in tests
+        log_rotation: None,
     }
 }
 
@@ -0,0 +1,317 @@
+//! Integration tests for agent-to-agent snapshot transfer
+//!
+//! These tests run the archive download endpoint on a local port and verify
+//! that pulls copy the archive byte for byte, resume an interrupted download
+//! with a range request only while the source archive is unchanged, never
+//! serve archives that are not complete, and only serve downloads holding a
+//! token for the archive.
+
+use agent::services::job_manager::ProgressReporter;
+use agent::services::signing::RequestVerifier;
+use agent::services::{archive, transfer};
+use agent::types::SnapshotPullRequest;
+use axum::extract::{Query, Request};
+use axum::http::{header::AUTHORIZATION, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::Router;
+use std::collections::HashMap;
+use std::fs;
+use tempfile::TempDir;
+
+const SNAPSHOT: &str = "pirin-1_20250121_17154420";
+const SOURCE_KEY: &str = "source-key";
+
+fn source_verifier() -> RequestVerifier {
+    RequestVerifier::new(vec![SOURCE_KEY.to_string()])
+}
+
+/// Token the source agent would issue for one of its archives
+fn token(source_backup: &str, snapshot_name: &str) -> String {
+    source_verifier().issue_download_token(
+        source_backup,
+        snapshot_name,
+        chrono::Utc::now().timestamp() + 60,
+    )
+}
+
+/// Checks the bearer download token like the agent's `/snapshot/archive` handler
+async fn archive_handler(
+    Query(query): Query<HashMap<String, String>>,
+    request: Request,
+) -> Response {
+    let presented = request
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .unwrap_or_default();
+    if source_verifier()
+        .verify_download_token(
+            presented,
+            &query["backup_path"],
+            &query["snapshot_name"],
+            chrono::Utc::now().timestamp(),
+        )
+        .is_err()
+    {
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+    transfer::serve_archive(&query["backup_path"], &query["snapshot_name"], request).await
+}
+
+/// Start a source agent serving `/snapshot/archive`, returning its base URL
+async fn start_source() -> String {
+    let app = Router::new().route("/snapshot/archive", get(archive_handler));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    format!("http://{}", address)
+}
+
+/// `Last-Modified` the source sends for one of its archives
+async fn source_last_modified(url: &str, source_backup: &str) -> String {
+    reqwest::Client::new()
+        .get(format!("{}/snapshot/archive", url))
+        .query(&[("backup_path", source_backup), ("snapshot_name", SNAPSHOT)])
+        .bearer_auth(token(source_backup, SNAPSHOT))
+        .send()
+        .await
+        .unwrap()
+        .headers()["last-modified"]
+        .to_str()
+        .unwrap()
+        .to_string()
+}
+
+fn archive_content() -> Vec<u8> {
+    (0..200_000u32).map(|i| (i % 251) as u8).collect()
+}
+
+fn pull_request(source_url: &str, source: &TempDir, target: &TempDir) -> SnapshotPullRequest {
+    let source_backup = source.path().to_str().unwrap();
+    SnapshotPullRequest {
+        source_url: source_url.to_string(),
+        source_token: token(source_backup, SNAPSHOT),
+        source_ca_cert: None,
+        source_backup_path: source_backup.to_string(),
+        snapshot_name: SNAPSHOT.to_string(),
+        backup_path: target.path().to_str().unwrap().to_string(),
+    }
+}
+
+#[tokio::test]
+async fn test_pull_copies_archive() {
+    let source = TempDir::new().unwrap();
+    let target = TempDir::new().unwrap();
+    let source_backup = source.path().to_str().unwrap();
+    fs::write(
+        archive::archive_path(source_backup, SNAPSHOT),
+        archive_content(),
+    )
+    .unwrap();
+    let url = start_source().await;
+
+    let result = transfer::pull_archive(
+        &pull_request(&url, &source, &target),
+        None,
+        &ProgressReporter::detached(),
+    )
+    .await
+    .unwrap();
+
+    assert!(!result.already_present);
+    assert_eq!(result.attempts, 1);
+    assert_eq!(result.resumed_from_bytes, 0);
+    assert_eq!(result.size_bytes, 200_000);
+    assert_eq!(fs::read(&result.archive_path).unwrap(), archive_content());
+    let target_backup = target.path().to_str().unwrap();
+    assert!(!std::path::Path::new(&transfer::download_path(target_backup, SNAPSHOT)).exists());
+    assert!(!std::path::Path::new(&transfer::validator_path(target_backup, SNAPSHOT)).exists());
+
+    // A second pull finds the archive in place
+    let again = transfer::pull_archive(
+        &pull_request(&url, &source, &target),
+        None,
+        &ProgressReporter::detached(),
+    )
+    .await
+    .unwrap();
+    assert!(again.already_present);
+}
+
+#[tokio::test]
+async fn test_pull_resumes_interrupted_download() {
+    let source = TempDir::new().unwrap();
+    let target = TempDir::new().unwrap();
+    let source_backup = source.path().to_str().unwrap();
+    let target_backup = target.path().to_str().unwrap();
+    let content = archive_content();
+    fs::write(archive::archive_path(source_backup, SNAPSHOT), &content).unwrap();
+    fs::write(
+        transfer::download_path(target_backup, SNAPSHOT),
+        &content[..50_000],
+    )
+    .unwrap();
+    let url = start_source().await;
+    fs::write(
+        transfer::validator_path(target_backup, SNAPSHOT),
+        source_last_modified(&url, source_backup).await,
+    )
+    .unwrap();
+
+    let result = transfer::pull_archive(
+        &pull_request(&url, &source, &target),
+        None,
+        &ProgressReporter::detached(),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(result.resumed_from_bytes, 50_000);
+    assert_eq!(fs::read(&result.archive_path).unwrap(), content);
+}
+
+#[tokio::test]
+async fn test_pull_restarts_when_the_source_archive_changed() {
+    let source = TempDir::new().unwrap();
+    let target = TempDir::new().unwrap();
+    let source_backup = source.path().to_str().unwrap();
+    let target_backup = target.path().to_str().unwrap();
+    let content = archive_content();
+    fs::write(archive::archive_path(source_backup, SNAPSHOT), &content).unwrap();
+    // Started from an earlier archive with different bytes
+    fs::write(
+        transfer::download_path(target_backup, SNAPSHOT),
+        vec![7u8; 50_000],
+    )
+    .unwrap();
+    fs::write(
+        transfer::validator_path(target_backup, SNAPSHOT),
+        "Mon, 01 Jan 2024 00:00:00 GMT",
+    )
+    .unwrap();
+    let url = start_source().await;
+
+    let result = transfer::pull_archive(
+        &pull_request(&url, &source, &target),
+        None,
+        &ProgressReporter::detached(),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(result.resumed_from_bytes, 0);
+    assert_eq!(fs::read(&result.archive_path).unwrap(), content);
+}
+
+#[tokio::test]
+async fn test_pull_does_not_resume_without_a_validator() {
+    let source = TempDir::new().unwrap();
+    let target = TempDir::new().unwrap();
+    let source_backup = source.path().to_str().unwrap();
+    let target_backup = target.path().to_str().unwrap();
+    let content = archive_content();
+    fs::write(archive::archive_path(source_backup, SNAPSHOT), &content).unwrap();
+    fs::write(
+        transfer::download_path(target_backup, SNAPSHOT),
+        vec![7u8; 50_000],
+    )
+    .unwrap();
+    let url = start_source().await;
+
+    let result = transfer::pull_archive(
+        &pull_request(&url, &source, &target),
+        None,
+        &ProgressReporter::detached(),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(result.resumed_from_bytes, 0);
+    assert_eq!(fs::read(&result.archive_path).unwrap(), content);
+}
+
+#[tokio::test]
+async fn test_source_serves_ranges_of_complete_archives_only() {
+    let source = TempDir::new().unwrap();
+    let source_backup = source.path().to_str().unwrap();
+    fs::write(
+        archive::partial_archive_path(source_backup, SNAPSHOT),
+        b"half written",
+    )
+    .unwrap();
+    let url = start_source().await;
+    let client = reqwest::Client::new();
+    let query = [("backup_path", source_backup), ("snapshot_name", SNAPSHOT)];
+    let snapshot_token = token(source_backup, SNAPSHOT);
+
+    let response = client
+        .get(format!("{}/snapshot/archive", url))
+        .query(&query)
+        .bearer_auth(&snapshot_token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::NOT_FOUND);
+
+    fs::write(
+        archive::archive_path(source_backup, SNAPSHOT),
+        b"0123456789",
+    )
+    .unwrap();
+    let response = client
+        .get(format!("{}/snapshot/archive", url))
+        .query(&query)
+        .bearer_auth(&snapshot_token)
+        .header("Range", "bytes=4-")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::PARTIAL_CONTENT);
+    assert_eq!(
+        response.headers()["content-range"].to_str().unwrap(),
+        "bytes 4-9/10"
+    );
+    assert_eq!(response.bytes().await.unwrap().as_ref(), b"456789");
+
+    let response = client
+        .get(format!("{}/snapshot/archive", url))
+        .query(&[("backup_path", source_backup), ("snapshot_name", "../etc")])
+        .bearer_auth(token(source_backup, "../etc"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_source_refuses_downloads_without_a_token_for_the_archive() {
+    let source = TempDir::new().unwrap();
+    let source_backup = source.path().to_str().unwrap();
+    fs::write(
+        archive::archive_path(source_backup, SNAPSHOT),
+        archive_content(),
+    )
+    .unwrap();
+    let url = start_source().await;
+    let client = reqwest::Client::new();
+    let query = [("backup_path", source_backup), ("snapshot_name", SNAPSHOT)];
+
+    let response = client
+        .get(format!("{}/snapshot/archive", url))
+        .query(&query)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+
+    let response = client
+        .get(format!("{}/snapshot/archive", url))
+        .query(&query)
+        .bearer_auth(token(source_backup, "pirin-1_20250120_17000000"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+}
@@ -0,0 +1,187 @@
+//! Tests for host and node resource telemetry served on `/telemetry`
+
+use agent::config::PathRoots;
+use agent::services::sandbox::{PathSandbox, RootKind};
+use agent::services::telemetry::{
+    filesystem_usage, filesystems_holding, node_disk_usage, parse_loadavg, parse_meminfo, SizeCache,
+};
+use agent::types::NodeTelemetryRequest;
+use std::sync::Arc;
+use std::time::Duration;
+use tempfile::TempDir;
+
+#[test]
+fn test_parse_loadavg() {
+    let load = parse_loadavg("0.52 0.58 0.59 1/234 5678\n").unwrap();
+    assert_eq!(load, [0.52, 0.58, 0.59]);
+
+    assert!(parse_loadavg("0.52 0.58").is_err());
+    assert!(parse_loadavg("high 0.58 0.59").is_err());
+}
+
+#[test]
+fn test_parse_meminfo_returns_bytes() {
+    let meminfo = "MemTotal:       16384000 kB\n\
+                   MemFree:         1024000 kB\n\
+                   MemAvailable:    8192000 kB\n\
+                   MemAvailableX:         1 kB\n";
+
+    let (total, available) = parse_meminfo(meminfo).unwrap();
+    assert_eq!(total, 16_384_000 * 1024);
+    assert_eq!(available, 8_192_000 * 1024);
+
+    assert!(parse_meminfo("MemTotal: 1024 kB\n").is_err());
+}
+
+#[test]
+fn test_filesystem_usage_of_a_root() {
+    let dir = TempDir::new().unwrap();
+    let root = dir.path().to_str().unwrap();
+
+    let usage = filesystem_usage(RootKind::Backup, root).unwrap();
+    assert_eq!(usage.kind, "backup");
+    assert_eq!(usage.root, root);
+    assert!(usage.total_bytes > 0);
+    assert!(usage.used_bytes <= usage.total_bytes);
+    assert!(usage.available_bytes <= usage.total_bytes);
+
+    assert!(filesystem_usage(RootKind::Backup, "/nonexistent/root").is_err());
+}
+
+#[test]
+fn test_filesystems_are_reported_once_per_device() {
+    let dir = TempDir::new().unwrap();
+    let root = dir.path().to_str().unwrap().to_string();
+    let deploy = format!("{}/node-1", root);
+    std::fs::create_dir_all(&deploy).unwrap();
+    // Not created yet: measured on the filesystem of the temp dir
+    let backup = format!("{}/snapshots/node-1", root);
+
+    let filesystems = filesystems_holding(&[
+        (RootKind::Deploy, root.clone()),
+        (RootKind::Deploy, deploy.clone()),
+        (RootKind::Backup, backup.clone()),
+        (RootKind::Log, "/proc/self".to_string()),
+    ]);
+
+    assert_eq!(filesystems.len(), 2);
+    assert_eq!(filesystems[0].kind, "deploy");
+    assert_eq!(filesystems[0].root, root);
+    assert_eq!(filesystems[0].paths, vec![root, deploy, backup]);
+    assert_eq!(filesystems[1].kind, "log");
+    assert_eq!(filesystems[1].paths, vec!["/proc/self".to_string()]);
+}
+
+#[tokio::test]
+async fn test_size_cache_measures_and_reuses_sizes() {
+    let dir = TempDir::new().unwrap();
+    std::fs::write(dir.path().join("a"), vec![0u8; 1000]).unwrap();
+    std::fs::create_dir(dir.path().join("sub")).unwrap();
+    std::fs::write(dir.path().join("sub/b"), vec![0u8; 500]).unwrap();
+    let path = dir.path().to_str().unwrap();
+    let cache = Arc::new(SizeCache::new(Duration::from_secs(900)));
+
+    let first = cache.size(path).await.expect("measured on first request");
+    assert_eq!(first.bytes, 1500);
+
+    // Within the cache age the previous measurement is returned unchanged
+    std::fs::write(dir.path().join("c"), vec![0u8; 100]).unwrap();
+    let second = cache.size(path).await.unwrap();
+    assert_eq!(second.bytes, 1500);
+    assert_eq!(second.measured_at, first.measured_at);
+}
+
+#[tokio::test]
+async fn test_size_cache_refreshes_stale_sizes() {
+    let dir = TempDir::new().unwrap();
+    std::fs::write(dir.path().join("a"), vec![0u8; 1000]).unwrap();
+    let path = dir.path().to_str().unwrap();
+    let cache = Arc::new(SizeCache::new(Duration::ZERO));
+
+    assert_eq!(cache.size(path).await.unwrap().bytes, 1000);
+    std::fs::write(dir.path().join("b"), vec![0u8; 200]).unwrap();
+
+    // A stale size is returned immediately and re-measured in the background
+    let mut bytes = cache.size(path).await.unwrap().bytes;
+    for _ in 0..50 {
+        if bytes == 1200 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        bytes = cache.size(path).await.unwrap().bytes;
+    }
+    assert_eq!(bytes, 1200);
+}
+
+#[tokio::test]
+async fn test_size_cache_missing_path() {
+    let cache = Arc::new(SizeCache::new(Duration::from_secs(900)));
+    assert!(cache.size("/nonexistent/node/data").await.is_none());
+}
+
+#[tokio::test]
+async fn test_node_disk_usage_measures_node_directories() {
+    let dir = TempDir::new().unwrap();
+    let deploy = dir.path().join("node-1");
+    std::fs::create_dir_all(deploy.join("data")).unwrap();
+    std::fs::create_dir_all(deploy.join("wasm")).unwrap();
+    std::fs::write(deploy.join("data/blocks.db"), vec![0u8; 4096]).unwrap();
+    std::fs::write(deploy.join("wasm/code.wasm"), vec![0u8; 512]).unwrap();
+    let cache = Arc::new(SizeCache::new(Duration::from_secs(900)));
+
+    let sandbox = PathSandbox::new(PathRoots {
+        deploy_roots: vec![dir.path().to_str().unwrap().to_string()],
+        ..PathRoots::default()
+    });
+
+    let usage = node_disk_usage(
+        &cache,
+        &sandbox,
+        &NodeTelemetryRequest {
+            node_name: "node-1".to_string(),
+            deploy_path: Some(deploy.to_str().unwrap().to_string()),
+            backup_path: None,
+            log_path: None,
+        },
+    )
+    .await;
+
+    assert_eq!(usage.node_name, "node-1");
+    assert_eq!(usage.data_bytes, Some(4096));
+    assert_eq!(usage.wasm_bytes, Some(512));
+    assert_eq!(usage.log_bytes, None);
+    assert!(usage.measured_at.is_some());
+    assert!(usage.error.is_none());
+}
+
+#[tokio::test]
+async fn test_node_disk_usage_rejects_paths_outside_roots() {
+    let dir = TempDir::new().unwrap();
+    let deploy_root = dir.path().join("deploy");
+    let outside = dir.path().join("outside");
+    std::fs::create_dir_all(deploy_root.join("node-1/data")).unwrap();
+    std::fs::create_dir_all(&outside).unwrap();
+    let sandbox = PathSandbox::new(PathRoots {
+        deploy_roots: vec![deploy_root.to_str().unwrap().to_string()],
+        log_roots: vec![deploy_root.to_str().unwrap().to_string()],
+        ..PathRoots::default()
+    });
+    let cache = Arc::new(SizeCache::new(Duration::from_secs(900)));
+
+    let usage = node_disk_usage(
+        &cache,
+        &sandbox,
+        &NodeTelemetryRequest {
+            node_name: "node-1".to_string(),
+            deploy_path: Some(deploy_root.join("node-1").to_str().unwrap().to_string()),
+            backup_path: None,
+            log_path: Some(outside.to_str().unwrap().to_string()),
+        },
+    )
+    .await;
+
+    assert_eq!(usage.data_bytes, Some(0));
+    assert_eq!(usage.log_bytes, None);
+    let error = usage.error.expect("log path rejected");
+    assert!(error.contains("outside the allowed log roots"));
+}
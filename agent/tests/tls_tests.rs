@@ -0,0 +1,154 @@
+//! Integration tests for the agent's TLS listener
+//!
+//! These tests generate a throwaway CA, serve a route over HTTPS with the
+//! agent's rustls configuration and verify that clients trusting the CA can
+//! connect, and that mutual TLS rejects clients without a certificate.
+
+use agent::services::tls::TlsSettings;
+use axum::routing::get;
+use axum::Router;
+use axum_server::tls_rustls::RustlsConfig;
+use rcgen::{BasicConstraints, CertificateParams, DnType, IsCa, KeyPair};
+use std::fs;
+use std::sync::Arc;
+use tempfile::TempDir;
+
+struct TestPki {
+    dir: TempDir,
+    ca_pem: String,
+}
+
+impl TestPki {
+    /// A CA plus a `localhost` server certificate and a client certificate it issued
+    fn generate() -> Self {
+        let dir = TempDir::new().unwrap();
+
+        let mut ca_params = CertificateParams::new(Vec::<String>::new()).unwrap();
+        ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        ca_params
+            .distinguished_name
+            .push(DnType::CommonName, "nodes-manager test CA");
+        let ca_key = KeyPair::generate().unwrap();
+        let ca_cert = ca_params.self_signed(&ca_key).unwrap();
+
+        for (name, subject) in [("server", "localhost"), ("client", "manager")] {
+            let key = KeyPair::generate().unwrap();
+            let mut params = CertificateParams::new(vec![subject.to_string()]).unwrap();
+            params.distinguished_name.push(DnType::CommonName, subject);
+            let cert = params.signed_by(&key, &ca_cert, &ca_key).unwrap();
+            fs::write(dir.path().join(format!("{}.pem", name)), cert.pem()).unwrap();
+            fs::write(
+                dir.path().join(format!("{}.key", name)),
+                key.serialize_pem(),
+            )
+            .unwrap();
+        }
+        fs::write(dir.path().join("ca.pem"), ca_cert.pem()).unwrap();
+
+        Self {
+            dir,
+            ca_pem: ca_cert.pem(),
+        }
+    }
+
+    fn path(&self, file: &str) -> String {
+        self.dir.path().join(file).to_str().unwrap().to_string()
+    }
+
+    fn settings(&self, mutual: bool) -> TlsSettings {
+        TlsSettings {
+            cert_path: self.path("server.pem"),
+            key_path: self.path("server.key"),
+            client_ca_path: mutual.then(|| self.path("ca.pem")),
+        }
+    }
+
+    fn client_identity(&self) -> reqwest::Identity {
+        let cert = fs::read(self.path("client.pem")).unwrap();
+        let key = fs::read(self.path("client.key")).unwrap();
+        reqwest::Identity::from_pem(&[cert, key].concat()).unwrap()
+    }
+
+    fn client(&self, identity: Option<reqwest::Identity>) -> reqwest::Client {
+        let ca = reqwest::Certificate::from_pem(self.ca_pem.as_bytes()).unwrap();
+        let mut builder = reqwest::Client::builder().add_root_certificate(ca);
+        if let Some(identity) = identity {
+            builder = builder.identity(identity);
+        }
+        builder.build().unwrap()
+    }
+}
+
+/// Serve `/status/busy` over HTTPS, returning the base URL
+async fn start_tls_server(settings: &TlsSettings) -> String {
+    let app = Router::new().route("/status/busy", get(|| async { "ok" }));
+    let config = RustlsConfig::from_config(Arc::new(settings.server_config().unwrap()));
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        axum_server::from_tcp_rustls(listener, config)
+            .serve(app.into_make_service())
+            .await
+            .unwrap()
+    });
+    format!("https://localhost:{}", port)
+}
+
+#[tokio::test]
+async fn test_tls_serves_clients_trusting_the_ca() {
+    let pki = TestPki::generate();
+    let url = start_tls_server(&pki.settings(false)).await;
+
+    let response = pki
+        .client(None)
+        .get(format!("{}/status/busy", url))
+        .send()
+        .await
+        .unwrap();
+
+    assert!(response.status().is_success());
+    assert_eq!(response.text().await.unwrap(), "ok");
+}
+
+#[tokio::test]
+async fn test_tls_rejects_clients_without_the_ca() {
+    let pki = TestPki::generate();
+    let url = start_tls_server(&pki.settings(false)).await;
+
+    let result = reqwest::Client::new()
+        .get(format!("{}/status/busy", url))
+        .send()
+        .await;
+
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn test_mutual_tls_requires_client_certificate() {
+    let pki = TestPki::generate();
+    let url = start_tls_server(&pki.settings(true)).await;
+
+    let without_cert = pki
+        .client(None)
+        .get(format!("{}/status/busy", url))
+        .send()
+        .await;
+    assert!(without_cert.is_err());
+
+    let with_cert = pki
+        .client(Some(pki.client_identity()))
+        .get(format!("{}/status/busy", url))
+        .send()
+        .await
+        .unwrap();
+    assert!(with_cert.status().is_success());
+}
+
+#[test]
+fn test_server_config_rejects_missing_certificate() {
+    let pki = TestPki::generate();
+    let mut settings = pki.settings(false);
+    settings.cert_path = pki.path("missing.pem");
+
+    assert!(settings.server_config().is_err());
+}
@@ -0,0 +1,199 @@
+//! Integration tests for the typed agent operations
+//!
+//! These tests cover the allowlisted file operations that replace raw
+//! command execution: snapshot listing and deletion, log search and path
+//! checks, including rejection of names that escape the backup directory.
+
+use agent::services::{logs, paths, snapshot_files};
+use std::fs;
+use tempfile::TempDir;
+
+fn make_snapshot(backup: &TempDir, name: &str, with_wasm: bool) {
+    let dir = backup.path().join(name);
+    fs::create_dir_all(dir.join("data")).unwrap();
+    if with_wasm {
+        fs::create_dir_all(dir.join("wasm")).unwrap();
+    }
+}
+
+#[tokio::test]
+async fn test_list_snapshots_for_network() {
+    let backup = TempDir::new().unwrap();
+    make_snapshot(&backup, "pirin-1_20250121_17154420", true);
+    make_snapshot(&backup, "pirin-1_20250122_17200000", false);
+    make_snapshot(&backup, "rila-1_20250122_100", true);
+    fs::write(
+        backup.path().join("pirin-1_20250121_17154420.tar.lz4"),
+        b"archive",
+    )
+    .unwrap();
+    fs::write(backup.path().join("pirin-1_20240101_1.tar.lz4"), b"old").unwrap();
+
+    let backup_path = backup.path().to_str().unwrap();
+    let listing = snapshot_files::list_snapshots(backup_path, "pirin-1")
+        .await
+        .unwrap();
+
+    assert_eq!(listing.snapshots.len(), 2);
+    let with_archive = listing
+        .snapshots
+        .iter()
+        .find(|s| s.name == "pirin-1_20250121_17154420")
+        .unwrap();
+    assert!(with_archive.has_data && with_archive.has_wasm);
+    assert_eq!(with_archive.archive_size_bytes, Some(7));
+
+    let without_wasm = listing
+        .snapshots
+        .iter()
+        .find(|s| s.name == "pirin-1_20250122_17200000")
+        .unwrap();
+    assert!(!without_wasm.has_wasm);
+    assert_eq!(without_wasm.archive_size_bytes, None);
+
+    let archives: Vec<_> = listing.archives.iter().map(|a| a.name.as_str()).collect();
+    assert_eq!(
+        archives,
+        vec![
+            "pirin-1_20240101_1.tar.lz4",
+            "pirin-1_20250121_17154420.tar.lz4"
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_list_snapshots_missing_backup_path_is_empty() {
+    let backup = TempDir::new().unwrap();
+    let missing = backup.path().join("not-created");
+
+    let listing = snapshot_files::list_snapshots(missing.to_str().unwrap(), "pirin-1")
+        .await
+        .unwrap();
+    assert!(listing.snapshots.is_empty());
+    assert!(listing.archives.is_empty());
+}
+
+#[tokio::test]
+async fn test_delete_snapshot_removes_directory_and_archive() {
+    let backup = TempDir::new().unwrap();
+    make_snapshot(&backup, "pirin-1_20250121_17154420", true);
+    fs::write(
+        backup.path().join("pirin-1_20250121_17154420.tar.lz4"),
+        b"archive",
+    )
+    .unwrap();
+
+    let backup_path = backup.path().to_str().unwrap();
+    let result = snapshot_files::delete_snapshot(backup_path, "pirin-1_20250121_17154420")
+        .await
+        .unwrap();
+    assert!(result.directory_removed);
+    assert!(result.archive_removed);
+    assert_eq!(fs::read_dir(backup.path()).unwrap().count(), 0);
+
+    // Deleting again is not an error
+    let result = snapshot_files::delete_snapshot(backup_path, "pirin-1_20250121_17154420")
+        .await
+        .unwrap();
+    assert!(!result.directory_removed);
+    assert!(!result.archive_removed);
+}
+
+#[tokio::test]
+async fn test_delete_snapshot_rejects_path_traversal() {
+    let backup = TempDir::new().unwrap();
+    let backup_path = backup.path().to_str().unwrap();
+
+    for name in ["", ".", "..", "../etc", "a/b"] {
+        assert!(
+            snapshot_files::delete_snapshot(backup_path, name)
+                .await
+                .is_err(),
+            "name {:?} should be rejected",
+            name
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_search_log_reports_matches() {
+    let dir = TempDir::new().unwrap();
+    let log_file = dir.path().join("out1.log");
+    fs::write(&log_file, "starting\nCONSENSUS FAILURE\nnext line\n").unwrap();
+    let log_file = log_file.to_str().unwrap();
+
+    let result = logs::search_logs(logs::tail_request(
+        log_file,
+        &["CONSENSUS FAILURE".to_string()],
+        500,
+        0,
+    ))
+    .await
+    .unwrap();
+    assert!(result.matched);
+    assert_eq!(result.output.trim(), "2:CONSENSUS FAILURE");
+
+    let result = logs::search_logs(logs::tail_request(log_file, &["panic".to_string()], 500, 0))
+        .await
+        .unwrap();
+    assert!(!result.matched);
+    assert!(result.output.is_empty());
+}
+
+#[tokio::test]
+async fn test_search_log_patterns_are_not_shell_interpreted() {
+    let dir = TempDir::new().unwrap();
+    let log_file = dir.path().join("out1.log");
+    fs::write(&log_file, "it's broken\n").unwrap();
+    let log_file = log_file.to_str().unwrap();
+
+    let result = logs::search_logs(logs::tail_request(log_file, &["it's".to_string()], 500, 0))
+        .await
+        .unwrap();
+    assert!(result.matched);
+
+    let result = logs::search_logs(logs::tail_request(
+        log_file,
+        &["'; touch injected; '".to_string()],
+        500,
+        0,
+    ))
+    .await
+    .unwrap();
+    assert!(!result.matched);
+    assert!(!dir.path().join("injected").exists());
+}
+
+#[tokio::test]
+async fn test_search_log_only_reads_tail() {
+    let dir = TempDir::new().unwrap();
+    let log_file = dir.path().join("out1.log");
+    fs::write(&log_file, "old error\nok\nok\n").unwrap();
+    let log_file = log_file.to_str().unwrap();
+
+    let result = logs::search_logs(logs::tail_request(log_file, &["error".to_string()], 2, 0))
+        .await
+        .unwrap();
+    assert!(!result.matched);
+}
+
+#[tokio::test]
+async fn test_check_path() {
+    let dir = TempDir::new().unwrap();
+    let file = dir.path().join("file.txt");
+    fs::write(&file, b"hello").unwrap();
+
+    let info = paths::check_path(dir.path().to_str().unwrap())
+        .await
+        .unwrap();
+    assert!(info.exists && info.is_dir && !info.is_file);
+
+    let info = paths::check_path(file.to_str().unwrap()).await.unwrap();
+    assert!(info.exists && info.is_file);
+    assert_eq!(info.size_bytes, Some(5));
+
+    let info = paths::check_path(dir.path().join("missing").to_str().unwrap())
+        .await
+        .unwrap();
+    assert!(!info.exists);
+}
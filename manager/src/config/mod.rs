@@ -20,6 +20,14 @@ pub struct Config {
     pub hermes_min_uptime_minutes: Option<u32>,
     pub auto_restore_trigger_words: Option<Vec<String>>,
     pub log_monitoring_context_lines: Option<i32>,
+    /// Filesystem usage (percent) that raises a disk space warning
+    pub disk_warning_percent: Option<f64>,
+    /// Filesystem usage (percent) that raises a critical disk space alert
+    pub disk_critical_percent: Option<f64>,
+    /// Alert when the current fill rate predicts a full disk within this many hours
+    pub disk_full_alert_hours: Option<f64>,
+    /// Prune nodes or clean up old snapshots when a filesystem turns critical
+    pub disk_auto_cleanup_enabled: Option<bool>,
     // Populated from individual server config files
     #[serde(skip)]
     pub servers: HashMap<String, ServerConfig>,
@@ -37,6 +45,76 @@ pub struct ServerConfig {
     #[serde(default = "default_request_timeout")]
     pub request_timeout_seconds: u64,
     pub max_concurrent_requests: Option<usize>,
+    #[serde(flatten)]
+    pub tls: AgentTlsConfig,
+}
+
+impl ServerConfig {
+    /// Base URL of the server's agent, e.g. `https://10.0.0.5:8745`
+    pub fn agent_base_url(&self) -> String {
+        format!(
+            "{}://{}:{}",
+            self.tls.agent_scheme, self.host, self.agent_port
+        )
+    }
+
+    /// Full URL of an agent endpoint such as `/snapshot/create`
+    pub fn agent_url(&self, endpoint: &str) -> String {
+        format!("{}{}", self.agent_base_url(), endpoint)
+    }
+}
+
+/// URL scheme the manager uses to reach an agent
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AgentScheme {
+    #[default]
+    Http,
+    Https,
+}
+
+impl AgentScheme {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AgentScheme::Http => "http",
+            AgentScheme::Https => "https",
+        }
+    }
+}
+
+impl std::fmt::Display for AgentScheme {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for AgentScheme {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "http" => Ok(AgentScheme::Http),
+            "https" => Ok(AgentScheme::Https),
+            other => Err(anyhow::anyhow!(
+                "Unknown agent scheme '{}' (expected http or https)",
+                other
+            )),
+        }
+    }
+}
+
+/// Transport security for the connection to a server's agent
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct AgentTlsConfig {
+    #[serde(default)]
+    pub agent_scheme: AgentScheme,
+    /// PEM bundle of CAs trusted for the agent's certificate, in addition to
+    /// the system roots
+    pub ca_cert_path: Option<String>,
+    /// PEM client certificate presented to agents that require mutual TLS
+    pub client_cert_path: Option<String>,
+    /// PEM private key (PKCS#8, PKCS#1 or SEC1) for `client_cert_path`
+    pub client_key_path: Option<String>,
 }
 
 fn default_request_timeout() -> u64 {
@@ -97,7 +175,32 @@ pub struct NodeConfig {
 
     // Log configuration
     pub log_path: Option<String>,
+    /// Where the node writes its log: "directory" (default) for the files
+    /// matching `log_glob` in `log_path`, "file" when `log_path` is the log
+    /// file itself, or "journald" for the systemd journal of
+    /// `log_journald_unit`
+    #[serde(default)]
+    pub log_source: Option<String>,
+    /// Files of a "directory" log source, defaults to "out1.log"
+    #[serde(default)]
+    pub log_glob: Option<String>,
+    /// Unit of a "journald" log source, defaults to `service_name`
+    #[serde(default)]
+    pub log_journald_unit: Option<String>,
     pub truncate_logs_enabled: Option<bool>,
+    /// How maintenance operations clear the node's log files: "truncate"
+    /// (default) empties them in place, "rotate" archives them first
+    #[serde(default)]
+    pub truncate_logs_mode: Option<String>,
+    /// Compression of rotated logs: "gzip" (default), "zstd" or "none"
+    #[serde(default)]
+    pub log_rotation_compression: Option<String>,
+    /// Archives kept per log file, 10 when neither limit is set
+    #[serde(default)]
+    pub log_rotation_keep_generations: Option<usize>,
+    /// Total size of the archives kept per log file
+    #[serde(default)]
+    pub log_rotation_keep_bytes: Option<u64>,
     // Per-node log monitoring configuration
     pub log_monitoring_enabled: Option<bool>,
     pub log_monitoring_patterns: Option<Vec<String>>,
@@ -167,6 +270,97 @@ impl NodeConfig {
 
         self
     }
+
+    /// The node's log source, `None` when its log cannot be located
+    pub fn log_source(&self) -> Option<LogSource> {
+        match self.log_source.as_deref().unwrap_or("directory") {
+            "journald" => Some(LogSource::Journald {
+                unit: self
+                    .log_journald_unit
+                    .clone()
+                    .unwrap_or_else(|| self.service_name.clone()),
+            }),
+            "file" => self.log_path.clone().map(LogSource::File),
+            "directory" => self.log_path.clone().map(|path| LogSource::Directory {
+                path,
+                glob: self
+                    .log_glob
+                    .clone()
+                    .unwrap_or_else(|| DEFAULT_LOG_GLOB.to_string()),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Where the node's log files are on disk, which agents measure and
+    /// truncate during maintenance. Journald sources have none: the journal
+    /// is shared by all units and never truncated, and agents read a
+    /// journald node's log from its current invocation instead.
+    pub fn log_files_path(&self) -> Option<&String> {
+        match self.log_source() {
+            Some(LogSource::Journald { .. }) => None,
+            _ => self.log_path.as_ref(),
+        }
+    }
+
+    /// Path of one of `NODE_CONFIG_FILES` in the node's home, `None` without
+    /// a deploy path
+    pub fn config_file_path(&self, file: &str) -> Option<String> {
+        self.deploy_path
+            .as_ref()
+            .map(|home| format!("{}/config/{}", home, file))
+    }
+}
+
+/// Node config files that can be patched from the manager
+pub const NODE_CONFIG_FILES: [&str; 2] = ["config.toml", "app.toml"];
+
+/// Accepted values of `log_source`
+pub const LOG_SOURCES: [&str; 3] = ["file", "directory", "journald"];
+
+/// Accepted values of `truncate_logs_mode`
+pub const TRUNCATE_LOGS_MODES: [&str; 2] = ["truncate", "rotate"];
+
+/// Accepted values of `log_rotation_compression`
+pub const LOG_ROTATION_COMPRESSIONS: [&str; 3] = ["gzip", "zstd", "none"];
+
+/// Files read from a "directory" log source without `log_glob`
+pub const DEFAULT_LOG_GLOB: &str = "out1.log";
+
+/// Where a node's log is read from, resolved from its `log_source` settings
+#[derive(Debug, Clone, PartialEq)]
+pub enum LogSource {
+    File(String),
+    Directory { path: String, glob: String },
+    Journald { unit: String },
+}
+
+impl LogSource {
+    /// Parameters selecting this source in agent `/logs/search` and
+    /// `/logs/stream` requests
+    pub fn agent_params(&self) -> Vec<(&'static str, String)> {
+        match self {
+            LogSource::File(path) => vec![("path", path.clone())],
+            // A glob without wildcards names a single file, which agents
+            // predating globs can read and which keeps existing cursor keys
+            LogSource::Directory { path, glob } if !glob.contains(['*', '?', '[']) => {
+                vec![("path", format!("{}/{}", path, glob))]
+            }
+            LogSource::Directory { path, glob } => {
+                vec![("path", path.clone()), ("glob", glob.clone())]
+            }
+            LogSource::Journald { unit } => vec![("journald_unit", unit.clone())],
+        }
+    }
+
+    /// How the source is named in alerts and logs
+    pub fn display_name(&self) -> String {
+        match self {
+            LogSource::File(path) => path.clone(),
+            LogSource::Directory { path, glob } => format!("{}/{}", path, glob),
+            LogSource::Journald { unit } => format!("journald:{}", unit),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
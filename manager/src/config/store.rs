@@ -14,7 +14,7 @@ use std::sync::Arc;
 use tracing::{debug, info, warn};
 use uuid::Uuid;
 
-use crate::config::{Config, HermesConfig, NodeConfig, ServerConfig};
+use crate::config::{AgentTlsConfig, Config, HermesConfig, NodeConfig, ServerConfig};
 use crate::database::{Database, GlobalSettingRecord, HermesRecord, NodeRecord, ServerRecord};
 
 /// ConfigStore provides high-level configuration management backed by SQLite.
@@ -56,6 +56,7 @@ impl ConfigStore {
                     api_key: String::new(), // Will be filled from secrets
                     request_timeout_seconds: record.request_timeout_seconds as u64,
                     max_concurrent_requests: None,
+                    tls: self.server_record_to_tls(record),
                 },
             );
             debug!("Loaded server config: {} (id: {})", record.name, id);
@@ -119,6 +120,18 @@ impl ConfigStore {
             log_monitoring_context_lines: settings
                 .get("log_monitoring_context_lines")
                 .and_then(|v| v.parse().ok()),
+            disk_warning_percent: settings
+                .get("disk_warning_percent")
+                .and_then(|v| v.parse().ok()),
+            disk_critical_percent: settings
+                .get("disk_critical_percent")
+                .and_then(|v| v.parse().ok()),
+            disk_full_alert_hours: settings
+                .get("disk_full_alert_hours")
+                .and_then(|v| v.parse().ok()),
+            disk_auto_cleanup_enabled: settings
+                .get("disk_auto_cleanup_enabled")
+                .and_then(|v| v.parse().ok()),
             servers,
             nodes,
             hermes,
@@ -139,6 +152,19 @@ impl ConfigStore {
         Ok(records.into_iter().map(|r| (r.key, r.value)).collect())
     }
 
+    fn server_record_to_tls(&self, record: &ServerRecord) -> AgentTlsConfig {
+        let agent_scheme = record.agent_scheme.parse().unwrap_or_else(|e| {
+            warn!("Server {}: {}, using http", record.name, e);
+            Default::default()
+        });
+        AgentTlsConfig {
+            agent_scheme,
+            ca_cert_path: record.ca_cert_path.clone(),
+            client_cert_path: record.client_cert_path.clone(),
+            client_key_path: record.client_key_path.clone(),
+        }
+    }
+
     fn node_record_to_config(&self, record: &NodeRecord, server_host: &str) -> NodeConfig {
         NodeConfig {
             rpc_url: record.rpc_url.clone(),
@@ -152,7 +178,14 @@ impl ConfigStore {
             pruning_keep_blocks: record.pruning_keep_blocks.map(|v| v as u32),
             pruning_keep_versions: record.pruning_keep_versions.map(|v| v as u32),
             log_path: record.log_path.clone(),
+            log_source: record.log_source.clone(),
+            log_glob: record.log_glob.clone(),
+            log_journald_unit: record.log_journald_unit.clone(),
             truncate_logs_enabled: Some(record.truncate_logs_enabled),
+            truncate_logs_mode: record.truncate_logs_mode.clone(),
+            log_rotation_compression: record.log_rotation_compression.clone(),
+            log_rotation_keep_generations: record.log_rotation_keep_generations.map(|v| v as usize),
+            log_rotation_keep_bytes: record.log_rotation_keep_bytes.map(|v| v as u64),
             log_monitoring_enabled: Some(record.log_monitoring_enabled),
             log_monitoring_patterns: record
                 .log_monitoring_patterns
@@ -213,6 +246,7 @@ impl ConfigStore {
         agent_port: u16,
         api_key_ref: String,
         request_timeout_seconds: u64,
+        tls: AgentTlsConfig,
     ) -> Result<ServerRecord> {
         let now = Utc::now();
         let record = ServerRecord {
@@ -222,6 +256,10 @@ impl ConfigStore {
             agent_port: agent_port as i64,
             api_key_ref,
             request_timeout_seconds: request_timeout_seconds as i64,
+            agent_scheme: tls.agent_scheme.to_string(),
+            ca_cert_path: tls.ca_cert_path,
+            client_cert_path: tls.client_cert_path,
+            client_key_path: tls.client_key_path,
             created_at: now,
             updated_at: now,
         };
@@ -400,6 +438,22 @@ impl ConfigStore {
             self.set_setting("log_monitoring_context_lines", &v.to_string())
                 .await?;
         }
+        if let Some(v) = config.disk_warning_percent {
+            self.set_setting("disk_warning_percent", &v.to_string())
+                .await?;
+        }
+        if let Some(v) = config.disk_critical_percent {
+            self.set_setting("disk_critical_percent", &v.to_string())
+                .await?;
+        }
+        if let Some(v) = config.disk_full_alert_hours {
+            self.set_setting("disk_full_alert_hours", &v.to_string())
+                .await?;
+        }
+        if let Some(v) = config.disk_auto_cleanup_enabled {
+            self.set_setting("disk_auto_cleanup_enabled", &v.to_string())
+                .await?;
+        }
 
         info!("Saved global settings to database");
         Ok(())
@@ -432,6 +486,10 @@ impl ConfigStore {
                     agent_port: server_config.agent_port as i64,
                     api_key_ref: name.clone(), // Use server name as key reference
                     request_timeout_seconds: server_config.request_timeout_seconds as i64,
+                    agent_scheme: server_config.tls.agent_scheme.to_string(),
+                    ca_cert_path: server_config.tls.ca_cert_path.clone(),
+                    client_cert_path: server_config.tls.client_cert_path.clone(),
+                    client_key_path: server_config.tls.client_key_path.clone(),
                     created_at: existing.created_at,
                     updated_at: now,
                 }
@@ -443,6 +501,10 @@ impl ConfigStore {
                     agent_port: server_config.agent_port as i64,
                     api_key_ref: name.clone(),
                     request_timeout_seconds: server_config.request_timeout_seconds as i64,
+                    agent_scheme: server_config.tls.agent_scheme.to_string(),
+                    ca_cert_path: server_config.tls.ca_cert_path.clone(),
+                    client_cert_path: server_config.tls.client_cert_path.clone(),
+                    client_key_path: server_config.tls.client_key_path.clone(),
                     created_at: now,
                     updated_at: now,
                 }
@@ -496,6 +558,9 @@ impl ConfigStore {
                 deploy_path: node_config.deploy_path.clone(),
                 log_path: node_config.log_path.clone(),
                 snapshot_backup_path: node_config.snapshot_backup_path.clone(),
+                log_source: node_config.log_source.clone(),
+                log_glob: node_config.log_glob.clone(),
+                log_journald_unit: node_config.log_journald_unit.clone(),
                 pruning_enabled: node_config.pruning_enabled.unwrap_or(false),
                 pruning_schedule: node_config.pruning_schedule.clone(),
                 pruning_keep_blocks: node_config.pruning_keep_blocks.map(|v| v as i64),
@@ -522,6 +587,12 @@ impl ConfigStore {
                     .as_ref()
                     .map(|v| serde_json::to_string(v).unwrap_or_default()),
                 truncate_logs_enabled: node_config.truncate_logs_enabled.unwrap_or(false),
+                truncate_logs_mode: node_config.truncate_logs_mode.clone(),
+                log_rotation_compression: node_config.log_rotation_compression.clone(),
+                log_rotation_keep_generations: node_config
+                    .log_rotation_keep_generations
+                    .map(|v| v as i64),
+                log_rotation_keep_bytes: node_config.log_rotation_keep_bytes.map(|v| v as i64),
                 created_at: existing.as_ref().map(|e| e.created_at).unwrap_or(now),
                 updated_at: now,
             };
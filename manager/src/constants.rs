@@ -51,6 +51,13 @@ pub mod operation_timeouts {
 
     /// Sleep duration after stopping Hermes service before starting it (seconds)
     pub const HERMES_RESTART_SLEEP_SECONDS: u64 = 3;
+
+    /// How long a config patch rollout waits for a restarted node to be
+    /// healthy and caught up before it stops
+    pub const CONFIG_PATCH_HEALTHY_MINUTES: u64 = 15;
+
+    /// Interval between health checks of a restarted node (seconds)
+    pub const CONFIG_PATCH_HEALTH_POLL_SECONDS: u64 = 10;
 }
 
 /// Cleanup and maintenance constants
@@ -67,6 +74,9 @@ pub mod cleanup {
     /// Hours after which old jobs are cleaned up from agent
     pub const JOB_CLEANUP_HOURS: i64 = 48;
 
+    /// Hours of resource telemetry kept; must cover the disk fill rate window
+    pub const TELEMETRY_RETENTION_HOURS: i64 = 7 * 24;
+
     /// Cleanup interval in seconds
     pub const CLEANUP_INTERVAL_SECONDS: u64 = 3600; // 1 hour
 }
@@ -93,6 +103,15 @@ pub mod alerts {
 
     /// Minimum hours between auto-restore attempts (cooldown)
     pub const AUTO_RESTORE_COOLDOWN_HOURS: i64 = 2;
+
+    /// Hours of telemetry history used to compute filesystem fill rates
+    pub const DISK_FILL_RATE_WINDOW_HOURS: i64 = 24;
+
+    /// Minimum hours covered by the history before a fill rate is trusted
+    pub const DISK_FILL_RATE_MIN_SPAN_HOURS: f64 = 1.0;
+
+    /// Text of the agent's error when a disk space preflight check fails
+    pub const INSUFFICIENT_DISK_SPACE_ERROR: &str = "Insufficient disk space";
 }
 
 /// Default configuration values
@@ -114,6 +133,15 @@ pub mod defaults {
 
     /// Default hermes minimum uptime in minutes before restart
     pub const HERMES_MIN_UPTIME_MINUTES: u32 = 5;
+
+    /// Default filesystem usage (percent) for a disk space warning
+    pub const DISK_WARNING_PERCENT: f64 = 80.0;
+
+    /// Default filesystem usage (percent) for a critical disk space alert
+    pub const DISK_CRITICAL_PERCENT: f64 = 90.0;
+
+    /// Default horizon (hours) for "disk full" predictions
+    pub const DISK_FULL_ALERT_HOURS: f64 = 48.0;
 }
 
 /// Limits and constraints
@@ -18,6 +18,7 @@ impl Database {
         let rows = sqlx::query(
             r#"
             SELECT id, name, host, agent_port, api_key_ref, request_timeout_seconds,
+                   agent_scheme, ca_cert_path, client_cert_path, client_key_path,
                    created_at, updated_at
             FROM config_servers
             ORDER BY name
@@ -35,6 +36,10 @@ impl Database {
                 agent_port: row.try_get("agent_port")?,
                 api_key_ref: row.try_get("api_key_ref")?,
                 request_timeout_seconds: row.try_get("request_timeout_seconds")?,
+                agent_scheme: row.try_get("agent_scheme")?,
+                ca_cert_path: row.try_get("ca_cert_path")?,
+                client_cert_path: row.try_get("client_cert_path")?,
+                client_key_path: row.try_get("client_key_path")?,
                 created_at: row.try_get("created_at")?,
                 updated_at: row.try_get("updated_at")?,
             });
@@ -46,6 +51,7 @@ impl Database {
         let row = sqlx::query(
             r#"
             SELECT id, name, host, agent_port, api_key_ref, request_timeout_seconds,
+                   agent_scheme, ca_cert_path, client_cert_path, client_key_path,
                    created_at, updated_at
             FROM config_servers
             WHERE id = ?
@@ -63,6 +69,10 @@ impl Database {
                 agent_port: row.try_get("agent_port")?,
                 api_key_ref: row.try_get("api_key_ref")?,
                 request_timeout_seconds: row.try_get("request_timeout_seconds")?,
+                agent_scheme: row.try_get("agent_scheme")?,
+                ca_cert_path: row.try_get("ca_cert_path")?,
+                client_cert_path: row.try_get("client_cert_path")?,
+                client_key_path: row.try_get("client_key_path")?,
                 created_at: row.try_get("created_at")?,
                 updated_at: row.try_get("updated_at")?,
             }))
@@ -75,6 +85,7 @@ impl Database {
         let row = sqlx::query(
             r#"
             SELECT id, name, host, agent_port, api_key_ref, request_timeout_seconds,
+                   agent_scheme, ca_cert_path, client_cert_path, client_key_path,
                    created_at, updated_at
             FROM config_servers
             WHERE name = ?
@@ -92,6 +103,10 @@ impl Database {
                 agent_port: row.try_get("agent_port")?,
                 api_key_ref: row.try_get("api_key_ref")?,
                 request_timeout_seconds: row.try_get("request_timeout_seconds")?,
+                agent_scheme: row.try_get("agent_scheme")?,
+                ca_cert_path: row.try_get("ca_cert_path")?,
+                client_cert_path: row.try_get("client_cert_path")?,
+                client_key_path: row.try_get("client_key_path")?,
                 created_at: row.try_get("created_at")?,
                 updated_at: row.try_get("updated_at")?,
             }))
@@ -104,14 +119,19 @@ impl Database {
         sqlx::query(
             r#"
             INSERT INTO config_servers (id, name, host, agent_port, api_key_ref, 
-                                        request_timeout_seconds, created_at, updated_at)
-            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+                                        request_timeout_seconds, agent_scheme, ca_cert_path,
+                                        client_cert_path, client_key_path, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             ON CONFLICT(id) DO UPDATE SET
                 name = excluded.name,
                 host = excluded.host,
                 agent_port = excluded.agent_port,
                 api_key_ref = excluded.api_key_ref,
                 request_timeout_seconds = excluded.request_timeout_seconds,
+                agent_scheme = excluded.agent_scheme,
+                ca_cert_path = excluded.ca_cert_path,
+                client_cert_path = excluded.client_cert_path,
+                client_key_path = excluded.client_key_path,
                 updated_at = excluded.updated_at
             "#,
         )
@@ -121,6 +141,10 @@ impl Database {
         .bind(server.agent_port)
         .bind(&server.api_key_ref)
         .bind(server.request_timeout_seconds)
+        .bind(&server.agent_scheme)
+        .bind(&server.ca_cert_path)
+        .bind(&server.client_cert_path)
+        .bind(&server.client_key_path)
         .bind(server.created_at)
         .bind(server.updated_at)
         .execute(&self.pool)
@@ -145,11 +169,14 @@ impl Database {
             r#"
             SELECT id, name, server_id, network, rpc_url, enabled, service_name,
                    deploy_path, log_path, snapshot_backup_path,
+                   log_source, log_glob, log_journald_unit,
                    pruning_enabled, pruning_schedule, pruning_keep_blocks, pruning_keep_versions,
                    snapshots_enabled, snapshot_schedule, snapshot_retention_count, auto_restore_enabled,
                    state_sync_enabled, state_sync_schedule, state_sync_rpc_sources,
                    state_sync_trust_height_offset, state_sync_max_sync_timeout_seconds,
                    log_monitoring_enabled, log_monitoring_patterns, truncate_logs_enabled,
+                   truncate_logs_mode, log_rotation_compression,
+                   log_rotation_keep_generations, log_rotation_keep_bytes,
                    created_at, updated_at
             FROM config_nodes
             ORDER BY name
@@ -170,11 +197,14 @@ impl Database {
             r#"
             SELECT id, name, server_id, network, rpc_url, enabled, service_name,
                    deploy_path, log_path, snapshot_backup_path,
+                   log_source, log_glob, log_journald_unit,
                    pruning_enabled, pruning_schedule, pruning_keep_blocks, pruning_keep_versions,
                    snapshots_enabled, snapshot_schedule, snapshot_retention_count, auto_restore_enabled,
                    state_sync_enabled, state_sync_schedule, state_sync_rpc_sources,
                    state_sync_trust_height_offset, state_sync_max_sync_timeout_seconds,
                    log_monitoring_enabled, log_monitoring_patterns, truncate_logs_enabled,
+                   truncate_logs_mode, log_rotation_compression,
+                   log_rotation_keep_generations, log_rotation_keep_bytes,
                    created_at, updated_at
             FROM config_nodes
             WHERE id = ?
@@ -196,11 +226,14 @@ impl Database {
             r#"
             SELECT id, name, server_id, network, rpc_url, enabled, service_name,
                    deploy_path, log_path, snapshot_backup_path,
+                   log_source, log_glob, log_journald_unit,
                    pruning_enabled, pruning_schedule, pruning_keep_blocks, pruning_keep_versions,
                    snapshots_enabled, snapshot_schedule, snapshot_retention_count, auto_restore_enabled,
                    state_sync_enabled, state_sync_schedule, state_sync_rpc_sources,
                    state_sync_trust_height_offset, state_sync_max_sync_timeout_seconds,
                    log_monitoring_enabled, log_monitoring_patterns, truncate_logs_enabled,
+                   truncate_logs_mode, log_rotation_compression,
+                   log_rotation_keep_generations, log_rotation_keep_bytes,
                    created_at, updated_at
             FROM config_nodes
             WHERE name = ?
@@ -222,11 +255,14 @@ impl Database {
             r#"
             SELECT id, name, server_id, network, rpc_url, enabled, service_name,
                    deploy_path, log_path, snapshot_backup_path,
+                   log_source, log_glob, log_journald_unit,
                    pruning_enabled, pruning_schedule, pruning_keep_blocks, pruning_keep_versions,
                    snapshots_enabled, snapshot_schedule, snapshot_retention_count, auto_restore_enabled,
                    state_sync_enabled, state_sync_schedule, state_sync_rpc_sources,
                    state_sync_trust_height_offset, state_sync_max_sync_timeout_seconds,
                    log_monitoring_enabled, log_monitoring_patterns, truncate_logs_enabled,
+                   truncate_logs_mode, log_rotation_compression,
+                   log_rotation_keep_generations, log_rotation_keep_bytes,
                    created_at, updated_at
             FROM config_nodes
             WHERE server_id = ?
@@ -256,6 +292,9 @@ impl Database {
             deploy_path: row.try_get("deploy_path")?,
             log_path: row.try_get("log_path")?,
             snapshot_backup_path: row.try_get("snapshot_backup_path")?,
+            log_source: row.try_get("log_source")?,
+            log_glob: row.try_get("log_glob")?,
+            log_journald_unit: row.try_get("log_journald_unit")?,
             pruning_enabled: row.try_get("pruning_enabled")?,
             pruning_schedule: row.try_get("pruning_schedule")?,
             pruning_keep_blocks: row.try_get("pruning_keep_blocks")?,
@@ -273,6 +312,10 @@ impl Database {
             log_monitoring_enabled: row.try_get("log_monitoring_enabled")?,
             log_monitoring_patterns: row.try_get("log_monitoring_patterns")?,
             truncate_logs_enabled: row.try_get("truncate_logs_enabled")?,
+            truncate_logs_mode: row.try_get("truncate_logs_mode")?,
+            log_rotation_compression: row.try_get("log_rotation_compression")?,
+            log_rotation_keep_generations: row.try_get("log_rotation_keep_generations")?,
+            log_rotation_keep_bytes: row.try_get("log_rotation_keep_bytes")?,
             created_at: row.try_get("created_at")?,
             updated_at: row.try_get("updated_at")?,
         })
@@ -284,14 +327,17 @@ impl Database {
             INSERT INTO config_nodes (
                 id, name, server_id, network, rpc_url, enabled, service_name,
                 deploy_path, log_path, snapshot_backup_path,
+                log_source, log_glob, log_journald_unit,
                 pruning_enabled, pruning_schedule, pruning_keep_blocks, pruning_keep_versions,
                 snapshots_enabled, snapshot_schedule, snapshot_retention_count, auto_restore_enabled,
                 state_sync_enabled, state_sync_schedule, state_sync_rpc_sources,
                 state_sync_trust_height_offset, state_sync_max_sync_timeout_seconds,
                 log_monitoring_enabled, log_monitoring_patterns, truncate_logs_enabled,
+                truncate_logs_mode, log_rotation_compression,
+                log_rotation_keep_generations, log_rotation_keep_bytes,
                 created_at, updated_at
             )
-            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             ON CONFLICT(id) DO UPDATE SET
                 name = excluded.name,
                 server_id = excluded.server_id,
@@ -302,6 +348,9 @@ impl Database {
                 deploy_path = excluded.deploy_path,
                 log_path = excluded.log_path,
                 snapshot_backup_path = excluded.snapshot_backup_path,
+                log_source = excluded.log_source,
+                log_glob = excluded.log_glob,
+                log_journald_unit = excluded.log_journald_unit,
                 pruning_enabled = excluded.pruning_enabled,
                 pruning_schedule = excluded.pruning_schedule,
                 pruning_keep_blocks = excluded.pruning_keep_blocks,
@@ -318,6 +367,10 @@ impl Database {
                 log_monitoring_enabled = excluded.log_monitoring_enabled,
                 log_monitoring_patterns = excluded.log_monitoring_patterns,
                 truncate_logs_enabled = excluded.truncate_logs_enabled,
+                truncate_logs_mode = excluded.truncate_logs_mode,
+                log_rotation_compression = excluded.log_rotation_compression,
+                log_rotation_keep_generations = excluded.log_rotation_keep_generations,
+                log_rotation_keep_bytes = excluded.log_rotation_keep_bytes,
                 updated_at = excluded.updated_at
             "#,
         )
@@ -331,6 +384,9 @@ impl Database {
         .bind(&node.deploy_path)
         .bind(&node.log_path)
         .bind(&node.snapshot_backup_path)
+        .bind(&node.log_source)
+        .bind(&node.log_glob)
+        .bind(&node.log_journald_unit)
         .bind(node.pruning_enabled)
         .bind(&node.pruning_schedule)
         .bind(node.pruning_keep_blocks)
@@ -347,6 +403,10 @@ impl Database {
         .bind(node.log_monitoring_enabled)
         .bind(&node.log_monitoring_patterns)
         .bind(node.truncate_logs_enabled)
+        .bind(&node.truncate_logs_mode)
+        .bind(&node.log_rotation_compression)
+        .bind(node.log_rotation_keep_generations)
+        .bind(node.log_rotation_keep_bytes)
         .bind(node.created_at)
         .bind(node.updated_at)
         .execute(&self.pool)
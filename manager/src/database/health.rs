@@ -1,10 +1,13 @@
 //! Health record database operations.
 
 use anyhow::Result;
+use chrono::{DateTime, Utc};
 use sqlx::Row;
 use tracing::{debug, error};
 
-use super::records::{HealthRecord, HermesHealthRecord};
+use super::records::{
+    HealthRecord, HermesHealthRecord, LogCursorRecord, NodeDiskUsageRecord, ServerTelemetryRecord,
+};
 use super::Database;
 
 impl Database {
@@ -154,4 +157,224 @@ impl Database {
             Ok(None)
         }
     }
+
+    pub async fn store_server_telemetry_record(
+        &self,
+        record: &ServerTelemetryRecord,
+    ) -> Result<()> {
+        debug!(
+            "Storing telemetry record for server: {}",
+            record.server_name
+        );
+
+        sqlx::query(
+            r#"
+            INSERT INTO server_telemetry_records (
+                server_name, timestamp, load_1m, load_5m, load_15m, cpu_count,
+                memory_total_bytes, memory_available_bytes, filesystems
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&record.server_name)
+        .bind(record.timestamp)
+        .bind(record.load_1m)
+        .bind(record.load_5m)
+        .bind(record.load_15m)
+        .bind(record.cpu_count)
+        .bind(record.memory_total_bytes)
+        .bind(record.memory_available_bytes)
+        .bind(&record.filesystems)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn get_latest_server_telemetry_record(
+        &self,
+        server_name: &str,
+    ) -> Result<Option<ServerTelemetryRecord>> {
+        let row = sqlx::query(
+            r#"
+            SELECT server_name, timestamp, load_1m, load_5m, load_15m, cpu_count,
+                   memory_total_bytes, memory_available_bytes, filesystems
+            FROM server_telemetry_records
+            WHERE server_name = ?
+            ORDER BY timestamp DESC
+            LIMIT 1
+            "#,
+        )
+        .bind(server_name)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.map(|row| {
+            Ok(ServerTelemetryRecord {
+                server_name: row.try_get("server_name")?,
+                timestamp: row.try_get("timestamp")?,
+                load_1m: row.try_get("load_1m")?,
+                load_5m: row.try_get("load_5m")?,
+                load_15m: row.try_get("load_15m")?,
+                cpu_count: row.try_get("cpu_count")?,
+                memory_total_bytes: row.try_get("memory_total_bytes")?,
+                memory_available_bytes: row.try_get("memory_available_bytes")?,
+                filesystems: row.try_get("filesystems")?,
+            })
+        })
+        .transpose()
+    }
+
+    /// Telemetry records of a server since `since`, oldest first
+    pub async fn get_server_telemetry_records_since(
+        &self,
+        server_name: &str,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<ServerTelemetryRecord>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT server_name, timestamp, load_1m, load_5m, load_15m, cpu_count,
+                   memory_total_bytes, memory_available_bytes, filesystems
+            FROM server_telemetry_records
+            WHERE server_name = ? AND timestamp >= ?
+            ORDER BY timestamp ASC
+            "#,
+        )
+        .bind(server_name)
+        .bind(since)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter()
+            .map(|row| {
+                Ok(ServerTelemetryRecord {
+                    server_name: row.try_get("server_name")?,
+                    timestamp: row.try_get("timestamp")?,
+                    load_1m: row.try_get("load_1m")?,
+                    load_5m: row.try_get("load_5m")?,
+                    load_15m: row.try_get("load_15m")?,
+                    cpu_count: row.try_get("cpu_count")?,
+                    memory_total_bytes: row.try_get("memory_total_bytes")?,
+                    memory_available_bytes: row.try_get("memory_available_bytes")?,
+                    filesystems: row.try_get("filesystems")?,
+                })
+            })
+            .collect()
+    }
+
+    pub async fn store_node_disk_usage_record(&self, record: &NodeDiskUsageRecord) -> Result<()> {
+        debug!("Storing disk usage record for: {}", record.node_name);
+
+        sqlx::query(
+            r#"
+            INSERT INTO node_disk_usage_records (
+                node_name, timestamp, data_bytes, wasm_bytes, log_bytes, measured_at
+            ) VALUES (?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&record.node_name)
+        .bind(record.timestamp)
+        .bind(record.data_bytes)
+        .bind(record.wasm_bytes)
+        .bind(record.log_bytes)
+        .bind(record.measured_at)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn get_latest_node_disk_usage_record(
+        &self,
+        node_name: &str,
+    ) -> Result<Option<NodeDiskUsageRecord>> {
+        let row = sqlx::query(
+            r#"
+            SELECT node_name, timestamp, data_bytes, wasm_bytes, log_bytes, measured_at
+            FROM node_disk_usage_records
+            WHERE node_name = ?
+            ORDER BY timestamp DESC
+            LIMIT 1
+            "#,
+        )
+        .bind(node_name)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.map(|row| {
+            Ok(NodeDiskUsageRecord {
+                node_name: row.try_get("node_name")?,
+                timestamp: row.try_get("timestamp")?,
+                data_bytes: row.try_get("data_bytes")?,
+                wasm_bytes: row.try_get("wasm_bytes")?,
+                log_bytes: row.try_get("log_bytes")?,
+                measured_at: row.try_get("measured_at")?,
+            })
+        })
+        .transpose()
+    }
+
+    /// Delete server telemetry and node disk usage records older than
+    /// `before`, returning how many were removed
+    pub async fn delete_telemetry_records_before(&self, before: DateTime<Utc>) -> Result<u64> {
+        let servers = sqlx::query("DELETE FROM server_telemetry_records WHERE timestamp < ?")
+            .bind(before)
+            .execute(&self.pool)
+            .await?;
+        let nodes = sqlx::query("DELETE FROM node_disk_usage_records WHERE timestamp < ?")
+            .bind(before)
+            .execute(&self.pool)
+            .await?;
+        Ok(servers.rows_affected() + nodes.rows_affected())
+    }
+
+    /// Log cursors of a node, one per monitored file
+    pub async fn get_log_cursors(&self, node_name: &str) -> Result<Vec<LogCursorRecord>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT node_name, file_path, inode, offset, fingerprint, journal_cursor, updated_at
+            FROM log_cursors
+            WHERE node_name = ?
+            "#,
+        )
+        .bind(node_name)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter()
+            .map(|row| {
+                Ok(LogCursorRecord {
+                    node_name: row.try_get("node_name")?,
+                    file_path: row.try_get("file_path")?,
+                    inode: row.try_get("inode")?,
+                    offset: row.try_get("offset")?,
+                    fingerprint: row.try_get("fingerprint")?,
+                    journal_cursor: row.try_get("journal_cursor")?,
+                    updated_at: row.try_get("updated_at")?,
+                })
+            })
+            .collect()
+    }
+
+    pub async fn upsert_log_cursor(&self, record: &LogCursorRecord) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO log_cursors (node_name, file_path, inode, offset, fingerprint, journal_cursor, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT(node_name, file_path) DO UPDATE SET
+                inode = excluded.inode,
+                offset = excluded.offset,
+                fingerprint = excluded.fingerprint,
+                journal_cursor = excluded.journal_cursor,
+                updated_at = excluded.updated_at
+            "#,
+        )
+        .bind(&record.node_name)
+        .bind(&record.file_path)
+        .bind(record.inode)
+        .bind(record.offset)
+        .bind(&record.fingerprint)
+        .bind(&record.journal_cursor)
+        .bind(record.updated_at)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
 }
@@ -1,6 +1,7 @@
 //! Maintenance operation database operations.
 
 use anyhow::Result;
+use sqlx::sqlite::SqliteRow;
 use sqlx::Row;
 use tracing::debug;
 
@@ -67,16 +68,7 @@ impl Database {
         .await?;
 
         if let Some(row) = row {
-            let operation = MaintenanceOperation {
-                id: row.try_get("id")?,
-                operation_type: row.try_get("operation_type")?,
-                target_name: row.try_get("target_name")?,
-                status: row.try_get("status")?,
-                started_at: row.try_get("started_at")?,
-                completed_at: row.try_get("completed_at")?,
-                error_message: row.try_get("error_message")?,
-                details: row.try_get("details")?,
-            };
+            let operation = row_to_maintenance_operation(&row)?;
             debug!("Found maintenance operation: {}", operation_id);
             Ok(Some(operation))
         } else {
@@ -84,4 +76,43 @@ impl Database {
             Ok(None)
         }
     }
+
+    /// Most recent operations of one type, newest first
+    pub async fn get_maintenance_operations_by_type(
+        &self,
+        operation_type: &str,
+        limit: i64,
+    ) -> Result<Vec<MaintenanceOperation>> {
+        debug!("Querying {} maintenance operations", operation_type);
+
+        let rows = sqlx::query(
+            r#"
+            SELECT id, operation_type, target_name, status, started_at,
+                   completed_at, error_message, details
+            FROM maintenance_operations
+            WHERE operation_type = ?
+            ORDER BY started_at DESC
+            LIMIT ?
+            "#,
+        )
+        .bind(operation_type)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter().map(row_to_maintenance_operation).collect()
+    }
+}
+
+fn row_to_maintenance_operation(row: &SqliteRow) -> Result<MaintenanceOperation> {
+    Ok(MaintenanceOperation {
+        id: row.try_get("id")?,
+        operation_type: row.try_get("operation_type")?,
+        target_name: row.try_get("target_name")?,
+        status: row.try_get("status")?,
+        started_at: row.try_get("started_at")?,
+        completed_at: row.try_get("completed_at")?,
+        error_message: row.try_get("error_message")?,
+        details: row.try_get("details")?,
+    })
 }
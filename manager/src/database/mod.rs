@@ -1,7 +1,8 @@
 //! Database layer for the nodes manager.
 //!
 //! This module provides SQLite persistence for:
-//! - Health records (node and Hermes health history)
+//! - Health records (node and Hermes health history, host and node resource telemetry,
+//!   log monitoring cursors)
 //! - Maintenance operations (tracking operation status)
 //! - Configuration (servers, nodes, hermes, settings)
 //!
@@ -229,6 +230,10 @@ impl Database {
                 agent_port INTEGER NOT NULL DEFAULT 8745,
                 api_key_ref TEXT NOT NULL,
                 request_timeout_seconds INTEGER NOT NULL DEFAULT 300,
+                agent_scheme TEXT NOT NULL DEFAULT 'http',
+                ca_cert_path TEXT,
+                client_cert_path TEXT,
+                client_key_path TEXT,
                 created_at DATETIME NOT NULL,
                 updated_at DATETIME NOT NULL
             )
@@ -239,6 +244,16 @@ impl Database {
         }
         info!("config_servers table created");
 
+        // Databases created before agent TLS support lack these columns
+        for column in [
+            "agent_scheme TEXT NOT NULL DEFAULT 'http'",
+            "ca_cert_path TEXT",
+            "client_cert_path TEXT",
+            "client_key_path TEXT",
+        ] {
+            self.add_column_if_missing("config_servers", column).await?;
+        }
+
         info!("Step 8: Creating config_nodes table...");
         let nodes_table_sql = r#"
             CREATE TABLE IF NOT EXISTS config_nodes (
@@ -278,6 +293,20 @@ impl Database {
         }
         info!("config_nodes table created");
 
+        // Databases created before journald and globbed log sources lack these columns
+        for column in ["log_source TEXT", "log_glob TEXT", "log_journald_unit TEXT"] {
+            self.add_column_if_missing("config_nodes", column).await?;
+        }
+        // ... and these, added with log rotation
+        for column in [
+            "truncate_logs_mode TEXT",
+            "log_rotation_compression TEXT",
+            "log_rotation_keep_generations INTEGER",
+            "log_rotation_keep_bytes INTEGER",
+        ] {
+            self.add_column_if_missing("config_nodes", column).await?;
+        }
+
         info!("Step 9: Creating config_nodes indexes...");
         let nodes_server_idx =
             "CREATE INDEX IF NOT EXISTS idx_config_nodes_server ON config_nodes(server_id)";
@@ -337,10 +366,110 @@ impl Database {
         }
         info!("global_settings table created");
 
+        info!("Step 13: Creating resource telemetry tables...");
+        let server_telemetry_table_sql = r#"
+            CREATE TABLE IF NOT EXISTS server_telemetry_records (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                server_name TEXT NOT NULL,
+                timestamp DATETIME NOT NULL,
+                load_1m REAL NOT NULL,
+                load_5m REAL NOT NULL,
+                load_15m REAL NOT NULL,
+                cpu_count INTEGER NOT NULL,
+                memory_total_bytes INTEGER NOT NULL,
+                memory_available_bytes INTEGER NOT NULL,
+                filesystems TEXT NOT NULL
+            )
+        "#;
+        if let Err(e) = sqlx::query(server_telemetry_table_sql)
+            .execute(&self.pool)
+            .await
+        {
+            error!("FAILED to create server_telemetry_records table: {}", e);
+            return Err(e.into());
+        }
+        let node_disk_usage_table_sql = r#"
+            CREATE TABLE IF NOT EXISTS node_disk_usage_records (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                node_name TEXT NOT NULL,
+                timestamp DATETIME NOT NULL,
+                data_bytes INTEGER,
+                wasm_bytes INTEGER,
+                log_bytes INTEGER,
+                measured_at DATETIME
+            )
+        "#;
+        if let Err(e) = sqlx::query(node_disk_usage_table_sql)
+            .execute(&self.pool)
+            .await
+        {
+            error!("FAILED to create node_disk_usage_records table: {}", e);
+            return Err(e.into());
+        }
+        for index_sql in [
+            "CREATE INDEX IF NOT EXISTS idx_server_telemetry_name_timestamp ON server_telemetry_records(server_name, timestamp DESC)",
+            "CREATE INDEX IF NOT EXISTS idx_node_disk_usage_name_timestamp ON node_disk_usage_records(node_name, timestamp DESC)",
+        ] {
+            if let Err(e) = sqlx::query(index_sql).execute(&self.pool).await {
+                error!("FAILED to create resource telemetry index: {}", e);
+                return Err(e.into());
+            }
+        }
+        info!("resource telemetry tables created");
+
+        info!("Step 14: Creating log_cursors table...");
+        let log_cursors_table_sql = r#"
+            CREATE TABLE IF NOT EXISTS log_cursors (
+                node_name TEXT NOT NULL,
+                file_path TEXT NOT NULL,
+                inode INTEGER NOT NULL,
+                offset INTEGER NOT NULL,
+                fingerprint TEXT NOT NULL,
+                updated_at DATETIME NOT NULL,
+                PRIMARY KEY (node_name, file_path)
+            )
+        "#;
+        if let Err(e) = sqlx::query(log_cursors_table_sql).execute(&self.pool).await {
+            error!("FAILED to create log_cursors table: {}", e);
+            return Err(e.into());
+        }
+        // Journal cursors came with journald log sources
+        self.add_column_if_missing("log_cursors", "journal_cursor TEXT")
+            .await?;
+        info!("log_cursors table created");
+
         info!("All database tables and indexes created successfully");
         Ok(())
     }
 
+    /// Add a column (given as `name TYPE ...`) to an existing table unless it is already there
+    async fn add_column_if_missing(&self, table: &str, column_definition: &str) -> Result<()> {
+        let column_name = column_definition
+            .split_whitespace()
+            .next()
+            .unwrap_or(column_definition);
+        let columns = sqlx::query(&format!("PRAGMA table_info({})", table))
+            .fetch_all(&self.pool)
+            .await?;
+        let exists = columns.iter().any(|row| {
+            row.try_get::<String, _>("name")
+                .map(|name| name == column_name)
+                .unwrap_or(false)
+        });
+        if exists {
+            return Ok(());
+        }
+
+        info!("Adding column {} to {}", column_name, table);
+        sqlx::query(&format!(
+            "ALTER TABLE {} ADD COLUMN {}",
+            table, column_definition
+        ))
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
     // Startup cleanup method to fix stuck maintenance operations
     async fn cleanup_stuck_maintenance_operations(&self) -> Result<u32> {
         info!("Checking for stuck maintenance operations...");
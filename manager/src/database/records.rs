@@ -17,6 +17,10 @@ pub struct ServerRecord {
     pub agent_port: i64,
     pub api_key_ref: String, // Reference to secrets file, not the actual key
     pub request_timeout_seconds: i64,
+    pub agent_scheme: String,
+    pub ca_cert_path: Option<String>,
+    pub client_cert_path: Option<String>,
+    pub client_key_path: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -34,6 +38,10 @@ pub struct NodeRecord {
     pub deploy_path: Option<String>,
     pub log_path: Option<String>,
     pub snapshot_backup_path: Option<String>,
+    // Log source
+    pub log_source: Option<String>,
+    pub log_glob: Option<String>,
+    pub log_journald_unit: Option<String>,
     // Pruning settings
     pub pruning_enabled: bool,
     pub pruning_schedule: Option<String>,
@@ -54,6 +62,10 @@ pub struct NodeRecord {
     pub log_monitoring_enabled: bool,
     pub log_monitoring_patterns: Option<String>, // JSON array
     pub truncate_logs_enabled: bool,
+    pub truncate_logs_mode: Option<String>,
+    pub log_rotation_compression: Option<String>,
+    pub log_rotation_keep_generations: Option<i64>,
+    pub log_rotation_keep_bytes: Option<i64>,
     // Timestamps
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
@@ -108,6 +120,45 @@ pub struct HermesHealthRecord {
     pub service_name: String,
 }
 
+/// Host resources reported by a server's agent
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServerTelemetryRecord {
+    pub server_name: String,
+    pub timestamp: DateTime<Utc>,
+    pub load_1m: f64,
+    pub load_5m: f64,
+    pub load_15m: f64,
+    pub cpu_count: i64,
+    pub memory_total_bytes: i64,
+    pub memory_available_bytes: i64,
+    pub filesystems: String, // JSON array of per-root filesystem usage
+}
+
+/// Sizes of a node's directories as reported by its agent
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NodeDiskUsageRecord {
+    pub node_name: String,
+    pub timestamp: DateTime<Utc>,
+    pub data_bytes: Option<i64>,
+    pub wasm_bytes: Option<i64>,
+    pub log_bytes: Option<i64>,
+    /// When the agent measured the sizes, which may predate `timestamp`
+    pub measured_at: Option<DateTime<Utc>>,
+}
+
+/// Where log monitoring last stopped reading one of a node's log files
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogCursorRecord {
+    pub node_name: String,
+    pub file_path: String,
+    pub inode: i64,
+    pub offset: i64,
+    pub fingerprint: String,
+    /// Position in the systemd journal for journald log sources
+    pub journal_cursor: Option<String>,
+    pub updated_at: DateTime<Utc>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MaintenanceOperation {
     pub id: String,
@@ -1,25 +1,24 @@
 //! Auto-restore trigger monitoring
 
+use super::log_monitor::search_node_log;
 use super::types::{AutoRestoreCooldown, HealthStatus};
-use anyhow::{anyhow, Result};
+use anyhow::Result;
 use chrono::Utc;
 use futures::future::join_all;
-use reqwest::Client as HttpClient;
 use std::collections::HashMap;
 use std::sync::Arc;
-use std::time::Duration;
 use tokio::sync::Mutex;
-use tokio::time::timeout;
 use tracing::{debug, error, info, warn};
 
-use crate::config::{Config, ServerConfig};
+use crate::config::{Config, LogSource, ServerConfig};
+use crate::http::AgentClients;
 use crate::services::alert_service::AlertService;
 use crate::snapshot::SnapshotManager;
 
 /// Monitor auto-restore triggers for unhealthy nodes
 pub async fn monitor_auto_restore_triggers(
     config: &Config,
-    client: &HttpClient,
+    agent_clients: &Arc<AgentClients>,
     health_statuses: &[HealthStatus],
     auto_restore_cooldowns: &Arc<Mutex<HashMap<String, AutoRestoreCooldown>>>,
     auto_restore_checked_states: &Arc<Mutex<HashMap<String, bool>>>,
@@ -66,8 +65,8 @@ pub async fn monitor_auto_restore_triggers(
             continue;
         }
 
-        let log_path = match &node_config.log_path {
-            Some(path) => path,
+        let log_source = match node_config.log_source() {
+            Some(source) => source,
             None => continue,
         };
 
@@ -78,9 +77,8 @@ pub async fn monitor_auto_restore_triggers(
 
         let node_name = status.node_name.clone();
         let server_host = status.server_host.clone();
-        let log_path = log_path.clone();
         let trigger_words = trigger_words.clone();
-        let client = client.clone();
+        let agent_clients = Arc::clone(agent_clients);
         let auto_restore_cooldowns = Arc::clone(auto_restore_cooldowns);
         let auto_restore_checked_states = Arc::clone(auto_restore_checked_states);
         let snapshot_manager = snapshot_manager.clone();
@@ -88,11 +86,11 @@ pub async fn monitor_auto_restore_triggers(
 
         let task = tokio::spawn(async move {
             check_auto_restore_triggers_for_node(
-                &client,
+                &agent_clients,
                 &node_name,
                 &server_host,
                 &server_config,
-                &log_path,
+                &log_source,
                 &trigger_words,
                 &auto_restore_cooldowns,
                 &auto_restore_checked_states,
@@ -134,11 +132,11 @@ async fn mark_auto_restore_checked(
 
 #[allow(clippy::too_many_arguments)]
 async fn check_auto_restore_triggers_for_node(
-    client: &HttpClient,
+    agent_clients: &AgentClients,
     node_name: &str,
     server_host: &str,
     server_config: &ServerConfig,
-    log_path: &str,
+    log_source: &LogSource,
     trigger_words: &[String],
     auto_restore_cooldowns: &Arc<Mutex<HashMap<String, AutoRestoreCooldown>>>,
     auto_restore_checked_states: &Arc<Mutex<HashMap<String, bool>>>,
@@ -151,20 +149,24 @@ async fn check_auto_restore_triggers_for_node(
         return Ok(());
     }
 
-    let log_file = format!("{}/out1.log", log_path);
-    let command = format!(
-        "tail -n 500 '{}' | grep -q -E '{}'",
-        log_file,
-        trigger_words.join("|")
-    );
-
     mark_auto_restore_checked(node_name, auto_restore_checked_states).await;
 
-    match execute_log_command(client, server_config, &command).await {
-        Ok(_) => {
+    match search_node_log(
+        agent_clients,
+        server_config,
+        log_source,
+        trigger_words,
+        500,
+        0,
+        None,
+    )
+    .await
+    {
+        Ok(result) if result.matched => {
             warn!(
-                "Auto-restore trigger words found in {} log file: {}",
-                node_name, log_file
+                "Auto-restore trigger words found in {} log: {}",
+                node_name,
+                log_source.display_name()
             );
             // execute_auto_restore handles all alerting internally
             if let Err(e) = execute_auto_restore(
@@ -182,9 +184,12 @@ async fn check_auto_restore_triggers_for_node(
                 info!("Auto-restore completed successfully for {}", node_name);
             }
         }
-        Err(_) => {
+        Ok(_) => {
             debug!("No auto-restore trigger words found for {}", node_name);
         }
+        Err(e) => {
+            debug!("Auto-restore log check for {} failed: {}", node_name, e);
+        }
     }
 
     Ok(())
@@ -246,7 +251,10 @@ async fn execute_auto_restore(
         .alert_auto_restore_started(node_name, server_host, trigger_words)
         .await?;
 
-    match snapshot_manager.restore_from_snapshot(node_name).await {
+    match snapshot_manager
+        .restore_from_snapshot(node_name, false)
+        .await
+    {
         Ok(snapshot_info) => {
             info!(
                 "Auto-restore completed for {} using snapshot: {}",
@@ -265,6 +273,14 @@ async fn execute_auto_restore(
         }
         Err(e) => {
             error!("Auto-restore failed for {}: {}", node_name, e);
+            alert_service
+                .alert_if_insufficient_disk_space(
+                    "auto_restore",
+                    node_name,
+                    server_host,
+                    &e.to_string(),
+                )
+                .await;
             // Alert: auto-restore failed
             alert_service
                 .alert_auto_restore_failed(node_name, server_host, &e.to_string(), trigger_words)
@@ -274,49 +290,6 @@ async fn execute_auto_restore(
     }
 }
 
-async fn execute_log_command(
-    client: &HttpClient,
-    server_config: &ServerConfig,
-    command: &str,
-) -> Result<String> {
-    let agent_url = format!(
-        "http://{}:{}/command/execute",
-        server_config.host, server_config.agent_port
-    );
-
-    let payload = serde_json::json!({
-        "command": command
-    });
-
-    let response = timeout(
-        Duration::from_secs(server_config.request_timeout_seconds),
-        client
-            .post(&agent_url)
-            .header("Authorization", format!("Bearer {}", server_config.api_key))
-            .json(&payload)
-            .send(),
-    )
-    .await
-    .map_err(|_| anyhow!("Log command timeout"))?
-    .map_err(|e| anyhow!("Log command request failed: {}", e))?;
-
-    if !response.status().is_success() {
-        return Err(anyhow!(
-            "Log command returned status: {}",
-            response.status()
-        ));
-    }
-
-    let result: serde_json::Value = response.json().await?;
-    let output = result
-        .get("output")
-        .and_then(|v| v.as_str())
-        .unwrap_or("")
-        .to_string();
-
-    Ok(output)
-}
-
 /// Clear auto-restore checked state for a node (called when node becomes healthy)
 pub async fn clear_auto_restore_checked_state(
     node_name: &str,
@@ -0,0 +1,482 @@
+//! Disk usage alerts from agent telemetry
+//!
+//! Each filesystem an agent reports is compared against the warning and
+//! critical usage thresholds, and its fill rate over the recent telemetry
+//! history is used to predict when it will be full. Alerts are sent when a
+//! filesystem changes level; when one turns critical and automatic cleanup is
+//! enabled, nodes on it are pruned or their old snapshots removed. A node
+//! whose deploy path is on none of the reported filesystems is alerted on too,
+//! since its disk would otherwise go unwatched.
+
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use tokio::sync::Mutex;
+use tracing::{debug, error, info, warn};
+
+use crate::config::Config;
+use crate::constants::{alerts, defaults};
+use crate::database::Database;
+use crate::http::agent_manager::AgentFilesystemUsage;
+use crate::services::alert_service::{AlertService, AlertSeverity, AlertType};
+use crate::services::MaintenanceService;
+
+const BYTES_PER_GB: f64 = 1024.0 * 1024.0 * 1024.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DiskThresholds {
+    pub warning_percent: f64,
+    pub critical_percent: f64,
+    pub full_alert_hours: f64,
+}
+
+impl DiskThresholds {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            warning_percent: config
+                .disk_warning_percent
+                .unwrap_or(defaults::DISK_WARNING_PERCENT),
+            critical_percent: config
+                .disk_critical_percent
+                .unwrap_or(defaults::DISK_CRITICAL_PERCENT),
+            full_alert_hours: config
+                .disk_full_alert_hours
+                .unwrap_or(defaults::DISK_FULL_ALERT_HOURS),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiskLevel {
+    #[default]
+    Ok,
+    Warning,
+    Critical,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DiskAssessment {
+    pub usage_percent: f64,
+    /// Growth of used space over the history window; negative when shrinking
+    pub fill_rate_bytes_per_hour: Option<f64>,
+    pub hours_until_full: Option<f64>,
+    pub level: DiskLevel,
+}
+
+/// Used share of the space available to unprivileged users, as `df` reports it
+pub fn usage_percent(filesystem: &AgentFilesystemUsage) -> f64 {
+    let usable = filesystem.used_bytes + filesystem.available_bytes;
+    if usable == 0 {
+        return 0.0;
+    }
+    filesystem.used_bytes as f64 / usable as f64 * 100.0
+}
+
+/// Least-squares slope of used bytes over time, in bytes per hour. `None`
+/// until the samples span at least `DISK_FILL_RATE_MIN_SPAN_HOURS`.
+pub fn fill_rate(samples: &[(DateTime<Utc>, u64)]) -> Option<f64> {
+    let (first, _) = samples.first()?;
+    let points: Vec<(f64, f64)> = samples
+        .iter()
+        .map(|(timestamp, used)| {
+            let hours = (*timestamp - *first).num_milliseconds() as f64 / 3_600_000.0;
+            (hours, *used as f64)
+        })
+        .collect();
+
+    let span = points.iter().map(|(hours, _)| *hours).fold(0.0, f64::max);
+    if points.len() < 2 || span < alerts::DISK_FILL_RATE_MIN_SPAN_HOURS {
+        return None;
+    }
+
+    let n = points.len() as f64;
+    let mean_hours = points.iter().map(|(hours, _)| hours).sum::<f64>() / n;
+    let mean_used = points.iter().map(|(_, used)| used).sum::<f64>() / n;
+    let (covariance, variance) =
+        points
+            .iter()
+            .fold((0.0, 0.0), |(covariance, variance), (hours, used)| {
+                let dx = hours - mean_hours;
+                (covariance + dx * (used - mean_used), variance + dx * dx)
+            });
+    (variance > 0.0).then(|| covariance / variance)
+}
+
+/// Hours until `available_bytes` are used up at `bytes_per_hour`
+pub fn hours_until_full(available_bytes: u64, bytes_per_hour: f64) -> Option<f64> {
+    (bytes_per_hour > 0.0).then(|| available_bytes as f64 / bytes_per_hour)
+}
+
+/// Level of a filesystem given its current usage and `history` of
+/// `(timestamp, used_bytes)` samples, oldest first
+pub fn assess(
+    filesystem: &AgentFilesystemUsage,
+    history: &[(DateTime<Utc>, u64)],
+    thresholds: &DiskThresholds,
+) -> DiskAssessment {
+    let usage_percent = usage_percent(filesystem);
+    let fill_rate_bytes_per_hour = fill_rate(history);
+    let hours_until_full = fill_rate_bytes_per_hour
+        .and_then(|rate| hours_until_full(filesystem.available_bytes, rate));
+
+    let level = if usage_percent >= thresholds.critical_percent {
+        DiskLevel::Critical
+    } else if usage_percent >= thresholds.warning_percent
+        || hours_until_full.is_some_and(|hours| hours <= thresholds.full_alert_hours)
+    {
+        DiskLevel::Warning
+    } else {
+        DiskLevel::Ok
+    };
+
+    DiskAssessment {
+        usage_percent,
+        fill_rate_bytes_per_hour,
+        hours_until_full,
+        level,
+    }
+}
+
+/// Alert on filesystems whose level changed since the previous check and run
+/// the configured cleanup on the ones that turned critical
+pub async fn check_disk_usage(
+    config: &Config,
+    database: &Database,
+    alert_service: &AlertService,
+    maintenance_service: &MaintenanceService,
+    levels: &Mutex<HashMap<String, DiskLevel>>,
+) {
+    let thresholds = DiskThresholds::from_config(config);
+    let servers: HashSet<&str> = config
+        .nodes
+        .values()
+        .filter(|node| node.enabled)
+        .map(|node| node.server_host.as_str())
+        .collect();
+    let since = Utc::now() - Duration::hours(alerts::DISK_FILL_RATE_WINDOW_HOURS);
+
+    for server_name in servers {
+        let records = match database
+            .get_server_telemetry_records_since(server_name, since)
+            .await
+        {
+            Ok(records) => records,
+            Err(e) => {
+                error!("Failed to load telemetry for {}: {}", server_name, e);
+                continue;
+            }
+        };
+
+        let mut history: HashMap<String, Vec<(DateTime<Utc>, u64)>> = HashMap::new();
+        let mut latest = Vec::new();
+        for record in &records {
+            let filesystems: Vec<AgentFilesystemUsage> =
+                match serde_json::from_str(&record.filesystems) {
+                    Ok(filesystems) => filesystems,
+                    Err(e) => {
+                        warn!(
+                            "Skipping unreadable telemetry record for {}: {}",
+                            server_name, e
+                        );
+                        continue;
+                    }
+                };
+            for filesystem in &filesystems {
+                history
+                    .entry(filesystem.root.clone())
+                    .or_default()
+                    .push((record.timestamp, filesystem.used_bytes));
+            }
+            latest = filesystems;
+        }
+        if records.is_empty() {
+            continue;
+        }
+
+        alert_on_unmonitored_nodes(config, alert_service, levels, server_name, &latest).await;
+
+        for filesystem in &latest {
+            let samples = history.get(&filesystem.root).map_or(&[][..], Vec::as_slice);
+            let assessment = assess(filesystem, samples, &thresholds);
+            debug!(
+                "Disk {} on {}: {:.1}% used, level {:?}",
+                filesystem.root, server_name, assessment.usage_percent, assessment.level
+            );
+
+            let key = format!("{}:{}", server_name, filesystem.root);
+            let previous = levels
+                .lock()
+                .await
+                .insert(key, assessment.level)
+                .unwrap_or_default();
+            if previous == assessment.level {
+                continue;
+            }
+
+            if let Err(e) = send_disk_alert(
+                alert_service,
+                server_name,
+                filesystem,
+                &assessment,
+                &thresholds,
+            )
+            .await
+            {
+                error!("Failed to send disk alert for {}: {}", server_name, e);
+            }
+
+            if assessment.level == DiskLevel::Critical
+                && config.disk_auto_cleanup_enabled.unwrap_or(false)
+            {
+                run_cleanup(
+                    config,
+                    maintenance_service,
+                    server_name,
+                    &latest,
+                    filesystem,
+                )
+                .await;
+            }
+        }
+    }
+}
+
+/// Alert when a node's deploy path stops (or starts again) being on one of
+/// the filesystems its agent reports
+async fn alert_on_unmonitored_nodes(
+    config: &Config,
+    alert_service: &AlertService,
+    levels: &Mutex<HashMap<String, DiskLevel>>,
+    server_name: &str,
+    latest: &[AgentFilesystemUsage],
+) {
+    let uncovered = uncovered_nodes(config, server_name, latest);
+    for (node_name, node) in config
+        .nodes
+        .iter()
+        .filter(|(_, node)| node.enabled && node.server_host == server_name)
+    {
+        let Some(deploy_path) = &node.deploy_path else {
+            continue;
+        };
+        let level = if uncovered.contains(node_name) {
+            DiskLevel::Warning
+        } else {
+            DiskLevel::Ok
+        };
+        let key = format!("{}:node:{}", server_name, node_name);
+        let previous = levels.lock().await.insert(key, level).unwrap_or_default();
+        if previous == level {
+            continue;
+        }
+
+        let (severity, message) = if level == DiskLevel::Warning {
+            warn!(
+                "No filesystem reported for {} of {} on {}",
+                deploy_path, node_name, server_name
+            );
+            (
+                AlertSeverity::Warning,
+                format!(
+                    "Disk usage of {} ({}) on {} is not monitored: the agent reports no filesystem holding it",
+                    node_name, deploy_path, server_name
+                ),
+            )
+        } else {
+            (
+                AlertSeverity::Recovery,
+                format!(
+                    "Disk usage of {} ({}) on {} is monitored again",
+                    node_name, deploy_path, server_name
+                ),
+            )
+        };
+        if let Err(e) = alert_service
+            .send_immediate_alert(
+                AlertType::DiskSpace,
+                severity,
+                node_name,
+                server_name,
+                message,
+                Some(serde_json::json!({ "deploy_path": deploy_path })),
+            )
+            .await
+        {
+            error!("Failed to send disk alert for {}: {}", node_name, e);
+        }
+    }
+}
+
+async fn send_disk_alert(
+    alert_service: &AlertService,
+    server_name: &str,
+    filesystem: &AgentFilesystemUsage,
+    assessment: &DiskAssessment,
+    thresholds: &DiskThresholds,
+) -> anyhow::Result<()> {
+    let (severity, message) = match assessment.level {
+        DiskLevel::Ok => (
+            AlertSeverity::Recovery,
+            format!(
+                "Disk usage of {} root {} on {} is back to normal ({:.1}%)",
+                filesystem.kind, filesystem.root, server_name, assessment.usage_percent
+            ),
+        ),
+        DiskLevel::Warning | DiskLevel::Critical => {
+            let mut message = format!(
+                "Disk usage of {} root {} on {} is {:.1}%",
+                filesystem.kind, filesystem.root, server_name, assessment.usage_percent
+            );
+            if let (Some(hours), Some(rate)) = (
+                assessment.hours_until_full,
+                assessment.fill_rate_bytes_per_hour,
+            ) {
+                message.push_str(&format!(
+                    " - disk full in {:.0} hours at {:.2} GB/hour",
+                    hours,
+                    rate / BYTES_PER_GB
+                ));
+            }
+            let severity = if assessment.level == DiskLevel::Critical {
+                AlertSeverity::Critical
+            } else {
+                AlertSeverity::Warning
+            };
+            (severity, message)
+        }
+    };
+
+    alert_service
+        .send_immediate_alert(
+            AlertType::DiskSpace,
+            severity,
+            server_name,
+            server_name,
+            message,
+            Some(serde_json::json!({
+                "kind": filesystem.kind,
+                "root": filesystem.root,
+                "level": assessment.level,
+                "usage_percent": assessment.usage_percent,
+                "total_bytes": filesystem.total_bytes,
+                "used_bytes": filesystem.used_bytes,
+                "available_bytes": filesystem.available_bytes,
+                "fill_rate_bytes_per_hour": assessment.fill_rate_bytes_per_hour,
+                "hours_until_full": assessment.hours_until_full,
+                "warning_percent": thresholds.warning_percent,
+                "critical_percent": thresholds.critical_percent,
+            })),
+        )
+        .await
+}
+
+/// The one of `filesystems` that `path` is on: the one with the deepest of
+/// its reported paths above `path`, so nested mounts resolve to their own device
+pub fn filesystem_holding<'a>(
+    filesystems: &'a [AgentFilesystemUsage],
+    path: &str,
+) -> Option<&'a AgentFilesystemUsage> {
+    filesystems
+        .iter()
+        .filter_map(|filesystem| Some((filesystem.matched_depth(path)?, filesystem)))
+        .max_by_key(|(depth, _)| *depth)
+        .map(|(_, filesystem)| filesystem)
+}
+
+/// Enabled nodes of `server_name` with a deploy path that none of
+/// `filesystems` holds, sorted by name
+pub fn uncovered_nodes(
+    config: &Config,
+    server_name: &str,
+    filesystems: &[AgentFilesystemUsage],
+) -> Vec<String> {
+    let mut nodes: Vec<String> = config
+        .nodes
+        .iter()
+        .filter(|(_, node)| node.enabled && node.server_host == server_name)
+        .filter(|(_, node)| {
+            node.deploy_path
+                .as_deref()
+                .is_some_and(|path| filesystem_holding(filesystems, path).is_none())
+        })
+        .map(|(name, _)| name.clone())
+        .collect();
+    nodes.sort();
+    nodes
+}
+
+/// Operations that free space on `filesystem`, one of the `filesystems` of
+/// `server_name`, as `(operation_type, node_name)`: pruning for nodes deployed
+/// on it, snapshot cleanup (once per network) for nodes whose snapshots are
+/// stored on it
+pub fn cleanup_operations(
+    config: &Config,
+    server_name: &str,
+    filesystems: &[AgentFilesystemUsage],
+    filesystem: &AgentFilesystemUsage,
+) -> Vec<(&'static str, String)> {
+    let mut nodes: Vec<_> = config
+        .nodes
+        .iter()
+        .filter(|(_, node)| node.enabled && node.server_host == server_name)
+        .collect();
+    nodes.sort_by_key(|(name, _)| name.as_str());
+
+    let on_filesystem = |path: &Option<String>| {
+        path.as_deref()
+            .and_then(|path| filesystem_holding(filesystems, path))
+            .is_some_and(|holding| holding.root == filesystem.root)
+    };
+
+    let mut operations = Vec::new();
+    let mut networks = HashSet::new();
+    for (node_name, node) in &nodes {
+        if node.pruning_enabled.unwrap_or(false) && on_filesystem(&node.deploy_path) {
+            operations.push(("pruning", node_name.to_string()));
+        }
+    }
+    for (node_name, node) in &nodes {
+        if node.snapshots_enabled.unwrap_or(false)
+            && node.snapshot_retention_count.is_some()
+            && on_filesystem(&node.snapshot_backup_path)
+            && networks.insert(node.network.as_str())
+        {
+            operations.push(("snapshot_cleanup", node_name.to_string()));
+        }
+    }
+    operations
+}
+
+async fn run_cleanup(
+    config: &Config,
+    maintenance_service: &MaintenanceService,
+    server_name: &str,
+    filesystems: &[AgentFilesystemUsage],
+    filesystem: &AgentFilesystemUsage,
+) {
+    let operations = cleanup_operations(config, server_name, filesystems, filesystem);
+    if operations.is_empty() {
+        info!(
+            "No cleanup available for {} root {} on {}",
+            filesystem.kind, filesystem.root, server_name
+        );
+        return;
+    }
+
+    for (operation_type, node_name) in operations {
+        info!(
+            "Disk {} on {} is critical - starting {} for {}",
+            filesystem.root, server_name, operation_type, node_name
+        );
+        if let Err(e) = maintenance_service
+            .execute_immediate_operation(operation_type, &node_name)
+            .await
+        {
+            error!(
+                "Failed to start {} for {}: {}",
+                operation_type, node_name, e
+            );
+        }
+    }
+}
@@ -2,19 +2,27 @@
 
 use super::types::HealthStatus;
 use anyhow::{anyhow, Result};
+use chrono::Utc;
 use futures::future::join_all;
-use reqwest::Client as HttpClient;
+use std::collections::HashMap;
+use std::sync::Arc;
 use std::time::Duration;
 use tokio::time::timeout;
 use tracing::{debug, error, info};
 
-use crate::config::{Config, ServerConfig};
+use crate::config::{Config, LogSource, ServerConfig};
+use crate::database::{Database, LogCursorRecord};
+use crate::http::agent_manager::{AgentLogCursor, AgentLogMatch};
+use crate::http::signing::send_signed;
+use crate::http::AgentClients;
 use crate::services::alert_service::AlertService;
 
-/// Monitor logs for each healthy node
+/// Monitor logs for each healthy node, searching only what was appended
+/// since the cursors stored by the previous check
 pub async fn monitor_logs_per_node(
     config: &Config,
-    client: &HttpClient,
+    database: &Arc<Database>,
+    agent_clients: &Arc<AgentClients>,
     health_statuses: &[&HealthStatus],
     alert_service: &AlertService,
 ) -> Result<()> {
@@ -47,10 +55,10 @@ pub async fn monitor_logs_per_node(
             }
         };
 
-        let log_path = match &node_config.log_path {
-            Some(path) => path,
+        let log_source = match node_config.log_source() {
+            Some(source) => source,
             None => {
-                debug!("No log path configured for node: {}", status.node_name);
+                debug!("No log source configured for node: {}", status.node_name);
                 continue;
             }
         };
@@ -62,19 +70,20 @@ pub async fn monitor_logs_per_node(
 
         let node_name = status.node_name.clone();
         let server_host = status.server_host.clone();
-        let log_path = log_path.clone();
         let patterns = patterns.clone();
-        let client = client.clone();
+        let database = Arc::clone(database);
+        let agent_clients = Arc::clone(agent_clients);
         let alert_service = alert_service.clone();
         let context_lines = context_lines_value;
 
         let task = tokio::spawn(async move {
             check_node_logs(
-                &client,
+                &database,
+                &agent_clients,
                 &node_name,
                 &server_host,
                 &server_config,
-                &log_path,
+                &log_source,
                 &patterns,
                 context_lines,
                 &alert_service,
@@ -107,36 +116,90 @@ pub async fn monitor_logs_per_node(
 
 #[allow(clippy::too_many_arguments)]
 async fn check_node_logs(
-    client: &HttpClient,
+    database: &Database,
+    agent_clients: &AgentClients,
     node_name: &str,
     server_host: &str,
     server_config: &ServerConfig,
-    log_path: &str,
+    log_source: &LogSource,
     patterns: &[String],
     context_lines: i32,
     alert_service: &AlertService,
 ) -> Result<()> {
-    let command = format!(
-        "tail -n 5000 {}/out1.log | grep -n -A {} -B {} -E '{}'",
-        log_path,
-        context_lines,
-        context_lines,
-        patterns.join("|")
-    );
-
     debug!("Checking log patterns for {}: {:?}", node_name, patterns);
 
-    match execute_log_command(client, server_config, &command).await {
-        Ok(output) => {
-            if !output.trim().is_empty() {
+    let cursors: HashMap<String, AgentLogCursor> = match database.get_log_cursors(node_name).await {
+        Ok(records) => records
+            .into_iter()
+            .map(|record| {
+                (
+                    record.file_path,
+                    AgentLogCursor {
+                        inode: record.inode as u64,
+                        offset: record.offset as u64,
+                        fingerprint: record.fingerprint,
+                        journal_cursor: record.journal_cursor,
+                    },
+                )
+            })
+            .collect(),
+        Err(e) => {
+            error!("Failed to load log cursors for {}: {}", node_name, e);
+            HashMap::new()
+        }
+    };
+
+    match search_node_log(
+        agent_clients,
+        server_config,
+        log_source,
+        patterns,
+        5000,
+        context_lines.max(0) as usize,
+        Some(&cursors),
+    )
+    .await
+    {
+        Ok(result) => {
+            if result.skipped_bytes > 0 {
+                info!(
+                    "Log of {} grew by more than the search window, {} bytes were not searched",
+                    node_name, result.skipped_bytes
+                );
+            }
+
+            if result.matched {
                 info!("Log patterns detected for {}, sending alert", node_name);
                 // Alert: log pattern match
                 alert_service
-                    .alert_log_pattern_match(node_name, server_host, log_path, &output, patterns)
+                    .alert_log_pattern_match(
+                        node_name,
+                        server_host,
+                        &log_source.display_name(),
+                        &result.output,
+                        patterns,
+                        &result.matches,
+                    )
                     .await?;
             } else {
                 debug!("No log patterns found for {}", node_name);
             }
+
+            // Only advance once the matches up to the new position are alerted
+            for (file_path, cursor) in result.cursors {
+                let record = LogCursorRecord {
+                    node_name: node_name.to_string(),
+                    file_path,
+                    inode: cursor.inode as i64,
+                    offset: cursor.offset as i64,
+                    fingerprint: cursor.fingerprint,
+                    journal_cursor: cursor.journal_cursor,
+                    updated_at: Utc::now(),
+                };
+                if let Err(e) = database.upsert_log_cursor(&record).await {
+                    error!("Failed to store log cursor for {}: {}", node_name, e);
+                }
+            }
         }
         Err(e) => {
             debug!("Log monitoring for {} failed: {}", node_name, e);
@@ -146,45 +209,81 @@ async fn check_node_logs(
     Ok(())
 }
 
-async fn execute_log_command(
-    client: &HttpClient,
+/// Result of an agent `/logs/search` request
+#[derive(Debug, serde::Deserialize)]
+pub(super) struct LogSearchResult {
+    pub matched: bool,
+    /// Matching lines with context, in `grep -n` format
+    pub output: String,
+    /// Agents predating structured search only return `output`
+    #[serde(default)]
+    pub matches: Vec<AgentLogMatch>,
+    /// Where the next incremental search of each file should resume
+    #[serde(default)]
+    pub cursors: HashMap<String, AgentLogCursor>,
+    /// Bytes appended since the cursor that were beyond the search window
+    #[serde(default)]
+    pub skipped_bytes: u64,
+}
+
+/// Search the last `tail_lines` lines of a node's log on the agent for any
+/// of `patterns`, or only the lines after the matching entry of `cursors`
+pub(super) async fn search_node_log(
+    agent_clients: &AgentClients,
     server_config: &ServerConfig,
-    command: &str,
-) -> Result<String> {
-    let agent_url = format!(
-        "http://{}:{}/command/execute",
-        server_config.host, server_config.agent_port
-    );
+    log_source: &LogSource,
+    patterns: &[String],
+    tail_lines: usize,
+    context_lines: usize,
+    cursors: Option<&HashMap<String, AgentLogCursor>>,
+) -> Result<LogSearchResult> {
+    let agent_url = server_config.agent_url("/logs/search");
+    let client = agent_clients.get(server_config)?;
 
-    let payload = serde_json::json!({
-        "command": command
+    let mut payload = serde_json::json!({
+        "patterns": patterns,
+        "tail_lines": tail_lines,
+        "context_lines": context_lines,
     });
+    for (key, value) in log_source.agent_params() {
+        payload[key] = serde_json::json!(value);
+    }
+    if let Some(cursors) = cursors {
+        payload["cursors"] = serde_json::json!(cursors);
+    }
 
     let response = timeout(
         Duration::from_secs(server_config.request_timeout_seconds),
-        client
-            .post(&agent_url)
-            .header("Authorization", format!("Bearer {}", server_config.api_key))
-            .json(&payload)
-            .send(),
+        send_signed(
+            &client,
+            client.post(&agent_url).json(&payload),
+            &server_config.api_key,
+        ),
     )
     .await
-    .map_err(|_| anyhow!("Log command timeout"))?
-    .map_err(|e| anyhow!("Log command request failed: {}", e))?;
+    .map_err(|_| anyhow!("Log search timeout"))?
+    .map_err(|e| anyhow!("Log search request failed: {}", e))?;
 
     if !response.status().is_success() {
-        return Err(anyhow!(
-            "Log command returned status: {}",
-            response.status()
-        ));
+        return Err(anyhow!("Log search returned status: {}", response.status()));
     }
 
     let result: serde_json::Value = response.json().await?;
+    if !result
+        .get("success")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false)
+    {
+        let error = result
+            .get("error")
+            .and_then(|v| v.as_str())
+            .unwrap_or("unknown error");
+        return Err(anyhow!("Log search failed: {}", error));
+    }
+
     let output = result
         .get("output")
         .and_then(|v| v.as_str())
-        .unwrap_or("")
-        .to_string();
-
-    Ok(output)
+        .ok_or_else(|| anyhow!("Log search response has no output"))?;
+    serde_json::from_str(output).map_err(|e| anyhow!("Invalid log search response: {}", e))
 }
@@ -4,10 +4,12 @@
 
 mod auto_restore;
 mod cosmos;
+pub mod disk;
 mod log_monitor;
 pub mod monitor;
 mod solana;
+mod telemetry;
 pub mod types;
 
 pub use monitor::HealthMonitor;
-pub use types::{HealthStatus, HermesHealthStatus};
+pub use types::{HealthStatus, HermesHealthStatus, NodeResources};
@@ -4,15 +4,20 @@
 
 use super::auto_restore::{clear_auto_restore_checked_state, monitor_auto_restore_triggers};
 use super::cosmos::check_cosmos_node_health;
+use super::disk::{check_disk_usage, DiskLevel};
 use super::log_monitor::monitor_logs_per_node;
 use super::solana::{check_solana_node_health, is_solana_network};
-use super::types::{AutoRestoreCooldown, BlockHeightState, HealthStatus, HermesHealthStatus};
+use super::telemetry::{collect_resource_telemetry, latest_node_resources};
+use super::types::{
+    AutoRestoreCooldown, BlockHeightState, HealthStatus, HermesHealthStatus, NodeResources,
+};
 
 use crate::config::{Config, HermesConfig, NodeConfig};
 use crate::database::{Database, HealthRecord, HermesHealthRecord};
 use crate::http::HttpAgentManager;
 use crate::maintenance_tracker::MaintenanceTracker;
 use crate::services::alert_service::AlertService;
+use crate::services::MaintenanceService;
 use crate::snapshot::SnapshotManager;
 
 use anyhow::{anyhow, Result};
@@ -34,10 +39,12 @@ pub struct HealthMonitor {
     snapshot_manager: Arc<SnapshotManager>,
     alert_service: Arc<AlertService>,
     http_manager: Arc<HttpAgentManager>,
+    maintenance_service: Arc<MaintenanceService>,
     client: HttpClient,
     auto_restore_cooldowns: Arc<Mutex<HashMap<String, AutoRestoreCooldown>>>,
     block_height_states: Arc<Mutex<HashMap<String, BlockHeightState>>>,
     auto_restore_checked_states: Arc<Mutex<HashMap<String, bool>>>,
+    disk_levels: Arc<Mutex<HashMap<String, DiskLevel>>>,
 }
 
 impl HealthMonitor {
@@ -48,6 +55,7 @@ impl HealthMonitor {
         snapshot_manager: Arc<SnapshotManager>,
         alert_service: Arc<AlertService>,
         http_manager: Arc<HttpAgentManager>,
+        maintenance_service: Arc<MaintenanceService>,
     ) -> Self {
         let client = HttpClient::builder()
             .timeout(Duration::from_secs(config.rpc_timeout_seconds))
@@ -61,10 +69,12 @@ impl HealthMonitor {
             snapshot_manager,
             alert_service,
             http_manager,
+            maintenance_service,
             client,
             auto_restore_cooldowns: Arc::new(Mutex::new(HashMap::new())),
             block_height_states: Arc::new(Mutex::new(HashMap::new())),
             auto_restore_checked_states: Arc::new(Mutex::new(HashMap::new())),
+            disk_levels: Arc::new(Mutex::new(HashMap::new())),
         }
     }
 
@@ -145,6 +155,16 @@ impl HealthMonitor {
             }
         }
 
+        collect_resource_telemetry(&self.config, &self.http_manager, &self.database).await;
+        check_disk_usage(
+            &self.config,
+            &self.database,
+            &self.alert_service,
+            &self.maintenance_service,
+            &self.disk_levels,
+        )
+        .await;
+
         // Per-node log monitoring - only for healthy nodes NOT in maintenance
         let non_maintenance_statuses: Vec<_> = health_statuses
             .iter()
@@ -154,7 +174,8 @@ impl HealthMonitor {
         if !non_maintenance_statuses.is_empty() {
             if let Err(e) = monitor_logs_per_node(
                 &self.config,
-                &self.client,
+                &self.database,
+                &self.http_manager.agent_clients,
                 &non_maintenance_statuses,
                 &self.alert_service,
             )
@@ -174,7 +195,7 @@ impl HealthMonitor {
         if !non_maintenance_statuses.is_empty() {
             if let Err(e) = monitor_auto_restore_triggers(
                 &self.config,
-                &self.client,
+                &self.http_manager.agent_clients,
                 &non_maintenance_statuses,
                 &self.auto_restore_cooldowns,
                 &self.auto_restore_checked_states,
@@ -301,6 +322,16 @@ impl HealthMonitor {
         }
     }
 
+    /// Latest resource telemetry stored for a node and its server
+    pub async fn get_node_resources(&self, node_name: &str) -> Result<NodeResources> {
+        let node_config = self
+            .config
+            .nodes
+            .get(node_name)
+            .ok_or_else(|| anyhow!("Node {} not found in configuration", node_name))?;
+        latest_node_resources(&self.database, node_name, &node_config.server_host).await
+    }
+
     /// Get cached health status for all nodes (parallel database reads)
     pub async fn get_all_nodes_health_cached(&self) -> Result<Vec<HealthStatus>> {
         let mut tasks = Vec::new();
@@ -0,0 +1,120 @@
+//! Host and node resource telemetry collected from agents
+
+use anyhow::Result;
+use chrono::Utc;
+use futures::future::join_all;
+use std::collections::HashMap;
+use tracing::{debug, warn};
+
+use super::types::{HostResources, NodeDiskUsage, NodeResources};
+use crate::config::{Config, NodeConfig};
+use crate::database::{Database, NodeDiskUsageRecord, ServerTelemetryRecord};
+use crate::http::agent_manager::AgentTelemetryReport;
+use crate::http::HttpAgentManager;
+
+/// Fetch telemetry from every server with enabled nodes and store the readings
+pub async fn collect_resource_telemetry(
+    config: &Config,
+    http_manager: &HttpAgentManager,
+    database: &Database,
+) {
+    let mut nodes_by_server: HashMap<&str, Vec<(&str, &NodeConfig)>> = HashMap::new();
+    for (node_name, node_config) in &config.nodes {
+        if node_config.enabled {
+            nodes_by_server
+                .entry(node_config.server_host.as_str())
+                .or_default()
+                .push((node_name.as_str(), node_config));
+        }
+    }
+
+    let tasks = nodes_by_server
+        .into_iter()
+        .map(|(server_name, nodes)| async move {
+            let result = match http_manager
+                .fetch_server_telemetry(server_name, &nodes)
+                .await
+            {
+                Ok(report) => store_telemetry(database, server_name, &report).await,
+                Err(e) => Err(e),
+            };
+            if let Err(e) = result {
+                warn!("Resource telemetry for {} failed: {}", server_name, e);
+            }
+        });
+    join_all(tasks).await;
+}
+
+async fn store_telemetry(
+    database: &Database,
+    server_name: &str,
+    report: &AgentTelemetryReport,
+) -> Result<()> {
+    let timestamp = Utc::now();
+    let host = &report.host;
+    database
+        .store_server_telemetry_record(&ServerTelemetryRecord {
+            server_name: server_name.to_string(),
+            timestamp,
+            load_1m: host.load_average[0],
+            load_5m: host.load_average[1],
+            load_15m: host.load_average[2],
+            cpu_count: host.cpu_count as i64,
+            memory_total_bytes: host.memory_total_bytes as i64,
+            memory_available_bytes: host.memory_available_bytes as i64,
+            filesystems: serde_json::to_string(&host.filesystems)?,
+        })
+        .await?;
+
+    for usage in &report.nodes {
+        if let Some(error) = &usage.error {
+            debug!("Disk usage of {} incomplete: {}", usage.node_name, error);
+        }
+        database
+            .store_node_disk_usage_record(&NodeDiskUsageRecord {
+                node_name: usage.node_name.clone(),
+                timestamp,
+                data_bytes: usage.data_bytes.map(|b| b as i64),
+                wasm_bytes: usage.wasm_bytes.map(|b| b as i64),
+                log_bytes: usage.log_bytes.map(|b| b as i64),
+                measured_at: usage.measured_at,
+            })
+            .await?;
+    }
+    Ok(())
+}
+
+/// Latest stored readings for a node and its server
+pub async fn latest_node_resources(
+    database: &Database,
+    node_name: &str,
+    server_name: &str,
+) -> Result<NodeResources> {
+    let host = database
+        .get_latest_server_telemetry_record(server_name)
+        .await?
+        .map(|record| -> Result<HostResources> {
+            Ok(HostResources {
+                last_check: record.timestamp,
+                load_average: [record.load_1m, record.load_5m, record.load_15m],
+                cpu_count: record.cpu_count as u32,
+                memory_total_bytes: record.memory_total_bytes as u64,
+                memory_available_bytes: record.memory_available_bytes as u64,
+                filesystems: serde_json::from_str(&record.filesystems)?,
+            })
+        })
+        .transpose()?;
+
+    let disk_usage = database
+        .get_latest_node_disk_usage_record(node_name)
+        .await?
+        .map(|record| NodeDiskUsage {
+            last_check: record.timestamp,
+            measured_at: record.measured_at,
+            data_bytes: record.data_bytes.map(|b| b as u64),
+            wasm_bytes: record.wasm_bytes.map(|b| b as u64),
+            log_bytes: record.log_bytes.map(|b| b as u64),
+        });
+
+    Ok(NodeResources { host, disk_usage })
+}
@@ -3,6 +3,8 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
+use crate::http::agent_manager::AgentFilesystemUsage;
+
 /// Health status for a blockchain node
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HealthStatus {
@@ -37,6 +39,36 @@ pub struct HermesHealthStatus {
     pub in_maintenance: bool,
 }
 
+/// Latest resource readings for a node and the server it runs on
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NodeResources {
+    pub host: Option<HostResources>,
+    pub disk_usage: Option<NodeDiskUsage>,
+}
+
+/// CPU, memory and filesystem usage of a server
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HostResources {
+    pub last_check: DateTime<Utc>,
+    /// 1, 5 and 15 minute load averages
+    pub load_average: [f64; 3],
+    pub cpu_count: u32,
+    pub memory_total_bytes: u64,
+    pub memory_available_bytes: u64,
+    pub filesystems: Vec<AgentFilesystemUsage>,
+}
+
+/// Sizes of a node's `data`, `wasm` and log directories
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NodeDiskUsage {
+    pub last_check: DateTime<Utc>,
+    /// When the agent measured the sizes; large trees are re-measured less often
+    pub measured_at: Option<DateTime<Utc>>,
+    pub data_bytes: Option<u64>,
+    pub wasm_bytes: Option<u64>,
+    pub log_bytes: Option<u64>,
+}
+
 /// Cosmos SDK RPC response structure
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RpcResponse {
@@ -0,0 +1,89 @@
+//! HTTP clients for talking to agents
+//!
+//! Agents served over HTTPS may use a private CA and require a client
+//! certificate, so each distinct TLS configuration gets its own client. Clients
+//! are built on first use and reused afterwards.
+
+use anyhow::{anyhow, Context, Result};
+use reqwest::{Certificate, Client, Identity};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::config::{AgentScheme, AgentTlsConfig, ServerConfig};
+
+pub struct AgentClients {
+    timeout: Option<Duration>,
+    connect_timeout: Duration,
+    clients: Mutex<HashMap<AgentTlsConfig, Client>>,
+}
+
+impl AgentClients {
+    /// `timeout` bounds whole requests; pass `None` when callers apply their own
+    pub fn new(timeout: Option<Duration>, connect_timeout: Duration) -> Self {
+        Self {
+            timeout,
+            connect_timeout,
+            clients: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Client configured for the server's agent scheme, CA bundle and client certificate
+    pub fn get(&self, server_config: &ServerConfig) -> Result<Client> {
+        let mut clients = self.clients.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(client) = clients.get(&server_config.tls) {
+            return Ok(client.clone());
+        }
+
+        let client = self.build(&server_config.tls)?;
+        clients.insert(server_config.tls.clone(), client.clone());
+        Ok(client)
+    }
+
+    fn build(&self, tls: &AgentTlsConfig) -> Result<Client> {
+        let mut builder = Client::builder().connect_timeout(self.connect_timeout);
+        if let Some(timeout) = self.timeout {
+            builder = builder.timeout(timeout);
+        }
+
+        if tls.agent_scheme == AgentScheme::Https {
+            if let Some(ca_path) = &tls.ca_cert_path {
+                let pem = std::fs::read(ca_path)
+                    .with_context(|| format!("Failed to read CA bundle {}", ca_path))?;
+                for cert in Certificate::from_pem_bundle(&pem)
+                    .with_context(|| format!("Invalid CA bundle {}", ca_path))?
+                {
+                    builder = builder.add_root_certificate(cert);
+                }
+            }
+
+            match (&tls.client_cert_path, &tls.client_key_path) {
+                (Some(cert_path), Some(key_path)) => {
+                    let cert = std::fs::read(cert_path).with_context(|| {
+                        format!("Failed to read client certificate {}", cert_path)
+                    })?;
+                    let key = std::fs::read(key_path)
+                        .with_context(|| format!("Failed to read client key {}", key_path))?;
+                    let identity =
+                        Identity::from_pem(&[cert, key].concat()).with_context(|| {
+                            format!(
+                                "Invalid client certificate {} / key {}",
+                                cert_path, key_path
+                            )
+                        })?;
+                    builder = builder.identity(identity);
+                }
+                (None, None) => {}
+                _ => {
+                    return Err(anyhow!(
+                        "client_cert_path and client_key_path must be set together"
+                    ))
+                }
+            }
+        }
+
+        builder
+            .build()
+            .map_err(|e| anyhow!("Failed to create agent HTTP client: {}", e))
+    }
+}
@@ -8,11 +8,13 @@ use std::sync::Arc;
 use tokio::time::{sleep, Duration as TokioDuration};
 use tracing::{debug, error, info, instrument, warn};
 
-use crate::config::{Config, HermesConfig};
+use crate::config::{AgentScheme, Config, HermesConfig, LogSource, NodeConfig};
 use crate::constants::{http, operation_timeouts};
+use crate::http::signing::send_signed;
+use crate::http::AgentClients;
 use crate::maintenance_tracker::MaintenanceTracker;
 use crate::operation_tracker::SimpleOperationTracker;
-use crate::snapshot::SnapshotInfo;
+use crate::snapshot::{ArchiveStatus, SnapshotInfo};
 
 #[non_exhaustive]
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -29,10 +31,255 @@ impl ServiceStatus {
     }
 }
 
+/// Snapshot directory as listed by the agent's `/snapshot/list`
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct AgentSnapshotEntry {
+    pub name: String,
+    pub path: String,
+    pub modified_unix: i64,
+    pub has_data: bool,
+    pub has_wasm: bool,
+    /// Size of the complete `.tar.lz4` archive, if there is one
+    pub archive_size_bytes: Option<u64>,
+    #[serde(default)]
+    pub archive_in_progress: bool,
+}
+
+impl AgentSnapshotEntry {
+    /// Block height from the `{network}_{date}_{block_height}` name
+    pub fn block_height(&self) -> Option<u64> {
+        self.name.rsplit('_').next()?.parse().ok()
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct AgentArchiveEntry {
+    pub name: String,
+    pub size_bytes: u64,
+    pub modified_unix: i64,
+}
+
+impl AgentArchiveEntry {
+    /// Block height from the `{network}_{date}_{block_height}.tar.lz4` name
+    pub fn block_height(&self) -> Option<u64> {
+        let snapshot_name = self.name.strip_suffix(".tar.lz4")?;
+        snapshot_name.rsplit('_').next()?.parse().ok()
+    }
+}
+
+/// Where a restore reads the network snapshot from
+#[derive(Debug, Clone)]
+enum NetworkSnapshotSource {
+    Directory(String),
+    Archive(String),
+}
+
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct AgentSnapshotListing {
+    pub snapshots: Vec<AgentSnapshotEntry>,
+    pub archives: Vec<AgentArchiveEntry>,
+    /// Archives still being written, named `{snapshot}.tar.lz4.partial`
+    #[serde(default)]
+    pub partial_archives: Vec<AgentArchiveEntry>,
+    /// Unfinished archive pulls, named `{snapshot}.tar.lz4.download`
+    #[serde(default)]
+    pub downloads: Vec<AgentArchiveEntry>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct AgentSnapshotDeleteResult {
+    pub directory_removed: bool,
+    pub archive_removed: bool,
+}
+
+/// Result of re-hashing a snapshot against its manifest (`/snapshot/verify`)
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct AgentSnapshotVerifyReport {
+    pub verified: bool,
+    pub files_checked: u64,
+    pub bytes_checked: u64,
+    pub mismatched: Vec<String>,
+    pub missing: Vec<String>,
+    pub unexpected: Vec<String>,
+}
+
+/// Token authorizing one archive download from an agent (`/snapshot/archive/token`)
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct AgentArchiveDownloadToken {
+    pub token: String,
+    pub expires_at: i64,
+}
+
+/// Result of pulling an archive from another agent (`/snapshot/pull`)
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct AgentSnapshotTransferResult {
+    pub archive_path: String,
+    pub size_bytes: u64,
+    pub resumed_from_bytes: u64,
+    pub attempts: u32,
+    pub already_present: bool,
+}
+
+/// Host resources and node directory sizes reported by the agent's `/telemetry`
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct AgentTelemetryReport {
+    pub host: AgentHostTelemetry,
+    pub nodes: Vec<AgentNodeDiskUsage>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct AgentHostTelemetry {
+    /// 1, 5 and 15 minute load averages
+    pub load_average: [f64; 3],
+    pub cpu_count: usize,
+    pub memory_total_bytes: u64,
+    pub memory_available_bytes: u64,
+    pub filesystems: Vec<AgentFilesystemUsage>,
+}
+
+/// Usage of a filesystem holding the agent's roots or node paths, reported
+/// once per device
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct AgentFilesystemUsage {
+    pub kind: String,
+    pub root: String,
+    /// Every root and node path the agent found on the filesystem; older
+    /// agents report only `root`
+    #[serde(default)]
+    pub paths: Vec<String>,
+    pub total_bytes: u64,
+    pub used_bytes: u64,
+    pub available_bytes: u64,
+}
+
+impl AgentFilesystemUsage {
+    /// Components of the longest of the paths the agent found on this
+    /// filesystem that `path` lies below, if any. A path is on the filesystem
+    /// with the longest such match, since a deeper path may be a separate mount.
+    pub fn matched_depth(&self, path: &str) -> Option<usize> {
+        let path = std::path::Path::new(path);
+        self.paths
+            .iter()
+            .map(std::path::Path::new)
+            .filter(|held| path.starts_with(held))
+            .map(|held| held.components().count())
+            .max()
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct AgentNodeDiskUsage {
+    pub node_name: String,
+    pub data_bytes: Option<u64>,
+    pub wasm_bytes: Option<u64>,
+    pub log_bytes: Option<u64>,
+    pub measured_at: Option<chrono::DateTime<Utc>>,
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+/// A line matched by an agent `/logs/search` request
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct AgentLogMatch {
+    pub file: String,
+    /// 1-based line number within the searched tail of the file
+    pub line_number: usize,
+    pub line: String,
+    pub pattern: String,
+    pub context_before: Vec<String>,
+    pub context_after: Vec<String>,
+    pub timestamp: Option<chrono::DateTime<Utc>>,
+}
+
+/// Position in a log file, or in a unit's journal, up to which an agent has
+/// already searched
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct AgentLogCursor {
+    #[serde(default)]
+    pub inode: u64,
+    #[serde(default)]
+    pub offset: u64,
+    /// SHA-256 of the bytes before `offset`, capped at the first 256
+    #[serde(default)]
+    pub fingerprint: String,
+    /// Journal cursor of the last entry searched, for journald sources
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub journal_cursor: Option<String>,
+}
+
+/// How an agent archives a node's logs before clearing them, sent with
+/// maintenance operations of nodes whose `truncate_logs_mode` is "rotate"
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct AgentLogRotation {
+    pub compression: String,
+    pub keep_generations: Option<usize>,
+    pub keep_bytes: Option<u64>,
+    /// Files rotated in a "directory" log source
+    pub log_glob: Option<String>,
+}
+
+impl AgentLogRotation {
+    pub fn for_node(node_config: &NodeConfig) -> Option<Self> {
+        if node_config.truncate_logs_mode.as_deref() != Some("rotate") {
+            return None;
+        }
+        Some(Self {
+            compression: node_config
+                .log_rotation_compression
+                .clone()
+                .unwrap_or_else(|| "gzip".to_string()),
+            keep_generations: node_config.log_rotation_keep_generations,
+            keep_bytes: node_config.log_rotation_keep_bytes,
+            log_glob: match node_config.log_source() {
+                Some(LogSource::Directory { glob, .. }) => Some(glob),
+                _ => None,
+            },
+        })
+    }
+}
+
+/// Compressed copy of a log file kept by a rotation
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct AgentLogArchive {
+    pub name: String,
+    pub log_file: String,
+    pub size_bytes: u64,
+    pub rotated_at: chrono::DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct AgentLogArchiveListing {
+    pub archive_dir: String,
+    pub archives: Vec<AgentLogArchive>,
+}
+
+/// A key set in a node config file: a dotted path such as
+/// `consensus.timeout_commit`, or `minimum-gas-prices` at the top level
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct AgentConfigChange {
+    pub key: String,
+    pub value: Value,
+}
+
+/// Result of an agent `/config/patch` request
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct AgentConfigPatchResult {
+    pub config_path: String,
+    pub changed: bool,
+    /// Unified diff of the file, empty when unchanged
+    pub diff: String,
+    /// Copy of the original file, when the patch was written
+    #[serde(default)]
+    pub backup_path: Option<String>,
+}
+
 #[derive(Clone)]
 pub struct HttpAgentManager {
     pub config: Arc<Config>,
     pub client: Client,
+    pub agent_clients: Arc<AgentClients>,
+    /// Clients without a request timeout, for responses that stay open
+    pub stream_clients: Arc<AgentClients>,
     pub operation_tracker: Arc<SimpleOperationTracker>,
     pub maintenance_tracker: Arc<MaintenanceTracker>,
 }
@@ -49,9 +296,17 @@ impl HttpAgentManager {
             .build()
             .expect("Failed to create HTTP client for HttpAgentManager");
 
+        let agent_clients = Arc::new(AgentClients::new(
+            Some(http::REQUEST_TIMEOUT),
+            http::CONNECT_TIMEOUT,
+        ));
+        let stream_clients = Arc::new(AgentClients::new(None, http::CONNECT_TIMEOUT));
+
         Self {
             config,
             client,
+            agent_clients,
+            stream_clients,
             operation_tracker,
             maintenance_tracker,
         }
@@ -67,7 +322,7 @@ impl HttpAgentManager {
     /// 5. Ends operation tracking
     ///
     /// Returns the result of the operation, ensuring cleanup happens even on errors.
-    async fn with_operation_lifecycle<T, F, Fut>(
+    pub(crate) async fn with_operation_lifecycle<T, F, Fut>(
         &self,
         node_name: &str,
         operation_type: &str,
@@ -132,7 +387,12 @@ impl HttpAgentManager {
     fn is_long_running_operation(endpoint: &str) -> bool {
         matches!(
             endpoint,
-            "/pruning/execute" | "/snapshot/create" | "/snapshot/restore" | "/state-sync/execute"
+            "/pruning/execute"
+                | "/snapshot/create"
+                | "/snapshot/restore"
+                | "/snapshot/verify"
+                | "/snapshot/pull"
+                | "/state-sync/execute"
         )
     }
 
@@ -141,6 +401,19 @@ impl HttpAgentManager {
         server_name: &str,
         endpoint: &str,
         payload: Value,
+    ) -> Result<Value> {
+        self.execute_tracked_operation(None, server_name, endpoint, payload)
+            .await
+    }
+
+    /// Like `execute_operation`, but links the agent job of a long-running
+    /// operation to `target_name` in the operation tracker so it can be cancelled.
+    async fn execute_tracked_operation(
+        &self,
+        target_name: Option<&str>,
+        server_name: &str,
+        endpoint: &str,
+        payload: Value,
     ) -> Result<Value> {
         let server_config = self
             .config
@@ -148,21 +421,18 @@ impl HttpAgentManager {
             .get(server_name)
             .ok_or_else(|| anyhow::anyhow!("Server {} not found", server_name))?;
 
-        let agent_url = format!(
-            "http://{}:{}{}",
-            server_config.host, server_config.agent_port, endpoint
-        );
+        let agent_url = server_config.agent_url(endpoint);
+        let client = self.agent_clients.get(server_config)?;
 
         info!("Starting operation on {}: {}", server_name, endpoint);
 
-        let response = self
-            .client
-            .post(&agent_url)
-            .header("Authorization", format!("Bearer {}", server_config.api_key))
-            .json(&payload)
-            .send()
-            .await
-            .map_err(|e| anyhow::anyhow!("HTTP request failed on {}: {}", server_name, e))?;
+        let response = send_signed(
+            &client,
+            client.post(&agent_url).json(&payload),
+            &server_config.api_key,
+        )
+        .await
+        .map_err(|e| anyhow::anyhow!("HTTP request failed on {}: {}", server_name, e))?;
 
         if !response.status().is_success() {
             let status = response.status();
@@ -202,7 +472,14 @@ impl HttpAgentManager {
                     "Long operation started with job_id: {} on {}",
                     job_id, server_name
                 );
-                return self.poll_for_completion(server_name, job_id).await;
+                if let Some(target_name) = target_name {
+                    self.operation_tracker
+                        .attach_agent_job(target_name, server_name, job_id)
+                        .await;
+                }
+                return self
+                    .poll_for_completion(target_name, server_name, job_id)
+                    .await;
             } else {
                 warn!(
                     "Long operation endpoint {} did not return job_id, treating as synchronous",
@@ -214,17 +491,22 @@ impl HttpAgentManager {
         Ok(result)
     }
 
-    async fn poll_for_completion(&self, server_name: &str, job_id: &str) -> Result<Value> {
+    /// Poll an agent job until it finishes. While it runs, the step-level progress
+    /// the agent reports is stored on `target_name`'s tracked operation.
+    async fn poll_for_completion(
+        &self,
+        target_name: Option<&str>,
+        server_name: &str,
+        job_id: &str,
+    ) -> Result<Value> {
         let server_config = self
             .config
             .servers
             .get(server_name)
             .ok_or_else(|| anyhow::anyhow!("Server {} not found", server_name))?;
 
-        let status_url = format!(
-            "http://{}:{}/operation/status/{}",
-            server_config.host, server_config.agent_port, job_id
-        );
+        let status_url = server_config.agent_url(&format!("/operation/status/{}", job_id));
+        let client = self.agent_clients.get(server_config)?;
 
         const POLL_INTERVAL_SECONDS: u64 = 60;
         let mut poll_count = 0;
@@ -246,12 +528,8 @@ impl HttpAgentManager {
 
             tokio::task::yield_now().await;
 
-            let poll_result = self
-                .client
-                .get(&status_url)
-                .header("Authorization", format!("Bearer {}", server_config.api_key))
-                .send()
-                .await;
+            let poll_result =
+                send_signed(&client, client.get(&status_url), &server_config.api_key).await;
 
             match poll_result {
                 Ok(response) if response.status().is_success() => {
@@ -293,7 +571,38 @@ impl HttpAgentManager {
                                             error_msg
                                         ));
                                     }
+                                    "Cancelled" => {
+                                        let error_msg = status_result
+                                            .get("error")
+                                            .and_then(|v| v.as_str())
+                                            .unwrap_or("Cancelled by request");
+                                        warn!("Job {} cancelled: {}", job_id, error_msg);
+                                        return Err(anyhow::anyhow!(
+                                            "Job {} cancelled: {}",
+                                            job_id,
+                                            error_msg
+                                        ));
+                                    }
+                                    "Interrupted" => {
+                                        let error_msg = status_result
+                                            .get("error")
+                                            .and_then(|v| v.as_str())
+                                            .unwrap_or("Agent restarted during the operation");
+                                        error!("Job {} interrupted: {}", job_id, error_msg);
+                                        return Err(anyhow::anyhow!(
+                                            "Job {} interrupted: {}",
+                                            job_id,
+                                            error_msg
+                                        ));
+                                    }
                                     "Running" => {
+                                        if let (Some(target_name), Some(progress)) =
+                                            (target_name, status_result.get("progress"))
+                                        {
+                                            self.operation_tracker
+                                                .update_progress(target_name, progress.clone())
+                                                .await;
+                                        }
                                         debug!(
                                             "Job {} still running, sleeping {}s until next poll",
                                             job_id, POLL_INTERVAL_SECONDS
@@ -306,6 +615,15 @@ impl HttpAgentManager {
                                         );
                                     }
                                 }
+                            } else if let Some(error_msg) = status_result
+                                .get("error")
+                                .and_then(|v| v.as_str())
+                                .filter(|e| e.contains("not found"))
+                            {
+                                // The agent keeps a durable job journal, so an unknown
+                                // job will not reappear by polling longer
+                                error!("Job {} unknown to agent: {}", job_id, error_msg);
+                                return Err(anyhow::anyhow!("Job {} lost: {}", job_id, error_msg));
                             } else {
                                 warn!("No job_status in polling response");
                             }
@@ -397,19 +715,229 @@ impl HttpAgentManager {
         Ok(uptime_seconds)
     }
 
-    pub async fn execute_single_command(&self, server_name: &str, command: &str) -> Result<String> {
-        let payload = json!({"command": command});
-        let result = self
-            .execute_operation(server_name, "/command/execute", payload)
-            .await?;
-
+    /// Parse the JSON result an agent returns in the `output` field of a typed operation
+    fn parse_output<T: serde::de::DeserializeOwned>(result: &Value, endpoint: &str) -> Result<T> {
         let output = result
             .get("output")
             .and_then(|v| v.as_str())
-            .unwrap_or("")
-            .to_string();
+            .ok_or_else(|| anyhow::anyhow!("Agent response for {} has no output", endpoint))?;
+        serde_json::from_str(output)
+            .map_err(|e| anyhow::anyhow!("Invalid agent response for {}: {}", endpoint, e))
+    }
+
+    pub async fn list_agent_snapshots(
+        &self,
+        server_name: &str,
+        backup_path: &str,
+        network: &str,
+    ) -> Result<AgentSnapshotListing> {
+        let payload = json!({"backup_path": backup_path, "network": network});
+        let result = self
+            .execute_operation(server_name, "/snapshot/list", payload)
+            .await?;
+        Self::parse_output(&result, "/snapshot/list")
+    }
+
+    /// Token from a server's agent that lets another agent download one of its
+    /// snapshot archives
+    pub async fn issue_archive_download_token(
+        &self,
+        server_name: &str,
+        backup_path: &str,
+        snapshot_name: &str,
+    ) -> Result<AgentArchiveDownloadToken> {
+        let payload = json!({"backup_path": backup_path, "snapshot_name": snapshot_name});
+        let result = self
+            .execute_operation(server_name, "/snapshot/archive/token", payload)
+            .await?;
+        Self::parse_output(&result, "/snapshot/archive/token")
+    }
+
+    /// Delete a snapshot directory and its `.tar.lz4` archive, whichever exist
+    pub async fn delete_agent_snapshot(
+        &self,
+        server_name: &str,
+        backup_path: &str,
+        snapshot_name: &str,
+    ) -> Result<AgentSnapshotDeleteResult> {
+        let payload = json!({"backup_path": backup_path, "snapshot_name": snapshot_name});
+        let result = self
+            .execute_operation(server_name, "/snapshot/delete", payload)
+            .await?;
+        Self::parse_output(&result, "/snapshot/delete")
+    }
+
+    /// Host resources of a server plus directory sizes of the given nodes on it
+    pub async fn fetch_server_telemetry(
+        &self,
+        server_name: &str,
+        nodes: &[(&str, &NodeConfig)],
+    ) -> Result<AgentTelemetryReport> {
+        let nodes: Vec<Value> = nodes
+            .iter()
+            .map(|(node_name, node_config)| {
+                json!({
+                    "node_name": node_name,
+                    "deploy_path": node_config.deploy_path,
+                    "backup_path": node_config.snapshot_backup_path,
+                    "log_path": node_config.log_files_path(),
+                })
+            })
+            .collect();
+        let result = self
+            .execute_operation(server_name, "/telemetry", json!({ "nodes": nodes }))
+            .await?;
+        Self::parse_output(&result, "/telemetry")
+    }
+
+    /// Open a live stream of a node's log from its agent. The response is a
+    /// `text/event-stream` that stays open until it is dropped.
+    pub async fn open_log_stream(
+        &self,
+        node_name: &str,
+        filter: Option<&str>,
+        tail_lines: Option<usize>,
+    ) -> Result<reqwest::Response> {
+        let node_config = self
+            .config
+            .nodes
+            .get(node_name)
+            .ok_or_else(|| anyhow::anyhow!("Node {} not found", node_name))?;
+        let log_source = node_config
+            .log_source()
+            .ok_or_else(|| anyhow::anyhow!("No log source configured for node {}", node_name))?;
+
+        let mut query = log_source.agent_params();
+        if let Some(filter) = filter {
+            query.push(("filter", filter.to_string()));
+        }
+        if let Some(tail_lines) = tail_lines {
+            query.push(("tail_lines", tail_lines.to_string()));
+        }
+
+        let response = self
+            .open_agent_download(node_config, "/logs/stream", &query)
+            .await
+            .map_err(|e| anyhow::anyhow!("Log stream of {} failed: {}", node_name, e))?;
+        info!(
+            "Streaming log of {} from {}",
+            node_name, node_config.server_host
+        );
+        Ok(response)
+    }
+
+    /// Log archives kept by rotations of a node's log files, newest first
+    pub async fn list_log_archives(&self, node_name: &str) -> Result<AgentLogArchiveListing> {
+        let node_config = self
+            .config
+            .nodes
+            .get(node_name)
+            .ok_or_else(|| anyhow::anyhow!("Node {} not found", node_name))?;
+        let log_path = node_config
+            .log_files_path()
+            .ok_or_else(|| anyhow::anyhow!("Node {} has no log files", node_name))?;
+
+        let result = self
+            .execute_operation(
+                &node_config.server_host,
+                "/logs/archives",
+                json!({ "log_path": log_path }),
+            )
+            .await?;
+        Self::parse_output(&result, "/logs/archives")
+    }
+
+    /// Open the download of one of a node's log archives from its agent
+    pub async fn open_log_archive(
+        &self,
+        node_name: &str,
+        archive_name: &str,
+    ) -> Result<reqwest::Response> {
+        let node_config = self
+            .config
+            .nodes
+            .get(node_name)
+            .ok_or_else(|| anyhow::anyhow!("Node {} not found", node_name))?;
+        let log_path = node_config
+            .log_files_path()
+            .ok_or_else(|| anyhow::anyhow!("Node {} has no log files", node_name))?;
+
+        let query = [
+            ("log_path", log_path.to_string()),
+            ("name", archive_name.to_string()),
+        ];
+        self.open_agent_download(node_config, "/logs/archive", &query)
+            .await
+            .map_err(|e| {
+                anyhow::anyhow!(
+                    "Download of log archive {} of {} failed: {}",
+                    archive_name,
+                    node_name,
+                    e
+                )
+            })
+    }
+
+    /// Signed GET to a node's agent whose body is read as it arrives, without
+    /// the request timeout of regular operations
+    async fn open_agent_download(
+        &self,
+        node_config: &NodeConfig,
+        path: &str,
+        query: &[(&str, String)],
+    ) -> Result<reqwest::Response> {
+        let server_config = self
+            .config
+            .servers
+            .get(&node_config.server_host)
+            .ok_or_else(|| anyhow::anyhow!("Server {} not found", node_config.server_host))?;
+
+        let client = self.stream_clients.get(server_config)?;
+        let response = send_signed(
+            &client,
+            client.get(server_config.agent_url(path)).query(query),
+            &server_config.api_key,
+        )
+        .await
+        .map_err(|e| {
+            anyhow::anyhow!("HTTP request failed on {}: {}", node_config.server_host, e)
+        })?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(anyhow::anyhow!("status {}: {}", status, error_text.trim()));
+        }
+        Ok(response)
+    }
 
-        Ok(output)
+    /// Set keys in one of a node's `NODE_CONFIG_FILES`. With `dry_run` the
+    /// agent only reports the diff; otherwise it backs up and rewrites the file.
+    pub async fn patch_node_config(
+        &self,
+        node_name: &str,
+        file: &str,
+        changes: &[AgentConfigChange],
+        dry_run: bool,
+    ) -> Result<AgentConfigPatchResult> {
+        let node_config = self
+            .config
+            .nodes
+            .get(node_name)
+            .ok_or_else(|| anyhow::anyhow!("Node {} not found", node_name))?;
+        let config_path = node_config
+            .config_file_path(file)
+            .ok_or_else(|| anyhow::anyhow!("No deploy path configured for {}", node_name))?;
+
+        let payload = json!({
+            "config_path": config_path,
+            "changes": changes,
+            "dry_run": dry_run,
+        });
+        let result = self
+            .execute_operation(&node_config.server_host, "/config/patch", payload)
+            .await?;
+        Self::parse_output(&result, "/config/patch")
     }
 
     pub async fn delete_all_files_in_directory(
@@ -441,7 +969,9 @@ impl HttpAgentManager {
         .await
     }
 
-    async fn restart_node_impl(&self, node_name: &str) -> Result<()> {
+    /// Restart a node without reserving it, for callers that already hold it
+    /// through `with_operation_lifecycle`
+    pub(crate) async fn restart_node_impl(&self, node_name: &str) -> Result<()> {
         let node_config = self
             .config
             .nodes
@@ -523,11 +1053,17 @@ impl HttpAgentManager {
             "keep_blocks": keep_blocks,
             "keep_versions": keep_versions,
             "service_name": service_name,
-            "log_path": node_config.log_path
+            "log_path": node_config.log_files_path(),
+            "log_rotation": AgentLogRotation::for_node(node_config)
         });
 
         let result = self
-            .execute_operation(&node_config.server_host, "/pruning/execute", payload)
+            .execute_tracked_operation(
+                Some(node_name),
+                &node_config.server_host,
+                "/pruning/execute",
+                payload,
+            )
             .await;
 
         match result {
@@ -629,11 +1165,17 @@ impl HttpAgentManager {
             "trust_height": sync_params.trust_height,
             "trust_hash": sync_params.trust_hash,
             "timeout_seconds": max_sync_timeout,
-            "log_path": node_config.log_path,
+            "log_path": node_config.log_files_path(),
+            "log_rotation": AgentLogRotation::for_node(node_config),
         });
 
         let _result = self
-            .execute_operation(&node_config.server_host, "/state-sync/execute", payload)
+            .execute_tracked_operation(
+                Some(node_name),
+                &node_config.server_host,
+                "/state-sync/execute",
+                payload,
+            )
             .await?;
 
         info!("✓ State sync completed successfully for {}", node_name);
@@ -710,15 +1252,25 @@ impl HttpAgentManager {
             "deploy_path": deploy_path,
             "backup_path": backup_path,
             "service_name": service_name,
-            "log_path": node_config.log_path
+            "log_path": node_config.log_files_path(),
+            "log_rotation": AgentLogRotation::for_node(node_config)
         });
 
         let result = self
-            .execute_operation(&node_config.server_host, "/snapshot/create", payload)
+            .execute_tracked_operation(
+                Some(node_name),
+                &node_config.server_host,
+                "/snapshot/create",
+                payload,
+            )
             .await?;
 
         let operation_result = result.get("result").unwrap_or(&result);
 
+        let compression_job_id = operation_result["compression_job_id"]
+            .as_str()
+            .map(|id| id.to_string());
+
         let snapshot_info = SnapshotInfo {
             node_name: node_name.to_string(),
             network: network.clone(),
@@ -736,8 +1288,25 @@ impl HttpAgentManager {
                 .as_str()
                 .unwrap_or("directory")
                 .to_string(),
+            archive_status: Some(if compression_job_id.is_some() {
+                ArchiveStatus::InProgress
+            } else {
+                ArchiveStatus::Missing
+            }),
+            compression_job_id,
         };
 
+        match &snapshot_info.compression_job_id {
+            Some(job_id) => info!(
+                "Snapshot {} is being archived by agent job {}",
+                snapshot_info.filename, job_id
+            ),
+            None => warn!(
+                "Agent did not start archiving snapshot {}",
+                snapshot_info.filename
+            ),
+        }
+
         Ok(snapshot_info)
     }
 
@@ -807,7 +1376,14 @@ impl HttpAgentManager {
         Ok(())
     }
 
-    pub async fn restore_node_from_snapshot(&self, node_name: &str) -> Result<SnapshotInfo> {
+    /// Restore a node from the latest network snapshot. Unless `force` is set the
+    /// agent refuses snapshots that fail verification against their manifest;
+    /// snapshots without a manifest are restored unverified.
+    pub async fn restore_node_from_snapshot(
+        &self,
+        node_name: &str,
+        force: bool,
+    ) -> Result<SnapshotInfo> {
         let node_name_owned = node_name.to_string();
         let self_ref = self.clone();
 
@@ -817,14 +1393,18 @@ impl HttpAgentManager {
             (operation_timeouts::SNAPSHOT_RESTORE_HOURS * 60) as u32,
             || async move {
                 self_ref
-                    .restore_node_from_snapshot_impl(&node_name_owned)
+                    .restore_node_from_snapshot_impl(&node_name_owned, force)
                     .await
             },
         )
         .await
     }
 
-    async fn restore_node_from_snapshot_impl(&self, node_name: &str) -> Result<SnapshotInfo> {
+    async fn restore_node_from_snapshot_impl(
+        &self,
+        node_name: &str,
+        force: bool,
+    ) -> Result<SnapshotInfo> {
         let node_config = self
             .config
             .nodes
@@ -860,94 +1440,324 @@ impl HttpAgentManager {
 
         let service_name = &node_config.service_name;
 
-        let latest_snapshot_dir = self
-            .find_latest_network_snapshot_directory(&node_config.server_host, backup_path, &network)
-            .await?;
-
-        info!(
-            "Restoring node {} from network snapshot: {}",
-            node_name, latest_snapshot_dir
-        );
+        let source = match self
+            .find_latest_network_snapshot(&node_config.server_host, backup_path, &network)
+            .await?
+        {
+            Some(source) => source,
+            None => NetworkSnapshotSource::Archive(
+                self.pull_network_snapshot_from_peer(node_name, node_config, backup_path, &network)
+                    .await?,
+            ),
+        };
 
-        let payload = json!({
+        let mut payload = json!({
             "node_name": node_name,
             "deploy_path": deploy_path,
-            "snapshot_dir": latest_snapshot_dir,
             "service_name": service_name,
-            "log_path": node_config.log_path
+            "log_path": node_config.log_files_path(),
+            "log_rotation": AgentLogRotation::for_node(node_config),
+            "force": force,
+            // The agent keeps the node's previous data until this RPC shows new blocks
+            "rpc_url": node_config.rpc_url
         });
+        let (snapshot_path, compression_type) = match &source {
+            NetworkSnapshotSource::Directory(path) => {
+                info!(
+                    "Restoring node {} from network snapshot: {}",
+                    node_name, path
+                );
+                payload["snapshot_dir"] = json!(path);
+                (path.clone(), "directory")
+            }
+            NetworkSnapshotSource::Archive(path) => {
+                info!(
+                    "Restoring node {} from network snapshot archive: {} (no snapshot directory left)",
+                    node_name, path
+                );
+                payload["archive_path"] = json!(path);
+                (path.clone(), "lz4")
+            }
+        };
 
         let _result = self
-            .execute_operation(&node_config.server_host, "/snapshot/restore", payload)
+            .execute_tracked_operation(
+                Some(node_name),
+                &node_config.server_host,
+                "/snapshot/restore",
+                payload,
+            )
             .await?;
 
+        let filename = snapshot_path.rsplit('/').next().unwrap_or("unknown");
         let snapshot_info = SnapshotInfo {
             node_name: node_name.to_string(),
             network,
-            filename: latest_snapshot_dir
-                .rsplit('/')
-                .next()
-                .unwrap_or("unknown")
+            filename: filename
+                .strip_suffix(".tar.lz4")
+                .unwrap_or(filename)
                 .to_string(),
             created_at: Utc::now(),
             file_size_bytes: None,
-            snapshot_path: latest_snapshot_dir,
-            compression_type: "directory".to_string(),
+            snapshot_path,
+            compression_type: compression_type.to_string(),
+            archive_status: None,
+            compression_job_id: None,
         };
 
         info!("Restore completed successfully for node {}", node_name);
         Ok(snapshot_info)
     }
 
-    async fn find_latest_network_snapshot_directory(
+    /// Re-hash a snapshot's files on the node's agent and compare them with its manifest
+    pub async fn verify_node_snapshot(
+        &self,
+        node_name: &str,
+        filename: &str,
+    ) -> Result<AgentSnapshotVerifyReport> {
+        let node_config = self
+            .config
+            .nodes
+            .get(node_name)
+            .ok_or_else(|| anyhow::anyhow!("Node {} not found", node_name))?;
+
+        let backup_path = node_config
+            .snapshot_backup_path
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("No backup path configured for {}", node_name))?;
+
+        let payload = json!({"snapshot_dir": format!("{}/{}", backup_path, filename)});
+        let result = self
+            .execute_operation(&node_config.server_host, "/snapshot/verify", payload)
+            .await?;
+
+        let report = result
+            .get("result")
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("Agent returned no verification result"))?;
+        serde_json::from_value(report)
+            .map_err(|e| anyhow::anyhow!("Invalid verification result: {}", e))
+    }
+
+    /// Pick the snapshot to restore from: the directory with the highest block
+    /// height, or if no directories are left, the newest complete archive.
+    /// Returns `None` if the server has neither for the network.
+    async fn find_latest_network_snapshot(
         &self,
         server_host: &str,
         backup_path: &str,
         network: &str,
-    ) -> Result<String> {
-        // Sort by block height (last field after underscore) numerically, not alphabetically
+    ) -> Result<Option<NetworkSnapshotSource>> {
+        // Pick by block height (last field after underscore), not by name or mtime
         // Snapshot format: network_date_blockheight (e.g., pirin-1_20250121_17154420)
-        let list_cmd = format!(
-            "find '{}' -maxdepth 1 -type d -name '{}_*' | awk -F_ '{{print $NF, $0}}' | sort -k1 -n | tail -1 | awk '{{print $2}}'",
-            backup_path, network
-        );
+        let listing = self
+            .list_agent_snapshots(server_host, backup_path, network)
+            .await?;
 
-        let output = self.execute_single_command(server_host, &list_cmd).await?;
+        let Some(snapshot) = listing
+            .snapshots
+            .iter()
+            .max_by_key(|s| s.block_height().unwrap_or(0))
+        else {
+            return Ok(listing
+                .archives
+                .iter()
+                .max_by_key(|a| a.block_height().unwrap_or(0))
+                .map(|archive| {
+                    NetworkSnapshotSource::Archive(format!(
+                        "{}/{}",
+                        backup_path.trim_end_matches('/'),
+                        archive.name
+                    ))
+                }));
+        };
 
-        let snapshot_dir = output.trim();
-        if snapshot_dir.is_empty() {
+        if !snapshot.has_data {
             return Err(anyhow::anyhow!(
-                "No network snapshots found for network {} in {}",
-                network,
-                backup_path
+                "Network snapshot directory {} does not contain data subdirectory",
+                snapshot.path
+            ));
+        }
+        if !snapshot.has_wasm {
+            return Err(anyhow::anyhow!(
+                "Network snapshot directory {} does not contain wasm subdirectory",
+                snapshot.path
             ));
         }
 
-        let verify_data_cmd = format!("test -d '{}/data'", snapshot_dir);
-        self.execute_single_command(server_host, &verify_data_cmd)
-            .await
-            .map_err(|_| {
-                anyhow::anyhow!(
-                    "Network snapshot directory {} does not contain data subdirectory",
-                    snapshot_dir
-                )
-            })?;
+        Ok(Some(NetworkSnapshotSource::Directory(
+            snapshot.path.clone(),
+        )))
+    }
 
-        let verify_wasm_cmd = format!("test -d '{}/wasm'", snapshot_dir);
-        self.execute_single_command(server_host, &verify_wasm_cmd)
-            .await
-            .map_err(|_| {
-                anyhow::anyhow!(
-                    "Network snapshot directory {} does not contain wasm subdirectory",
-                    snapshot_dir
-                )
-            })?;
+    /// Find the newest archive for `network` on any other server and have the
+    /// node's agent pull it into its own backup path. Returns the local archive path.
+    async fn pull_network_snapshot_from_peer(
+        &self,
+        node_name: &str,
+        node_config: &NodeConfig,
+        backup_path: &str,
+        network: &str,
+    ) -> Result<String> {
+        info!(
+            "No local snapshot for network {} on {}, looking on other servers",
+            network, node_config.server_host
+        );
+
+        let mut peers: Vec<(&str, &str)> = self
+            .config
+            .nodes
+            .values()
+            .filter(|peer| peer.server_host != node_config.server_host)
+            .filter_map(|peer| {
+                Some((
+                    peer.server_host.as_str(),
+                    peer.snapshot_backup_path.as_deref()?,
+                ))
+            })
+            .collect();
+        peers.sort();
+        peers.dedup();
+
+        let mut best: Option<(&str, &str, AgentArchiveEntry)> = None;
+        for (server, peer_backup_path) in peers {
+            let listing = match self
+                .list_agent_snapshots(server, peer_backup_path, network)
+                .await
+            {
+                Ok(listing) => listing,
+                Err(e) => {
+                    warn!("Could not list snapshots on {}: {}", server, e);
+                    continue;
+                }
+            };
+            for archive in listing.archives {
+                let height = archive.block_height().unwrap_or(0);
+                let newer = match &best {
+                    Some((_, _, current)) => height > current.block_height().unwrap_or(0),
+                    None => true,
+                };
+                if newer {
+                    best = Some((server, peer_backup_path, archive));
+                }
+            }
+        }
 
-        Ok(snapshot_dir.to_string())
+        let (source_server, source_backup_path, archive) = best.ok_or_else(|| {
+            anyhow::anyhow!(
+                "No network snapshots found for network {} on {} or any other server",
+                network,
+                node_config.server_host
+            )
+        })?;
+        let source_config = self
+            .config
+            .servers
+            .get(source_server)
+            .ok_or_else(|| anyhow::anyhow!("Server {} not found", source_server))?;
+        let snapshot_name = archive
+            .name
+            .strip_suffix(".tar.lz4")
+            .unwrap_or(&archive.name);
+
+        info!(
+            "Pulling {} ({:.1} MB) from {} to {} for node {}",
+            archive.name,
+            archive.size_bytes as f64 / 1024.0 / 1024.0,
+            source_server,
+            node_config.server_host,
+            node_name
+        );
+
+        // The pulling agent has to trust the source agent's private CA too
+        let source_ca_cert = match &source_config.tls.ca_cert_path {
+            Some(ca_path) if source_config.tls.agent_scheme == AgentScheme::Https => Some(
+                tokio::fs::read_to_string(ca_path)
+                    .await
+                    .map_err(|e| anyhow::anyhow!("Failed to read CA bundle {}: {}", ca_path, e))?,
+            ),
+            _ => None,
+        };
+
+        // The pulling agent gets a token for this one archive, never the
+        // source agent's API key
+        let token = self
+            .issue_archive_download_token(source_server, source_backup_path, snapshot_name)
+            .await?;
+
+        let payload = json!({
+            "source_url": source_config.agent_base_url(),
+            "source_token": token.token,
+            "source_ca_cert": source_ca_cert,
+            "source_backup_path": source_backup_path,
+            "snapshot_name": snapshot_name,
+            "backup_path": backup_path,
+        });
+        let result = self
+            .execute_tracked_operation(
+                Some(node_name),
+                &node_config.server_host,
+                "/snapshot/pull",
+                payload,
+            )
+            .await?;
+
+        let transfer: AgentSnapshotTransferResult =
+            serde_json::from_value(result.get("result").cloned().unwrap_or(result))
+                .map_err(|e| anyhow::anyhow!("Invalid transfer result: {}", e))?;
+        info!(
+            "Pulled {} to {} ({} attempt(s), resumed from {} bytes)",
+            snapshot_name, transfer.archive_path, transfer.attempts, transfer.resumed_from_bytes
+        );
+        Ok(transfer.archive_path)
     }
 
-    pub async fn cancel_operation(&self, target_name: &str) -> Result<()> {
-        self.operation_tracker.cancel_operation(target_name).await
+    /// Cancel the operation running on a target. If the agent already accepted a
+    /// job for it, the cancellation is propagated so the agent aborts the job and
+    /// runs its cleanup. The target stays reserved until the operation polling
+    /// the job sees it cancelled, so nothing else starts while the agent cleans
+    /// up; it is released right away when there is no job or the agent could
+    /// not be asked to cancel it.
+    pub async fn cancel_operation(&self, target_name: &str) -> Result<String> {
+        let active = self
+            .operation_tracker
+            .get_active_operation(target_name)
+            .await
+            .ok_or_else(|| anyhow::anyhow!("No active operation found on {}", target_name))?;
+
+        let message = match (&active.agent_server, &active.agent_job_id) {
+            (Some(server_name), Some(job_id)) => {
+                match self
+                    .execute_operation(
+                        server_name,
+                        &format!("/operation/cancel/{}", job_id),
+                        json!({}),
+                    )
+                    .await
+                {
+                    Ok(_) => {
+                        return Ok(format!(
+                            "Cancelling agent job {} on {} for {}; {} is released once the job has stopped",
+                            job_id, server_name, target_name, target_name
+                        ));
+                    }
+                    Err(e) => {
+                        warn!(
+                            "Failed to cancel agent job {} on {}: {}",
+                            job_id, server_name, e
+                        );
+                        format!(
+                            "Released {} locally, but agent job {} on {} could not be cancelled: {}",
+                            target_name, job_id, server_name, e
+                        )
+                    }
+                }
+            }
+            _ => format!("Released {} (no agent job was running for it)", target_name),
+        };
+
+        self.operation_tracker.cancel_operation(target_name).await?;
+        Ok(message)
     }
 
     pub async fn get_active_operations(&self) -> crate::operation_tracker::OperationStatus {
@@ -976,16 +1786,17 @@ impl HttpAgentManager {
             _ => return Ok(false),
         };
 
-        let log_path = match &node_config.log_path {
-            Some(path) => path,
+        let log_source = match node_config.log_source() {
+            Some(source) => source,
             None => return Ok(false),
         };
 
-        let log_file = format!("{}/out1.log", log_path);
-        let payload = json!({
-            "log_file": log_file,
-            "trigger_words": trigger_words
-        });
+        let mut payload = json!({ "trigger_words": trigger_words });
+        for (key, value) in log_source.agent_params() {
+            // The agent names the log file or directory `log_file` here
+            let key = if key == "path" { "log_file" } else { key };
+            payload[key] = json!(value);
+        }
 
         let result = self
             .execute_operation(
@@ -21,6 +21,17 @@
 //! 3. Manager polls for completion status
 //! 4. Operation completes or fails with detailed error
 //!
+//! # Authentication
+//!
+//! Requests are signed with the server's API key (see [`signing`]); agents reject
+//! stale or replayed signatures.
+//!
+//! # Transport Security
+//!
+//! Servers configured with `agent_scheme = "https"` are reached over TLS, trusting
+//! the server's `ca_cert_path` bundle and presenting `client_cert_path` /
+//! `client_key_path` to agents that require mutual TLS.
+//!
 //! # Safety Features
 //!
 //! - Direct HTTP per operation (no persistent connections)
@@ -28,7 +39,10 @@
 //! - Operation timeout handling
 //! - Busy node detection to prevent concurrent operations
 
+pub mod agent_client;
 pub mod agent_manager;
 pub mod operations;
+pub mod signing;
 
+pub use agent_client::AgentClients;
 pub use agent_manager::HttpAgentManager;
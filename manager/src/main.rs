@@ -27,8 +27,8 @@ use maintenance_tracker::MaintenanceTracker;
 use operation_tracker::SimpleOperationTracker;
 use scheduler::MaintenanceScheduler;
 use services::{
-    AlertService, HermesService, MaintenanceService, OperationExecutor, SnapshotService,
-    StateSyncService,
+    AlertService, ConfigPatchService, HermesService, MaintenanceService, OperationExecutor,
+    SnapshotService, StateSyncService,
 };
 use snapshot::SnapshotManager;
 
@@ -113,7 +113,17 @@ async fn main() -> Result<()> {
     ));
     info!("Snapshot manager initialized with centralized alerting");
 
+    let maintenance_service = Arc::new(MaintenanceService::new(
+        config.clone(),
+        database.clone(),
+        http_manager.clone(),
+        snapshot_manager.clone(),
+        alert_service.clone(),
+    ));
+    info!("MaintenanceService initialized with alert integration");
+
     // Initialize health monitor WITH AlertService (for auto-restore and health alerts)
+    // and MaintenanceService (for disk space cleanup)
     let health_monitor = Arc::new(HealthMonitor::new(
         config.clone(),
         database.clone(),
@@ -121,6 +131,7 @@ async fn main() -> Result<()> {
         snapshot_manager.clone(),
         alert_service.clone(),
         http_manager.clone(),
+        maintenance_service.clone(),
     ));
     info!("Health monitor initialized with centralized alerting and auto-restore capability");
 
@@ -199,6 +210,28 @@ async fn main() -> Result<()> {
         }
     });
 
+    // Start periodic telemetry cleanup (configurable interval)
+    let database_clone = database.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(
+            cleanup::CLEANUP_INTERVAL_SECONDS,
+        ));
+        loop {
+            interval.tick().await;
+            let before =
+                chrono::Utc::now() - chrono::Duration::hours(cleanup::TELEMETRY_RETENTION_HOURS);
+            match database_clone.delete_telemetry_records_before(before).await {
+                Ok(deleted) if deleted > 0 => info!(
+                    "Deleted {} telemetry records older than {} hours",
+                    deleted,
+                    cleanup::TELEMETRY_RETENTION_HOURS
+                ),
+                Ok(_) => {}
+                Err(e) => warn!("Telemetry cleanup failed: {}", e),
+            }
+        }
+    });
+
     info!("Background tasks started with {}s health check interval (including nodes, auto-restore monitoring and centralized alerting)", check_interval);
 
     // Additional startup alert validation
@@ -228,14 +261,6 @@ async fn main() -> Result<()> {
     ));
     info!("HermesService initialized with alert integration");
 
-    let maintenance_service = Arc::new(MaintenanceService::new(
-        config.clone(),
-        database.clone(),
-        http_manager.clone(),
-        alert_service.clone(),
-    ));
-    info!("MaintenanceService initialized with alert integration");
-
     let snapshot_service_v2 = Arc::new(SnapshotService::new(
         config.clone(),
         snapshot_manager.clone(),
@@ -249,6 +274,15 @@ async fn main() -> Result<()> {
     ));
     info!("StateSyncService initialized with alert integration");
 
+    let config_patch_service = Arc::new(ConfigPatchService::new(
+        config.clone(),
+        database.clone(),
+        http_manager.clone(),
+        health_monitor.clone(),
+        alert_service.clone(),
+    ));
+    info!("ConfigPatchService initialized with alert integration");
+
     // Initialize and start scheduler with service layer integration
     let scheduler = Arc::new(
         MaintenanceScheduler::new(
@@ -281,6 +315,7 @@ async fn main() -> Result<()> {
         maintenance_service,
         snapshot_service_v2,
         state_sync_service,
+        config_patch_service,
     )
     .await?;
 
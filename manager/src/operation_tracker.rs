@@ -37,6 +37,12 @@ pub struct ActiveOperation {
     pub target_name: String,
     pub started_at: DateTime<Utc>,
     pub user_info: Option<String>,
+    /// Server running the agent-side job, once the agent has accepted it
+    pub agent_server: Option<String>,
+    /// Agent job ID, used to propagate cancellation to the agent
+    pub agent_job_id: Option<String>,
+    /// Latest step-level progress reported by the agent for the job
+    pub progress: Option<serde_json::Value>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -87,6 +93,9 @@ impl SimpleOperationTracker {
             target_name: target_name.to_string(),
             started_at: Utc::now(),
             user_info,
+            agent_server: None,
+            agent_job_id: None,
+            progress: None,
         };
 
         active.insert(target_name.to_string(), operation);
@@ -109,6 +118,23 @@ impl SimpleOperationTracker {
         }
     }
 
+    /// Link an active operation to the job the agent started for it
+    pub async fn attach_agent_job(&self, target_name: &str, server_name: &str, job_id: &str) {
+        let mut active = self.active_operations.write().await;
+        if let Some(op) = active.get_mut(target_name) {
+            op.agent_server = Some(server_name.to_string());
+            op.agent_job_id = Some(job_id.to_string());
+        }
+    }
+
+    /// Store the latest progress the agent reported for a target's job
+    pub async fn update_progress(&self, target_name: &str, progress: serde_json::Value) {
+        let mut active = self.active_operations.write().await;
+        if let Some(op) = active.get_mut(target_name) {
+            op.progress = Some(progress);
+        }
+    }
+
     /// Force cancel an operation (for manual cleanup)
     pub async fn cancel_operation(&self, target_name: &str) -> Result<()> {
         let mut active = self.active_operations.write().await;
@@ -261,4 +287,53 @@ mod tests {
         // Should fail to cancel non-existent operation
         assert!(tracker.cancel_operation("node-1").await.is_err());
     }
+
+    #[tokio::test]
+    async fn test_attach_agent_job() {
+        let tracker = SimpleOperationTracker::new();
+
+        tracker
+            .try_start_operation("node-1", "pruning", None)
+            .await
+            .unwrap();
+        tracker
+            .attach_agent_job("node-1", "server-a", "pruning_node-1_1700000000")
+            .await;
+
+        let op = tracker.get_active_operation("node-1").await.unwrap();
+        assert_eq!(op.agent_server.as_deref(), Some("server-a"));
+        assert_eq!(
+            op.agent_job_id.as_deref(),
+            Some("pruning_node-1_1700000000")
+        );
+
+        // Attaching to an idle target is a no-op
+        tracker.attach_agent_job("node-2", "server-a", "job").await;
+        assert!(!tracker.is_busy("node-2").await);
+    }
+
+    #[tokio::test]
+    async fn test_agent_job_progress_is_reported() {
+        let tracker = SimpleOperationTracker::new();
+
+        tracker
+            .try_start_operation("node-1", "pruning", None)
+            .await
+            .unwrap();
+        tracker
+            .attach_agent_job("node-1", "server-a", "pruning_node-1_1700000000")
+            .await;
+        tracker
+            .update_progress(
+                "node-1",
+                serde_json::json!({"current_step": {"name": "Running cosmos-pruner"}}),
+            )
+            .await;
+
+        let op = tracker.get_active_operation("node-1").await.unwrap();
+        assert_eq!(
+            op.progress.unwrap()["current_step"]["name"],
+            "Running cosmos-pruner"
+        );
+    }
 }
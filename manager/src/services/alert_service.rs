@@ -11,6 +11,7 @@ use tokio::time::timeout;
 use tracing::{debug, error, info, warn};
 
 use crate::constants::alerts;
+use crate::http::agent_manager::AgentLogMatch;
 
 #[non_exhaustive]
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -21,6 +22,7 @@ pub enum AlertType {
     Hermes,
     LogPattern,
     Maintenance,
+    DiskSpace,
 }
 
 #[non_exhaustive]
@@ -324,6 +326,52 @@ impl AlertService {
         .await
     }
 
+    /// Alert when an operation was refused because the agent's disk space
+    /// preflight found too little free space. The node was not stopped.
+    pub async fn alert_insufficient_disk_space(
+        &self,
+        operation_type: &str,
+        node_name: &str,
+        server_host: &str,
+        error: &str,
+    ) -> Result<()> {
+        self.send_immediate_alert(
+            AlertType::DiskSpace,
+            AlertSeverity::Critical,
+            node_name,
+            server_host,
+            format!(
+                "Not enough disk space for {} on {} - operation aborted before stopping the node",
+                operation_type, node_name
+            ),
+            Some(serde_json::json!({
+                "operation_type": operation_type,
+                "status": "failed",
+                "error_message": error
+            })),
+        )
+        .await
+    }
+
+    /// Send a disk space alert when `error` comes from a failed preflight check
+    pub async fn alert_if_insufficient_disk_space(
+        &self,
+        operation_type: &str,
+        node_name: &str,
+        server_host: &str,
+        error: &str,
+    ) {
+        if !error.contains(alerts::INSUFFICIENT_DISK_SPACE_ERROR) {
+            return;
+        }
+        if let Err(e) = self
+            .alert_insufficient_disk_space(operation_type, node_name, server_host, error)
+            .await
+        {
+            warn!("Failed to send disk space alert: {}", e);
+        }
+    }
+
     // --- Hermes Operations ---
 
     /// Alert when Hermes restart starts
@@ -569,6 +617,7 @@ impl AlertService {
         log_path: &str,
         log_output: &str,
         patterns: &[String],
+        matches: &[AgentLogMatch],
     ) -> Result<()> {
         self.send_immediate_alert(
             AlertType::LogPattern,
@@ -579,7 +628,8 @@ impl AlertService {
             Some(serde_json::json!({
                 "log_path": log_path,
                 "log_output": log_output,
-                "patterns": patterns
+                "patterns": patterns,
+                "matches": matches
             })),
         )
         .await
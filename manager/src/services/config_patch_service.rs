@@ -0,0 +1,480 @@
+// File: manager/src/services/config_patch_service.rs
+//
+// Fleet-wide changes to node config.toml and app.toml through the agents' /config/patch
+//
+use crate::config::{Config, NODE_CONFIG_FILES};
+use crate::constants::operation_timeouts;
+use crate::database::{Database, MaintenanceOperation};
+use crate::health::{HealthMonitor, HealthStatus};
+use crate::http::agent_manager::{AgentConfigChange, AgentConfigPatchResult};
+use crate::http::HttpAgentManager;
+use crate::services::alert_service::AlertService;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::{debug, error, info, warn};
+use uuid::Uuid;
+
+/// Operation type config patches are recorded under in `maintenance_operations`
+pub const CONFIG_PATCH_OPERATION: &str = "config_patch";
+
+/// Nodes a config patch applies to. Every criterion given must match; nodes
+/// selected by network or server must also be enabled.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NodeSelection {
+    pub network: Option<String>,
+    pub server: Option<String>,
+    pub nodes: Option<Vec<String>>,
+}
+
+impl NodeSelection {
+    /// Names of the selected nodes, sorted
+    pub fn select(&self, config: &Config) -> Result<Vec<String>> {
+        if self.network.is_none() && self.server.is_none() && self.nodes.is_none() {
+            return Err(anyhow!(
+                "Select nodes by network, server or a list of node names"
+            ));
+        }
+        if let Some(nodes) = &self.nodes {
+            if let Some(unknown) = nodes.iter().find(|name| !config.nodes.contains_key(*name)) {
+                return Err(anyhow!("Node {} not found", unknown));
+            }
+        }
+
+        let mut selected: Vec<String> = config
+            .nodes
+            .iter()
+            .filter(|(name, node)| {
+                let listed = match &self.nodes {
+                    Some(nodes) => nodes.contains(name),
+                    None => node.enabled,
+                };
+                listed
+                    && self.network.as_ref().is_none_or(|n| &node.network == n)
+                    && self.server.as_ref().is_none_or(|s| &node.server_host == s)
+            })
+            .map(|(name, _)| name.clone())
+            .collect();
+        selected.sort();
+
+        if selected.is_empty() {
+            return Err(anyhow!("No nodes match the selection"));
+        }
+        Ok(selected)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigPatchRequest {
+    #[serde(flatten)]
+    pub selection: NodeSelection,
+    /// One of `NODE_CONFIG_FILES`
+    pub file: String,
+    pub changes: Vec<AgentConfigChange>,
+    /// Restart each changed node and wait for it to be healthy and caught up
+    /// before patching the next one
+    #[serde(default)]
+    pub restart: bool,
+}
+
+impl ConfigPatchRequest {
+    pub fn validate(&self) -> Result<()> {
+        if !NODE_CONFIG_FILES.contains(&self.file.as_str()) {
+            return Err(anyhow!(
+                "file must be one of {}, got {:?}",
+                NODE_CONFIG_FILES.join(", "),
+                self.file
+            ));
+        }
+        if self.changes.is_empty() {
+            return Err(anyhow!("No config changes given"));
+        }
+        if self
+            .changes
+            .iter()
+            .any(|change| change.key.trim().is_empty())
+        {
+            return Err(anyhow!("Config change keys must not be empty"));
+        }
+        Ok(())
+    }
+}
+
+/// What a patch would change on one node
+#[derive(Debug, Clone, Serialize)]
+pub struct NodeConfigPatchPreview {
+    pub node_name: String,
+    pub server_host: String,
+    pub config_path: Option<String>,
+    pub changed: bool,
+    pub diff: String,
+    /// The node's agent could not produce a diff
+    pub error: Option<String>,
+}
+
+/// A rollout that was started, one node at a time in `nodes` order
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigPatchRun {
+    pub run_id: String,
+    pub nodes: Vec<String>,
+    pub restart: bool,
+}
+
+/// Stored as the `details` of a config patch's maintenance operation
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ConfigPatchDetails {
+    pub run_id: String,
+    pub file: String,
+    pub changes: Vec<AgentConfigChange>,
+    pub restart: bool,
+    pub config_path: Option<String>,
+    pub diff: Option<String>,
+    pub backup_path: Option<String>,
+    #[serde(default)]
+    pub restarted: bool,
+}
+
+/// A config patch of one node as recorded in `maintenance_operations`
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigPatchRecord {
+    pub operation_id: String,
+    pub node_name: String,
+    pub status: String,
+    pub started_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub error_message: Option<String>,
+    pub details: Option<ConfigPatchDetails>,
+}
+
+impl From<MaintenanceOperation> for ConfigPatchRecord {
+    fn from(operation: MaintenanceOperation) -> Self {
+        Self {
+            details: operation
+                .details
+                .as_deref()
+                .and_then(|details| serde_json::from_str(details).ok()),
+            operation_id: operation.id,
+            node_name: operation.target_name,
+            status: operation.status,
+            started_at: operation.started_at,
+            completed_at: operation.completed_at,
+            error_message: operation.error_message,
+        }
+    }
+}
+
+pub struct ConfigPatchService {
+    config: Arc<Config>,
+    database: Arc<Database>,
+    http_manager: Arc<HttpAgentManager>,
+    health_monitor: Arc<HealthMonitor>,
+    alert_service: Arc<AlertService>,
+}
+
+impl ConfigPatchService {
+    pub fn new(
+        config: Arc<Config>,
+        database: Arc<Database>,
+        http_manager: Arc<HttpAgentManager>,
+        health_monitor: Arc<HealthMonitor>,
+        alert_service: Arc<AlertService>,
+    ) -> Self {
+        Self {
+            config,
+            database,
+            http_manager,
+            health_monitor,
+            alert_service,
+        }
+    }
+
+    /// Diff of the patch on every selected node, leaving their files as they are
+    pub async fn preview(
+        &self,
+        request: &ConfigPatchRequest,
+    ) -> Result<Vec<NodeConfigPatchPreview>> {
+        request.validate()?;
+        let nodes = request.selection.select(&self.config)?;
+        info!("Previewing {} patch of {} nodes", request.file, nodes.len());
+
+        let previews = nodes.iter().map(|node_name| async move {
+            let result = self
+                .http_manager
+                .patch_node_config(node_name, &request.file, &request.changes, true)
+                .await;
+            self.preview_of(node_name, &request.file, result)
+        });
+        Ok(futures::future::join_all(previews).await)
+    }
+
+    fn preview_of(
+        &self,
+        node_name: &str,
+        file: &str,
+        result: Result<AgentConfigPatchResult>,
+    ) -> NodeConfigPatchPreview {
+        let node_config = self.config.nodes.get(node_name);
+        let mut preview = NodeConfigPatchPreview {
+            node_name: node_name.to_string(),
+            server_host: node_config
+                .map(|n| n.server_host.clone())
+                .unwrap_or_default(),
+            config_path: node_config.and_then(|n| n.config_file_path(file)),
+            changed: false,
+            diff: String::new(),
+            error: None,
+        };
+        match result {
+            Ok(patch) => {
+                preview.config_path = Some(patch.config_path);
+                preview.changed = patch.changed;
+                preview.diff = patch.diff;
+            }
+            Err(e) => preview.error = Some(e.to_string()),
+        }
+        preview
+    }
+
+    /// Start patching the selected nodes one at a time in the background. A
+    /// node that fails, or that is not healthy and caught up in time after its
+    /// restart, stops the rollout; the nodes after it are recorded as skipped.
+    pub async fn apply(&self, request: ConfigPatchRequest) -> Result<ConfigPatchRun> {
+        request.validate()?;
+        let nodes = request.selection.select(&self.config)?;
+        let run = ConfigPatchRun {
+            run_id: Uuid::new_v4().to_string(),
+            nodes,
+            restart: request.restart,
+        };
+        info!(
+            "Starting {} patch of {} nodes (run_id: {}, restart: {})",
+            request.file,
+            run.nodes.len(),
+            run.run_id,
+            run.restart
+        );
+
+        let rollout = Rollout {
+            config: self.config.clone(),
+            database: self.database.clone(),
+            http_manager: self.http_manager.clone(),
+            health_monitor: self.health_monitor.clone(),
+            alert_service: self.alert_service.clone(),
+        };
+        let (run_id, nodes) = (run.run_id.clone(), run.nodes.clone());
+        tokio::spawn(async move { rollout.run(&run_id, &nodes, &request).await });
+
+        Ok(run)
+    }
+
+    /// Config patches recorded for nodes, newest first
+    pub async fn history(&self, limit: i64) -> Result<Vec<ConfigPatchRecord>> {
+        let operations = self
+            .database
+            .get_maintenance_operations_by_type(CONFIG_PATCH_OPERATION, limit)
+            .await?;
+        Ok(operations
+            .into_iter()
+            .map(ConfigPatchRecord::from)
+            .collect())
+    }
+}
+
+/// Poll `check` until it reports the node healthy and no longer catching up.
+/// Failed checks count as not ready, since the node may still be starting;
+/// a node that is not ready within `timeout` is an error.
+pub async fn wait_until_ready<F, Fut>(
+    node_name: &str,
+    timeout: Duration,
+    poll_interval: Duration,
+    mut check: F,
+) -> Result<()>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<HealthStatus>>,
+{
+    let deadline = Instant::now() + timeout;
+    loop {
+        let reason = match check().await {
+            Ok(status) if status.is_healthy && !status.is_catching_up => return Ok(()),
+            Ok(status) if status.is_catching_up => "still catching up".to_string(),
+            Ok(status) => status
+                .error_message
+                .unwrap_or_else(|| "not healthy".to_string()),
+            Err(e) => e.to_string(),
+        };
+        if Instant::now() >= deadline {
+            return Err(anyhow!(
+                "Node {} was not healthy {}s after its restart: {}",
+                node_name,
+                timeout.as_secs(),
+                reason
+            ));
+        }
+        debug!("Waiting for {} after restart: {}", node_name, reason);
+        tokio::time::sleep(poll_interval).await;
+    }
+}
+
+/// The background half of `ConfigPatchService::apply`
+struct Rollout {
+    config: Arc<Config>,
+    database: Arc<Database>,
+    http_manager: Arc<HttpAgentManager>,
+    health_monitor: Arc<HealthMonitor>,
+    alert_service: Arc<AlertService>,
+}
+
+impl Rollout {
+    async fn run(&self, run_id: &str, nodes: &[String], request: &ConfigPatchRequest) {
+        let mut failed: Option<String> = None;
+        for node_name in nodes {
+            let mut details = ConfigPatchDetails {
+                run_id: run_id.to_string(),
+                file: request.file.clone(),
+                changes: request.changes.clone(),
+                restart: request.restart,
+                ..Default::default()
+            };
+            let mut operation = MaintenanceOperation {
+                id: Uuid::new_v4().to_string(),
+                operation_type: CONFIG_PATCH_OPERATION.to_string(),
+                target_name: node_name.clone(),
+                status: "started".to_string(),
+                started_at: Utc::now(),
+                completed_at: None,
+                error_message: None,
+                details: serde_json::to_string(&details).ok(),
+            };
+
+            if let Some(failed_node) = &failed {
+                operation.status = "skipped".to_string();
+                operation.completed_at = Some(Utc::now());
+                operation.error_message =
+                    Some(format!("Rollout stopped after {} failed", failed_node));
+                self.store(&operation).await;
+                continue;
+            }
+            self.store(&operation).await;
+
+            let result = self.patch_node(node_name, request, &mut details).await;
+            operation.details = serde_json::to_string(&details).ok();
+            operation.completed_at = Some(Utc::now());
+            match result {
+                Ok(()) => {
+                    operation.status = "completed".to_string();
+                    info!(
+                        "Patched {} of {} (run_id: {})",
+                        request.file, node_name, run_id
+                    );
+                }
+                Err(e) => {
+                    operation.status = "failed".to_string();
+                    operation.error_message = Some(e.to_string());
+                    error!(
+                        "Patching {} of {} failed (run_id: {}): {}",
+                        request.file, node_name, run_id, e
+                    );
+                    self.alert_failure(node_name, &e.to_string()).await;
+                    failed = Some(node_name.clone());
+                }
+            }
+            self.store(&operation).await;
+        }
+
+        match failed {
+            Some(node_name) => warn!(
+                "Config patch run {} stopped after {} failed",
+                run_id, node_name
+            ),
+            None => info!("Config patch run {} completed", run_id),
+        }
+    }
+
+    /// Patch a node and restart it if asked, keeping it reserved in the
+    /// operation tracker (and in a maintenance window) until it is healthy again
+    async fn patch_node(
+        &self,
+        node_name: &str,
+        request: &ConfigPatchRequest,
+        details: &mut ConfigPatchDetails,
+    ) -> Result<()> {
+        let node_config = self
+            .config
+            .nodes
+            .get(node_name)
+            .ok_or_else(|| anyhow!("Node {} not found", node_name))?;
+        let duration_minutes = operation_timeouts::NODE_RESTART_MINUTES
+            + operation_timeouts::CONFIG_PATCH_HEALTHY_MINUTES;
+
+        self.http_manager
+            .with_operation_lifecycle(
+                node_name,
+                CONFIG_PATCH_OPERATION,
+                duration_minutes as u32,
+                || async {
+                    let patch = self
+                        .http_manager
+                        .patch_node_config(node_name, &request.file, &request.changes, false)
+                        .await?;
+                    details.config_path = Some(patch.config_path);
+                    details.diff = Some(patch.diff);
+                    details.backup_path = patch.backup_path;
+
+                    if request.restart && patch.changed {
+                        self.http_manager.restart_node_impl(node_name).await?;
+                        details.restarted = true;
+                        wait_until_ready(
+                            node_name,
+                            Duration::from_secs(
+                                operation_timeouts::CONFIG_PATCH_HEALTHY_MINUTES * 60,
+                            ),
+                            Duration::from_secs(
+                                operation_timeouts::CONFIG_PATCH_HEALTH_POLL_SECONDS,
+                            ),
+                            || {
+                                self.health_monitor
+                                    .check_node_health(node_name, node_config)
+                            },
+                        )
+                        .await?;
+                    }
+                    Ok(())
+                },
+            )
+            .await
+    }
+
+    async fn store(&self, operation: &MaintenanceOperation) {
+        if let Err(e) = self.database.store_maintenance_operation(operation).await {
+            error!(
+                "Failed to record config patch of {}: {}",
+                operation.target_name, e
+            );
+        }
+    }
+
+    async fn alert_failure(&self, node_name: &str, error_message: &str) {
+        let server_host = self
+            .config
+            .nodes
+            .get(node_name)
+            .map(|n| n.server_host.as_str())
+            .unwrap_or("unknown");
+        if let Err(e) = self
+            .alert_service
+            .alert_operation_failed(
+                CONFIG_PATCH_OPERATION,
+                node_name,
+                server_host,
+                error_message,
+            )
+            .await
+        {
+            error!("Failed to send failure alert: {}", e);
+        }
+    }
+}
@@ -3,11 +3,14 @@ use crate::config::Config;
 use crate::database::Database;
 use crate::http::HttpAgentManager;
 use crate::services::operation_executor::OperationExecutor;
+use crate::snapshot::SnapshotManager;
 use anyhow::Result;
 use std::sync::Arc;
 
 pub struct MaintenanceService {
+    config: Arc<Config>,
     http_manager: Arc<HttpAgentManager>,
+    snapshot_manager: Arc<SnapshotManager>,
     operation_executor: Arc<OperationExecutor>,
 }
 
@@ -16,13 +19,20 @@ impl MaintenanceService {
         config: Arc<Config>,
         database: Arc<Database>,
         http_manager: Arc<HttpAgentManager>,
+        snapshot_manager: Arc<SnapshotManager>,
         alert_service: Arc<crate::services::AlertService>,
     ) -> Self {
         // Create OperationExecutor for delegating all operations
-        let operation_executor = Arc::new(OperationExecutor::new(config, database, alert_service));
+        let operation_executor = Arc::new(OperationExecutor::new(
+            config.clone(),
+            database,
+            alert_service,
+        ));
 
         Self {
+            config,
             http_manager,
+            snapshot_manager,
             operation_executor,
         }
     }
@@ -35,6 +45,12 @@ impl MaintenanceService {
         target_name: &str,
     ) -> Result<String> {
         let http_manager = self.http_manager.clone();
+        let snapshot_manager = self.snapshot_manager.clone();
+        let retention_count = self
+            .config
+            .nodes
+            .get(target_name)
+            .and_then(|n| n.snapshot_retention_count);
         let target_name_clone = target_name.to_string();
         let operation_type_owned = operation_type.to_string();
 
@@ -51,6 +67,18 @@ impl MaintenanceService {
                             .create_node_snapshot(&target_name)
                             .await
                             .map(|_| ()),
+                        "snapshot_cleanup" => {
+                            let retention = retention_count.ok_or_else(|| {
+                                anyhow::anyhow!(
+                                    "No snapshot retention count configured for {}",
+                                    target_name
+                                )
+                            })?;
+                            snapshot_manager
+                                .cleanup_old_snapshots(&target_name, retention as u32)
+                                .await
+                                .map(|_| ())
+                        }
                         "node_restart" => http_manager.restart_node(&target_name).await,
                         _ => Err(anyhow::anyhow!("Unknown operation type: {}", op_type)),
                     }
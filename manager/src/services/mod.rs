@@ -9,6 +9,7 @@
 //!
 //! - **AlertService**: Centralized webhook-based alerting with progressive rate limiting
 //! - **OperationExecutor**: Generic background operation executor with tracking and alerting
+//! - **ConfigPatchService**: Previews and rolls out node config file changes across the fleet
 //! - **MaintenanceService**: Orchestrates maintenance operations (pruning, snapshots, etc.)
 //! - **HermesService**: Manages Hermes relayer instances
 //! - **HealthService**: Health monitoring and status queries
@@ -21,6 +22,7 @@
 //! - Services coordinate between HTTP agents, database, and tracking systems
 
 pub mod alert_service;
+pub mod config_patch_service;
 pub mod hermes_service;
 pub mod maintenance_service;
 pub mod operation_executor;
@@ -28,6 +30,7 @@ pub mod snapshot_service;
 pub mod state_sync_service;
 
 pub use alert_service::AlertService;
+pub use config_patch_service::ConfigPatchService;
 pub use hermes_service::HermesService;
 pub use maintenance_service::MaintenanceService;
 pub use operation_executor::OperationExecutor;
@@ -169,6 +169,14 @@ impl OperationExecutor {
                     {
                         error!("Failed to send failure alert: {}", alert_err);
                     }
+                    alert_service
+                        .alert_if_insufficient_disk_space(
+                            &operation_type_owned,
+                            &target_name_owned,
+                            &server_host_clone,
+                            &e.to_string(),
+                        )
+                        .await;
 
                     error!(
                         "{} failed for {} (operation_id: {}): {}",
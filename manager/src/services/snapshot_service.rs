@@ -6,6 +6,7 @@ use std::sync::Arc;
 use tracing::info;
 
 use crate::config::Config;
+use crate::http::agent_manager::AgentSnapshotVerifyReport;
 use crate::snapshot::SnapshotManager;
 
 #[derive(Clone)]
@@ -35,12 +36,32 @@ impl SnapshotService {
     pub async fn restore_from_snapshot(
         &self,
         node_name: &str,
+        force: bool,
     ) -> Result<crate::snapshot::SnapshotInfo> {
         self.validate_node_name(node_name)?;
         self.validate_auto_restore_enabled(node_name)?;
 
-        info!("Restoring latest snapshot for node: {}", node_name);
-        self.snapshot_manager.restore_from_snapshot(node_name).await
+        info!(
+            "Restoring latest snapshot for node: {} (force: {})",
+            node_name, force
+        );
+        self.snapshot_manager
+            .restore_from_snapshot(node_name, force)
+            .await
+    }
+
+    pub async fn verify_snapshot(
+        &self,
+        node_name: &str,
+        filename: &str,
+    ) -> Result<AgentSnapshotVerifyReport> {
+        self.validate_node_name(node_name)?;
+        self.validate_snapshot_access(node_name)?;
+
+        info!("Verifying snapshot {} for node: {}", filename, node_name);
+        self.snapshot_manager
+            .verify_snapshot(node_name, filename)
+            .await
     }
 
     pub async fn delete_snapshot(&self, node_name: &str, filename: &str) -> Result<()> {
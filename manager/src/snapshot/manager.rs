@@ -2,10 +2,12 @@
 use anyhow::Result;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::sync::Arc;
 use tracing::{debug, error, info, warn};
 
 use crate::config::{Config, NodeConfig};
+use crate::http::agent_manager::{AgentSnapshotListing, AgentSnapshotVerifyReport};
 use crate::http::HttpAgentManager;
 use crate::services::alert_service::AlertService;
 
@@ -18,6 +20,22 @@ pub struct SnapshotInfo {
     pub file_size_bytes: Option<u64>,
     pub snapshot_path: String,
     pub compression_type: String,
+    /// State of the snapshot's `.tar.lz4` archive, when known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub archive_status: Option<ArchiveStatus>,
+    /// Agent job compressing a newly created snapshot
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compression_job_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArchiveStatus {
+    /// The archive was fully written; agents only publish archives once complete
+    Complete,
+    /// A compression job is writing the archive, or stopped before finishing it
+    InProgress,
+    Missing,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -50,8 +68,13 @@ impl SnapshotManager {
         }
     }
 
-    /// Restore from latest snapshot via HTTP agent
-    pub async fn restore_from_snapshot(&self, node_name: &str) -> Result<SnapshotInfo> {
+    /// Restore from latest snapshot via HTTP agent. `force` restores even if the
+    /// snapshot fails verification against its manifest.
+    pub async fn restore_from_snapshot(
+        &self,
+        node_name: &str,
+        force: bool,
+    ) -> Result<SnapshotInfo> {
         let node_config = self.get_node_config(node_name)?;
 
         if !node_config.auto_restore_enabled.unwrap_or(false) {
@@ -69,7 +92,7 @@ impl SnapshotManager {
         // HttpAgentManager handles all maintenance tracking - no duplicate tracking needed
         let restore_result = self
             .http_manager
-            .restore_node_from_snapshot(node_name)
+            .restore_node_from_snapshot(node_name, force)
             .await;
 
         // Handle result and send alerts using AlertService
@@ -142,28 +165,12 @@ impl SnapshotManager {
             anyhow::anyhow!("No snapshot backup path configured for node {}", node_name)
         })?;
 
-        // List snapshot directories with their corresponding .tar.lz4 file sizes
-        // Format: directory_path lz4_size timestamp
-        let list_cmd = format!(
-            "find '{}' -maxdepth 1 -type d -name '{}_*' 2>/dev/null | while read dir; do \
-             if [ -d \"$dir\" ]; then \
-               lz4_file=\"$dir.tar.lz4\"; \
-               if [ -f \"$lz4_file\" ]; then \
-                 stat -c '%Y' \"$dir\" 2>/dev/null | xargs -I{{}} echo \"$dir $(stat -c '%s' \"$lz4_file\" 2>/dev/null || echo 0) {{}}\"; \
-               else \
-                 stat -c '%n 0 %Y' \"$dir\" 2>/dev/null || echo \"$dir 0 0\"; \
-               fi; \
-             fi; \
-             done | sort -k3 -nr",
-            backup_path, node_config.network
-        );
-
-        let output = match self
+        let listing = match self
             .http_manager
-            .execute_single_command(&node_config.server_host, &list_cmd)
+            .list_agent_snapshots(&node_config.server_host, backup_path, &node_config.network)
             .await
         {
-            Ok(output) => output,
+            Ok(listing) => listing,
             Err(e) => {
                 warn!(
                     "Failed to list snapshots for node {} on {}: {}",
@@ -177,35 +184,8 @@ impl SnapshotManager {
             }
         };
 
-        let mut snapshots = Vec::new();
-        for line in output.lines() {
-            if line.trim().is_empty() {
-                continue;
-            }
-
-            let parts: Vec<&str> = line.split_whitespace().collect();
-            if parts.len() >= 3 {
-                let full_path = parts[0];
-                let filename = full_path.rsplit('/').next().unwrap_or(parts[0]).to_string();
-                let file_size_bytes = parts[1].parse::<u64>().ok();
-                let timestamp_unix = parts[2].parse::<i64>().unwrap_or(0);
-
-                // Use filesystem timestamp as primary source (works with both old timestamp and new block height formats)
-                let created_at = DateTime::from_timestamp(timestamp_unix, 0)
-                    .map(|dt| dt.with_timezone(&Utc))
-                    .unwrap_or_else(Utc::now);
-
-                snapshots.push(SnapshotInfo {
-                    node_name: node_name.to_string(),
-                    network: node_config.network.clone(),
-                    filename,
-                    created_at,
-                    file_size_bytes,
-                    snapshot_path: full_path.to_string(),
-                    compression_type: "directory".to_string(),
-                });
-            }
-        }
+        let snapshots =
+            snapshots_from_listing(node_name, &node_config.network, backup_path, listing);
 
         debug!(
             "Found {} snapshots for network {}",
@@ -258,10 +238,15 @@ impl SnapshotManager {
                 )
             })?;
 
+        let backup_path = node_config
+            .snapshot_backup_path
+            .as_deref()
+            .ok_or_else(|| anyhow::anyhow!("No snapshot backup path configured"))?;
+
         let mut snapshots = self.list_snapshots(node_name).await?;
 
         // Sort by creation date (newest first)
-        snapshots.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+        snapshots.sort_by_key(|s| std::cmp::Reverse(s.created_at));
 
         debug!(
             "Cleanup analysis for {}: found {} snapshots, keeping {}",
@@ -270,26 +255,26 @@ impl SnapshotManager {
             retention_count
         );
 
-        if snapshots.len() <= retention_count as usize {
+        let kept_count = snapshots.len().min(retention_count as usize);
+        let (kept, snapshots_to_delete) = snapshots.split_at(kept_count);
+        let mut deleted_count = 0;
+
+        if snapshots_to_delete.is_empty() {
             info!(
                 "No old network snapshots to clean up for {} (have {}, keeping {})",
                 network,
                 snapshots.len(),
                 retention_count
             );
-            return Ok(0);
+        } else {
+            info!(
+                "Cleaning up {} old network snapshots for {} (keeping {} most recent) via HTTP agent",
+                snapshots_to_delete.len(),
+                network,
+                retention_count
+            );
         }
 
-        let snapshots_to_delete = &snapshots[(retention_count as usize)..];
-        let mut deleted_count = 0;
-
-        info!(
-            "Cleaning up {} old network snapshots for {} (keeping {} most recent) via HTTP agent",
-            snapshots_to_delete.len(),
-            network,
-            retention_count
-        );
-
         for snapshot in snapshots_to_delete {
             info!(
                 "Attempting to delete old snapshot: {} (created: {})",
@@ -300,7 +285,7 @@ impl SnapshotManager {
             match self
                 .delete_snapshot_with_robust_cleanup(
                     &node_config.server_host,
-                    &snapshot.snapshot_path,
+                    backup_path,
                     &snapshot.filename,
                 )
                 .await
@@ -322,138 +307,99 @@ impl SnapshotManager {
             }
         }
 
-        info!(
-            "Cleanup completed: deleted {} out of {} old network snapshots for {}",
-            deleted_count,
-            snapshots_to_delete.len(),
-            network
-        );
+        if !snapshots_to_delete.is_empty() {
+            info!(
+                "Cleanup completed: deleted {} out of {} old network snapshots for {}",
+                deleted_count,
+                snapshots_to_delete.len(),
+                network
+            );
+        }
 
-        // PHASE 2: Clean up orphaned LZ4 files that no longer have corresponding directories
-        let orphaned_lz4_count = self
-            .cleanup_orphaned_lz4_files(node_name, node_config)
+        // PHASE 2: Clean up leftover .partial/.download files that can no longer
+        // become a snapshot worth keeping
+        let retention_full = snapshots.len() >= retention_count as usize;
+        let leftover_count = self
+            .cleanup_orphaned_leftovers(node_name, node_config, kept, retention_full)
             .await?;
 
-        if orphaned_lz4_count > 0 {
+        if leftover_count > 0 {
             info!(
-                "Cleaned up {} orphaned LZ4 archives for network {}",
-                orphaned_lz4_count, network
+                "Cleaned up {} orphaned partial archives and downloads for network {}",
+                leftover_count, network
             );
         }
 
-        Ok(deleted_count + orphaned_lz4_count)
+        Ok(deleted_count + leftover_count)
     }
 
-    /// Clean up orphaned LZ4 files (archives without corresponding directories)
-    async fn cleanup_orphaned_lz4_files(
+    /// Delete `.tar.lz4.partial` and `.tar.lz4.download` files whose snapshot
+    /// will never be usable, see [`orphaned_leftovers`]
+    async fn cleanup_orphaned_leftovers(
         &self,
         node_name: &str,
         node_config: &crate::config::NodeConfig,
+        kept: &[SnapshotInfo],
+        retention_full: bool,
     ) -> Result<u32> {
         let backup_path = node_config
             .snapshot_backup_path
             .as_ref()
             .ok_or_else(|| anyhow::anyhow!("No snapshot backup path configured"))?;
 
-        // Find all LZ4 files for this network (using portable command that works on both Linux and macOS)
-        let list_lz4_cmd = format!(
-            "find '{}' -maxdepth 1 -type f -name '{}_*.tar.lz4' 2>/dev/null | while read f; do basename \"$f\"; done | sort",
-            backup_path, node_config.network
-        );
-
-        let output = match self
+        let listing = match self
             .http_manager
-            .execute_single_command(&node_config.server_host, &list_lz4_cmd)
+            .list_agent_snapshots(&node_config.server_host, backup_path, &node_config.network)
             .await
         {
-            Ok(output) => output,
+            Ok(listing) => listing,
             Err(e) => {
                 warn!(
-                    "Failed to list LZ4 files for network {}: {}",
+                    "Failed to list leftover archives for network {}: {}",
                     node_config.network, e
                 );
-                // Don't fail the entire cleanup, just skip orphaned LZ4 cleanup
+                // Don't fail the entire cleanup, just skip leftover cleanup
                 return Ok(0);
             }
         };
 
-        if output.trim().is_empty() {
-            debug!("No LZ4 files found for network {}", node_config.network);
-            return Ok(0);
-        }
-
-        let lz4_files: Vec<String> = output
-            .lines()
-            .map(|s| s.trim().to_string())
-            .filter(|s| !s.is_empty())
-            .collect();
-
-        debug!(
-            "Found {} LZ4 files for network {}",
-            lz4_files.len(),
-            node_config.network
-        );
-
-        // Get current snapshot directories
-        let snapshots = self.list_snapshots(node_name).await?;
-        let snapshot_basenames: std::collections::HashSet<String> =
-            snapshots.iter().map(|s| s.filename.clone()).collect();
-
         debug!(
-            "Found {} active snapshot directories for network {}",
-            snapshot_basenames.len(),
-            node_config.network
+            "Found {} partial archives and {} downloads for network {} on {}",
+            listing.partial_archives.len(),
+            listing.downloads.len(),
+            node_config.network,
+            node_name
         );
 
-        // Find orphaned LZ4 files (those without a corresponding directory)
-        let mut orphaned_lz4_files = Vec::new();
-        for lz4_file in &lz4_files {
-            // Remove the .tar.lz4 extension to get the base name
-            if let Some(basename) = lz4_file.strip_suffix(".tar.lz4") {
-                if !snapshot_basenames.contains(basename) {
-                    orphaned_lz4_files.push((lz4_file.clone(), basename.to_string()));
-                }
-            }
-        }
-
-        if orphaned_lz4_files.is_empty() {
+        let leftovers = orphaned_leftovers(&listing, kept, retention_full);
+        if leftovers.is_empty() {
             debug!(
-                "No orphaned LZ4 files found for network {}",
+                "No orphaned leftovers found for network {}",
                 node_config.network
             );
             return Ok(0);
         }
 
-        info!(
-            "Found {} orphaned LZ4 files for network {} (no matching directory) - will delete all",
-            orphaned_lz4_files.len(),
-            node_config.network
-        );
-
-        // Orphaned LZ4 files (without matching directories) should be deleted
-        // These are leftover archives from snapshots that have already been cleaned up
-        // We delete ALL orphaned files since they serve no purpose without their directories
         let mut deleted_count = 0;
 
-        for (lz4_file, _basename) in &orphaned_lz4_files {
-            let lz4_path = format!("{}/{}", backup_path, lz4_file);
-            let delete_cmd = format!("rm -f '{}'", lz4_path);
-
-            debug!("Deleting orphaned LZ4 file: {}", lz4_file);
+        for (file, basename) in &leftovers {
+            debug!("Deleting orphaned leftover: {}", file);
 
+            // The agent deletes every file of the snapshot; orphaned_leftovers only
+            // returns names without a directory or complete archive
             match self
                 .http_manager
-                .execute_single_command(&node_config.server_host, &delete_cmd)
+                .delete_agent_snapshot(&node_config.server_host, backup_path, basename)
                 .await
             {
                 Ok(_) => {
-                    info!("Successfully deleted orphaned LZ4 file: {}", lz4_file);
+                    info!("Successfully deleted orphaned leftover: {}", file);
                     deleted_count += 1;
                 }
                 Err(e) => {
                     warn!(
-                        "Failed to delete orphaned LZ4 file {} (continuing): {}",
-                        lz4_file, e
+                        "Failed to delete orphaned leftover {} (continuing): {}",
+                        file, e
                     );
                     // Continue with other deletions
                 }
@@ -463,6 +409,34 @@ impl SnapshotManager {
         Ok(deleted_count)
     }
 
+    /// Verify a snapshot's files against its manifest via HTTP agent
+    pub async fn verify_snapshot(
+        &self,
+        node_name: &str,
+        filename: &str,
+    ) -> Result<AgentSnapshotVerifyReport> {
+        let report = self
+            .http_manager
+            .verify_node_snapshot(node_name, filename)
+            .await?;
+
+        if report.verified {
+            info!(
+                "Snapshot {} verified: {} files match the manifest",
+                filename, report.files_checked
+            );
+        } else {
+            warn!(
+                "Snapshot {} failed verification: {} mismatched, {} missing, {} unexpected",
+                filename,
+                report.mismatched.len(),
+                report.missing.len(),
+                report.unexpected.len()
+            );
+        }
+        Ok(report)
+    }
+
     /// Delete a specific snapshot via HTTP agent - ENHANCED to include LZ4 cleanup
     pub async fn delete_snapshot(&self, node_name: &str, filename: &str) -> Result<()> {
         let node_config = self.get_node_config(node_name)?;
@@ -478,13 +452,8 @@ impl SnapshotManager {
             anyhow::anyhow!("No snapshot backup path configured for node {}", node_name)
         })?;
 
-        let snapshot_path = format!("{}/{}", backup_path, filename);
-        self.delete_snapshot_with_robust_cleanup(
-            &node_config.server_host,
-            &snapshot_path,
-            filename,
-        )
-        .await?;
+        self.delete_snapshot_with_robust_cleanup(&node_config.server_host, backup_path, filename)
+            .await?;
 
         info!(
             "Deleted network snapshot (directory + LZ4) {} via HTTP agent",
@@ -493,80 +462,32 @@ impl SnapshotManager {
         Ok(())
     }
 
-    /// FIXED: Robust snapshot deletion with separated commands and better error handling
+    /// Delete a snapshot directory and its LZ4 archive through the agent's typed endpoint
     async fn delete_snapshot_with_robust_cleanup(
         &self,
         server_host: &str,
-        dir_path: &str,
+        backup_path: &str,
         filename: &str,
     ) -> Result<()> {
-        // Extract the backup path from the full directory path
-        let backup_path = dir_path
-            .rsplit_once('/')
-            .map(|(path, _)| path)
-            .unwrap_or("");
-        let lz4_path = format!("{}/{}.tar.lz4", backup_path, filename);
-
-        debug!("Deleting snapshot directory: {}", dir_path);
-        debug!("Checking for LZ4 file: {}", lz4_path);
+        debug!("Deleting snapshot {} in {}", filename, backup_path);
 
-        // Step 1: Delete the directory first
-        let delete_dir_cmd = format!("rm -rf '{}'", dir_path);
-        debug!("Executing directory deletion: {}", delete_dir_cmd);
-
-        match self
+        let result = self
             .http_manager
-            .execute_single_command(server_host, &delete_dir_cmd)
+            .delete_agent_snapshot(server_host, backup_path, filename)
             .await
-        {
-            Ok(_) => {
-                info!("Successfully deleted snapshot directory: {}", filename);
-            }
-            Err(e) => {
-                error!("Failed to delete snapshot directory {}: {}", filename, e);
-                return Err(anyhow::anyhow!(
-                    "Failed to delete snapshot directory {}: {}",
-                    filename,
-                    e
-                ));
-            }
-        }
-
-        // Step 2: Check if LZ4 file exists and delete it
-        let check_lz4_cmd = format!("test -f '{}'", lz4_path);
-        debug!("Checking if LZ4 file exists: {}", check_lz4_cmd);
+            .map_err(|e| {
+                error!("Failed to delete snapshot {}: {}", filename, e);
+                anyhow::anyhow!("Failed to delete snapshot directory {}: {}", filename, e)
+            })?;
 
-        match self
-            .http_manager
-            .execute_single_command(server_host, &check_lz4_cmd)
-            .await
-        {
-            Ok(_) => {
-                // LZ4 file exists, delete it
-                let delete_lz4_cmd = format!("rm -f '{}'", lz4_path);
-                debug!("Deleting LZ4 file: {}", delete_lz4_cmd);
-
-                match self
-                    .http_manager
-                    .execute_single_command(server_host, &delete_lz4_cmd)
-                    .await
-                {
-                    Ok(_) => {
-                        info!("Successfully deleted LZ4 file for snapshot: {}", filename);
-                    }
-                    Err(e) => {
-                        warn!(
-                            "Failed to delete LZ4 file for {} (continuing anyway): {}",
-                            filename, e
-                        );
-                        // Don't fail the entire operation for LZ4 cleanup issues
-                    }
-                }
-            }
-            Err(_) => {
-                debug!("No LZ4 file found for snapshot: {}", filename);
-                // This is not an error - LZ4 files might not exist for all snapshots
-            }
+        if result.directory_removed {
+            info!("Successfully deleted snapshot directory: {}", filename);
+        }
+        if result.archive_removed {
+            info!("Successfully deleted LZ4 file for snapshot: {}", filename);
+        } else {
+            // Not an error - LZ4 files might not exist for all snapshots
+            debug!("No LZ4 file found for snapshot: {}", filename);
         }
 
         Ok(())
@@ -606,3 +527,123 @@ impl SnapshotManager {
             .ok_or_else(|| anyhow::anyhow!("Node {} not found", node_name))
     }
 }
+
+/// Snapshots in an agent listing, newest first. Directories come with the state
+/// of their archive; a complete archive whose directory is gone (e.g. one pulled
+/// from another server) is a snapshot of its own and restorable as-is.
+pub fn snapshots_from_listing(
+    node_name: &str,
+    network: &str,
+    backup_path: &str,
+    listing: AgentSnapshotListing,
+) -> Vec<SnapshotInfo> {
+    let created_at = |modified_unix: i64| {
+        // Use filesystem timestamp as primary source (works with both old timestamp and new block height formats)
+        DateTime::from_timestamp(modified_unix, 0)
+            .map(|dt| dt.with_timezone(&Utc))
+            .unwrap_or_else(Utc::now)
+    };
+
+    let directories: HashSet<String> = listing.snapshots.iter().map(|s| s.name.clone()).collect();
+    let mut snapshots: Vec<SnapshotInfo> = listing
+        .snapshots
+        .into_iter()
+        .map(|entry| SnapshotInfo {
+            node_name: node_name.to_string(),
+            network: network.to_string(),
+            filename: entry.name,
+            created_at: created_at(entry.modified_unix),
+            file_size_bytes: Some(entry.archive_size_bytes.unwrap_or(0)),
+            snapshot_path: entry.path,
+            compression_type: "directory".to_string(),
+            archive_status: Some(if entry.archive_size_bytes.is_some() {
+                ArchiveStatus::Complete
+            } else if entry.archive_in_progress {
+                ArchiveStatus::InProgress
+            } else {
+                ArchiveStatus::Missing
+            }),
+            compression_job_id: None,
+        })
+        .collect();
+
+    for archive in listing.archives {
+        let Some(basename) = archive.name.strip_suffix(".tar.lz4") else {
+            continue;
+        };
+        if directories.contains(basename) {
+            continue;
+        }
+        snapshots.push(SnapshotInfo {
+            node_name: node_name.to_string(),
+            network: network.to_string(),
+            filename: basename.to_string(),
+            created_at: created_at(archive.modified_unix),
+            file_size_bytes: Some(archive.size_bytes),
+            snapshot_path: format!("{}/{}", backup_path, archive.name),
+            compression_type: "lz4".to_string(),
+            archive_status: Some(ArchiveStatus::Complete),
+            compression_job_id: None,
+        });
+    }
+
+    snapshots.sort_by_key(|s| std::cmp::Reverse(s.created_at));
+    snapshots
+}
+
+/// `.tar.lz4.partial` and `.tar.lz4.download` files that can be deleted, as
+/// `(file name, snapshot name)`. A partial archive is orphaned once its
+/// directory is gone, since its compression can no longer finish. A download is
+/// orphaned once retention is full and it is below the block height of every
+/// kept snapshot, since retention would delete it as soon as it completed;
+/// other downloads may still be running or resumed. Leftovers of a snapshot
+/// that has a directory or complete archive are never returned, because the
+/// agent deletes all of a snapshot's files together.
+pub fn orphaned_leftovers(
+    listing: &AgentSnapshotListing,
+    kept: &[SnapshotInfo],
+    retention_full: bool,
+) -> Vec<(String, String)> {
+    let snapshot_names: HashSet<&str> = listing
+        .snapshots
+        .iter()
+        .map(|s| s.name.as_str())
+        .chain(
+            listing
+                .archives
+                .iter()
+                .filter_map(|a| a.name.strip_suffix(".tar.lz4")),
+        )
+        .collect();
+    let lowest_kept_height = kept
+        .iter()
+        .map(|s| block_height(&s.filename).unwrap_or(0))
+        .min();
+
+    let partials = listing
+        .partial_archives
+        .iter()
+        .filter_map(|archive| Some((archive, archive.name.strip_suffix(".tar.lz4.partial")?)));
+    let downloads = listing
+        .downloads
+        .iter()
+        .filter_map(|archive| Some((archive, archive.name.strip_suffix(".tar.lz4.download")?)))
+        .filter(|(_, basename)| {
+            retention_full
+                && match (block_height(basename), lowest_kept_height) {
+                    (Some(height), Some(lowest)) => height < lowest,
+                    _ => false,
+                }
+        });
+
+    partials
+        .chain(downloads)
+        .filter(|(_, basename)| !snapshot_names.contains(basename))
+        .map(|(archive, basename)| (archive.name.clone(), basename.to_string()))
+        .collect()
+}
+
+/// Block height from a `{network}_{date}_{block_height}` snapshot name
+fn block_height(snapshot_name: &str) -> Option<u64> {
+    snapshot_name.rsplit('_').next()?.parse().ok()
+}
@@ -30,4 +30,4 @@
 
 pub mod manager;
 
-pub use manager::{SnapshotInfo, SnapshotManager, SnapshotStats};
+pub use manager::{ArchiveStatus, SnapshotInfo, SnapshotManager, SnapshotStats};
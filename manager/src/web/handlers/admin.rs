@@ -13,6 +13,11 @@ use serde_json::{json, Value};
 use tracing::{error, info};
 
 use super::common::{ApiResponse, ApiResult};
+use crate::config::{
+    AgentScheme, AgentTlsConfig, ServerConfig, LOG_ROTATION_COMPRESSIONS, LOG_SOURCES,
+    TRUNCATE_LOGS_MODES,
+};
+use crate::http::AgentClients;
 use crate::web::AppState;
 
 // ============================================================================
@@ -28,6 +33,8 @@ pub struct CreateServerRequest {
     pub api_key_ref: String,
     #[serde(default = "default_request_timeout")]
     pub request_timeout_seconds: u64,
+    #[serde(flatten)]
+    pub tls: AgentTlsConfig,
 }
 
 fn default_agent_port() -> u16 {
@@ -45,6 +52,10 @@ pub struct UpdateServerRequest {
     pub agent_port: Option<u16>,
     pub api_key_ref: Option<String>,
     pub request_timeout_seconds: Option<u64>,
+    pub agent_scheme: Option<AgentScheme>,
+    pub ca_cert_path: Option<String>,
+    pub client_cert_path: Option<String>,
+    pub client_key_path: Option<String>,
 }
 
 pub async fn get_all_servers(State(state): State<AppState>) -> ApiResult<Value> {
@@ -73,6 +84,10 @@ pub async fn get_all_servers(State(state): State<AppState>) -> ApiResult<Value>
                         "agent_port": s.agent_port,
                         "api_key_configured": !s.api_key_ref.is_empty(),
                         "request_timeout_seconds": s.request_timeout_seconds,
+                        "agent_scheme": s.agent_scheme,
+                        "ca_cert_path": s.ca_cert_path,
+                        "client_cert_path": s.client_cert_path,
+                        "client_key_path": s.client_key_path,
                         "created_at": s.created_at.to_rfc3339(),
                         "updated_at": s.updated_at.to_rfc3339()
                     })
@@ -111,6 +126,10 @@ pub async fn get_server(Path(id): Path<String>, State(state): State<AppState>) -
             "agent_port": server.agent_port,
             "api_key_configured": !server.api_key_ref.is_empty(),
             "request_timeout_seconds": server.request_timeout_seconds,
+            "agent_scheme": server.agent_scheme,
+            "ca_cert_path": server.ca_cert_path,
+            "client_cert_path": server.client_cert_path,
+            "client_key_path": server.client_key_path,
             "created_at": server.created_at.to_rfc3339(),
             "updated_at": server.updated_at.to_rfc3339()
         })))),
@@ -166,13 +185,31 @@ pub async fn create_server(
     }
 
     // Test connectivity to the agent
-    let test_url = format!("http://{}:{}/status/busy", req.host, req.agent_port);
+    let probe_config = ServerConfig {
+        host: req.host.clone(),
+        agent_port: req.agent_port,
+        api_key: String::new(),
+        request_timeout_seconds: req.request_timeout_seconds,
+        max_concurrent_requests: None,
+        tls: req.tls.clone(),
+    };
+    let test_url = probe_config.agent_url("/status/busy");
     info!("Testing connectivity to agent at {}", test_url);
 
-    let client = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(10))
-        .build()
-        .unwrap_or_default();
+    let client = AgentClients::new(
+        Some(std::time::Duration::from_secs(10)),
+        std::time::Duration::from_secs(10),
+    )
+    .get(&probe_config)
+    .map_err(|e| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(ApiResponse::error(format!(
+                "Invalid TLS settings for agent at {}:{} - {:#}",
+                req.host, req.agent_port, e
+            ))),
+        )
+    })?;
 
     if let Err(e) = client.get(&test_url).send().await {
         return Err((
@@ -191,6 +228,7 @@ pub async fn create_server(
             req.agent_port,
             req.api_key_ref,
             req.request_timeout_seconds,
+            req.tls,
         )
         .await
     {
@@ -261,6 +299,18 @@ pub async fn update_server(
     if let Some(timeout) = req.request_timeout_seconds {
         server.request_timeout_seconds = timeout as i64;
     }
+    if let Some(scheme) = req.agent_scheme {
+        server.agent_scheme = scheme.to_string();
+    }
+    if let Some(path) = req.ca_cert_path {
+        server.ca_cert_path = Some(path);
+    }
+    if let Some(path) = req.client_cert_path {
+        server.client_cert_path = Some(path);
+    }
+    if let Some(path) = req.client_key_path {
+        server.client_key_path = Some(path);
+    }
 
     match store.update_server(server).await {
         Ok(updated) => {
@@ -334,6 +384,10 @@ pub struct CreateNodeRequest {
     pub deploy_path: Option<String>,
     pub log_path: Option<String>,
     pub snapshot_backup_path: Option<String>,
+    // Log source
+    pub log_source: Option<String>,
+    pub log_glob: Option<String>,
+    pub log_journald_unit: Option<String>,
     // Pruning
     #[serde(default)]
     pub pruning_enabled: bool,
@@ -359,6 +413,10 @@ pub struct CreateNodeRequest {
     pub log_monitoring_patterns: Option<Vec<String>>,
     #[serde(default)]
     pub truncate_logs_enabled: bool,
+    pub truncate_logs_mode: Option<String>,
+    pub log_rotation_compression: Option<String>,
+    pub log_rotation_keep_generations: Option<i64>,
+    pub log_rotation_keep_bytes: Option<i64>,
 }
 
 #[derive(Deserialize)]
@@ -372,6 +430,9 @@ pub struct UpdateNodeRequest {
     pub deploy_path: Option<String>,
     pub log_path: Option<String>,
     pub snapshot_backup_path: Option<String>,
+    pub log_source: Option<String>,
+    pub log_glob: Option<String>,
+    pub log_journald_unit: Option<String>,
     pub pruning_enabled: Option<bool>,
     pub pruning_schedule: Option<String>,
     pub pruning_keep_blocks: Option<i64>,
@@ -387,6 +448,10 @@ pub struct UpdateNodeRequest {
     pub log_monitoring_enabled: Option<bool>,
     pub log_monitoring_patterns: Option<Vec<String>>,
     pub truncate_logs_enabled: Option<bool>,
+    pub truncate_logs_mode: Option<String>,
+    pub log_rotation_compression: Option<String>,
+    pub log_rotation_keep_generations: Option<i64>,
+    pub log_rotation_keep_bytes: Option<i64>,
 }
 
 #[derive(Deserialize)]
@@ -510,6 +575,18 @@ pub async fn create_node(
         Ok(Some(_)) => {}
     }
 
+    check_choice("log_source", req.log_source.as_deref(), &LOG_SOURCES)?;
+    check_choice(
+        "truncate_logs_mode",
+        req.truncate_logs_mode.as_deref(),
+        &TRUNCATE_LOGS_MODES,
+    )?;
+    check_choice(
+        "log_rotation_compression",
+        req.log_rotation_compression.as_deref(),
+        &LOG_ROTATION_COMPRESSIONS,
+    )?;
+
     // Test RPC connectivity
     info!("Testing RPC connectivity to {}", req.rpc_url);
     let client = reqwest::Client::builder()
@@ -540,6 +617,9 @@ pub async fn create_node(
         deploy_path: req.deploy_path,
         log_path: req.log_path,
         snapshot_backup_path: req.snapshot_backup_path,
+        log_source: req.log_source,
+        log_glob: req.log_glob,
+        log_journald_unit: req.log_journald_unit,
         pruning_enabled: req.pruning_enabled,
         pruning_schedule: req.pruning_schedule,
         pruning_keep_blocks: req.pruning_keep_blocks,
@@ -560,6 +640,10 @@ pub async fn create_node(
             .log_monitoring_patterns
             .map(|v| serde_json::to_string(&v).unwrap_or_default()),
         truncate_logs_enabled: req.truncate_logs_enabled,
+        truncate_logs_mode: req.truncate_logs_mode,
+        log_rotation_compression: req.log_rotation_compression,
+        log_rotation_keep_generations: req.log_rotation_keep_generations,
+        log_rotation_keep_bytes: req.log_rotation_keep_bytes,
         created_at: now,
         updated_at: now,
     };
@@ -592,6 +676,26 @@ pub async fn create_node(
     }
 }
 
+/// Reject values of a node setting that take one of a fixed set of names
+fn check_choice(
+    field: &str,
+    value: Option<&str>,
+    allowed: &[&str],
+) -> Result<(), (StatusCode, Json<ApiResponse<()>>)> {
+    match value {
+        Some(value) if !allowed.contains(&value) => Err((
+            StatusCode::BAD_REQUEST,
+            Json(ApiResponse::error(format!(
+                "Invalid {} '{}', expected one of: {}",
+                field,
+                value,
+                allowed.join(", ")
+            ))),
+        )),
+        _ => Ok(()),
+    }
+}
+
 pub async fn update_node(
     Path(id): Path<String>,
     State(state): State<AppState>,
@@ -653,6 +757,16 @@ pub async fn update_node(
     if let Some(v) = req.snapshot_backup_path {
         node.snapshot_backup_path = Some(v);
     }
+    if let Some(v) = req.log_source {
+        check_choice("log_source", Some(&v), &LOG_SOURCES)?;
+        node.log_source = Some(v);
+    }
+    if let Some(v) = req.log_glob {
+        node.log_glob = Some(v);
+    }
+    if let Some(v) = req.log_journald_unit {
+        node.log_journald_unit = Some(v);
+    }
     if let Some(v) = req.pruning_enabled {
         node.pruning_enabled = v;
     }
@@ -698,6 +812,24 @@ pub async fn update_node(
     if let Some(v) = req.truncate_logs_enabled {
         node.truncate_logs_enabled = v;
     }
+    if let Some(v) = req.truncate_logs_mode {
+        check_choice("truncate_logs_mode", Some(&v), &TRUNCATE_LOGS_MODES)?;
+        node.truncate_logs_mode = Some(v);
+    }
+    if let Some(v) = req.log_rotation_compression {
+        check_choice(
+            "log_rotation_compression",
+            Some(&v),
+            &LOG_ROTATION_COMPRESSIONS,
+        )?;
+        node.log_rotation_compression = Some(v);
+    }
+    if let Some(v) = req.log_rotation_keep_generations {
+        node.log_rotation_keep_generations = Some(v);
+    }
+    if let Some(v) = req.log_rotation_keep_bytes {
+        node.log_rotation_keep_bytes = Some(v);
+    }
 
     match store.update_node(node).await {
         Ok(updated) => {
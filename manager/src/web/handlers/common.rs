@@ -51,6 +51,13 @@ pub struct RetentionQuery {
     pub retention_count: u32,
 }
 
+#[derive(Deserialize)]
+pub struct RestoreQuery {
+    /// Restore even if the snapshot fails verification against its manifest
+    #[serde(default)]
+    pub force: bool,
+}
+
 #[derive(Deserialize)]
 pub struct EmergencyCleanupQuery {
     #[serde(default = "default_max_hours")]
@@ -61,10 +68,29 @@ fn default_max_hours() -> i64 {
     12
 }
 
+#[derive(Deserialize)]
+pub struct LogStreamQuery {
+    /// Regular expression lines must match to be streamed
+    pub filter: Option<String>,
+    /// Lines of existing output sent before following; the agent's default when unset
+    pub tail_lines: Option<usize>,
+}
+
+#[derive(Deserialize)]
+pub struct HistoryQuery {
+    #[serde(default = "default_history_limit")]
+    pub limit: i64,
+}
+
+fn default_history_limit() -> i64 {
+    50
+}
+
 // Health status conversion helpers
 pub async fn convert_health_to_summary(
     health: &crate::health::HealthStatus,
     config: &crate::config::Config,
+    resources: Option<crate::health::NodeResources>,
 ) -> NodeHealthSummary {
     let node_config = config.nodes.get(&health.node_name);
 
@@ -110,6 +136,7 @@ pub async fn convert_health_to_summary(
             .unwrap_or(false),
         snapshot_retention_count: node_config
             .and_then(|c| c.snapshot_retention_count.map(|cnt| cnt as u32)),
+        resources,
     }
 }
 
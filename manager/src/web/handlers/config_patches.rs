@@ -0,0 +1,69 @@
+// Fleet-wide node config.toml and app.toml patches
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::Json,
+};
+use tracing::{error, info};
+
+use super::common::{ApiResponse, ApiResult, HistoryQuery};
+use crate::services::config_patch_service::{
+    ConfigPatchRecord, ConfigPatchRequest, ConfigPatchRun, NodeConfigPatchPreview,
+};
+use crate::web::AppState;
+
+/// Diff of a config patch on every selected node, without changing any file
+pub async fn preview_config_patch(
+    State(state): State<AppState>,
+    Json(request): Json<ConfigPatchRequest>,
+) -> ApiResult<Vec<NodeConfigPatchPreview>> {
+    match state.config_patch_service.preview(&request).await {
+        Ok(previews) => Ok(Json(ApiResponse::success(previews))),
+        Err(e) => Err((
+            StatusCode::BAD_REQUEST,
+            Json(ApiResponse::error(e.to_string())),
+        )),
+    }
+}
+
+/// Start rolling a config patch out to the selected nodes
+pub async fn apply_config_patch(
+    State(state): State<AppState>,
+    Json(request): Json<ConfigPatchRequest>,
+) -> ApiResult<ConfigPatchRun> {
+    match state.config_patch_service.apply(request).await {
+        Ok(run) => {
+            info!(
+                "Config patch run {} started for {} nodes",
+                run.run_id,
+                run.nodes.len()
+            );
+            Ok(Json(ApiResponse::success(run)))
+        }
+        Err(e) => {
+            error!("Failed to start config patch: {}", e);
+            Err((
+                StatusCode::BAD_REQUEST,
+                Json(ApiResponse::error(e.to_string())),
+            ))
+        }
+    }
+}
+
+/// Config patches applied to nodes, newest first
+pub async fn list_config_patches(
+    Query(query): Query<HistoryQuery>,
+    State(state): State<AppState>,
+) -> ApiResult<Vec<ConfigPatchRecord>> {
+    match state.config_patch_service.history(query.limit).await {
+        Ok(records) => Ok(Json(ApiResponse::success(records))),
+        Err(e) => {
+            error!("Failed to list config patches: {}", e);
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiResponse::error(e.to_string())),
+            ))
+        }
+    }
+}
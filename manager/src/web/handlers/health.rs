@@ -5,14 +5,26 @@ use axum::{
     http::StatusCode,
     response::Json,
 };
-use tracing::{error, info};
+use tracing::{error, info, warn};
 
 use super::common::{
     convert_health_to_summary, convert_hermes_health_to_instance, ApiResponse, ApiResult,
     IncludeDisabledQuery,
 };
+use crate::health::NodeResources;
 use crate::web::{AppState, HermesInstance, NodeHealthSummary};
 
+/// Latest resource telemetry for a node; missing readings are not an error
+async fn node_resources(state: &AppState, node_name: &str) -> Option<NodeResources> {
+    match state.health_monitor.get_node_resources(node_name).await {
+        Ok(resources) => Some(resources),
+        Err(e) => {
+            warn!("Failed to load resource telemetry for {}: {}", node_name, e);
+            None
+        }
+    }
+}
+
 /// Get cached health status for all nodes
 pub async fn get_all_nodes_health(
     Query(query): Query<IncludeDisabledQuery>,
@@ -24,7 +36,12 @@ pub async fn get_all_nodes_health(
 
             for health in health_statuses {
                 if query.include_disabled || health.enabled {
-                    let summary = convert_health_to_summary(&health, &state.config).await;
+                    let summary = convert_health_to_summary(
+                        &health,
+                        &state.config,
+                        node_resources(&state, &health.node_name).await,
+                    )
+                    .await;
                     summaries.push(summary);
                 }
             }
@@ -53,7 +70,12 @@ pub async fn refresh_all_nodes_health(
 
             for health in health_statuses {
                 if query.include_disabled || health.enabled {
-                    let summary = convert_health_to_summary(&health, &state.config).await;
+                    let summary = convert_health_to_summary(
+                        &health,
+                        &state.config,
+                        node_resources(&state, &health.node_name).await,
+                    )
+                    .await;
                     summaries.push(summary);
                 }
             }
@@ -77,7 +99,12 @@ pub async fn get_node_health(
 ) -> ApiResult<NodeHealthSummary> {
     match state.health_monitor.get_node_health(&node_name).await {
         Ok(Some(health_status)) => {
-            let summary = convert_health_to_summary(&health_status, &state.config).await;
+            let summary = convert_health_to_summary(
+                &health_status,
+                &state.config,
+                node_resources(&state, &node_name).await,
+            )
+            .await;
             Ok(Json(ApiResponse::success(summary)))
         }
         Ok(None) => Err((
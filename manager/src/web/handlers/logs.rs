@@ -0,0 +1,149 @@
+// Live node log streaming and rotated log archives
+
+use axum::{
+    body::Body,
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Json, Response},
+};
+use tracing::{error, info};
+
+use super::common::{ApiResponse, ApiResult, LogStreamQuery};
+use crate::http::agent_manager::AgentLogArchiveListing;
+use crate::web::AppState;
+
+/// Stream a node's log as Server-Sent Events, proxied from its agent with the
+/// server's stored credentials
+pub async fn stream_node_logs(
+    Path(node_name): Path<String>,
+    Query(query): Query<LogStreamQuery>,
+    State(state): State<AppState>,
+) -> Response {
+    if !state.config.nodes.contains_key(&node_name) {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(ApiResponse::<()>::error(format!(
+                "Node {} not found",
+                node_name
+            ))),
+        )
+            .into_response();
+    }
+
+    info!("Log stream requested for: {}", node_name);
+    let response = match state
+        .http_agent_manager
+        .open_log_stream(&node_name, query.filter.as_deref(), query.tail_lines)
+        .await
+    {
+        Ok(response) => response,
+        Err(e) => {
+            error!("Failed to open log stream for {}: {}", node_name, e);
+            return (
+                StatusCode::BAD_GATEWAY,
+                Json(ApiResponse::<()>::error(e.to_string())),
+            )
+                .into_response();
+        }
+    };
+
+    (
+        [
+            (header::CONTENT_TYPE, "text/event-stream"),
+            (header::CACHE_CONTROL, "no-cache"),
+        ],
+        proxy_body(response),
+    )
+        .into_response()
+}
+
+/// List the compressed logs kept by rotations of a node's log files
+pub async fn list_node_log_archives(
+    Path(node_name): Path<String>,
+    State(state): State<AppState>,
+) -> ApiResult<AgentLogArchiveListing> {
+    if !state.config.nodes.contains_key(&node_name) {
+        return Err((
+            StatusCode::NOT_FOUND,
+            Json(ApiResponse::error(format!("Node {} not found", node_name))),
+        ));
+    }
+
+    match state.http_agent_manager.list_log_archives(&node_name).await {
+        Ok(listing) => Ok(Json(ApiResponse::success(listing))),
+        Err(e) => {
+            error!("Failed to list log archives of {}: {}", node_name, e);
+            Err((
+                StatusCode::BAD_GATEWAY,
+                Json(ApiResponse::error(e.to_string())),
+            ))
+        }
+    }
+}
+
+/// Download one of a node's log archives, proxied from its agent
+pub async fn download_node_log_archive(
+    Path((node_name, archive_name)): Path<(String, String)>,
+    State(state): State<AppState>,
+) -> Response {
+    if !state.config.nodes.contains_key(&node_name) {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(ApiResponse::<()>::error(format!(
+                "Node {} not found",
+                node_name
+            ))),
+        )
+            .into_response();
+    }
+
+    info!("Log archive {} requested for: {}", archive_name, node_name);
+    let response = match state
+        .http_agent_manager
+        .open_log_archive(&node_name, &archive_name)
+        .await
+    {
+        Ok(response) => response,
+        Err(e) => {
+            error!("Failed to download log archive of {}: {}", node_name, e);
+            return (
+                StatusCode::BAD_GATEWAY,
+                Json(ApiResponse::<()>::error(e.to_string())),
+            )
+                .into_response();
+        }
+    };
+
+    let content_type = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("application/octet-stream")
+        .to_string();
+    let disposition = format!(
+        "attachment; filename=\"{}\"",
+        archive_name.replace(['"', '\\'], "")
+    );
+    (
+        [
+            (header::CONTENT_TYPE, content_type),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        proxy_body(response),
+    )
+        .into_response()
+}
+
+/// Forward an agent response's chunks as they arrive; dropping the body
+/// closes the agent connection
+fn proxy_body(response: reqwest::Response) -> Body {
+    let chunks = futures::stream::unfold(Some(response), |response| async move {
+        let mut response = response?;
+        match response.chunk().await {
+            Ok(Some(chunk)) => Some((Ok::<_, reqwest::Error>(chunk), Some(response))),
+            Ok(None) => None,
+            Err(e) => Some((Err(e), None)),
+        }
+    });
+    Body::from_stream(chunks)
+}
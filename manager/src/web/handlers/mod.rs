@@ -4,7 +4,9 @@
 //! - `admin` - CRUD operations for servers, nodes, hermes, and settings
 //! - `common` - Shared types, query structs, and utilities
 //! - `config` - Read-only configuration endpoints
+//! - `config_patches` - Previewing and rolling out node config file changes
 //! - `health` - Health monitoring endpoints
+//! - `logs` - Live node log streaming and rotated log archives
 //! - `maintenance` - Manual operation execution endpoints
 //! - `operations` - Operation tracking and management
 //! - `snapshots` - Snapshot and state sync operations
@@ -12,7 +14,9 @@
 pub mod admin;
 pub mod common;
 pub mod config;
+pub mod config_patches;
 pub mod health;
+pub mod logs;
 pub mod maintenance;
 pub mod operations;
 pub mod snapshots;
@@ -21,7 +25,9 @@ pub mod snapshots;
 // Note: common module is internal, used only by sibling modules
 pub use admin::*;
 pub use config::*;
+pub use config_patches::*;
 pub use health::*;
+pub use logs::*;
 pub use maintenance::*;
 pub use operations::*;
 pub use snapshots::*;
@@ -30,11 +30,9 @@ pub async fn cancel_operation(
         .cancel_operation(&target_name)
         .await
     {
-        Ok(_) => {
-            info!("Operation cancelled successfully for {}", target_name);
-            Ok(Json(ApiResponse::success(json!({
-                "message": format!("Operation cancelled for {}", target_name)
-            }))))
+        Ok(message) => {
+            info!("{}", message);
+            Ok(Json(ApiResponse::success(json!({ "message": message }))))
         }
         Err(e) => {
             error!("Failed to cancel operation for {}: {}", target_name, e);
@@ -9,7 +9,7 @@ use chrono::Utc;
 use serde_json::{json, Value};
 use tracing::{error, info};
 
-use super::common::{ApiResponse, ApiResult, RetentionQuery};
+use super::common::{ApiResponse, ApiResult, RestoreQuery, RetentionQuery};
 use crate::snapshot::{SnapshotInfo, SnapshotStats};
 use crate::web::AppState;
 
@@ -116,6 +116,65 @@ pub async fn delete_snapshot(
     }
 }
 
+/// Verify a snapshot's files against its manifest
+pub async fn verify_snapshot(
+    Path((node_name, filename)): Path<(String, String)>,
+    State(state): State<AppState>,
+) -> ApiResult<Value> {
+    info!(
+        "Snapshot verification requested for {}: {}",
+        node_name, filename
+    );
+
+    let node_name_clone = node_name.clone();
+    let filename_clone = filename.clone();
+    let snapshot_service = state.snapshot_service.clone();
+
+    match state
+        .operation_executor
+        .execute_async("snapshot_verify", &node_name, move || {
+            let snapshot_service = snapshot_service.clone();
+            let node_name = node_name_clone.clone();
+            let filename = filename_clone.clone();
+            async move {
+                let report = snapshot_service
+                    .verify_snapshot(&node_name, &filename)
+                    .await?;
+                if report.verified {
+                    Ok(())
+                } else {
+                    Err(anyhow::anyhow!(
+                        "Snapshot {} failed verification: {} mismatched, {} missing, {} unexpected files",
+                        filename,
+                        report.mismatched.len(),
+                        report.missing.len(),
+                        report.unexpected.len()
+                    ))
+                }
+            }
+        })
+        .await
+    {
+        Ok(operation_id) => Ok(Json(ApiResponse::success(json!({
+            "message": format!("Verification started for snapshot {}", filename),
+            "operation_id": operation_id,
+            "node_name": node_name,
+            "filename": filename,
+            "status": "started"
+        })))),
+        Err(e) => {
+            error!(
+                "Failed to start verification of {} for {}: {}",
+                filename, node_name, e
+            );
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiResponse::error(e.to_string())),
+            ))
+        }
+    }
+}
+
 /// Get snapshot statistics for a node
 pub async fn get_snapshot_stats(
     Path(node_name): Path<String>,
@@ -166,12 +225,14 @@ pub async fn cleanup_old_snapshots(
 /// Restore from latest snapshot
 pub async fn execute_manual_restore_from_latest(
     Path(node_name): Path<String>,
+    Query(query): Query<RestoreQuery>,
     State(state): State<AppState>,
 ) -> ApiResult<Value> {
     info!(
-        "Manual restore from latest snapshot requested for: {}",
-        node_name
+        "Manual restore from latest snapshot requested for: {} (force: {})",
+        node_name, query.force
     );
+    let force = query.force;
 
     let node_name_clone = node_name.clone();
     let snapshot_service = state.snapshot_service.clone();
@@ -183,7 +244,7 @@ pub async fn execute_manual_restore_from_latest(
             let node_name = node_name_clone.clone();
             async move {
                 snapshot_service
-                    .restore_from_snapshot(&node_name)
+                    .restore_from_snapshot(&node_name, force)
                     .await
                     .map(|_| ())
             }
@@ -13,7 +13,9 @@ use crate::health::HealthMonitor;
 use crate::http::HttpAgentManager;
 use crate::operation_tracker::SimpleOperationTracker;
 use crate::scheduler::MaintenanceScheduler;
-use crate::services::{HermesService, OperationExecutor, SnapshotService, StateSyncService};
+use crate::services::{
+    ConfigPatchService, HermesService, OperationExecutor, SnapshotService, StateSyncService,
+};
 use crate::snapshot::SnapshotManager;
 
 // Application state shared across all handlers
@@ -30,6 +32,7 @@ pub struct AppState {
     // Note: MaintenanceService removed from AppState - only used by scheduler, not web handlers
     pub snapshot_service: Arc<SnapshotService>,
     pub state_sync_service: Arc<StateSyncService>,
+    pub config_patch_service: Arc<ConfigPatchService>,
     // Low-level infrastructure services (kept for background tasks)
     pub health_monitor: Arc<HealthMonitor>,
     pub http_agent_manager: Arc<HttpAgentManager>,
@@ -51,6 +54,7 @@ impl AppState {
         _maintenance_service: Arc<crate::services::MaintenanceService>, // Only used by scheduler
         snapshot_service: Arc<SnapshotService>,
         state_sync_service: Arc<StateSyncService>,
+        config_patch_service: Arc<ConfigPatchService>,
     ) -> Self {
         Self {
             config,
@@ -60,6 +64,7 @@ impl AppState {
             hermes_service,
             snapshot_service,
             state_sync_service,
+            config_patch_service,
             health_monitor,
             http_agent_manager: http_manager,
         }
@@ -82,6 +87,8 @@ pub struct NodeHealthSummary {
     pub auto_restore_enabled: bool,
     pub scheduled_snapshots_enabled: bool,
     pub snapshot_retention_count: Option<u32>,
+    /// Latest CPU, memory and disk readings, if the agent has reported any
+    pub resources: Option<crate::health::NodeResources>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -5,7 +5,9 @@ use crate::health::HealthMonitor;
 use crate::http::HttpAgentManager;
 use crate::operation_tracker::SimpleOperationTracker;
 use crate::scheduler::MaintenanceScheduler;
-use crate::services::{HermesService, OperationExecutor, SnapshotService, StateSyncService};
+use crate::services::{
+    ConfigPatchService, HermesService, OperationExecutor, SnapshotService, StateSyncService,
+};
 use crate::snapshot::SnapshotManager;
 use crate::web::{handlers, AppState};
 use anyhow::Result;
@@ -33,6 +35,7 @@ pub async fn start_web_server(
     maintenance_service: Arc<crate::services::MaintenanceService>,
     snapshot_service_v2: Arc<SnapshotService>,
     state_sync_service: Arc<StateSyncService>,
+    config_patch_service: Arc<ConfigPatchService>,
 ) -> Result<()> {
     let state = AppState::new(
         config.clone(),
@@ -48,6 +51,7 @@ pub async fn start_web_server(
         maintenance_service,
         snapshot_service_v2,
         state_sync_service,
+        config_patch_service,
     );
 
     if state.config.host == "0.0.0.0" && state.config.port == 8095 {
@@ -111,6 +115,19 @@ fn create_router(state: AppState) -> Router {
             "/api/maintenance/hermes/{hermes_name}/restart",
             post(handlers::execute_manual_hermes_restart),
         )
+        // === LOG ROUTES ===
+        .route(
+            "/api/nodes/{node_name}/logs/stream",
+            get(handlers::stream_node_logs),
+        )
+        .route(
+            "/api/nodes/{node_name}/logs/archives",
+            get(handlers::list_node_log_archives),
+        )
+        .route(
+            "/api/nodes/{node_name}/logs/archives/{archive_name}",
+            get(handlers::download_node_log_archive),
+        )
         // === SNAPSHOT MANAGEMENT ROUTES ===
         .route(
             "/api/snapshots/{node_name}/create",
@@ -128,6 +145,10 @@ fn create_router(state: AppState) -> Router {
             "/api/snapshots/{node_name}/{filename}",
             delete(handlers::delete_snapshot),
         )
+        .route(
+            "/api/snapshots/{node_name}/{filename}/verify",
+            post(handlers::verify_snapshot),
+        )
         .route(
             "/api/snapshots/{node_name}/cleanup",
             post(handlers::cleanup_old_snapshots),
@@ -150,6 +171,16 @@ fn create_router(state: AppState) -> Router {
             "/api/state-sync/{node_name}/execute",
             post(handlers::execute_manual_state_sync),
         )
+        // === NODE CONFIG PATCH ROUTES ===
+        .route("/api/config-patches", get(handlers::list_config_patches))
+        .route(
+            "/api/config-patches/preview",
+            post(handlers::preview_config_patch),
+        )
+        .route(
+            "/api/config-patches/apply",
+            post(handlers::apply_config_patch),
+        )
         // === OPERATION MANAGEMENT ROUTES ===
         .route(
             "/api/operations/active",
@@ -3,3 +3,22 @@
 //! This module is automatically included by all tests in the tests/ directory.
 
 pub mod fixtures;
+
+use manager::config::NodeConfig;
+
+/// Node config parsed from TOML: the required keys of a node named
+/// `full-node-3` on `server-1`, with the keys in `extra` added or overridden
+#[allow(dead_code)]
+pub fn node(extra: &str) -> NodeConfig {
+    let mut table: toml::Table = toml::from_str(
+        r#"
+        rpc_url = "http://localhost:26657"
+        server_host = "server-1"
+        enabled = true
+        service_name = "full-node-3"
+        "#,
+    )
+    .unwrap();
+    table.extend(toml::from_str::<toml::Table>(extra).unwrap());
+    toml::Value::Table(table).try_into().unwrap()
+}
@@ -0,0 +1,236 @@
+//! Tests for selecting nodes for fleet-wide config patches and recording them
+
+mod common;
+
+use chrono::Utc;
+use common::node;
+use manager::config::Config;
+use manager::database::{Database, MaintenanceOperation};
+use manager::health::HealthStatus;
+use manager::services::config_patch_service::{
+    wait_until_ready, ConfigPatchRecord, ConfigPatchRequest, NodeSelection, CONFIG_PATCH_OPERATION,
+};
+use serde_json::json;
+use std::time::Duration;
+use tempfile::TempDir;
+
+fn config() -> Config {
+    let mut config: Config = toml::from_str(
+        r#"
+        host = "0.0.0.0"
+        port = 8095
+        check_interval_seconds = 90
+        rpc_timeout_seconds = 10
+        alarm_webhook_url = ""
+        "#,
+    )
+    .unwrap();
+    for (name, network, server_host, enabled) in [
+        ("pirin-1", "pirin-1", "server-1", true),
+        ("pirin-2", "pirin-1", "server-2", true),
+        ("pirin-3", "pirin-1", "server-2", false),
+        ("osmosis-1", "osmosis-1", "server-1", true),
+    ] {
+        let node = node(&format!(
+            r#"
+            network = "{}"
+            server_host = "{}"
+            enabled = {}
+            deploy_path = "/opt/deploy/node"
+            "#,
+            network, server_host, enabled
+        ));
+        config.nodes.insert(name.to_string(), node);
+    }
+    config
+}
+
+fn selection(value: serde_json::Value) -> NodeSelection {
+    serde_json::from_value(value).unwrap()
+}
+
+#[test]
+fn test_select_by_network_or_server_skips_disabled_nodes() {
+    let config = config();
+
+    assert_eq!(
+        selection(json!({ "network": "pirin-1" }))
+            .select(&config)
+            .unwrap(),
+        vec!["pirin-1", "pirin-2"]
+    );
+    assert_eq!(
+        selection(json!({ "server": "server-1" }))
+            .select(&config)
+            .unwrap(),
+        vec!["osmosis-1", "pirin-1"]
+    );
+    assert_eq!(
+        selection(json!({ "network": "pirin-1", "server": "server-2" }))
+            .select(&config)
+            .unwrap(),
+        vec!["pirin-2"]
+    );
+}
+
+#[test]
+fn test_select_by_list_takes_named_nodes() {
+    let config = config();
+
+    assert_eq!(
+        selection(json!({ "nodes": ["pirin-3", "osmosis-1"] }))
+            .select(&config)
+            .unwrap(),
+        vec!["osmosis-1", "pirin-3"]
+    );
+    assert_eq!(
+        selection(json!({ "nodes": ["pirin-3", "osmosis-1"], "network": "pirin-1" }))
+            .select(&config)
+            .unwrap(),
+        vec!["pirin-3"]
+    );
+}
+
+#[test]
+fn test_invalid_selections_are_rejected() {
+    let config = config();
+
+    assert!(selection(json!({})).select(&config).is_err());
+    assert!(selection(json!({ "nodes": ["missing"] }))
+        .select(&config)
+        .is_err());
+    assert!(selection(json!({ "network": "cosmoshub-4" }))
+        .select(&config)
+        .is_err());
+}
+
+#[test]
+fn test_request_validation() {
+    let request = |file: &str, changes: serde_json::Value| -> ConfigPatchRequest {
+        serde_json::from_value(json!({
+            "network": "pirin-1",
+            "file": file,
+            "changes": changes,
+            "restart": true,
+        }))
+        .unwrap()
+    };
+    let changes = json!([{ "key": "minimum-gas-prices", "value": "0.0025unls" }]);
+
+    let valid = request("app.toml", changes.clone());
+    assert!(valid.validate().is_ok());
+    assert_eq!(valid.selection.network.as_deref(), Some("pirin-1"));
+    assert!(valid.restart);
+
+    assert!(request("client.toml", changes).validate().is_err());
+    assert!(request("app.toml", json!([])).validate().is_err());
+    assert!(request("config.toml", json!([{ "key": " ", "value": 1 }]))
+        .validate()
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_config_patches_are_listed_from_maintenance_operations() {
+    let dir = TempDir::new().unwrap();
+    let database = Database::new(dir.path().join("nodes.db").to_str().unwrap())
+        .await
+        .unwrap();
+
+    let details = json!({
+        "run_id": "run-1",
+        "file": "app.toml",
+        "changes": [{ "key": "minimum-gas-prices", "value": "0.0025unls" }],
+        "restart": true,
+        "config_path": "/opt/deploy/node/config/app.toml",
+        "diff": "-minimum-gas-prices = \"\"\n+minimum-gas-prices = \"0.0025unls\"\n",
+        "backup_path": null,
+        "restarted": true,
+    });
+    for (id, operation_type) in [("op-1", CONFIG_PATCH_OPERATION), ("op-2", "pruning")] {
+        database
+            .store_maintenance_operation(&MaintenanceOperation {
+                id: id.to_string(),
+                operation_type: operation_type.to_string(),
+                target_name: "pirin-1".to_string(),
+                status: "completed".to_string(),
+                started_at: Utc::now(),
+                completed_at: Some(Utc::now()),
+                error_message: None,
+                details: Some(details.to_string()),
+            })
+            .await
+            .unwrap();
+    }
+
+    let records: Vec<ConfigPatchRecord> = database
+        .get_maintenance_operations_by_type(CONFIG_PATCH_OPERATION, 10)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(ConfigPatchRecord::from)
+        .collect();
+
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].node_name, "pirin-1");
+    let details = records[0].details.as_ref().unwrap();
+    assert_eq!(details.run_id, "run-1");
+    assert_eq!(details.changes[0].key, "minimum-gas-prices");
+    assert!(details.restarted);
+}
+
+fn health(is_healthy: bool, is_catching_up: bool) -> HealthStatus {
+    HealthStatus {
+        node_name: "pirin-1".to_string(),
+        rpc_url: "http://localhost:26657".to_string(),
+        is_healthy,
+        error_message: None,
+        last_check: Utc::now(),
+        block_height: Some(100),
+        is_syncing: Some(is_catching_up),
+        is_catching_up,
+        validator_address: None,
+        network: "pirin-1".to_string(),
+        server_host: "server-1".to_string(),
+        enabled: true,
+        in_maintenance: false,
+    }
+}
+
+#[tokio::test]
+async fn test_restarted_node_is_ready_once_healthy_and_caught_up() {
+    let mut checks = vec![
+        Err(anyhow::anyhow!("connection refused")),
+        Ok(health(true, true)),
+        Ok(health(true, false)),
+    ]
+    .into_iter();
+    let mut calls = 0;
+
+    wait_until_ready(
+        "pirin-1",
+        Duration::from_secs(5),
+        Duration::from_millis(1),
+        || {
+            calls += 1;
+            let check = checks.next().unwrap();
+            async move { check }
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(calls, 3);
+}
+
+#[tokio::test]
+async fn test_restarted_node_that_stays_behind_times_out() {
+    let error = wait_until_ready(
+        "pirin-1",
+        Duration::from_millis(20),
+        Duration::from_millis(5),
+        || async { Ok(health(true, true)) },
+    )
+    .await
+    .unwrap_err();
+
+    assert!(error.to_string().contains("still catching up"));
+}
@@ -71,6 +71,71 @@ enabled = true
     assert_eq!(server_config.server.max_concurrent_requests, Some(5));
 }
 
+#[test]
+fn test_parse_server_tls_config() {
+    let server_toml = r#"
+[server]
+host = "agent.example.com"
+agent_port = 8745
+api_key = "test-key"
+agent_scheme = "https"
+ca_cert_path = "/etc/nodes-manager/ca.pem"
+client_cert_path = "/etc/nodes-manager/manager.pem"
+client_key_path = "/etc/nodes-manager/manager.key"
+
+[nodes.dummy]
+rpc_url = "http://localhost:26657"
+server_host = "agent.example.com"
+service_name = "dummy"
+enabled = true
+    "#;
+
+    let config: manager::config::ServerConfigFile = toml::from_str(server_toml).unwrap();
+    let tls = &config.server.tls;
+
+    assert_eq!(tls.agent_scheme, manager::config::AgentScheme::Https);
+    assert_eq!(
+        tls.ca_cert_path.as_deref(),
+        Some("/etc/nodes-manager/ca.pem")
+    );
+    assert_eq!(
+        tls.client_cert_path.as_deref(),
+        Some("/etc/nodes-manager/manager.pem")
+    );
+    assert_eq!(
+        tls.client_key_path.as_deref(),
+        Some("/etc/nodes-manager/manager.key")
+    );
+    assert_eq!(
+        config.server.agent_url("/snapshot/create"),
+        "https://agent.example.com:8745/snapshot/create"
+    );
+}
+
+#[test]
+fn test_server_config_defaults_to_http() {
+    let server_toml = r#"
+[server]
+host = "localhost"
+agent_port = 8745
+api_key = "key"
+
+[nodes.dummy]
+rpc_url = "http://localhost:26657"
+server_host = "localhost"
+service_name = "dummy"
+enabled = true
+    "#;
+
+    let config: manager::config::ServerConfigFile = toml::from_str(server_toml).unwrap();
+
+    assert_eq!(
+        config.server.tls.agent_scheme,
+        manager::config::AgentScheme::Http
+    );
+    assert_eq!(config.server.agent_base_url(), "http://localhost:8745");
+}
+
 #[test]
 fn test_parse_node_config() {
     let server_toml = r#"
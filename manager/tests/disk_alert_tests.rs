@@ -0,0 +1,327 @@
+//! Tests for disk usage levels, fill rate prediction and cleanup selection
+
+mod common;
+
+use chrono::{Duration, TimeZone, Utc};
+use common::node;
+use manager::config::{Config, NodeConfig};
+use manager::database::{Database, ServerTelemetryRecord};
+use manager::health::disk::{
+    assess, cleanup_operations, filesystem_holding, fill_rate, hours_until_full, uncovered_nodes,
+    usage_percent, DiskLevel, DiskThresholds,
+};
+use manager::http::agent_manager::AgentFilesystemUsage;
+use tempfile::TempDir;
+
+const GB: u64 = 1024 * 1024 * 1024;
+
+fn filesystem(kind: &str, root: &str, used_gb: u64, available_gb: u64) -> AgentFilesystemUsage {
+    AgentFilesystemUsage {
+        kind: kind.to_string(),
+        root: root.to_string(),
+        paths: vec![root.to_string()],
+        total_bytes: (used_gb + available_gb) * GB,
+        used_bytes: used_gb * GB,
+        available_bytes: available_gb * GB,
+    }
+}
+
+fn thresholds() -> DiskThresholds {
+    DiskThresholds {
+        warning_percent: 80.0,
+        critical_percent: 90.0,
+        full_alert_hours: 48.0,
+    }
+}
+
+fn pruned_node(network: &str, deploy_path: &str) -> NodeConfig {
+    node(&format!(
+        r#"
+        network = "{}"
+        deploy_path = "{}"
+        pruning_enabled = true
+        pruning_keep_blocks = 1000
+        pruning_keep_versions = 1000
+        snapshots_enabled = true
+        snapshot_backup_path = "/backup/snapshots"
+        snapshot_retention_count = 3
+        "#,
+        network, deploy_path
+    ))
+}
+
+fn config(nodes: Vec<(&str, NodeConfig)>) -> Config {
+    let mut config: Config = toml::from_str(
+        r#"
+        host = "0.0.0.0"
+        port = 8095
+        check_interval_seconds = 90
+        rpc_timeout_seconds = 10
+        alarm_webhook_url = ""
+        disk_auto_cleanup_enabled = true
+        "#,
+    )
+    .unwrap();
+    for (name, node) in nodes {
+        config.nodes.insert(name.to_string(), node);
+    }
+    config
+}
+
+#[test]
+fn test_usage_percent_excludes_reserved_blocks() {
+    let mut fs = filesystem("deploy", "/opt/deploy", 45, 45);
+    // Blocks reserved for root count towards the total but not towards usage
+    fs.total_bytes += 10 * GB;
+    assert_eq!(usage_percent(&fs), 50.0);
+
+    assert_eq!(
+        usage_percent(&filesystem("deploy", "/opt/deploy", 0, 0)),
+        0.0
+    );
+}
+
+#[test]
+fn test_fill_rate_from_history() {
+    let start = Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap();
+    let samples: Vec<_> = (0..5)
+        .map(|hour| (start + Duration::hours(hour), (100 + 2 * hour as u64) * GB))
+        .collect();
+
+    let rate = fill_rate(&samples).unwrap();
+    assert!((rate - 2.0 * GB as f64).abs() < 1.0);
+    assert_eq!(hours_until_full(10 * GB, rate).map(f64::round), Some(5.0));
+}
+
+#[test]
+fn test_fill_rate_needs_enough_history() {
+    let start = Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap();
+
+    assert_eq!(fill_rate(&[]), None);
+    assert_eq!(fill_rate(&[(start, GB)]), None);
+    assert_eq!(
+        fill_rate(&[(start, GB), (start + Duration::minutes(10), 2 * GB)]),
+        None
+    );
+}
+
+#[test]
+fn test_shrinking_disk_never_fills() {
+    let start = Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap();
+    let samples = [(start, 100 * GB), (start + Duration::hours(2), 90 * GB)];
+
+    let rate = fill_rate(&samples).unwrap();
+    assert!(rate < 0.0);
+    assert_eq!(hours_until_full(10 * GB, rate), None);
+}
+
+#[test]
+fn test_levels_follow_thresholds() {
+    let thresholds = thresholds();
+
+    assert_eq!(
+        assess(&filesystem("deploy", "/opt", 50, 50), &[], &thresholds).level,
+        DiskLevel::Ok
+    );
+    assert_eq!(
+        assess(&filesystem("deploy", "/opt", 85, 15), &[], &thresholds).level,
+        DiskLevel::Warning
+    );
+    assert_eq!(
+        assess(&filesystem("deploy", "/opt", 95, 5), &[], &thresholds).level,
+        DiskLevel::Critical
+    );
+}
+
+#[test]
+fn test_predicted_full_disk_raises_warning() {
+    let start = Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap();
+    // 50% used but growing 2 GB an hour with 100 GB left: full in 50 hours
+    let history = [(start, 96 * GB), (start + Duration::hours(2), 100 * GB)];
+    let fs = filesystem("deploy", "/opt", 100, 100);
+
+    let assessment = assess(&fs, &history, &thresholds());
+    assert_eq!(assessment.level, DiskLevel::Ok);
+    assert_eq!(assessment.hours_until_full.map(f64::round), Some(50.0));
+
+    let history = [(start, 94 * GB), (start + Duration::hours(2), 100 * GB)];
+    let assessment = assess(&fs, &history, &thresholds());
+    assert_eq!(assessment.level, DiskLevel::Warning);
+}
+
+#[test]
+fn test_cleanup_prunes_nodes_below_deploy_root() {
+    let config = config(vec![
+        ("osmosis-1", pruned_node("osmosis-1", "/opt/deploy/osmosis")),
+        ("nolus-1", pruned_node("pirin-1", "/srv/nolus")),
+    ]);
+
+    let deploy = filesystem("deploy", "/opt/deploy", 95, 5);
+    let operations =
+        cleanup_operations(&config, "server-1", std::slice::from_ref(&deploy), &deploy);
+    assert_eq!(operations, vec![("pruning", "osmosis-1".to_string())]);
+}
+
+#[test]
+fn test_cleanup_removes_snapshots_once_per_network() {
+    let mut without_retention = pruned_node("neutron-1", "/opt/deploy/neutron");
+    without_retention.snapshot_retention_count = None;
+    let config = config(vec![
+        (
+            "osmosis-a",
+            pruned_node("osmosis-1", "/opt/deploy/osmosis-a"),
+        ),
+        (
+            "osmosis-b",
+            pruned_node("osmosis-1", "/opt/deploy/osmosis-b"),
+        ),
+        ("neutron-1", without_retention),
+    ]);
+
+    let backup = filesystem("backup", "/backup", 95, 5);
+    let log = filesystem("log", "/var/log", 95, 5);
+    let filesystems = [backup.clone(), log.clone()];
+    let operations = cleanup_operations(&config, "server-1", &filesystems, &backup);
+    assert_eq!(
+        operations,
+        vec![("snapshot_cleanup", "osmosis-a".to_string())]
+    );
+
+    assert!(cleanup_operations(&config, "server-2", &filesystems, &backup).is_empty());
+    assert!(cleanup_operations(&config, "server-1", &filesystems, &log).is_empty());
+}
+
+#[test]
+fn test_cleanup_covers_every_path_on_a_shared_filesystem() {
+    let config = config(vec![(
+        "osmosis-1",
+        pruned_node("osmosis-1", "/data/osmosis"),
+    )]);
+    // The agent found the deploy and backup paths on the root filesystem
+    let mut fs = filesystem("deploy", "/opt/deploy", 95, 5);
+    fs.paths
+        .extend(["/data/osmosis".to_string(), "/backup/snapshots".to_string()]);
+
+    assert_eq!(
+        cleanup_operations(&config, "server-1", std::slice::from_ref(&fs), &fs),
+        vec![
+            ("pruning", "osmosis-1".to_string()),
+            ("snapshot_cleanup", "osmosis-1".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn test_nested_mounts_resolve_to_their_own_filesystem() {
+    let config = config(vec![
+        ("osmosis-1", pruned_node("osmosis-1", "/opt/deploy/osmosis")),
+        ("nolus-1", pruned_node("pirin-1", "/opt/deploy/nolus")),
+    ]);
+    // /opt/deploy/osmosis is a separate mount below the deploy root, and the
+    // backup path is on the root filesystem; root names are never matched
+    let mut deploy = filesystem("deploy", "/opt/deploy", 95, 5);
+    deploy.paths.push("/backup/snapshots".to_string());
+    let mut osmosis = filesystem("deploy", "/", 95, 5);
+    osmosis.paths = vec!["/opt/deploy/osmosis".to_string()];
+    let filesystems = [deploy.clone(), osmosis.clone()];
+
+    assert_eq!(
+        filesystem_holding(&filesystems, "/opt/deploy/osmosis/data").map(|fs| &fs.root),
+        Some(&osmosis.root)
+    );
+    assert_eq!(
+        cleanup_operations(&config, "server-1", &filesystems, &deploy),
+        vec![
+            ("pruning", "nolus-1".to_string()),
+            ("snapshot_cleanup", "nolus-1".to_string()),
+            ("snapshot_cleanup", "osmosis-1".to_string()),
+        ]
+    );
+    assert_eq!(
+        cleanup_operations(&config, "server-1", &filesystems, &osmosis),
+        vec![("pruning", "osmosis-1".to_string())]
+    );
+    assert!(uncovered_nodes(&config, "server-1", &filesystems).is_empty());
+    assert_eq!(
+        uncovered_nodes(&config, "server-1", &[osmosis]),
+        vec!["nolus-1".to_string()]
+    );
+}
+
+#[test]
+fn test_nodes_without_a_reported_filesystem_are_uncovered() {
+    let config = config(vec![
+        ("osmosis-1", pruned_node("osmosis-1", "/opt/deploy/osmosis")),
+        ("nolus-1", pruned_node("pirin-1", "/srv/nolus")),
+        ("neutron-1", node(r#"network = "neutron-1""#)),
+        (
+            "disabled-1",
+            node(
+                r#"
+                enabled = false
+                deploy_path = "/srv/disabled"
+                "#,
+            ),
+        ),
+    ]);
+    let mut backup = filesystem("backup", "/backup", 10, 90);
+    backup.paths.push("/srv/other".to_string());
+
+    assert_eq!(
+        uncovered_nodes(
+            &config,
+            "server-1",
+            &[filesystem("deploy", "/opt/deploy", 10, 90), backup.clone()]
+        ),
+        vec!["nolus-1".to_string()]
+    );
+
+    backup.paths.push("/srv/nolus".to_string());
+    assert!(uncovered_nodes(
+        &config,
+        "server-1",
+        &[filesystem("deploy", "/opt/deploy", 10, 90), backup]
+    )
+    .is_empty());
+    assert_eq!(
+        uncovered_nodes(&config, "server-1", &[]),
+        vec!["nolus-1".to_string(), "osmosis-1".to_string()]
+    );
+}
+
+#[tokio::test]
+async fn test_old_telemetry_records_are_deleted() {
+    let dir = TempDir::new().unwrap();
+    let database = Database::new(dir.path().join("nodes.db").to_str().unwrap())
+        .await
+        .unwrap();
+    let now = Utc::now();
+    for hours_ago in [200, 100, 1] {
+        database
+            .store_server_telemetry_record(&ServerTelemetryRecord {
+                server_name: "server-1".to_string(),
+                timestamp: now - Duration::hours(hours_ago),
+                load_1m: 0.5,
+                load_5m: 0.5,
+                load_15m: 0.5,
+                cpu_count: 4,
+                memory_total_bytes: 8 * GB as i64,
+                memory_available_bytes: 4 * GB as i64,
+                filesystems: "[]".to_string(),
+            })
+            .await
+            .unwrap();
+    }
+
+    let deleted = database
+        .delete_telemetry_records_before(now - Duration::hours(168))
+        .await
+        .unwrap();
+    assert_eq!(deleted, 1);
+
+    let remaining = database
+        .get_server_telemetry_records_since("server-1", now - Duration::hours(1000))
+        .await
+        .unwrap();
+    assert_eq!(remaining.len(), 2);
+}
@@ -0,0 +1,80 @@
+//! Tests for choosing between truncating and rotating node logs
+
+mod common;
+
+use common::node;
+use manager::config::NodeConfig;
+use manager::http::agent_manager::AgentLogRotation;
+
+/// Node whose logs are truncated or rotated, with the keys in `extra`
+fn logging_node(extra: &str) -> NodeConfig {
+    node(&format!(
+        r#"
+        log_path = "/var/log/full-node-3"
+        truncate_logs_enabled = true
+        {}
+        "#,
+        extra
+    ))
+}
+
+#[test]
+fn test_logs_are_truncated_by_default() {
+    assert!(AgentLogRotation::for_node(&logging_node("")).is_none());
+    assert!(
+        AgentLogRotation::for_node(&logging_node(r#"truncate_logs_mode = "truncate""#)).is_none()
+    );
+}
+
+#[test]
+fn test_rotate_mode_defaults_to_gzip() {
+    let rotation =
+        AgentLogRotation::for_node(&logging_node(r#"truncate_logs_mode = "rotate""#)).unwrap();
+    assert_eq!(rotation.compression, "gzip");
+    assert_eq!(rotation.keep_generations, None);
+    assert_eq!(rotation.keep_bytes, None);
+}
+
+#[test]
+fn test_rotate_mode_sends_limits() {
+    let rotation = AgentLogRotation::for_node(&logging_node(
+        r#"
+        truncate_logs_mode = "rotate"
+        log_rotation_compression = "zstd"
+        log_rotation_keep_generations = 5
+        log_rotation_keep_bytes = 1073741824
+        "#,
+    ))
+    .unwrap();
+
+    assert_eq!(
+        serde_json::to_value(&rotation).unwrap(),
+        serde_json::json!({
+            "compression": "zstd",
+            "keep_generations": 5,
+            "keep_bytes": 1073741824u64,
+            "log_glob": "out1.log",
+        })
+    );
+}
+
+#[test]
+fn test_rotate_mode_sends_the_log_glob_of_directory_sources() {
+    let rotation = AgentLogRotation::for_node(&logging_node(
+        r#"
+        truncate_logs_mode = "rotate"
+        log_glob = "out*.log"
+        "#,
+    ))
+    .unwrap();
+    assert_eq!(rotation.log_glob.as_deref(), Some("out*.log"));
+
+    let rotation = AgentLogRotation::for_node(&logging_node(
+        r#"
+        truncate_logs_mode = "rotate"
+        log_source = "file"
+        "#,
+    ))
+    .unwrap();
+    assert_eq!(rotation.log_glob, None);
+}
@@ -0,0 +1,116 @@
+//! Tests for resolving where a node's log is read from
+
+mod common;
+
+use common::node;
+use manager::config::LogSource;
+
+#[test]
+fn test_directory_source_defaults_to_out1_log() {
+    let node = node(r#"log_path = "/var/log/full-node-3""#);
+    let source = node.log_source().unwrap();
+
+    assert_eq!(
+        source,
+        LogSource::Directory {
+            path: "/var/log/full-node-3".to_string(),
+            glob: "out1.log".to_string(),
+        }
+    );
+    // Sent as the file itself, as before log sources existed
+    assert_eq!(
+        source.agent_params(),
+        vec![("path", "/var/log/full-node-3/out1.log".to_string())]
+    );
+    assert_eq!(node.log_files_path().unwrap(), "/var/log/full-node-3");
+}
+
+#[test]
+fn test_directory_source_with_wildcard_glob() {
+    let node = node(
+        r#"
+        log_path = "/var/log/full-node-3"
+        log_glob = "*.log"
+        "#,
+    );
+    let source = node.log_source().unwrap();
+
+    assert_eq!(
+        source.agent_params(),
+        vec![
+            ("path", "/var/log/full-node-3".to_string()),
+            ("glob", "*.log".to_string()),
+        ]
+    );
+    assert_eq!(source.display_name(), "/var/log/full-node-3/*.log");
+}
+
+#[test]
+fn test_file_source() {
+    let node = node(
+        r#"
+        log_path = "/var/log/nolusd.log"
+        log_source = "file"
+        "#,
+    );
+
+    assert_eq!(
+        node.log_source(),
+        Some(LogSource::File("/var/log/nolusd.log".to_string()))
+    );
+    assert_eq!(node.log_files_path().unwrap(), "/var/log/nolusd.log");
+}
+
+#[test]
+fn test_journald_source_defaults_to_service_name() {
+    let derived = node(
+        r#"
+        log_path = "/var/log/full-node-3"
+        log_source = "journald"
+        "#,
+    );
+    let source = derived.log_source().unwrap();
+
+    assert_eq!(
+        source,
+        LogSource::Journald {
+            unit: "full-node-3".to_string()
+        }
+    );
+    assert_eq!(
+        source.agent_params(),
+        vec![("journald_unit", "full-node-3".to_string())]
+    );
+    assert_eq!(source.display_name(), "journald:full-node-3");
+    // The derived log path is never truncated for journald nodes
+    assert_eq!(derived.log_files_path(), None);
+
+    let named = node(
+        r#"
+        log_source = "journald"
+        log_journald_unit = "nolusd.service"
+        "#,
+    );
+    assert_eq!(
+        named.log_source(),
+        Some(LogSource::Journald {
+            unit: "nolusd.service".to_string()
+        })
+    );
+}
+
+#[test]
+fn test_unresolvable_sources() {
+    assert_eq!(node("").log_source(), None);
+    assert_eq!(node(r#"log_source = "file""#).log_source(), None);
+    assert_eq!(
+        node(
+            r#"
+            log_path = "/var/log/full-node-3"
+            log_source = "syslog"
+            "#
+        )
+        .log_source(),
+        None
+    );
+}
@@ -54,6 +54,14 @@ async fn get_maintenance_operations(
 
 /// Helper to create a test OperationExecutor with all dependencies
 async fn setup_test_executor() -> (Arc<OperationExecutor>, Arc<Database>, Arc<AlertService>) {
+    // Alert service disabled for tests
+    setup_test_executor_with_webhook("").await
+}
+
+/// Same as `setup_test_executor`, with alerts posted to `webhook_url`
+async fn setup_test_executor_with_webhook(
+    webhook_url: &str,
+) -> (Arc<OperationExecutor>, Arc<Database>, Arc<AlertService>) {
     // Create test database
     let db = Database::new(":memory:")
         .await
@@ -78,8 +86,7 @@ async fn setup_test_executor() -> (Arc<OperationExecutor>, Arc<Database>, Arc<Al
             .expect("Failed to create config manager");
     let config = config_manager.get_current_config().await;
 
-    // Create alert service (disabled for tests)
-    let alert_service = Arc::new(AlertService::new("".to_string()));
+    let alert_service = Arc::new(AlertService::new(webhook_url.to_string()));
 
     // Create OperationExecutor
     let executor = Arc::new(OperationExecutor::new(
@@ -420,3 +427,66 @@ async fn test_operations_with_same_type_different_targets() {
     assert_ne!(op2, op3);
     assert_ne!(op1, op3);
 }
+
+#[tokio::test]
+async fn test_disk_space_failure_sends_dedicated_alert() {
+    let mut server = mockito::Server::new_async().await;
+    let disk_alert = server
+        .mock("POST", "/webhook")
+        .match_body(mockito::Matcher::PartialJson(serde_json::json!({
+            "alert_type": "DiskSpace",
+            "node_name": "test-node-1",
+            "details": { "operation_type": "pruning" }
+        })))
+        .expect(1)
+        .create_async()
+        .await;
+    let other_alerts = server
+        .mock("POST", "/webhook")
+        .expect_at_least(2)
+        .create_async()
+        .await;
+    let (executor, _, _) =
+        setup_test_executor_with_webhook(&format!("{}/webhook", server.url())).await;
+
+    executor
+        .execute_async("pruning", "test-node-1", || async {
+            Err(anyhow::anyhow!(
+                "Job pruning_test-node-1_1 failed: Insufficient disk space on /opt/deploy/nolus \
+                 for pruning: 12.0 GB required, 3.5 GB available"
+            ))
+        })
+        .await
+        .expect("Operation should start successfully");
+    sleep(Duration::from_millis(500)).await;
+
+    // Started and failed alerts are still sent alongside the disk space alert
+    disk_alert.assert_async().await;
+    other_alerts.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_other_failures_send_no_disk_space_alert() {
+    let mut server = mockito::Server::new_async().await;
+    let disk_alert = server
+        .mock("POST", "/webhook")
+        .match_body(mockito::Matcher::PartialJson(
+            serde_json::json!({ "alert_type": "DiskSpace" }),
+        ))
+        .expect(0)
+        .create_async()
+        .await;
+    server.mock("POST", "/webhook").create_async().await;
+    let (executor, _, _) =
+        setup_test_executor_with_webhook(&format!("{}/webhook", server.url())).await;
+
+    executor
+        .execute_async("pruning", "test-node-1", || async {
+            Err(anyhow::anyhow!("cosmos-pruner exited with status 1"))
+        })
+        .await
+        .expect("Operation should start successfully");
+    sleep(Duration::from_millis(500)).await;
+
+    disk_alert.assert_async().await;
+}
@@ -0,0 +1,76 @@
+//! Tests for signing requests to agents
+//!
+//! The agent verifies the same signature scheme; the known vector below is
+//! checked on both sides so the two implementations cannot drift apart.
+
+use manager::http::signing::{
+    sign_request, signature, NONCE_HEADER, SIGNATURE_HEADER, TIMESTAMP_HEADER,
+};
+
+#[test]
+fn test_signature_matches_known_vector() {
+    assert_eq!(
+        signature(
+            "test-key",
+            "post",
+            "/logs/search",
+            1_700_000_000,
+            "abc123",
+            b"{}"
+        ),
+        "372329c14ad75d36ce7154b23dc5e400654f927c44758c909d0c2b9759923a2c"
+    );
+}
+
+#[test]
+fn test_sign_request_covers_path_query_and_body() {
+    let client = reqwest::Client::new();
+    let mut request = client
+        .post("https://agent.example.com:8745/snapshot/archive")
+        .query(&[("snapshot_name", "pirin-1_20250121_17154420")])
+        .json(&serde_json::json!({"backup_path": "/backup"}))
+        .build()
+        .unwrap();
+
+    sign_request(&mut request, "test-key").unwrap();
+
+    let header = |name: &str| {
+        request
+            .headers()
+            .get(name)
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_string()
+    };
+    let expected = signature(
+        "test-key",
+        "POST",
+        "/snapshot/archive?snapshot_name=pirin-1_20250121_17154420",
+        header(TIMESTAMP_HEADER).parse().unwrap(),
+        &header(NONCE_HEADER),
+        br#"{"backup_path":"/backup"}"#,
+    );
+    assert_eq!(header(SIGNATURE_HEADER), expected);
+}
+
+#[test]
+fn test_sign_request_uses_fresh_nonces() {
+    let client = reqwest::Client::new();
+    let mut first = client
+        .get("http://agent:8745/operation/status/1")
+        .build()
+        .unwrap();
+    let mut second = client
+        .get("http://agent:8745/operation/status/1")
+        .build()
+        .unwrap();
+
+    sign_request(&mut first, "test-key").unwrap();
+    sign_request(&mut second, "test-key").unwrap();
+
+    assert_ne!(
+        first.headers().get(NONCE_HEADER),
+        second.headers().get(NONCE_HEADER)
+    );
+}
@@ -0,0 +1,133 @@
+//! Tests for which snapshots count towards retention and which leftover
+//! archive files the cleanup deletes
+
+use manager::http::agent_manager::{AgentArchiveEntry, AgentSnapshotEntry, AgentSnapshotListing};
+use manager::snapshot::manager::{orphaned_leftovers, snapshots_from_listing};
+use manager::snapshot::ArchiveStatus;
+
+const BACKUP: &str = "/backup/pirin";
+
+fn directory(name: &str, modified_unix: i64, archive: bool) -> AgentSnapshotEntry {
+    AgentSnapshotEntry {
+        name: name.to_string(),
+        path: format!("{}/{}", BACKUP, name),
+        modified_unix,
+        has_data: true,
+        has_wasm: true,
+        archive_size_bytes: archive.then_some(100),
+        archive_in_progress: false,
+    }
+}
+
+fn file(name: &str, modified_unix: i64) -> AgentArchiveEntry {
+    AgentArchiveEntry {
+        name: name.to_string(),
+        size_bytes: 100,
+        modified_unix,
+    }
+}
+
+fn listing(
+    snapshots: Vec<AgentSnapshotEntry>,
+    archives: Vec<AgentArchiveEntry>,
+    partial_archives: Vec<AgentArchiveEntry>,
+    downloads: Vec<AgentArchiveEntry>,
+) -> AgentSnapshotListing {
+    AgentSnapshotListing {
+        snapshots,
+        archives,
+        partial_archives,
+        downloads,
+    }
+}
+
+#[test]
+fn test_archive_only_snapshots_are_listed() {
+    let snapshots = snapshots_from_listing(
+        "pirin-node-1",
+        "pirin-1",
+        BACKUP,
+        listing(
+            vec![directory("pirin-1_20250102_200", 2_000, true)],
+            vec![
+                file("pirin-1_20250102_200.tar.lz4", 2_000),
+                file("pirin-1_20250103_300.tar.lz4", 3_000),
+            ],
+            vec![],
+            vec![],
+        ),
+    );
+
+    assert_eq!(snapshots.len(), 2);
+    let pulled = &snapshots[0];
+    assert_eq!(pulled.filename, "pirin-1_20250103_300");
+    assert_eq!(pulled.compression_type, "lz4");
+    assert_eq!(
+        pulled.snapshot_path,
+        "/backup/pirin/pirin-1_20250103_300.tar.lz4"
+    );
+    assert_eq!(pulled.archive_status, Some(ArchiveStatus::Complete));
+    assert_eq!(snapshots[1].filename, "pirin-1_20250102_200");
+    assert_eq!(snapshots[1].compression_type, "directory");
+}
+
+#[test]
+fn test_complete_archives_are_never_leftovers() {
+    let listing = listing(
+        vec![],
+        vec![file("pirin-1_20250101_100.tar.lz4", 1_000)],
+        vec![],
+        vec![],
+    );
+    let kept = snapshots_from_listing("pirin-node-1", "pirin-1", BACKUP, listing.clone());
+
+    assert!(orphaned_leftovers(&listing, &kept, true).is_empty());
+}
+
+#[test]
+fn test_partial_archives_without_a_directory_are_leftovers() {
+    let listing = listing(
+        vec![directory("pirin-1_20250102_200", 2_000, false)],
+        vec![],
+        vec![
+            file("pirin-1_20250101_100.tar.lz4.partial", 1_000),
+            file("pirin-1_20250102_200.tar.lz4.partial", 2_000),
+        ],
+        vec![],
+    );
+    let kept = snapshots_from_listing("pirin-node-1", "pirin-1", BACKUP, listing.clone());
+
+    assert_eq!(
+        orphaned_leftovers(&listing, &kept, false),
+        vec![(
+            "pirin-1_20250101_100.tar.lz4.partial".to_string(),
+            "pirin-1_20250101_100".to_string()
+        )]
+    );
+}
+
+#[test]
+fn test_downloads_are_kept_unless_retention_would_drop_them() {
+    let listing = listing(
+        vec![
+            directory("pirin-1_20250102_200", 2_000, true),
+            directory("pirin-1_20250103_300", 3_000, true),
+        ],
+        vec![],
+        vec![],
+        vec![
+            file("pirin-1_20250101_100.tar.lz4.download", 1_000),
+            file("pirin-1_20250104_400.tar.lz4.download", 4_000),
+        ],
+    );
+    let kept = snapshots_from_listing("pirin-node-1", "pirin-1", BACKUP, listing.clone());
+
+    assert!(orphaned_leftovers(&listing, &kept, false).is_empty());
+    assert_eq!(
+        orphaned_leftovers(&listing, &kept, true),
+        vec![(
+            "pirin-1_20250101_100.tar.lz4.download".to_string(),
+            "pirin-1_20250101_100".to_string()
+        )]
+    );
+}
@@ -29,6 +29,10 @@ async fn test_deploy_path_is_home_directory_not_data() {
         hermes_min_uptime_minutes: Some(5),
         auto_restore_trigger_words: None,
         log_monitoring_context_lines: None,
+        disk_warning_percent: None,
+        disk_critical_percent: None,
+        disk_full_alert_hours: None,
+        disk_auto_cleanup_enabled: None,
         servers: HashMap::new(),
         nodes: HashMap::new(),
         hermes: HashMap::new(),
@@ -46,7 +50,14 @@ async fn test_deploy_path_is_home_directory_not_data() {
         pruning_keep_blocks: Some(1000),
         pruning_keep_versions: Some(1000),
         log_path: Some("/var/log/test-node".to_string()),
+        log_source: None,
+        log_glob: None,
+        log_journald_unit: None,
         truncate_logs_enabled: Some(true),
+        truncate_logs_mode: None,
+        log_rotation_compression: None,
+        log_rotation_keep_generations: None,
+        log_rotation_keep_bytes: None,
         log_monitoring_enabled: None,
         log_monitoring_patterns: None,
         snapshots_enabled: Some(true),
@@ -182,7 +193,14 @@ async fn test_state_sync_config_validation() {
         pruning_keep_blocks: None,
         pruning_keep_versions: None,
         log_path: None,
+        log_source: None,
+        log_glob: None,
+        log_journald_unit: None,
         truncate_logs_enabled: None,
+        truncate_logs_mode: None,
+        log_rotation_compression: None,
+        log_rotation_keep_generations: None,
+        log_rotation_keep_bytes: None,
         log_monitoring_enabled: None,
         log_monitoring_patterns: None,
         snapshots_enabled: None,
@@ -225,7 +243,14 @@ async fn test_default_timeout_is_30_minutes() {
         pruning_keep_blocks: None,
         pruning_keep_versions: None,
         log_path: None,
+        log_source: None,
+        log_glob: None,
+        log_journald_unit: None,
         truncate_logs_enabled: None,
+        truncate_logs_mode: None,
+        log_rotation_compression: None,
+        log_rotation_keep_generations: None,
+        log_rotation_keep_bytes: None,
         log_monitoring_enabled: None,
         log_monitoring_patterns: None,
         snapshots_enabled: None,